@@ -1,4 +1,4 @@
-//! Typed orchestration for the `count` and `diff` commands.
+//! Typed orchestration for the `count`, `diff` and `deps` commands.
 //!
 //! Each function takes one typed request from [`crate::command`], selects the
 //! right `rustloclib` entry point, and builds the canonical response
@@ -17,28 +17,41 @@
 //! the library and is only *called* from here.
 
 use rustloclib::{
-    count_directory_with_options, count_file_with_filter, count_workspace, diff_revspec,
-    diff_workdir, Aggregation, CountOptions, CountQuerySet, CountResult, DiffOptions, DiffQuerySet,
-    LineTypes,
+    count_dependencies, count_directory_with_options, count_file_with_filter, count_workspace,
+    count_workspaces, diff_revspec, diff_workdir, Aggregation, CountOptions, CountQuerySet,
+    CountResult, DiffOptions, DiffQuerySet, LineTypes,
 };
 
-use crate::command::{CountPath, CountRequest, DiffEndpoints, DiffRequest, QueryRequest};
+use crate::command::{
+    CountPath, CountRequest, DepsRequest, DiffEndpoints, DiffRequest, QueryRequest,
+};
 
 /// Run a count and return its canonical response.
 ///
 /// # Errors
 ///
-/// Fails when `--by-crate` is asked of a non-workspace path, or when the
-/// library cannot read the target.
+/// Fails when `--by-target` is asked of a non-workspace path,
+/// when `--by-workspace` is asked without `--workspaces`, or when the library
+/// cannot read the target.
 pub fn count(request: &CountRequest) -> Result<CountQuerySet, anyhow::Error> {
     let query = &request.query;
 
-    if matches!(query.aggregation, Aggregation::ByCrate)
-        && !matches!(request.path, CountPath::Workspace(_))
+    if query.aggregation == Aggregation::ByWorkspace
+        && !matches!(request.path, CountPath::Workspaces(_))
+    {
+        return Err(anyhow::anyhow!("--by-workspace requires --workspaces"));
+    }
+
+    // `--by-crate` works on any directory through the project model, but
+    // targets only exist in Cargo manifests.
+    if query.aggregation == Aggregation::ByTarget
+        && !matches!(
+            request.path,
+            CountPath::Workspace(_) | CountPath::Workspaces(_)
+        )
     {
         return Err(anyhow::anyhow!(
-            "{} requires a Cargo workspace (directory with Cargo.toml), but '{}' is not a workspace",
-            "--by-crate",
+            "--by-target requires a Cargo workspace (directory with Cargo.toml), but '{}' is not a workspace",
             request.path.as_path().display(),
         ));
     }
@@ -55,11 +68,13 @@ pub fn count(request: &CountRequest) -> Result<CountQuerySet, anyhow::Error> {
             .filter(query.filter.clone())
             .aggregation(query.aggregation)
             .line_types(LineTypes::everything())
+            .workspace_discovery(query.workspace_discovery)
     };
 
     let result: CountResult = match &request.path {
         CountPath::Workspace(path) => count_workspace(path, options())?,
         CountPath::Directory(path) => count_directory_with_options(path, options())?,
+        CountPath::Workspaces(path) => count_workspaces(path, options())?,
         // A lone file has no workspace or module structure to aggregate over,
         // so it bypasses the aggregating entry points and becomes a
         // single-file result directly.
@@ -93,7 +108,8 @@ pub fn diff(request: &DiffRequest) -> Result<DiffQuerySet, anyhow::Error> {
         .crates(query.crates.clone())
         .filter(query.filter.clone())
         .aggregation(query.aggregation)
-        .line_types(LineTypes::everything());
+        .line_types(LineTypes::everything())
+        .workspace_discovery(query.workspace_discovery);
 
     let result = match &request.endpoints {
         // The revspec goes to the library verbatim; gix owns rev parsing.
@@ -109,6 +125,30 @@ pub fn diff(request: &DiffRequest) -> Result<DiffQuerySet, anyhow::Error> {
     ))
 }
 
+/// Count a workspace's dependencies and return the canonical count response,
+/// one row per dependency.
+///
+/// # Errors
+///
+/// Fails when the path is not a Cargo workspace, or when the dependency graph
+/// cannot be resolved from the sources already on this machine.
+pub fn deps(request: &DepsRequest) -> Result<CountQuerySet, anyhow::Error> {
+    let query = &request.query;
+    let options = CountOptions::new()
+        .filter(query.filter.clone())
+        .aggregation(query.aggregation)
+        .line_types(LineTypes::everything());
+
+    let result = count_dependencies(&request.path, &request.dependencies, options)?;
+
+    Ok(narrow(
+        CountQuerySet::from_result(&result, query.aggregation, query.line_types, query.ordering),
+        query,
+        CountQuerySet::filter,
+        CountQuerySet::top,
+    ))
+}
+
 /// Apply `--<field>-<op>` predicates, then `--top`, in that order.
 ///
 /// Order matters: filtering first means `--top` slices the already-filtered
@@ -150,6 +190,7 @@ mod tests {
                 ordering: Ordering::default(),
                 top: None,
                 predicates: Vec::new(),
+                workspace_discovery: Default::default(),
             },
         }
     }
@@ -187,15 +228,15 @@ mod tests {
     }
 
     #[test]
-    fn count_by_crate_on_a_non_workspace_is_an_error() {
+    fn count_by_target_on_a_non_workspace_is_an_error() {
         // The error path a test could not reach before without an ArgMatches.
         let dir = tempfile::tempdir().unwrap();
         let mut request = request_for(dir.path());
-        request.query.aggregation = Aggregation::ByCrate;
+        request.query.aggregation = Aggregation::ByTarget;
 
         let err = count(&request).unwrap_err();
         assert!(
-            err.to_string().contains("--by-crate")
+            err.to_string().contains("--by-target")
                 && err.to_string().contains("requires a Cargo workspace"),
             "unexpected message: {err}"
         );
@@ -2,15 +2,15 @@
 //!
 //! This module is the **only** place that interprets raw [`ArgMatches`] as
 //! command logic. It converts CLI syntax into typed request values
-//! ([`CountRequest`] / [`DiffRequest`]) that [`crate::application`] can
+//! ([`CountRequest`] / [`DiffRequest`] / [`DepsRequest`]) that [`crate::application`] can
 //! orchestrate without knowing clap exists.
 //!
 //! Two other modules touch `ArgMatches`, and neither is a competing reader:
 //!
 //! - [`crate::filter_args`] owns both ends of the synthetic `--<field>-<op>`
-//!   grid — it registers the 42 hidden args and reads them back. Its
+//!   grid — it registers the 54 hidden args and reads them back. Its
 //!   `extract` is called *from here* ([`QueryRequest::from_matches`]), so the
-//!   grid stays a detail of the module that invents it rather than 42 cases
+//!   grid stays a detail of the module that invents it rather than 54 cases
 //!   spelled out at this boundary.
 //! - `crate::presentation` reads the single injected `_output_mode` arg at the
 //!   render boundary. That is a render decision, not command logic.
@@ -36,10 +36,38 @@ use std::path::{Path, PathBuf};
 
 use clap::ArgMatches;
 use rustloclib::{
-    available_languages, default_languages, Aggregation, FilterConfig, LanguageName,
-    LanguageSelection, LineTypes, OrderBy, OrderDirection, Ordering, Predicate, WorkdirDiffMode,
+    default_languages, Aggregation, BackendRegistry, ClassifyRule, DependencyKind, DependencyQuery,
+    FilterConfig, LanguageConfig, LanguageName, LanguageSelection, LineTypes, OrderBy,
+    OrderDirection, Ordering, Predicate, WorkdirDiffMode, WorkspaceDiscovery,
 };
 
+/// Language definitions file looked up in the analyzed path when `--config`
+/// is absent.
+pub const DEFAULT_CONFIG_FILE: &str = ".rustloc.toml";
+
+/// Parse a `--max-file-size` value: bytes, optionally with a `K`, `M` or `G`
+/// suffix meaning powers of 1024 (`512K`, `2M`). A trailing `B` is accepted.
+pub fn parse_file_size(s: &str) -> Result<u64, String> {
+    let upper = s.trim().to_ascii_uppercase();
+    let digits = upper.strip_suffix('B').unwrap_or(&upper);
+    let (digits, multiplier) = match digits.char_indices().last() {
+        Some((i, 'K')) => (&digits[..i], 1 << 10),
+        Some((i, 'M')) => (&digits[..i], 1 << 20),
+        Some((i, 'G')) => (&digits[..i], 1 << 30),
+        _ => (digits, 1),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| {
+            format!(
+                "invalid size '{}': expected bytes, e.g. 500000, 512K or 2M",
+                s
+            )
+        })
+}
+
 /// Parse an `--ordering` value (`code`, `-code`, `+label`) into an [`Ordering`].
 ///
 /// Wired as a clap `value_parser`, so an unknown field or a bare direction
@@ -85,6 +113,8 @@ pub enum CountPath {
     File(PathBuf),
     /// A plain directory with no manifest.
     Directory(PathBuf),
+    /// A directory whose workspaces beneath it are all counted (`--workspaces`).
+    Workspaces(PathBuf),
 }
 
 impl CountPath {
@@ -108,7 +138,7 @@ impl CountPath {
     /// The underlying path, for library calls and error messages.
     pub fn as_path(&self) -> &Path {
         match self {
-            Self::Workspace(p) | Self::File(p) | Self::Directory(p) => p,
+            Self::Workspace(p) | Self::File(p) | Self::Directory(p) | Self::Workspaces(p) => p,
         }
     }
 }
@@ -194,19 +224,33 @@ pub struct QueryRequest {
     pub top: Option<usize>,
     /// Threshold filters from the `--<field>-<op> N` grid, AND-combined.
     pub predicates: Vec<Predicate>,
+    /// How a workspace's crates are found: its manifests, or `cargo metadata`.
+    pub workspace_discovery: WorkspaceDiscovery,
 }
 
 impl QueryRequest {
-    /// Convert the shared count/diff flags out of `matches`.
-    pub fn from_matches(matches: &ArgMatches) -> Result<Self, anyhow::Error> {
+    /// Convert the shared count/diff flags out of `matches`. `root` is where
+    /// the default language definitions file is looked up.
+    pub fn from_matches(matches: &ArgMatches, root: &Path) -> Result<Self, anyhow::Error> {
+        Self::grouped(matches, root, aggregation_from_matches(matches))
+    }
+
+    /// Like [`from_matches`](Self::from_matches), for commands whose grouping
+    /// is fixed rather than chosen with `--by-*` flags.
+    pub fn grouped(
+        matches: &ArgMatches,
+        root: &Path,
+        aggregation: Aggregation,
+    ) -> Result<Self, anyhow::Error> {
+        let filter = build_filter(matches, root)?;
         Ok(Self {
+            line_types: line_types_from_matches(matches, &filter.languages),
             crates: matches
                 .get_many::<String>("crates")
                 .map(|v| v.cloned().collect())
                 .unwrap_or_default(),
-            filter: build_filter(matches)?,
-            aggregation: aggregation_from_matches(matches),
-            line_types: line_types_from_matches(matches),
+            filter,
+            aggregation,
             // clap already validated this via `parse_ordering`, so an absent
             // value means "not supplied", never "supplied but unparseable".
             ordering: matches
@@ -215,6 +259,7 @@ impl QueryRequest {
                 .unwrap_or_default(),
             top: matches.get_one::<usize>("top").copied(),
             predicates: crate::filter_args::extract(matches),
+            workspace_discovery: WorkspaceDiscovery::default(),
         })
     }
 }
@@ -236,10 +281,28 @@ impl CountRequest {
             .map(|s| s.as_str())
             .unwrap_or(".");
 
-        Ok(Self {
-            path: CountPath::classify(path),
-            query: QueryRequest::from_matches(matches)?,
-        })
+        let mut query = QueryRequest::from_matches(matches, Path::new(path))?;
+        // Diffs read tracked files from git, so only `count` walks the tree.
+        query.filter = query
+            .filter
+            .no_ignore(matches.get_flag("no_ignore"))
+            .tracked_only(matches.get_flag("tracked_only"));
+        // clap restricts `--symlinks` to the policy names, so parsing cannot fail.
+        if let Some(policy) = matches
+            .get_one::<String>("symlinks")
+            .and_then(|s| s.parse().ok())
+        {
+            query.filter = query.filter.symlinks(policy);
+        }
+        query.workspace_discovery = workspace_discovery_from_matches(matches);
+
+        let path = if matches.get_flag("workspaces") {
+            CountPath::Workspaces(PathBuf::from(path))
+        } else {
+            CountPath::classify(path)
+        };
+
+        Ok(Self { path, query })
     }
 }
 
@@ -262,6 +325,9 @@ impl DiffRequest {
             .map(|s| s.as_str())
             .unwrap_or(".");
 
+        let mut query = QueryRequest::from_matches(matches, Path::new(repo))?;
+        query.workspace_discovery = workspace_discovery_from_matches(matches);
+
         Ok(Self {
             repo: PathBuf::from(repo),
             endpoints: DiffEndpoints::resolve(
@@ -269,7 +335,47 @@ impl DiffRequest {
                 matches.get_one::<String>("to"),
                 matches.get_flag("staged"),
             )?,
-            query: QueryRequest::from_matches(matches)?,
+            query,
+        })
+    }
+}
+
+/// A fully typed `deps` invocation.
+#[derive(Debug, Clone)]
+pub struct DepsRequest {
+    /// Workspace whose dependencies are counted.
+    pub path: PathBuf,
+    /// Which dependencies to resolve. Its members come from `--crate`.
+    pub dependencies: DependencyQuery,
+    /// Shared view controls, always grouped one row per dependency.
+    pub query: QueryRequest,
+}
+
+impl DepsRequest {
+    /// Convert `matches` into a typed deps request.
+    pub fn from_matches(matches: &ArgMatches) -> Result<Self, anyhow::Error> {
+        let path = matches
+            .get_one::<String>("path")
+            .map(|s| s.as_str())
+            .unwrap_or(".");
+        let mut query = QueryRequest::grouped(matches, Path::new(path), Aggregation::ByCrate)?;
+
+        // clap restricts `--kind` to the three names, so parsing cannot fail.
+        let kinds: Vec<DependencyKind> = matches
+            .get_many::<String>("kinds")
+            .map(|v| v.filter_map(|s| s.parse().ok()).collect())
+            .unwrap_or_default();
+        let mut dependencies = DependencyQuery::new()
+            .transitive(!matches.get_flag("direct"))
+            .members(std::mem::take(&mut query.crates));
+        if !kinds.is_empty() {
+            dependencies = dependencies.kinds(kinds);
+        }
+
+        Ok(Self {
+            path: PathBuf::from(path),
+            dependencies,
+            query,
         })
     }
 }
@@ -277,27 +383,46 @@ impl DiffRequest {
 /// The `--by-*` flags are mutually exclusive (clap enforces it), so the first
 /// set flag wins and no flag means totals only.
 fn aggregation_from_matches(matches: &ArgMatches) -> Aggregation {
-    if matches.get_flag("by_file") {
+    // Only `count` defines `--by-workspace`, and `get_flag` panics on an id
+    // the command does not know.
+    let by_workspace = matches!(matches.try_get_one::<bool>("by_workspace"), Ok(Some(true)));
+    if by_workspace {
+        Aggregation::ByWorkspace
+    } else if matches.get_flag("by_file") {
         Aggregation::ByFile
     } else if matches.get_flag("by_module") {
         Aggregation::ByModule
-    } else if matches.get_flag("by_crate") {
+    } else if matches.get_flag("by_target") {
+        Aggregation::ByTarget
+    } else if matches.get_flag("by_crate") || matches.get_flag("by_package") {
         Aggregation::ByCrate
     } else {
         Aggregation::Total
     }
 }
 
-/// Absent `--type` means "show everything" ([`LineTypes::default`]); otherwise
-/// only the named types are displayed.
-fn line_types_from_matches(matches: &ArgMatches) -> LineTypes {
+fn workspace_discovery_from_matches(matches: &ArgMatches) -> WorkspaceDiscovery {
+    if matches.get_flag("cargo_metadata") {
+        WorkspaceDiscovery::CargoMetadata
+    } else {
+        WorkspaceDiscovery::Manifest
+    }
+}
+
+/// Absent `--type` means "show everything" ([`LineTypes::default`]), plus the
+/// `config` column when the config backend is selected, since it counts
+/// nothing else; otherwise only the named types are displayed.
+fn line_types_from_matches(matches: &ArgMatches, languages: &LanguageSelection) -> LineTypes {
     let types: Vec<&str> = matches
         .get_many::<String>("line_types")
         .map(|v| v.map(|s| s.as_str()).collect())
         .unwrap_or_default();
 
     if types.is_empty() {
-        return LineTypes::default();
+        return LineTypes {
+            config: languages.contains(LanguageName::Config),
+            ..LineTypes::default()
+        };
     }
 
     LineTypes {
@@ -307,12 +432,17 @@ fn line_types_from_matches(matches: &ArgMatches) -> LineTypes {
         docs: types.contains(&"docs"),
         comments: types.contains(&"comments"),
         blanks: types.contains(&"blanks"),
+        preprocessor: types.contains(&"preprocessor"),
+        config: types.contains(&"config"),
+        categories: types.contains(&"categories"),
         total: types.contains(&"total"),
     }
 }
 
-fn build_filter(matches: &ArgMatches) -> Result<FilterConfig, anyhow::Error> {
-    let mut filter = FilterConfig::new().languages(languages_from_matches(matches)?);
+fn build_filter(matches: &ArgMatches, root: &Path) -> Result<FilterConfig, anyhow::Error> {
+    let registry = registry_from_matches(matches, root)?;
+    let mut filter = FilterConfig::new().languages(languages_from_matches(matches, &registry)?);
+    filter = filter.registry(registry);
 
     if let Some(includes) = matches.get_many::<String>("include") {
         for pattern in includes {
@@ -326,12 +456,84 @@ fn build_filter(matches: &ArgMatches) -> Result<FilterConfig, anyhow::Error> {
         }
     }
 
-    Ok(filter)
+    Ok(filter
+        .max_file_size(matches.get_one::<u64>("max_file_size").copied())
+        .strict(matches.get_flag("strict")))
+}
+
+/// Register the languages from `--config`, or from [`DEFAULT_CONFIG_FILE`] in
+/// `root` when it exists. An explicit file that is missing or invalid is an
+/// error; so is an invalid default file, rather than silently counting less.
+/// `--classify` rules are tried before the file's `[[classify]]` tables,
+/// and both are anchored at `root`.
+fn registry_from_matches(
+    matches: &ArgMatches,
+    root: &Path,
+) -> Result<BackendRegistry, anyhow::Error> {
+    let mut rules = matches
+        .get_many::<String>("classify")
+        .into_iter()
+        .flatten()
+        .map(|rule| rule.parse::<ClassifyRule>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let dir = if root.is_file() {
+        root.parent().unwrap_or(root)
+    } else {
+        root
+    };
+    let anchored = |registry: BackendRegistry| {
+        let classifier = registry.classifier().clone().with_root(dir);
+        registry.with_classifier(classifier)
+    };
+    let explicit = matches.get_one::<String>("config");
+    let path = match explicit {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let path = dir.join(DEFAULT_CONFIG_FILE);
+            if !path.is_file() {
+                return Ok(anchored(BackendRegistry::with_config(LanguageConfig {
+                    classify: rules,
+                    ..LanguageConfig::default()
+                })?));
+            }
+            path
+        }
+    };
+    let mut config = LanguageConfig::load(&path)?;
+    rules.append(&mut config.classify);
+    config.classify = rules;
+    // A discovered config comes with the checkout being analyzed, which may
+    // be untrusted (a pull request), so its plugins need an explicit opt-in.
+    if explicit.is_none() && !matches.get_flag("allow_plugins") && !config.plugins.is_empty() {
+        let requested: Vec<&str> = matches
+            .get_many::<String>("languages")
+            .into_iter()
+            .flatten()
+            .map(|s| s.as_str())
+            .collect();
+        if let Some(plugin) = config.plugins.iter().find(|plugin| {
+            requested
+                .iter()
+                .any(|value| plugin.name.eq_ignore_ascii_case(value))
+        }) {
+            anyhow::bail!(
+                "'{}' is a plugin from {}; pass --allow-plugins or --config to run it",
+                plugin.name,
+                path.display()
+            );
+        }
+        config.plugins.clear();
+    }
+    Ok(anchored(BackendRegistry::with_config(config)?))
 }
 
 /// Absent `--lang` means the default backends; `all` is a shorthand for every
-/// available backend group.
-fn languages_from_matches(matches: &ArgMatches) -> Result<LanguageSelection, anyhow::Error> {
+/// backend in `registry` except plugins, which must be named. Other values
+/// must name a registered backend (or an alias of a built-in group).
+fn languages_from_matches(
+    matches: &ArgMatches,
+    registry: &BackendRegistry,
+) -> Result<LanguageSelection, anyhow::Error> {
     let values: Vec<&str> = matches
         .get_many::<String>("languages")
         .map(|v| v.map(|s| s.as_str()).collect())
@@ -341,15 +543,28 @@ fn languages_from_matches(matches: &ArgMatches) -> Result<LanguageSelection, any
         return Ok(LanguageSelection::new(default_languages()));
     }
 
-    if values.iter().any(|value| value.eq_ignore_ascii_case("all")) {
-        return Ok(LanguageSelection::new(available_languages()));
-    }
-
-    let mut languages = Vec::new();
-    for value in values {
-        languages.push(value.parse::<LanguageName>().map_err(anyhow::Error::msg)?);
+    let all = values.iter().any(|value| value.eq_ignore_ascii_case("all"));
+    let names = values
+        .into_iter()
+        .filter(|value| !value.eq_ignore_ascii_case("all"))
+        .map(|value| {
+            registry.resolve(value).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "unknown language '{}'; available languages: {}",
+                    value,
+                    registry.names().join(", ")
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if all {
+        return Ok(names
+            .into_iter()
+            .fold(registry.all_languages(), |selection, name| {
+                selection.with_name(name)
+            }));
     }
-    Ok(LanguageSelection::new(&languages))
+    Ok(LanguageSelection::from_names(names))
 }
 
 #[cfg(test)]
@@ -405,6 +620,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn file_size_accepts_bytes_and_binary_suffixes() {
+        assert_eq!(parse_file_size("500000"), Ok(500_000));
+        assert_eq!(parse_file_size("512K"), Ok(512 * 1024));
+        assert_eq!(parse_file_size("2mb"), Ok(2 * 1024 * 1024));
+        for bad in ["", "M", "1.5M", "-1", "12X"] {
+            assert!(parse_file_size(bad).is_err(), "{bad:?} should be rejected");
+        }
+    }
+
     #[test]
     fn count_path_classifies_workspace_dir_manifest_and_file() {
         let dir = tempfile::tempdir().unwrap();
@@ -5,17 +5,45 @@
 //! ## Overview
 //!
 //! rustloc is built on top of rustloclib and provides a command-line interface for
-//! analyzing Rust, Python, TypeScript, and generic source trees. It separates production code
-//! from test code, even when a language backend can find both in the same file.
+//! analyzing Rust, Python, TypeScript, Java/Kotlin, C/C++, Zig, Ruby, Markdown, config, and
+//! generic source trees. It separates production code from test code, even when a language
+//! backend can find both in the same file.
 //!
 //! ## Features
 //!
-//! - **Language-aware**: Distinguishes code, tests, examples, comments, docs, and blanks
-//! - **Language selection**: Rust by default; opt into Python, TypeScript, or generic counting
-//! - **Cargo workspace support**: Filter by crate with `--crate` or `-c`
+//! - **Language-aware**: Distinguishes code, tests, examples, comments, docs, blanks,
+//!   preprocessor directives, and config
+//! - **Language selection**: Rust by default; opt into Python, TypeScript, Java/Kotlin, C/C++,
+//!   Zig, Ruby, Markdown, config files, or generic counting
+//! - **Cargo workspace support**: Filter by crate with `--crate` or `-c`; crates are
+//!   scanned through their Cargo targets, and `--by-target` breaks counts down per target.
+//!   Manifests are read directly, so no Cargo toolchain is needed (`--cargo-metadata`
+//!   asks Cargo instead)
+//! - **Monorepos**: `--workspaces` counts every workspace and standalone crate
+//!   beneath a directory, grouped with `--by-workspace` or `--by-crate`
+//! - **Packages in any language**: outside a Cargo workspace, `--by-crate` groups
+//!   files by their Python, Node, Go or Cargo package (`--by-package`), so mixed
+//!   repositories and pnpm/npm/yarn, Nx or Turborepo monorepos work too; `-c` filters
+//!   by package name
 //! - **Glob filtering**: Include/exclude files with glob patterns
+//! - **Ignore files**: Skips paths ignored by `.gitignore`, `.ignore` or
+//!   `.rustlocignore` (`--no-ignore` counts them anyway)
+//! - **Tracked files only**: `--tracked-only` counts the files in the git index,
+//!   so untracked scratch files and build outputs never affect the numbers
+//! - **Symlink safety**: links are followed without loops or double counting;
+//!   `--symlinks skip|error` decides about links leaving the path
+//! - **Content checks**: binary and minified files are skipped with a reason
+//!   instead of failing the run; `--max-file-size` skips oversized ones
+//! - **Encoding detection**: UTF-16 and Windows-1252 sources are transcoded
+//!   before counting; `--verbose` lists them per file
+//! - **Partial results**: unreadable and unparsable files become diagnostics
+//!   while the rest are counted; `--strict` fails on the first one instead
+//! - **Classification rules**: `--classify 'src/testing/**=tests'` and
+//!   `[[classify]]` tables decide which files are tests or examples
 //! - **Multiple output formats**: Table (default), JSON, YAML, XML, CSV
 //! - **Git diff analysis**: Compare LOC between commits
+//! - **Dependency counts**: `rustloc deps` counts third-party crates from the
+//!   local registry cache, `vendor/` or path dependencies, per dependency
 //!
 //! ## Usage
 //!
@@ -48,6 +76,9 @@
 //!
 //! # Diff only staged changes (like git diff --cached)
 //! rustloc diff --staged
+//!
+//! # Count the code in your dependencies
+//! rustloc deps --direct
 //! ```
 //!
 //! ## Origins
@@ -73,15 +104,20 @@ mod table;
 #[command(long_about = "\
 Language-aware lines of code counter with test/code separation.
 
-Rust is analyzed by default. Python, TypeScript, and generic source files can
-be selected with --lang. Rust and Python backends classify same-file test code;
-the TypeScript and generic backends use file paths for code/test/example context.")]
+Rust is analyzed by default. Python, TypeScript, Java/Kotlin, C/C++, Zig, Ruby,
+Markdown, config files, and generic source files can be selected with --lang.
+The Rust, Python, JVM, C/C++, Zig, and Ruby backends classify same-file test
+code; the TypeScript, Markdown, config, and generic backends use file paths for
+code/test/example context.")]
 #[command(after_help = "Use --help for examples")]
 #[command(after_long_help = "\
 Examples:
   rustloc                              Totals for current directory
   rustloc --by-crate                   Group by crate
+  rustloc --by-package -c @acme/ui     One package of a JS/TS monorepo
+  rustloc --workspaces --by-workspace  Every workspace beneath ., one row each
   rustloc --by-module                  Group by module
+  rustloc --by-target                  Group by Cargo target
   rustloc --by-file                    Group by file
   rustloc --by-file -o -code           Sort files by code (descending)
   rustloc -t code,tests               Only code and test lines
@@ -93,7 +129,8 @@ Examples:
   rustloc diff                         Changes since last commit
   rustloc diff --lang python           Python changes since last commit
   rustloc diff --lang typescript       TypeScript changes since last commit
-  rustloc diff HEAD~5..HEAD --by-file  Per-file diff between commits")]
+  rustloc diff HEAD~5..HEAD --by-file  Per-file diff between commits
+  rustloc deps                         Per-dependency counts")]
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
@@ -133,8 +170,65 @@ Examples:
   rustloc diff main...feature          From their merge base to feature
   rustloc diff -t code                 Only code line changes")]
     Diff(DiffArgs),
+
+    /// Count lines of code in the dependencies of a Cargo workspace
+    #[dispatch(template = "count_table", post_dispatch = presentation::count)]
+    #[command(long_about = "\
+Count lines of code in the dependencies of a Cargo workspace.
+
+Resolves the dependency graph with `cargo metadata --offline` and counts each
+dependency where Cargo keeps it: the registry cache (~/.cargo/registry/src),
+git checkouts, a vendor/ directory, or a path dependency. Nothing is
+downloaded, so run `cargo fetch` (or a build) first. Workspace members are
+never counted.")]
+    #[command(after_help = "Use --help for examples")]
+    #[command(after_long_help = "\
+Examples:
+  rustloc deps                         Transitive normal dependencies
+  rustloc deps --direct                Only direct dependencies
+  rustloc deps -k normal,dev,build     Include dev and build dependencies
+  rustloc deps -c my-cli               Dependencies of one member
+  rustloc deps -o -code --top 10       The 10 largest dependencies")]
+    Deps(DepsArgs),
 }
 
+/// Long help for `--max-file-size`, shared by `count`, `diff` and `deps`.
+const MAX_FILE_SIZE_HELP: &str = "\
+Skip files larger than SIZE instead of counting them. SIZE is a number of
+bytes with an optional K, M or G suffix (powers of 1024).
+
+Binary files and minified bundles are always skipped; skipped files are
+reported below the table and in structured output.
+
+  --max-file-size 1M     Skip files over one mebibyte";
+
+/// Long help for `--strict`, shared by `count`, `diff` and `deps`.
+const STRICT_HELP: &str = "\
+Fail on the first file that cannot be read, that a backend fails on, or that
+a parser rejects.
+
+Without it such files are reported as diagnostics below the table and in
+structured output, and the rest are counted; files a parser rejects are still
+counted line by line.";
+
+/// Long help for `--classify`, shared by `count`, `diff` and `deps`.
+const CLASSIFY_HELP: &str = "\
+Count files matching GLOB as CONTEXT (code, tests or examples) instead of
+deciding from each backend's path conventions or the kind of the Cargo target
+holding them. Semantic classification still applies on top, so #[test]
+functions in a file classified as code are tests. Any other context names a
+new category with a column of its own, which takes the code lines of its
+files; category names use letters, digits, - and _.
+
+Rules are tried in order and the first match wins; they come before the
+[[classify]] tables of the config file. A glob is matched against the path
+relative to the analyzed path (the repository for diff); start it with **/ to
+match anywhere.
+
+  --classify 'src/testing/**=tests'
+  --classify 'crates/*/fixtures/**=fixtures' --classify '**/demos/**=examples'
+  --classify 'tests/common/**=code'";
+
 /// Shared arguments for count command and top-level
 #[derive(Args, Clone, Default)]
 struct CountArgs {
@@ -142,7 +236,7 @@ struct CountArgs {
     #[arg(default_value = ".")]
     path: String,
 
-    /// Only count specific crate(s) [-c my-lib -c my-cli]
+    /// Only count specific crate(s) or package(s) [-c my-lib -c @acme/ui]
     #[arg(short = 'c', long = "crate", action = clap::ArgAction::Append)]
     crates: Vec<String>,
 
@@ -154,12 +248,18 @@ struct CountArgs {
 Language backend groups to analyze.
 
 Default: rust
-Available: rust, python, typescript, generic
+Available: rust, python, typescript, jvm, cpp, zig, ruby, markdown, config, generic
 
   -l python            Analyze Python files only
   -l rust,python       Analyze Rust and Python files
   -l typescript        Analyze TypeScript files only
   -l rust,typescript   Analyze Rust and TypeScript files
+  -l jvm               Analyze Java and Kotlin files only
+  -l cpp               Analyze C and C++ files only
+  -l zig               Analyze Zig files only
+  -l ruby              Analyze Ruby files only
+  -l rust,markdown     Analyze Rust and Markdown documentation
+  -l config            Analyze TOML, YAML, JSON, INI and SQL files only
   -l all               Analyze all available backend groups")]
     languages: Vec<String>,
 
@@ -171,31 +271,131 @@ Available: rust, python, typescript, generic
     #[arg(short = 'e', long = "exclude", action = clap::ArgAction::Append)]
     exclude: Vec<String>,
 
+    /// Also count files ignored by .gitignore, .ignore or .rustlocignore
+    #[arg(long = "no-ignore")]
+    no_ignore: bool,
+
+    /// Only count files tracked in the git index
+    #[arg(long = "tracked-only")]
+    tracked_only: bool,
+
+    /// What to do with symlinks leading outside the path [default: follow]
+    #[arg(long = "symlinks", value_name = "POLICY")]
+    #[arg(value_parser = ["follow", "skip", "error"])]
+    #[arg(long_help = "\
+What to do with symbolic links leading outside the analyzed path.
+
+Links are always followed without loops, and a file reachable through several
+paths is counted once; skipped links are reported below the table and in
+structured output.
+
+  --symlinks follow    Count what outside links lead to (default)
+  --symlinks skip      Leave outside links out and report them
+  --symlinks error     Fail on the first outside link")]
+    symlinks: Option<String>,
+
+    /// Skip files larger than SIZE bytes [--max-file-size 1M]
+    #[arg(long = "max-file-size", value_name = "SIZE", value_parser = command::parse_file_size)]
+    #[arg(long_help = MAX_FILE_SIZE_HELP)]
+    max_file_size: Option<u64>,
+
+    /// Fail on the first unreadable or unparsable file
+    #[arg(long = "strict")]
+    #[arg(long_help = STRICT_HELP)]
+    strict: bool,
+
+    /// Count files matching a glob as code, tests, examples or a category [--classify 'demos/**=examples']
+    #[arg(long = "classify", value_name = "GLOB=CONTEXT", action = clap::ArgAction::Append)]
+    #[arg(long_help = CLASSIFY_HELP)]
+    classify: Vec<String>,
+
+    /// Discover the workspace with `cargo metadata` instead of reading Cargo.toml
+    #[arg(long = "cargo-metadata")]
+    cargo_metadata: bool,
+
+    /// Show per-file detail below the table, such as content-detected languages and transcoded encodings
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+
+    /// Language definitions file [default: .rustloc.toml in the analyzed path]
+    #[arg(long = "config", value_name = "FILE")]
+    #[arg(long_help = "\
+TOML file declaring extra languages, each selectable with --lang by name.
+Defaults to .rustloc.toml in the analyzed path (the repository for diff) when
+that file exists.
+
+  [[language]]
+  name = \"terraform\"
+  extensions = [\"tf\", \"tfvars\"]
+  line_comments = [\"#\", \"//\"]
+  block_comments = [[\"/*\", \"*/\"]]
+  test_paths = [\"**/tests/**\"]
+
+Other keys: filenames, shebangs, doc_comments, nested_comments, quotes,
+example_paths.
+
+[[plugin]] tables (name, extensions, filenames, command, args, timeout)
+delegate a language to an external program that answers with per-line classes
+as JSON. Plugins run only when named with --lang, and a discovered
+.rustloc.toml runs none unless --allow-plugins is given.
+
+[[classify]] tables (path, context) count matching files as code, tests or
+examples; see --classify.")]
+    config: Option<String>,
+
+    /// Run [[plugin]] commands from a discovered .rustloc.toml
+    #[arg(long = "allow-plugins")]
+    allow_plugins: bool,
+
     /// Line types to show (comma-separated)
     #[arg(short = 't', long = "type", value_delimiter = ',')]
-    #[arg(value_parser = ["code", "tests", "examples", "docs", "comments", "blanks", "total"])]
+    #[arg(value_parser = ["code", "tests", "examples", "docs", "comments", "blanks", "preprocessor", "config", "categories", "total"])]
     #[arg(long_help = "\
 Line types to show (comma-separated).
 
 By default all types are shown. Use this to filter to specific types.
-Values: code, tests, examples, docs, comments, blanks, total
+Values: code, tests, examples, docs, comments, blanks, preprocessor, config,
+categories (the columns of user-defined --classify categories), total
 
   -t code,tests       Show only code and test lines
   -t code             Show only code lines")]
     line_types: Vec<String>,
 
-    /// Group results by crate
-    #[arg(long = "by-crate", conflicts_with_all = ["by_file", "by_module"])]
+    /// Count every Cargo workspace and standalone crate beneath the path
+    #[arg(long = "workspaces")]
+    #[arg(long_help = "\
+Find every Cargo workspace and standalone crate beneath the path and count
+them together, each with its own crate structure. Crate rows are qualified
+with their workspace (services/api:server); --crate accepts either form.
+
+  rustloc --workspaces --by-workspace   One row per workspace
+  rustloc --workspaces --by-crate       One row per crate")]
+    workspaces: bool,
+
+    /// Group results by workspace [requires --workspaces]
+    #[arg(long = "by-workspace", conflicts_with_all = ["by_crate", "by_package", "by_file", "by_module", "by_target"])]
+    by_workspace: bool,
+
+    /// Group results by crate (or by package outside a Cargo workspace)
+    #[arg(long = "by-crate", conflicts_with_all = ["by_workspace", "by_package", "by_file", "by_module", "by_target"])]
     by_crate: bool,
 
+    /// Group results by package, as --by-crate does, with package labels
+    #[arg(long = "by-package", conflicts_with_all = ["by_workspace", "by_crate", "by_file", "by_module", "by_target"])]
+    by_package: bool,
+
     /// Group results by file
-    #[arg(short = 'f', long = "by-file", conflicts_with_all = ["by_crate", "by_module"])]
+    #[arg(short = 'f', long = "by-file", conflicts_with_all = ["by_workspace", "by_crate", "by_package", "by_module", "by_target"])]
     by_file: bool,
 
     /// Group results by module
-    #[arg(short = 'm', long = "by-module", conflicts_with_all = ["by_crate", "by_file"])]
+    #[arg(short = 'm', long = "by-module", conflicts_with_all = ["by_workspace", "by_crate", "by_package", "by_file", "by_target"])]
     by_module: bool,
 
+    /// Group results by Cargo target (lib, bins, tests, examples, benches, build script)
+    #[arg(long = "by-target", conflicts_with_all = ["by_workspace", "by_crate", "by_package", "by_file", "by_module"])]
+    by_target: bool,
+
     /// Sort by field [-o FIELD, prefix - for desc: -o -code]
     // `allow_hyphen_values` keeps `-o -code` from being read as a flag;
     // `value_parser` makes an unknown field a clap usage error at parse time
@@ -210,7 +410,7 @@ Values: code, tests, examples, docs, comments, blanks, total
     #[arg(long_help = "\
 Sort by field. Prefix with - for descending, + for ascending.
 
-Fields: label, code, tests, examples, docs, comments, blanks, total
+Fields: label, code, tests, examples, docs, comments, blanks, preprocessor, config, total
 Default direction: descending for numeric fields, ascending for label.
 
   -o code         Sort by code lines (descending)
@@ -257,7 +457,7 @@ the resolved hash from `git rev-parse` if you need them.")]
     #[arg(long = "staged", visible_alias = "cached")]
     staged: bool,
 
-    /// Only count specific crate(s)
+    /// Only count specific crate(s) or package(s)
     #[arg(short = 'c', long = "crate", action = clap::ArgAction::Append)]
     crates: Vec<String>,
 
@@ -269,12 +469,18 @@ the resolved hash from `git rev-parse` if you need them.")]
 Language backend groups to analyze.
 
 Default: rust
-Available: rust, python, typescript, generic
+Available: rust, python, typescript, jvm, cpp, zig, ruby, markdown, config, generic
 
   -l python            Analyze Python file changes only
   -l rust,python       Analyze Rust and Python file changes
   -l typescript        Analyze TypeScript file changes only
   -l rust,typescript   Analyze Rust and TypeScript file changes
+  -l jvm               Analyze Java and Kotlin file changes only
+  -l cpp               Analyze C and C++ file changes only
+  -l zig               Analyze Zig file changes only
+  -l ruby              Analyze Ruby file changes only
+  -l rust,markdown     Analyze Rust and Markdown documentation changes
+  -l config            Analyze TOML, YAML, JSON, INI and SQL file changes only
   -l all               Analyze all available backend groups")]
     languages: Vec<String>,
 
@@ -286,31 +492,93 @@ Available: rust, python, typescript, generic
     #[arg(short = 'e', long = "exclude", action = clap::ArgAction::Append)]
     exclude: Vec<String>,
 
+    /// Skip files larger than SIZE bytes [--max-file-size 1M]
+    #[arg(long = "max-file-size", value_name = "SIZE", value_parser = command::parse_file_size)]
+    #[arg(long_help = MAX_FILE_SIZE_HELP)]
+    max_file_size: Option<u64>,
+
+    /// Fail on the first unreadable or unparsable file
+    #[arg(long = "strict")]
+    #[arg(long_help = STRICT_HELP)]
+    strict: bool,
+
+    /// Count files matching a glob as code, tests, examples or a category [--classify 'demos/**=examples']
+    #[arg(long = "classify", value_name = "GLOB=CONTEXT", action = clap::ArgAction::Append)]
+    #[arg(long_help = CLASSIFY_HELP)]
+    classify: Vec<String>,
+
+    /// Discover the workspace with `cargo metadata` instead of reading Cargo.toml
+    #[arg(long = "cargo-metadata")]
+    cargo_metadata: bool,
+
+    /// Show per-file detail below the table, such as content-detected languages and transcoded encodings
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+
+    /// Language definitions file [default: .rustloc.toml in the analyzed path]
+    #[arg(long = "config", value_name = "FILE")]
+    #[arg(long_help = "\
+TOML file declaring extra languages, each selectable with --lang by name.
+Defaults to .rustloc.toml in the analyzed path (the repository for diff) when
+that file exists.
+
+  [[language]]
+  name = \"terraform\"
+  extensions = [\"tf\", \"tfvars\"]
+  line_comments = [\"#\", \"//\"]
+  block_comments = [[\"/*\", \"*/\"]]
+  test_paths = [\"**/tests/**\"]
+
+Other keys: filenames, shebangs, doc_comments, nested_comments, quotes,
+example_paths.
+
+[[plugin]] tables (name, extensions, filenames, command, args, timeout)
+delegate a language to an external program that answers with per-line classes
+as JSON. Plugins run only when named with --lang, and a discovered
+.rustloc.toml runs none unless --allow-plugins is given.
+
+[[classify]] tables (path, context) count matching files as code, tests or
+examples; see --classify.")]
+    config: Option<String>,
+
+    /// Run [[plugin]] commands from a discovered .rustloc.toml
+    #[arg(long = "allow-plugins")]
+    allow_plugins: bool,
+
     /// Line types to show (comma-separated)
     #[arg(short = 't', long = "type", value_delimiter = ',')]
-    #[arg(value_parser = ["code", "tests", "examples", "docs", "comments", "blanks", "total"])]
+    #[arg(value_parser = ["code", "tests", "examples", "docs", "comments", "blanks", "preprocessor", "config", "categories", "total"])]
     #[arg(long_help = "\
 Line types to show (comma-separated).
 
 By default all types are shown. Use this to filter to specific types.
-Values: code, tests, examples, docs, comments, blanks, total
+Values: code, tests, examples, docs, comments, blanks, preprocessor, config,
+categories (the columns of user-defined --classify categories), total
 
   -t code,tests       Show only code and test lines
   -t code             Show only code lines")]
     line_types: Vec<String>,
 
-    /// Group results by crate
-    #[arg(long = "by-crate", conflicts_with_all = ["by_file", "by_module"])]
+    /// Group results by crate (or by package outside a Cargo workspace)
+    #[arg(long = "by-crate", conflicts_with_all = ["by_package", "by_file", "by_module", "by_target"])]
     by_crate: bool,
 
+    /// Group results by package, as --by-crate does, with package labels
+    #[arg(long = "by-package", conflicts_with_all = ["by_crate", "by_file", "by_module", "by_target"])]
+    by_package: bool,
+
     /// Group results by file
-    #[arg(short = 'f', long = "by-file", conflicts_with_all = ["by_crate", "by_module"])]
+    #[arg(short = 'f', long = "by-file", conflicts_with_all = ["by_crate", "by_package", "by_module", "by_target"])]
     by_file: bool,
 
     /// Group results by module
-    #[arg(short = 'm', long = "by-module", conflicts_with_all = ["by_crate", "by_file"])]
+    #[arg(short = 'm', long = "by-module", conflicts_with_all = ["by_crate", "by_package", "by_file", "by_target"])]
     by_module: bool,
 
+    /// Group results by Cargo target (lib, bins, tests, examples, benches, build script)
+    #[arg(long = "by-target", conflicts_with_all = ["by_crate", "by_package", "by_file", "by_module"])]
+    by_target: bool,
+
     /// Sort by field [-o FIELD, prefix - for desc: -o -code]
     // `allow_hyphen_values` keeps `-o -code` from being read as a flag;
     // `value_parser` makes an unknown field a clap usage error at parse time
@@ -325,7 +593,7 @@ Values: code, tests, examples, docs, comments, blanks, total
     #[arg(long_help = "\
 Sort by field. Prefix with - for descending, + for ascending.
 
-Fields: label, code, tests, examples, docs, comments, blanks, total
+Fields: label, code, tests, examples, docs, comments, blanks, preprocessor, config, total
 Default direction: descending for numeric fields, ascending for label.
 
   -o code         Sort by code lines (descending)
@@ -346,6 +614,90 @@ truncated slice. No-op when no `--by-*` aggregation is in effect.")]
     top: Option<usize>,
 }
 
+/// Arguments for deps command
+#[derive(Args, Clone)]
+struct DepsArgs {
+    /// Workspace whose dependencies are counted
+    #[arg(default_value = ".")]
+    path: String,
+
+    /// Only count dependencies of specific member crate(s)
+    #[arg(short = 'c', long = "crate", action = clap::ArgAction::Append)]
+    crates: Vec<String>,
+
+    /// Dependency kinds to follow from the members [default: normal]
+    #[arg(short = 'k', long = "kind", value_delimiter = ',', action = clap::ArgAction::Append)]
+    #[arg(value_parser = ["normal", "dev", "build"])]
+    #[arg(long_help = "\
+Dependency kinds to follow from the workspace members (comma-separated).
+
+Kinds select the members' own edges; dependencies of dependencies are always
+followed through their normal and build dependencies.
+
+  -k normal           [dependencies] only (default)
+  -k normal,dev       Also [dev-dependencies]
+  -k build            Only [build-dependencies]")]
+    kinds: Vec<String>,
+
+    /// Only count direct dependencies, not their dependencies
+    #[arg(long = "direct")]
+    direct: bool,
+
+    /// Language backend(s) to analyze [-l rust,cpp]
+    #[arg(short = 'l', long = "lang", value_delimiter = ',', action = clap::ArgAction::Append)]
+    languages: Vec<String>,
+
+    /// Only include files matching a glob
+    #[arg(short = 'i', long = "include", action = clap::ArgAction::Append)]
+    include: Vec<String>,
+
+    /// Exclude files matching a glob
+    #[arg(short = 'e', long = "exclude", action = clap::ArgAction::Append)]
+    exclude: Vec<String>,
+
+    /// Skip files larger than SIZE bytes [--max-file-size 1M]
+    #[arg(long = "max-file-size", value_name = "SIZE", value_parser = command::parse_file_size)]
+    #[arg(long_help = MAX_FILE_SIZE_HELP)]
+    max_file_size: Option<u64>,
+
+    /// Fail on the first unreadable or unparsable file
+    #[arg(long = "strict")]
+    #[arg(long_help = STRICT_HELP)]
+    strict: bool,
+
+    /// Count files matching a glob as code, tests, examples or a category [--classify 'demos/**=examples']
+    #[arg(long = "classify", value_name = "GLOB=CONTEXT", action = clap::ArgAction::Append)]
+    #[arg(long_help = CLASSIFY_HELP)]
+    classify: Vec<String>,
+
+    /// Language definitions file [default: .rustloc.toml in the workspace]
+    #[arg(long = "config", value_name = "FILE")]
+    config: Option<String>,
+
+    /// Run [[plugin]] commands from a discovered .rustloc.toml
+    #[arg(long = "allow-plugins")]
+    allow_plugins: bool,
+
+    /// Line types to show (comma-separated)
+    #[arg(short = 't', long = "type", value_delimiter = ',')]
+    #[arg(value_parser = ["code", "tests", "examples", "docs", "comments", "blanks", "preprocessor", "config", "categories", "total"])]
+    line_types: Vec<String>,
+
+    /// Sort by field [-o FIELD, prefix - for desc: -o -code]
+    #[arg(
+        short = 'o',
+        long = "ordering",
+        value_name = "FIELD",
+        allow_hyphen_values = true,
+        value_parser = command::parse_ordering
+    )]
+    ordering: Option<Ordering>,
+
+    /// Show only the top N dependencies after sorting
+    #[arg(long = "top", value_name = "N")]
+    top: Option<usize>,
+}
+
 /// Command handlers — the dispatch boundary.
 ///
 /// Handlers are deliberately **thin**: each converts `ArgMatches` into a typed
@@ -363,7 +715,7 @@ truncated slice. No-op when no `--by-*` aggregation is in effect.")]
 ///
 /// Standout's `#[handler]` macro would normally generate this bridge from typed
 /// parameters, but it maps one parameter per named clap arg, and the count/diff
-/// grammar includes the 42 dynamically registered `--<field>-<op>` filter flags
+/// grammar includes the 54 dynamically registered `--<field>-<op>` filter flags
 /// (see [`super::filter_args`]) that no fixed parameter list can express. Its
 /// `#[matches]` escape hatch would hand the raw matches back to the handler
 /// anyway, so we keep the plain dispatch signature and put the typed seam in
@@ -371,7 +723,7 @@ truncated slice. No-op when no `--by-*` aggregation is in effect.")]
 /// cannot cover the grammar.
 mod handlers {
     use crate::application;
-    use crate::command::{CountRequest, DiffRequest};
+    use crate::command::{CountRequest, DepsRequest, DiffRequest};
     use clap::ArgMatches;
     use rustloclib::{CountQuerySet, DiffQuerySet};
     use standout::cli::{CommandContext, HandlerResult, Output};
@@ -387,6 +739,12 @@ mod handlers {
         let request = DiffRequest::from_matches(matches)?;
         Ok(Output::Render(application::diff(&request)?))
     }
+
+    /// Handler for the deps command.
+    pub fn deps(matches: &ArgMatches, _ctx: &CommandContext) -> HandlerResult<CountQuerySet> {
+        let request = DepsRequest::from_matches(matches)?;
+        Ok(Output::Render(application::deps(&request)?))
+    }
 }
 
 /// Presentation adapters — the render boundary.
@@ -420,6 +778,7 @@ mod presentation {
     use serde::{Deserialize, Serialize};
     use serde_json::Value;
     use standout::cli::{CommandContext, HookError};
+    use std::collections::BTreeMap;
 
     /// How the canonical response should reach the user.
     enum Target {
@@ -446,6 +805,21 @@ mod presentation {
         }
     }
 
+    /// Whether `--verbose` was given. `deps` shares the count table but has
+    /// no per-file rows, and so no such flag.
+    fn verbose(matches: &ArgMatches) -> bool {
+        matches!(matches.try_get_one::<bool>("verbose"), Ok(Some(true)))
+    }
+
+    /// The table's aggregation key: `--by-package` asks for the `--by-crate`
+    /// query, but its rows are labelled as packages.
+    fn aggregation(matches: &ArgMatches, key: &'static str) -> &'static str {
+        match matches.try_get_one::<bool>("by_package") {
+            Ok(Some(true)) => "package",
+            _ => key,
+        }
+    }
+
     fn decode<T: for<'de> Deserialize<'de>>(data: Value) -> Result<T, HookError> {
         serde_json::from_value(data).map_err(|e| {
             HookError::post_dispatch(format!("canonical response was not well-formed: {e}"))
@@ -464,6 +838,9 @@ mod presentation {
     /// a display filter for the human table; narrowing CSV columns to match it
     /// would make the schema vary per invocation, which is exactly what a
     /// machine-readable format must not do.
+    ///
+    /// User-defined categories add one column each, named after the category
+    /// and present on every row, as the classification rules in effect decide.
     #[derive(Serialize)]
     struct CountCsvRow {
         label: String,
@@ -473,11 +850,15 @@ mod presentation {
         docs: u64,
         comments: u64,
         blanks: u64,
+        preprocessor: u64,
+        config: u64,
+        #[serde(flatten)]
+        categories: BTreeMap<String, u64>,
         total: u64,
     }
 
     impl CountCsvRow {
-        fn new(label: impl Into<String>, stats: &Locs) -> Self {
+        fn new(label: impl Into<String>, stats: &Locs, categories: &[String]) -> Self {
             Self {
                 label: label.into(),
                 code: stats.code,
@@ -486,6 +867,12 @@ mod presentation {
                 docs: stats.docs,
                 comments: stats.comments,
                 blanks: stats.blanks,
+                preprocessor: stats.preprocessor,
+                config: stats.config,
+                categories: categories
+                    .iter()
+                    .map(|name| (name.clone(), stats.category(name)))
+                    .collect(),
                 total: stats.total,
             }
         }
@@ -495,7 +882,8 @@ mod presentation {
     /// every line type. Same stability contract as [`CountCsvRow`].
     ///
     /// `net_*` is `i64` so a net removal reads `-42` rather than underflowing
-    /// into a very large positive number.
+    /// into a very large positive number. A user-defined category adds
+    /// `added_<name>`, `removed_<name>` and `net_<name>` columns.
     #[derive(Serialize)]
     struct DiffCsvRow {
         label: String,
@@ -505,6 +893,8 @@ mod presentation {
         added_docs: u64,
         added_comments: u64,
         added_blanks: u64,
+        added_preprocessor: u64,
+        added_config: u64,
         added_total: u64,
         removed_code: u64,
         removed_tests: u64,
@@ -512,6 +902,8 @@ mod presentation {
         removed_docs: u64,
         removed_comments: u64,
         removed_blanks: u64,
+        removed_preprocessor: u64,
+        removed_config: u64,
         removed_total: u64,
         net_code: i64,
         net_tests: i64,
@@ -519,11 +911,26 @@ mod presentation {
         net_docs: i64,
         net_comments: i64,
         net_blanks: i64,
+        net_preprocessor: i64,
+        net_config: i64,
+        #[serde(flatten)]
+        categories: BTreeMap<String, i64>,
         net_total: i64,
     }
 
     impl DiffCsvRow {
-        fn new(label: impl Into<String>, d: &LocsDiff) -> Self {
+        fn new(label: impl Into<String>, d: &LocsDiff, categories: &[String]) -> Self {
+            let mut columns = BTreeMap::new();
+            for name in categories {
+                let (added, removed) = (d.added.category(name), d.removed.category(name));
+                let count = |lines: u64| i64::try_from(lines).unwrap_or(i64::MAX);
+                columns.insert(format!("added_{name}"), count(added));
+                columns.insert(format!("removed_{name}"), count(removed));
+                columns.insert(
+                    format!("net_{name}"),
+                    rustloclib::sat_sub_u64(added, removed),
+                );
+            }
             Self {
                 label: label.into(),
                 added_code: d.added.code,
@@ -532,6 +939,8 @@ mod presentation {
                 added_docs: d.added.docs,
                 added_comments: d.added.comments,
                 added_blanks: d.added.blanks,
+                added_preprocessor: d.added.preprocessor,
+                added_config: d.added.config,
                 added_total: d.added.total,
                 removed_code: d.removed.code,
                 removed_tests: d.removed.tests,
@@ -539,6 +948,8 @@ mod presentation {
                 removed_docs: d.removed.docs,
                 removed_comments: d.removed.comments,
                 removed_blanks: d.removed.blanks,
+                removed_preprocessor: d.removed.preprocessor,
+                removed_config: d.removed.config,
                 removed_total: d.removed.total,
                 net_code: d.net_code(),
                 net_tests: d.net_tests(),
@@ -546,6 +957,9 @@ mod presentation {
                 net_docs: d.net_docs(),
                 net_comments: d.net_comments(),
                 net_blanks: d.net_blanks(),
+                net_preprocessor: d.net_preprocessor(),
+                net_config: d.net_config(),
+                categories: columns,
                 net_total: d.net_total(),
             }
         }
@@ -553,21 +967,32 @@ mod presentation {
 
     /// One row per item, then a `TOTAL` summary row.
     fn count_csv_rows(qs: &CountQuerySet) -> Vec<CountCsvRow> {
+        let categories: Vec<String> = qs.total.categories.keys().cloned().collect();
         let mut rows: Vec<CountCsvRow> = qs
             .items
             .iter()
-            .map(|item| CountCsvRow::new(item.label.clone(), &item.stats))
+            .map(|item| CountCsvRow::new(item.label.clone(), &item.stats, &categories))
             .collect();
-        rows.push(CountCsvRow::new("TOTAL", &qs.total));
+        rows.push(CountCsvRow::new("TOTAL", &qs.total, &categories));
         rows
     }
 
     /// One row per item, an optional `SKIPPED` row, then a `TOTAL` row.
     fn diff_csv_rows(qs: &DiffQuerySet) -> Vec<DiffCsvRow> {
+        let mut categories: Vec<String> = qs
+            .total
+            .added
+            .categories
+            .keys()
+            .chain(qs.total.removed.categories.keys())
+            .cloned()
+            .collect();
+        categories.sort();
+        categories.dedup();
         let mut rows: Vec<DiffCsvRow> = qs
             .items
             .iter()
-            .map(|item| DiffCsvRow::new(item.label.clone(), &item.stats))
+            .map(|item| DiffCsvRow::new(item.label.clone(), &item.stats, &categories))
             .collect();
 
         // Preserve the skipped-file summary that the text footer and JSON
@@ -586,10 +1011,10 @@ mod presentation {
                     ..Locs::default()
                 },
             };
-            rows.push(DiffCsvRow::new("SKIPPED", &non_rust));
+            rows.push(DiffCsvRow::new("SKIPPED", &non_rust, &categories));
         }
 
-        rows.push(DiffCsvRow::new("TOTAL", &qs.total));
+        rows.push(DiffCsvRow::new("TOTAL", &qs.total, &categories));
         rows
     }
 
@@ -602,7 +1027,14 @@ mod presentation {
         match target(matches) {
             Target::Data => Ok(data),
             Target::Csv => encode(count_csv_rows(&decode::<CountQuerySet>(data)?)),
-            Target::Table => encode(CountView::from_queryset(&decode::<CountQuerySet>(data)?)),
+            Target::Table => {
+                let view = CountView::from_queryset(&decode::<CountQuerySet>(data)?);
+                encode(CountView {
+                    verbose: verbose(matches),
+                    aggregation: aggregation(matches, view.aggregation),
+                    ..view
+                })
+            }
         }
     }
 
@@ -615,14 +1047,21 @@ mod presentation {
         match target(matches) {
             Target::Data => Ok(data),
             Target::Csv => encode(diff_csv_rows(&decode::<DiffQuerySet>(data)?)),
-            Target::Table => encode(DiffView::from_queryset(&decode::<DiffQuerySet>(data)?)),
+            Target::Table => {
+                let view = DiffView::from_queryset(&decode::<DiffQuerySet>(data)?);
+                encode(DiffView {
+                    verbose: verbose(matches),
+                    aggregation: aggregation(matches, view.aggregation),
+                    ..view
+                })
+            }
         }
     }
 }
 
 /// Filter-flag generation.
 ///
-/// We support a `--<field>-<op> <N>` grid: 7 fields × 6 ops = 42 hidden args.
+/// We support a `--<field>-<op> <N>` grid: 9 fields × 6 ops = 54 hidden args.
 /// Listing each individually would clutter `--help`, so we hide them and
 /// document the synthetic pattern via `after_long_help`. clap still parses
 /// them natively, which gives us tab-completion-friendly errors and bypasses
@@ -632,14 +1071,14 @@ mod filter_args {
     use rustloclib::{Field, Op, Predicate};
     use std::sync::OnceLock;
 
-    /// (field, op, leaked-static-name) for each of the 42 (field × op) pairs.
+    /// (field, op, leaked-static-name) for each of the 54 (field × op) pairs.
     ///
     /// Computed exactly once and cached. Each name is leaked as a
     /// `&'static str` because clap's `Arg::new` / `Arg::long` want
     /// `IntoResettable<Str>` which is implemented for `&'static str` but
     /// not for `String`. Caching avoids re-leaking on repeated calls
     /// (`make_args` is invoked once per injection point: top-level + count
-    /// + diff = three calls), keeping the leak count to exactly 42.
+    /// + diff = three calls), keeping the leak count to exactly 54.
     fn flag_table() -> &'static [(Field, Op, &'static str)] {
         static TABLE: OnceLock<Vec<(Field, Op, &'static str)>> = OnceLock::new();
         TABLE.get_or_init(|| {
@@ -671,11 +1110,11 @@ mod filter_args {
     }
 
     /// Synthetic doc block describing the filter pattern. Rendered in
-    /// `--help` via `after_long_help` so users see one block instead of 42
+    /// `--help` via `after_long_help` so users see one block instead of 54
     /// individual flag lines.
     pub const SYNTHETIC_DOC: &str = "Filter options (combine with AND):\n  \
          --<category>-<op> <N>\n  \
-         Categories: code, tests, examples, docs, comments, blanks, total\n  \
+         Categories: code, tests, examples, docs, comments, blanks, preprocessor, config, total\n  \
          Operators:  gt, gte, eq, ne, lt, lte\n\
          \n\
          Examples:\n  \
@@ -697,8 +1136,8 @@ mod filter_args {
         cmd.after_long_help(combined)
     }
 
-    /// Inject the filter args + synthetic doc onto the top-level Cli, the
-    /// `count` subcommand, and the `diff` subcommand.
+    /// Inject the filter args + synthetic doc onto the top-level Cli and the
+    /// `count`, `diff` and `deps` subcommands.
     ///
     /// All three are required:
     /// - top-level: for the bare-call form (`rustloc --code-gte 100 .`)
@@ -706,8 +1145,9 @@ mod filter_args {
     ///   the flattened `CountArgs`.
     /// - count subcommand: for the explicit form (`rustloc count ...`).
     /// - diff subcommand: for the diff path.
+    /// - deps subcommand: for the dependency count.
     ///
-    /// Adding to fewer than all three breaks one or more of the call shapes.
+    /// Adding to fewer than all of them breaks one or more of the call shapes.
     pub fn inject(cmd: Command) -> Command {
         fn augment(sub: Command) -> Command {
             let mut sub = sub;
@@ -724,6 +1164,7 @@ mod filter_args {
         append_long_help(cmd, SYNTHETIC_DOC)
             .mut_subcommand("count", augment)
             .mut_subcommand("diff", augment)
+            .mut_subcommand("deps", augment)
     }
 
     /// Read all filter args back from `ArgMatches` into a flat
@@ -46,7 +46,7 @@
 //! detectors, whereas the argv-driven runs below touch no global state and are
 //! safe to run in parallel.
 
-use rustloclib::{CountQuerySet, DiffQuerySet};
+use rustloclib::{CountQuerySet, DiagnosticKind, DiffQuerySet, SkipReason};
 use serial_test::serial;
 use standout::cli::RunResult;
 use standout::{ColorMode, Theme, DEFAULT_MISSING_STYLE_INDICATOR};
@@ -234,6 +234,8 @@ fn structured_modes_carry_the_same_canonical_numbers() {
 /// total with JSON so malformed XML or a mode-specific response fails here,
 /// below the process boundary.
 #[test]
+// Newer clippy suggests a match guard here; the nested `if` reads better.
+#[allow(clippy::collapsible_match)]
 fn xml_is_well_formed_and_carries_the_canonical_total() {
     use quick_xml::events::Event;
     use quick_xml::Reader;
@@ -359,7 +361,16 @@ fn csv_renders_one_row_per_file_plus_a_total() {
 /// that drops, say, `examples` fails here rather than slipping through a
 /// looser "some headers exist" check.
 const COUNT_CSV_HEADERS: &[&str] = &[
-    "label", "code", "tests", "examples", "docs", "comments", "blanks", "total",
+    "label",
+    "code",
+    "tests",
+    "examples",
+    "docs",
+    "comments",
+    "blanks",
+    "preprocessor",
+    "config",
+    "total",
 ];
 
 fn csv_headers(out: &str) -> Vec<String> {
@@ -444,6 +455,774 @@ fn type_flag_narrows_the_table_columns() {
     assert!(!out.contains("Docs"), "Docs should be hidden in:\n{out}");
 }
 
+/// `preprocessor` is opt-in like `comments`: a C/C++ tree shows the column
+/// only when asked for, and the grid filters on it like any other line type.
+#[test]
+fn preprocessor_column_and_filters_follow_the_cpp_backend() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("main.c"),
+        "#include <stdio.h>\n#define ANSWER 42\n\nint main(void) { return ANSWER; }\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("util.c"), "int util(void) { return 0; }\n").unwrap();
+    let path = path_of(&dir);
+
+    let default_table = stdout(&[&path, "--lang", "cpp", "--output", "text"]);
+    assert!(
+        !default_table.contains("Preprocessor"),
+        "preprocessor should be opt-in in:\n{default_table}"
+    );
+    let table = stdout(&[
+        &path,
+        "--lang",
+        "cpp",
+        "--output",
+        "text",
+        "--type",
+        "code,preprocessor",
+    ]);
+    assert!(
+        table.contains("Preprocessor"),
+        "missing column in:\n{table}"
+    );
+
+    let out = stdout(&[
+        &path,
+        "--lang",
+        "c",
+        "--by-file",
+        "--preprocessor-gte",
+        "1",
+        "--output",
+        "json",
+    ]);
+    let parsed: CountQuerySet = serde_json::from_str(&out).unwrap();
+    assert_eq!(parsed.items.len(), 1, "only main.c has directives");
+    assert_eq!(parsed.items[0].stats.preprocessor, 2);
+    assert_eq!(parsed.total.preprocessor, 2);
+}
+
+/// Ignored paths are skipped during discovery; `--no-ignore` brings them back.
+#[test]
+fn ignore_files_are_honoured_unless_no_ignore() {
+    let dir = TempDir::new().unwrap();
+    std::fs::create_dir_all(dir.path().join("vendor")).unwrap();
+    std::fs::write(dir.path().join(".gitignore"), "vendor/\n").unwrap();
+    std::fs::write(dir.path().join(".rustlocignore"), "gen.rs\n").unwrap();
+    std::fs::write(dir.path().join("lib.rs"), "fn main() {}\n").unwrap();
+    std::fs::write(dir.path().join("gen.rs"), "fn gen() {}\n").unwrap();
+    std::fs::write(dir.path().join("vendor/dep.rs"), "fn dep() {}\n").unwrap();
+    let path = path_of(&dir);
+
+    let parsed: CountQuerySet =
+        serde_json::from_str(&stdout(&[&path, "--output", "json"])).unwrap();
+    assert_eq!(parsed.file_count, 1);
+
+    let parsed: CountQuerySet =
+        serde_json::from_str(&stdout(&[&path, "--no-ignore", "--output", "json"])).unwrap();
+    assert_eq!(parsed.file_count, 3);
+}
+
+/// `--tracked-only` counts the git index, not whatever is lying around.
+#[test]
+fn tracked_only_counts_files_in_the_git_index() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("lib.rs"), "fn main() {}\n").unwrap();
+    std::fs::write(dir.path().join("scratch.rs"), "fn scratch() {}\n").unwrap();
+    let path = path_of(&dir);
+
+    let message = error(&[&path, "--tracked-only"]);
+    assert!(message.contains("git repository"), "{message}");
+
+    git(dir.path(), &["init", "-q"]);
+    git(dir.path(), &["add", "lib.rs"]);
+
+    let parsed: CountQuerySet =
+        serde_json::from_str(&stdout(&[&path, "--tracked-only", "--output", "json"])).unwrap();
+    assert_eq!(parsed.file_count, 1);
+    assert_eq!(parsed.total.code, 1);
+}
+
+/// Symlinks never loop or double count; what was skipped is reported in the
+/// table and in structured output, and `--symlinks` decides about links that
+/// leave the path.
+#[cfg(unix)]
+#[test]
+fn symlinks_are_followed_once_and_reported() {
+    use std::os::unix::fs::symlink;
+
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("root");
+    let outside = dir.path().join("outside");
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::create_dir_all(&outside).unwrap();
+    std::fs::write(root.join("src/lib.rs"), "fn main() {}\n").unwrap();
+    std::fs::write(outside.join("extra.rs"), "fn extra() {}\n").unwrap();
+    symlink(root.join("src"), root.join("src/again")).unwrap();
+    symlink(root.join("src/lib.rs"), root.join("alias.rs")).unwrap();
+    symlink(&outside, root.join("outside")).unwrap();
+    let path = root.to_str().unwrap();
+
+    let parsed: CountQuerySet = serde_json::from_str(&stdout(&[path, "--output", "json"])).unwrap();
+    assert_eq!(parsed.file_count, 2);
+    assert_eq!(parsed.skipped_links.len(), 2);
+
+    let parsed: CountQuerySet =
+        serde_json::from_str(&stdout(&[path, "--symlinks", "skip", "--output", "json"])).unwrap();
+    assert_eq!(parsed.file_count, 1);
+    assert_eq!(parsed.skipped_links.len(), 3);
+
+    let table = stdout(&[path, "--output", "text"]);
+    assert!(table.contains("Skipped 2 symbolic links"), "{table}");
+
+    let message = error(&[path, "--symlinks", "error"]);
+    assert!(message.contains("leads outside the root"), "{message}");
+}
+
+/// Binary, minified and oversized files are left out of both count and diff
+/// and listed with a reason, rather than failing the run or inflating totals.
+#[test]
+fn binary_minified_and_oversized_files_are_skipped_with_reasons() {
+    let dir = TempDir::new().unwrap();
+    let p = dir.path();
+    git(p, &["init", "-q"]);
+    std::fs::write(p.join("lib.rs"), "fn main() {}\n").unwrap();
+    git(p, &["add", "."]);
+    git(p, &["commit", "-q", "-m", "init"]);
+
+    std::fs::write(p.join("blob.rs"), b"fn f() {}\n\0\x01\x02").unwrap();
+    let bundle = format!("!function(){{{}}}();\n", "var a=1;".repeat(200));
+    std::fs::write(p.join("bundle.min.js"), bundle).unwrap();
+    std::fs::write(p.join("table.rs"), "const X: u8 = 0;\n".repeat(200)).unwrap();
+    git(p, &["add", "."]);
+    git(p, &["commit", "-q", "-m", "add"]);
+    let path = path_of(&dir);
+
+    let parsed: CountQuerySet = serde_json::from_str(&stdout(&[
+        &path,
+        "--lang",
+        "rust,generic",
+        "--max-file-size",
+        "2K",
+        "--output",
+        "json",
+    ]))
+    .unwrap();
+    assert_eq!(parsed.file_count, 1);
+    let reasons: Vec<_> = parsed
+        .skipped
+        .iter()
+        .map(|file| {
+            (
+                file.path.file_name().unwrap().to_str().unwrap(),
+                file.reason,
+            )
+        })
+        .collect();
+    assert_eq!(
+        reasons,
+        [
+            ("blob.rs", SkipReason::Binary),
+            ("bundle.min.js", SkipReason::Minified),
+            ("table.rs", SkipReason::TooLarge),
+        ]
+    );
+
+    let table = stdout(&[path.as_str(), "--output", "text"]);
+    assert!(
+        table.contains("Skipped 1 binary, minified or oversized file;"),
+        "{table}"
+    );
+
+    let parsed: DiffQuerySet = serde_json::from_str(&stdout(&[
+        "diff",
+        "-p",
+        &path,
+        "HEAD~1",
+        "--by-file",
+        "--output",
+        "json",
+    ]))
+    .unwrap();
+    assert_eq!(parsed.file_count, 1);
+    assert_eq!(parsed.skipped.len(), 1);
+}
+
+/// UTF-16 and Latin-1 sources are transcoded and counted like any other file,
+/// in both count and diff; `--verbose` lists what each was decoded from.
+#[test]
+fn non_utf8_sources_are_transcoded_and_listed_when_verbose() {
+    let utf16 = |text: &str| -> Vec<u8> {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        bytes
+    };
+    let dir = TempDir::new().unwrap();
+    let p = dir.path();
+    git(p, &["init", "-q"]);
+    std::fs::write(p.join("wide.rs"), utf16("// café\nfn a() {}\n")).unwrap();
+    std::fs::write(p.join("latin.rs"), b"// caf\xe9\nfn b() {}\n").unwrap();
+    std::fs::write(p.join("lib.rs"), "fn main() {}\n").unwrap();
+    git(p, &["add", "."]);
+    git(p, &["commit", "-q", "-m", "init"]);
+    let path = path_of(&dir);
+
+    let parsed: CountQuerySet =
+        serde_json::from_str(&stdout(&[&path, "--by-file", "--output", "json"])).unwrap();
+    let encodings: Vec<_> = parsed
+        .items
+        .iter()
+        .map(|item| (item.label.as_str(), item.encoding.map(|e| e.name())))
+        .collect();
+    assert_eq!(
+        encodings,
+        [
+            ("latin.rs", Some("windows-1252")),
+            ("lib.rs", None),
+            ("wide.rs", Some("utf-16le")),
+        ]
+    );
+    assert_eq!(parsed.total.code, 3);
+    assert_eq!(parsed.total.comments, 2);
+
+    let quiet = stdout(&[path.as_str(), "--by-file", "--output", "text"]);
+    assert!(!quiet.contains("Transcoded"), "{quiet}");
+    let verbose = stdout(&[path.as_str(), "--by-file", "--verbose", "--output", "text"]);
+    assert!(verbose.contains("  wide.rs  utf-16le"), "{verbose}");
+    assert!(verbose.contains("  latin.rs  windows-1252"), "{verbose}");
+    let total = stdout(&[path.as_str(), "--verbose", "--output", "text"]);
+    assert!(total.contains("  wide.rs  utf-16le"), "{total}");
+
+    std::fs::write(p.join("wide.rs"), utf16("// café\nfn a() {}\nfn c() {}\n")).unwrap();
+    let total = stdout(&["diff", "-p", &path, "--verbose", "--output", "text"]);
+    assert!(total.contains("  wide.rs  utf-16le"), "{total}");
+    let parsed: DiffQuerySet = serde_json::from_str(&stdout(&[
+        "diff",
+        "-p",
+        &path,
+        "--by-file",
+        "--output",
+        "json",
+    ]))
+    .unwrap();
+    assert_eq!(parsed.items.len(), 1);
+    assert_eq!(parsed.items[0].encoding.map(|e| e.name()), Some("utf-16le"));
+    assert_eq!(parsed.total.added.code, 1);
+}
+
+/// Files without a known extension are detected by their shebang in diff as
+/// in count, and `--verbose` lists how they were detected.
+#[test]
+fn shebang_scripts_are_diffed_and_their_detection_listed_when_verbose() {
+    let dir = TempDir::new().unwrap();
+    let p = dir.path();
+    git(p, &["init", "-q"]);
+    std::fs::write(p.join("tool"), "#!/usr/bin/env python3\nprint(1)\n").unwrap();
+    std::fs::write(p.join("lib.rs"), "fn main() {}\n").unwrap();
+    git(p, &["add", "."]);
+    git(p, &["commit", "-q", "-m", "init"]);
+    std::fs::write(
+        p.join("tool"),
+        "#!/usr/bin/env python3\n# note\nprint(1)\nprint(2)\n",
+    )
+    .unwrap();
+    let path = path_of(&dir);
+
+    let args = ["diff", "-p", &path, "--lang", "python", "--by-file"];
+    let parsed: DiffQuerySet =
+        serde_json::from_str(&stdout(&[&args[..], &["--output", "json"]].concat())).unwrap();
+    let [item] = parsed.items.as_slice() else {
+        panic!("expected one changed file: {:?}", parsed.items);
+    };
+    assert_eq!(item.label, "tool");
+    let detection = item.detection.as_ref().unwrap();
+    assert_eq!(
+        (detection.backend.as_str(), detection.reason.name()),
+        ("python", "shebang")
+    );
+    assert_eq!(parsed.total.added.code, 1);
+    assert_eq!(parsed.total.added.comments, 1);
+
+    let verbose = stdout(&[&args[..], &["--verbose", "--output", "text"]].concat());
+    assert!(verbose.contains("  tool  python (shebang)"), "{verbose}");
+    let count = stdout(&[
+        &path,
+        "--lang",
+        "python",
+        "--by-file",
+        "-v",
+        "--output",
+        "text",
+    ]);
+    assert!(count.contains("  tool  python (shebang)"), "{count}");
+}
+
+/// Python modules of a src-layout distribution get the same import names
+/// when counted and when diffed.
+#[test]
+fn python_module_names_agree_between_count_and_diff_in_a_src_layout() {
+    let dir = TempDir::new().unwrap();
+    let p = dir.path();
+    git(p, &["init", "-q"]);
+    std::fs::write(p.join("pyproject.toml"), "[project]\nname = \"acme\"\n").unwrap();
+    std::fs::create_dir_all(p.join("src/acme/io")).unwrap();
+    std::fs::write(p.join("src/acme/__init__.py"), "x = 1\n").unwrap();
+    std::fs::write(p.join("src/acme/io/disk.py"), "def read():\n    pass\n").unwrap();
+    git(p, &["add", "."]);
+    git(p, &["commit", "-q", "-m", "init"]);
+    std::fs::write(p.join("src/acme/__init__.py"), "x = 1\ny = 2\n").unwrap();
+    std::fs::write(
+        p.join("src/acme/io/disk.py"),
+        "def read():\n    pass\n\ndef write():\n    pass\n",
+    )
+    .unwrap();
+    let path = path_of(&dir);
+
+    let labels = |args: &[&str]| -> Vec<String> {
+        let out = stdout(
+            &[
+                args,
+                &["--lang", "python", "--by-module", "--output", "json"],
+            ]
+            .concat(),
+        );
+        let parsed: serde_json::Value = serde_json::from_str(&out).unwrap();
+        let mut labels: Vec<String> = parsed["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["label"].as_str().unwrap().to_string())
+            .collect();
+        labels.sort();
+        labels
+    };
+    let counted = labels(&[&path]);
+    assert_eq!(counted, ["acme", "acme.io.disk"]);
+    assert_eq!(labels(&["diff", "-p", &path]), counted);
+}
+
+/// A file the parser rejects is still counted, and reported with where it
+/// failed; `--strict` turns it back into an error.
+#[test]
+fn parse_failures_are_diagnostics_unless_strict() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("ok.py"), "import os\n").unwrap();
+    std::fs::write(dir.path().join("broken.py"), "x = 1\ndef broken(\n").unwrap();
+    let path = path_of(&dir);
+
+    let parsed: CountQuerySet =
+        serde_json::from_str(&stdout(&[&path, "--lang", "python", "--output", "json"])).unwrap();
+    assert_eq!(parsed.file_count, 2);
+    let [diagnostic] = parsed.diagnostics.as_slice() else {
+        panic!("expected one diagnostic: {:?}", parsed.diagnostics);
+    };
+    assert_eq!(diagnostic.kind, DiagnosticKind::ParseError);
+    assert!(diagnostic.path.ends_with("broken.py"));
+    assert_eq!(diagnostic.line, Some(3));
+    assert_eq!(diagnostic.fallback.as_deref(), Some("line-level"));
+
+    let table = stdout(&[path.as_str(), "--lang", "python", "--output", "text"]);
+    assert!(
+        table.contains("Diagnostics: 1 parse error counted with a fallback;"),
+        "{table}"
+    );
+
+    let message = error(&[path.as_str(), "--lang", "python", "--strict"]);
+    assert!(message.contains("failed to parse"), "{message}");
+}
+
+/// A workspace member whose manifest does not parse is reported rather than
+/// silently dropped from the counts; `--strict` turns it into an error.
+#[test]
+fn unparsable_member_manifests_are_diagnostics_unless_strict() {
+    let dir = TempDir::new().unwrap();
+    let p = dir.path();
+    std::fs::write(
+        p.join("Cargo.toml"),
+        "[workspace]\nmembers = [\"crates/*\"]\n",
+    )
+    .unwrap();
+    for (name, manifest) in [
+        (
+            "good",
+            "[package]\nname = \"good\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        ),
+        ("broken", "[package\n"),
+    ] {
+        std::fs::create_dir_all(p.join("crates").join(name).join("src")).unwrap();
+        std::fs::write(p.join("crates").join(name).join("Cargo.toml"), manifest).unwrap();
+        std::fs::write(
+            p.join("crates").join(name).join("src/lib.rs"),
+            "pub fn f() {}\n",
+        )
+        .unwrap();
+    }
+    let path = path_of(&dir);
+
+    let parsed: CountQuerySet =
+        serde_json::from_str(&stdout(&[&path, "--by-crate", "--output", "json"])).unwrap();
+    let labels: Vec<&str> = parsed.items.iter().map(|i| i.label.as_str()).collect();
+    assert_eq!(labels, ["good"]);
+    let [diagnostic] = parsed.diagnostics.as_slice() else {
+        panic!("expected one diagnostic: {:?}", parsed.diagnostics);
+    };
+    assert!(diagnostic.path.ends_with("crates/broken/Cargo.toml"));
+
+    let message = error(&[path.as_str(), "--strict"]);
+    assert!(message.contains("Cargo.toml"), "{message}");
+}
+
+/// `--classify` and `[[classify]]` tables decide which files are tests or
+/// examples; command-line rules are tried first, and globs are anchored at
+/// the analyzed path unless they start with `**/`.
+#[test]
+fn classify_rules_from_the_command_line_and_config_set_file_contexts() {
+    let dir = TempDir::new().unwrap();
+    std::fs::create_dir_all(dir.path().join("src/testing")).unwrap();
+    std::fs::create_dir_all(dir.path().join("demos")).unwrap();
+    std::fs::create_dir_all(dir.path().join("tools/demos")).unwrap();
+    std::fs::write(dir.path().join("tools/demos/tool.rs"), "fn main() {}\n").unwrap();
+    std::fs::write(dir.path().join("src/lib.rs"), "pub fn f() {}\n").unwrap();
+    std::fs::write(
+        dir.path().join("src/testing/helpers.rs"),
+        "pub fn fixture() {}\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("demos/app.rs"), "fn main() {}\n").unwrap();
+    std::fs::write(
+        dir.path().join(".rustloc.toml"),
+        "[[classify]]\npath = \"demos/**\"\ncontext = \"examples\"\n",
+    )
+    .unwrap();
+    let path = path_of(&dir);
+    let count = |extra: &[&str]| -> CountQuerySet {
+        let mut args = vec![path.as_str(), "--output", "json"];
+        args.extend_from_slice(extra);
+        serde_json::from_str(&stdout(&args)).unwrap()
+    };
+
+    let parsed = count(&[]);
+    assert_eq!(
+        (parsed.total.code, parsed.total.tests, parsed.total.examples),
+        (3, 0, 1)
+    );
+
+    let parsed = count(&["--classify", "src/testing/**=tests"]);
+    assert_eq!(
+        (parsed.total.code, parsed.total.tests, parsed.total.examples),
+        (2, 1, 1)
+    );
+
+    let parsed = count(&["--classify", "demos/**=code"]);
+    assert_eq!((parsed.total.code, parsed.total.examples), (4, 0));
+
+    let parsed = count(&["--classify", "**/demos/**=examples"]);
+    assert_eq!((parsed.total.code, parsed.total.examples), (2, 2));
+
+    // Any other context names a category, counted in a column of its own,
+    // and a `*` stays within one directory.
+    let parsed = count(&["--classify", "*/demos/**=tooling"]);
+    assert_eq!(
+        (parsed.total.code, parsed.total.category("tooling")),
+        (2, 1)
+    );
+    let parsed = count(&["--classify", "tools/*.rs=tooling"]);
+    assert_eq!(parsed.total.category("tooling"), 0);
+    let csv = stdout(&[
+        path.as_str(),
+        "--classify",
+        "tools/**=tooling",
+        "--output",
+        "csv",
+    ]);
+    assert!(
+        csv.starts_with(
+            "blanks,code,comments,config,docs,examples,label,preprocessor,tests,tooling,total\n"
+        ),
+        "{csv}"
+    );
+    let table = stdout(&[
+        path.as_str(),
+        "--classify",
+        "tools/**=tooling",
+        "--output",
+        "text",
+    ]);
+    assert!(table.contains("Tooling"), "{table}");
+
+    // A line type is not a category, so the rule is an error, not silently code.
+    let message = error(&[path.as_str(), "--classify", "demos/**=docs"]);
+    assert!(
+        message.contains("demos/**=docs") && message.contains("line type"),
+        "{message}"
+    );
+}
+
+/// A rule wins over the kind of the Cargo target holding the file, so shared
+/// integration-test helpers can be counted as code, in count and diff alike.
+#[test]
+fn classify_rules_override_cargo_target_kinds() {
+    let dir = workspace();
+    let p = dir.path();
+    std::fs::create_dir_all(p.join("tests/common")).unwrap();
+    std::fs::write(p.join("tests/common/mod.rs"), "pub fn setup() {}\n").unwrap();
+    std::fs::write(
+        p.join("tests/it.rs"),
+        "mod common;\n\n#[test]\nfn works() {\n    common::setup();\n}\n",
+    )
+    .unwrap();
+    git(p, &["init", "-q"]);
+    git(p, &["add", "."]);
+    git(p, &["commit", "-q", "-m", "init"]);
+    let path = path_of(&dir);
+    let rule = ["--classify", "tests/common/**=code"];
+
+    let count = |extra: &[&str]| -> CountQuerySet {
+        serde_json::from_str(&stdout(
+            &[&[path.as_str(), "--output", "json"], extra].concat(),
+        ))
+        .unwrap()
+    };
+    assert_eq!(count(&[]).total.code, 4);
+    let classified = count(&rule);
+    assert_eq!(classified.total.code, 5);
+    assert_eq!(classified.total.tests, count(&[]).total.tests - 1);
+
+    std::fs::write(
+        p.join("tests/common/mod.rs"),
+        "pub fn setup() {}\npub fn teardown() {}\n",
+    )
+    .unwrap();
+    let diff = |extra: &[&str]| -> DiffQuerySet {
+        serde_json::from_str(&stdout(
+            &[&["diff", "-p", path.as_str(), "--output", "json"], extra].concat(),
+        ))
+        .unwrap()
+    };
+    assert_eq!(diff(&[]).total.added.tests, 1);
+    let classified = diff(&rule);
+    assert_eq!(
+        (classified.total.added.code, classified.total.added.tests),
+        (1, 0)
+    );
+    let categorized = diff(&["--classify", "tests/common/**=helpers"]);
+    assert_eq!(
+        (
+            categorized.total.added.code,
+            categorized.total.added.category("helpers")
+        ),
+        (0, 1)
+    );
+}
+
+/// `--lang rust,markdown` puts documentation effort next to code: prose is
+/// docs, fenced blocks are examples, and front matter is the opt-in `config`
+/// column.
+#[test]
+fn markdown_backend_counts_prose_examples_and_front_matter() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("guide.md"),
+        "---\ntitle: Guide\n---\n# Guide\n\n```rust\nlet x = 1;\n```\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("lib.rs"), "fn main() {}\n").unwrap();
+    let path = path_of(&dir);
+
+    let rust_only: CountQuerySet =
+        serde_json::from_str(&stdout(&[&path, "--output", "json"])).unwrap();
+    assert_eq!(rust_only.total.total, 1, "markdown should be opt-in");
+
+    let out = stdout(&[&path, "--lang", "rust,md", "--by-file", "--output", "json"]);
+    let parsed: CountQuerySet = serde_json::from_str(&out).unwrap();
+    assert_eq!(parsed.items.len(), 2);
+    assert_eq!(parsed.total.code, 1);
+    assert_eq!(parsed.total.config, 3);
+    assert_eq!(parsed.total.docs, 3);
+    assert_eq!(parsed.total.examples, 1);
+    assert_eq!(parsed.total.blanks, 1);
+
+    let table = stdout(&[
+        &path,
+        "--lang",
+        "rust,markdown",
+        "--output",
+        "text",
+        "--type",
+        "code,docs,config",
+    ]);
+    assert!(table.contains("Config"), "missing column in:\n{table}");
+}
+
+/// `--lang config` tracks manifests, CI files and migrations in the `config`
+/// column without touching the code numbers.
+#[test]
+fn config_backend_keeps_data_files_out_of_code() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("pyproject.toml"),
+        "[project]\nname = \"demo\"\n",
+    )
+    .unwrap();
+    std::fs::create_dir_all(dir.path().join("migrations")).unwrap();
+    std::fs::write(
+        dir.path().join("migrations/0001.sql"),
+        "-- init\nCREATE TABLE t (id INT);\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("lib.rs"), "fn main() {}\n").unwrap();
+    let path = path_of(&dir);
+
+    let out = stdout(&[&path, "--lang", "rust,config", "--output", "json"]);
+    let parsed: CountQuerySet = serde_json::from_str(&out).unwrap();
+    assert_eq!(parsed.file_count, 3);
+    assert_eq!(parsed.total.code, 1);
+    assert_eq!(parsed.total.config, 3);
+    assert_eq!(parsed.total.comments, 1);
+}
+
+/// In a Cargo project `--lang config` finds the manifest and CI workflows,
+/// and the default table shows their column.
+#[test]
+fn config_backend_counts_manifests_and_workflows_of_a_workspace() {
+    let dir = workspace();
+    std::fs::create_dir_all(dir.path().join(".github/workflows")).unwrap();
+    std::fs::write(
+        dir.path().join(".github/workflows/ci.yml"),
+        "on: push\njobs: {}\n",
+    )
+    .unwrap();
+    let path = path_of(&dir);
+
+    let out = stdout(&[&path, "--lang", "config", "--output", "json"]);
+    let parsed: CountQuerySet = serde_json::from_str(&out).unwrap();
+    assert_eq!(parsed.file_count, 2);
+    assert_eq!(parsed.total.config, 6);
+
+    let table = stdout(&[&path, "--lang", "config"]);
+    assert!(table.contains("Config"), "missing column in:\n{table}");
+}
+
+/// `.github` is walked for config files only: scripts and action packages
+/// in it stay out of other languages' counts and of package discovery.
+#[test]
+fn ci_dir_is_left_to_the_config_backend() {
+    let dir = TempDir::new().unwrap();
+    let write = |file: &str, content: &str| {
+        let path = dir.path().join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    };
+    write("app.py", "print(1)\n");
+    write("web/package.json", "{\"name\": \"web\"}\n");
+    write("web/index.ts", "export const x = 1;\n");
+    write(".github/workflows/ci.yml", "on: push\n");
+    write(".github/scripts/release.py", "print(2)\n");
+    write(
+        ".github/actions/x/package.json",
+        "{\"name\": \"ci-action\"}\n",
+    );
+    write(".github/actions/x/index.ts", "export const y = 2;\n");
+    let path = path_of(&dir);
+    let labels = |args: &[&str]| -> Vec<String> {
+        let out = stdout(&[&[path.as_str()], args, &["--output", "json"]].concat());
+        let parsed: CountQuerySet = serde_json::from_str(&out).unwrap();
+        parsed.items.into_iter().map(|item| item.label).collect()
+    };
+
+    assert_eq!(labels(&["--lang", "python", "--by-file"]), ["app.py"]);
+    assert_eq!(
+        labels(&["--lang", "python,config", "--by-file"]),
+        [
+            ".github/actions/x/package.json",
+            ".github/workflows/ci.yml",
+            "app.py",
+            "web/package.json"
+        ]
+    );
+    let packages = labels(&["--lang", "all", "--by-crate"]);
+    assert!(
+        !packages.iter().any(|label| label == "ci-action"),
+        "{packages:?}"
+    );
+    assert!(packages.iter().any(|label| label == "web"), "{packages:?}");
+}
+
+#[test]
+fn user_defined_languages_load_from_the_project_config() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join(".rustloc.toml"),
+        "[[language]]\nname = \"terraform\"\nextensions = [\"tf\"]\nline_comments = [\"#\"]\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("main.tf"), "# vars\nvariable \"x\" {}\n\n").unwrap();
+    std::fs::write(dir.path().join("lib.rs"), "fn main() {}\n").unwrap();
+    let path = path_of(&dir);
+
+    let out = stdout(&[&path, "--lang", "terraform", "--output", "json"]);
+    let parsed: CountQuerySet = serde_json::from_str(&out).unwrap();
+    assert_eq!(parsed.file_count, 1);
+    assert_eq!(parsed.total.code, 1);
+    assert_eq!(parsed.total.comments, 1);
+    assert_eq!(parsed.total.blanks, 1);
+
+    // `all` includes the config backend, which counts `.rustloc.toml` too.
+    let out = stdout(&[&path, "--lang", "all", "--output", "json"]);
+    let parsed: CountQuerySet = serde_json::from_str(&out).unwrap();
+    assert_eq!(parsed.file_count, 3);
+    assert_eq!(parsed.total.config, 4);
+
+    // Without the definitions the name is unknown.
+    let other = TempDir::new().unwrap();
+    std::fs::write(other.path().join("main.tf"), "x = 1\n").unwrap();
+    let message = error(&[&path_of(&other), "--lang", "terraform"]);
+    assert!(
+        message.contains("unknown language 'terraform'"),
+        "unexpected error: {message}"
+    );
+}
+
+/// A discovered `.rustloc.toml` comes with the checkout, so its plugins run
+/// only with `--allow-plugins`, and only when named.
+#[cfg(unix)]
+#[test]
+fn plugins_from_a_discovered_config_need_an_explicit_opt_in() {
+    let dir = TempDir::new().unwrap();
+    let marker = dir.path().join("plugin-ran");
+    std::fs::write(
+        dir.path().join(".rustloc.toml"),
+        format!(
+            "[[plugin]]\nname = \"dsl\"\nextensions = [\"dsl\"]\ncommand = \"sh\"\n\
+             args = [\"-c\", \"touch {}\"]\n",
+            marker.display()
+        ),
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("main.dsl"), "run()\n").unwrap();
+    let path = path_of(&dir);
+
+    let parsed: CountQuerySet =
+        serde_json::from_str(&stdout(&[&path, "--lang", "all", "--output", "json"])).unwrap();
+    assert!(parsed.diagnostics.is_empty(), "{:?}", parsed.diagnostics);
+    assert!(!marker.exists());
+
+    let message = error(&[&path, "--lang", "dsl"]);
+    assert!(message.contains("pass --allow-plugins"), "{message}");
+    assert!(!marker.exists());
+
+    stdout(&[
+        &path,
+        "--lang",
+        "dsl",
+        "--allow-plugins",
+        "--output",
+        "json",
+    ]);
+    assert!(marker.exists());
+}
+
 // ---------------------------------------------------------------------------
 // The rendering modes, and the theme behind them
 // ---------------------------------------------------------------------------
@@ -693,6 +1472,29 @@ fn long_help_keeps_both_the_examples_and_the_grid_doc() {
     );
 }
 
+/// The intro paragraph names the same backends as the `--lang` value list, so
+/// the two halves of `--help` don't contradict each other.
+#[test]
+fn long_help_intro_names_every_selectable_backend() {
+    let help = stdout(&["--help"]);
+    let intro = help.split("Usage:").next().unwrap();
+    for backend in [
+        "Python",
+        "TypeScript",
+        "Java/Kotlin",
+        "C/C++",
+        "Zig",
+        "Ruby",
+        "Markdown",
+        "config",
+    ] {
+        assert!(
+            intro.contains(backend),
+            "{backend} missing from the intro:\n{intro}"
+        );
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Errors at the parsing boundary
 // ---------------------------------------------------------------------------
@@ -760,20 +1562,230 @@ fn non_numeric_grid_value_is_rejected() {
     );
 }
 
-/// `--by-crate` needs a workspace. The rule lives in `application`, and this
-/// pins that its error survives dispatch instead of being swallowed.
+/// Outside a Cargo workspace, `--by-crate` groups files by the packages of
+/// every ecosystem; files no manifest claims land in an `(other)` row.
 #[test]
-fn by_crate_on_a_non_workspace_reports_the_orchestration_error() {
-    let dir = tempfile::tempdir().unwrap();
-    let path = dir.path().to_str().unwrap();
+fn by_crate_groups_a_mixed_repository_by_package() {
+    let dir = TempDir::new().unwrap();
+    let write = |file: &str, content: &str| {
+        let path = dir.path().join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    };
+    write("py/api/pyproject.toml", "[project]\nname = \"api\"\n");
+    write("py/api/app.py", "def run():\n    return 1\n");
+    write("web/package.json", "{\"name\": \"@acme/web\"}\n");
+    write("web/index.ts", "export const x = 1;\n");
+    write("svc/go.mod", "module example.com/svc\n");
+    write("svc/main.go", "package main\n\nfunc main() {\n}\n");
+    write("scripts/tool.py", "print(1)\n");
+
+    let parsed: CountQuerySet = serde_json::from_str(&stdout(&[
+        &path_of(&dir),
+        "--lang",
+        "python,typescript,generic",
+        "--by-crate",
+        "--output",
+        "json",
+    ]))
+    .unwrap();
+    let rows: Vec<_> = parsed
+        .items
+        .iter()
+        .map(|item| (item.label.as_str(), item.stats.code))
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ("(other)", 1),
+            ("@acme/web", 1),
+            ("api", 2),
+            ("example.com/svc", 3),
+        ]
+    );
+}
 
-    match run(&[path, "--by-crate"]) {
-        RunResult::Error(msg) => assert!(
-            msg.contains("requires a Cargo workspace"),
-            "unexpected message: {msg}"
-        ),
-        other => panic!("expected an Error, got {other:?}"),
+/// `--by-package` is `--by-crate` under its JavaScript name, and `-c`
+/// selects workspace packages by name outside Cargo too.
+#[test]
+fn by_package_filters_a_pnpm_workspace_by_package_name() {
+    let dir = TempDir::new().unwrap();
+    let write = |file: &str, content: &str| {
+        let path = dir.path().join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    };
+    write("pnpm-workspace.yaml", "packages: ['packages/*']\n");
+    write("packages/ui/package.json", "{\"name\": \"@acme/ui\"}");
+    write(
+        "packages/ui/index.ts",
+        "export const a = 1;\nexport const b = 2;\n",
+    );
+    write("packages/api/package.json", "{\"name\": \"@acme/api\"}");
+    write("packages/api/index.ts", "export const c = 3;\n");
+
+    let path = path_of(&dir);
+    let rows = |args: &[&str]| -> Vec<(String, u64)> {
+        let mut argv = vec![path.as_str(), "--lang", "typescript", "--output", "json"];
+        argv.extend_from_slice(args);
+        let parsed: CountQuerySet = serde_json::from_str(&stdout(&argv)).unwrap();
+        parsed
+            .items
+            .into_iter()
+            .map(|item| (item.label, item.stats.code))
+            .collect()
+    };
+
+    assert_eq!(
+        rows(&["--by-package"]),
+        [("@acme/api".to_string(), 1), ("@acme/ui".to_string(), 2)]
+    );
+    assert_eq!(
+        rows(&["--by-package", "-c", "@acme/ui"]),
+        [("@acme/ui".to_string(), 2)]
+    );
+}
+
+/// `--by-target` splits a crate into its Cargo targets; test targets count as
+/// tests wherever their files live.
+#[test]
+fn by_target_rows_follow_the_cargo_targets() {
+    let dir = workspace();
+    std::fs::create_dir(dir.path().join("tests")).unwrap();
+    std::fs::write(dir.path().join("tests/it.rs"), "fn it() {}\n").unwrap();
+    let path = path_of(&dir);
+
+    let parsed: CountQuerySet =
+        serde_json::from_str(&stdout(&[&path, "--by-target", "--output", "json"])).unwrap();
+    let rows: Vec<_> = parsed
+        .items
+        .iter()
+        .map(|item| (item.label.as_str(), item.stats.code, item.stats.tests))
+        .collect();
+    assert_eq!(rows, [("demo (lib)", 4, 0), ("demo (test it)", 0, 1)]);
+
+    let message = error(&[dir.path().join("src").to_str().unwrap(), "--by-target"]);
+    assert!(
+        message.contains("--by-target requires a Cargo workspace"),
+        "{message}"
+    );
+}
+
+#[test]
+fn workspaces_are_read_from_manifests_unless_cargo_metadata_is_asked_for() {
+    let dir = TempDir::new().unwrap();
+    let write = |file: &str, content: &str| {
+        let path = dir.path().join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    };
+    write("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n");
+    write(
+        "crates/good/Cargo.toml",
+        "[package]\nname = \"good\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    );
+    write("crates/good/src/lib.rs", "pub fn a() {}\n");
+    // A broken member stops Cargo, but not the manifest reader.
+    write("crates/broken/Cargo.toml", "[package\n");
+    let path = path_of(&dir);
+
+    let parsed: CountQuerySet =
+        serde_json::from_str(&stdout(&[&path, "--by-crate", "--output", "json"])).unwrap();
+    let labels: Vec<_> = parsed
+        .items
+        .iter()
+        .map(|item| item.label.as_str())
+        .collect();
+    assert_eq!(labels, ["good"]);
+
+    let message = error(&[&path, "--by-crate", "--cargo-metadata"]);
+    assert!(message.contains("cargo metadata"), "{message}");
+}
+
+#[test]
+fn workspaces_counts_each_workspace_beneath_a_directory() {
+    let dir = TempDir::new().unwrap();
+    for name in ["alpha", "beta"] {
+        let root = dir.path().join(name);
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n"),
+        )
+        .unwrap();
+        std::fs::write(root.join("src/lib.rs"), "pub fn a() {}\n").unwrap();
     }
+    let path = path_of(&dir);
+
+    let parsed: CountQuerySet = serde_json::from_str(&stdout(&[
+        &path,
+        "--workspaces",
+        "--by-workspace",
+        "--output",
+        "json",
+    ]))
+    .unwrap();
+    let labels: Vec<_> = parsed
+        .items
+        .iter()
+        .map(|item| item.label.as_str())
+        .collect();
+    assert_eq!(labels, ["alpha", "beta"]);
+
+    let parsed: CountQuerySet = serde_json::from_str(&stdout(&[
+        &path,
+        "--workspaces",
+        "--by-crate",
+        "--output",
+        "json",
+    ]))
+    .unwrap();
+    let labels: Vec<_> = parsed
+        .items
+        .iter()
+        .map(|item| item.label.as_str())
+        .collect();
+    assert_eq!(labels, ["alpha:alpha", "beta:beta"]);
+
+    let message = error(&[&path, "--by-workspace"]);
+    assert!(
+        message.contains("--by-workspace requires --workspaces"),
+        "{message}"
+    );
+}
+
+#[test]
+fn deps_counts_one_row_per_dependency() {
+    let dir = workspace();
+    let helper = dir.path().join("helper");
+    std::fs::create_dir_all(helper.join("src")).unwrap();
+    std::fs::write(
+        helper.join("Cargo.toml"),
+        "[package]\nname = \"helper\"\nversion = \"0.2.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    std::fs::write(helper.join("src/lib.rs"), "pub fn h() {}\npub fn i() {}\n").unwrap();
+    std::fs::write(
+        dir.path().join("Cargo.toml"),
+        "[package]\nname = \"demo\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+         [dependencies]\nhelper = { path = \"helper\" }\n",
+    )
+    .unwrap();
+    let path = path_of(&dir);
+
+    let parsed: CountQuerySet =
+        serde_json::from_str(&stdout(&["deps", &path, "--output", "json"])).unwrap();
+    let rows: Vec<_> = parsed
+        .items
+        .iter()
+        .map(|item| (item.label.as_str(), item.stats.code))
+        .collect();
+    assert_eq!(rows, [("helper@0.2.0", 2)]);
+    assert_eq!(parsed.total.code, 2, "workspace members are never counted");
+
+    let parsed: CountQuerySet =
+        serde_json::from_str(&stdout(&["deps", &path, "-k", "dev", "--output", "json"])).unwrap();
+    assert!(parsed.items.is_empty());
 }
 
 // ---------------------------------------------------------------------------
@@ -1232,7 +2244,7 @@ fn count_all_columns_text_matches_the_approved_fixture() {
 }
 
 /// Each `--by-*` aggregation names its label column differently ("Crate" vs
-/// "Module" vs "File") and counts a different unit in the footer ("crates" vs
+/// "Package" vs "Module" vs "File") and counts a different unit in the footer ("crates" vs
 /// "modules" vs "files"). Total aggregation is covered by
 /// [`count_total_text_matches_the_approved_fixture`]; this covers the rest.
 ///
@@ -1246,6 +2258,7 @@ fn each_aggregation_names_its_label_column_and_unit() {
 
     for (flag, label_header, unit) in [
         ("--by-crate", "Crate", "crates"),
+        ("--by-package", "Package", "packages"),
         ("--by-module", "Module", "modules"),
         ("--by-file", "File", "files"),
     ] {
@@ -41,7 +41,8 @@
 //! Everything else a reader sees is in the templates.
 
 use rustloclib::{
-    sat_sub_u64, Aggregation, CountQuerySet, DiffQuerySet, LineTypes, Locs, LocsDiff,
+    sat_sub_u64, Aggregation, CountQuerySet, DetectedFile, Diagnostic, DiagnosticKind,
+    DiffQuerySet, LineTypes, Locs, LocsDiff, TranscodedFile,
 };
 use serde::Serialize;
 
@@ -57,6 +58,73 @@ pub struct Row<V> {
     pub values: Vec<V>,
 }
 
+/// How many files of each diagnostic kind a run recorded.
+///
+/// Skipped files are left out: they have a note of their own.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct DiagnosticCounts {
+    /// Files that could not be read.
+    pub unreadable: usize,
+    /// Files a backend failed on.
+    pub failed: usize,
+    /// Files a parser rejected, counted with a fallback.
+    pub fallbacks: usize,
+}
+
+impl DiagnosticCounts {
+    fn new(diagnostics: &[Diagnostic]) -> Self {
+        let count = |kind| diagnostics.iter().filter(|d| d.kind == kind).count();
+        DiagnosticCounts {
+            unreadable: count(DiagnosticKind::ReadError),
+            failed: count(DiagnosticKind::AnalysisError),
+            fallbacks: count(DiagnosticKind::ParseError),
+        }
+    }
+}
+
+/// A file that was decoded from an encoding other than plain UTF-8.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileEncoding {
+    /// The file's label, as its row would read when aggregating by file.
+    pub label: String,
+    /// The encoding's name, as in structured output.
+    pub encoding: &'static str,
+}
+
+/// Files that were transcoded, whatever the aggregation.
+fn transcoded(files: &[TranscodedFile]) -> Vec<FileEncoding> {
+    files
+        .iter()
+        .map(|f| FileEncoding {
+            label: f.path.to_string_lossy().to_string(),
+            encoding: f.encoding.name(),
+        })
+        .collect()
+}
+
+/// A file routed to its backend by something other than its extension.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileDetection {
+    /// The file's label, as its row would read when aggregating by file.
+    pub label: String,
+    /// The backend's registered name.
+    pub backend: String,
+    /// What routed the file there, as in structured output.
+    pub reason: &'static str,
+}
+
+/// Files detected by file name, modeline or shebang, whatever the aggregation.
+fn detected(files: &[DetectedFile]) -> Vec<FileDetection> {
+    files
+        .iter()
+        .map(|f| FileDetection {
+            label: f.path.to_string_lossy().to_string(),
+            backend: f.detection.backend.clone(),
+            reason: f.detection.reason.name(),
+        })
+        .collect()
+}
+
 /// The facts the footer's wording is derived from.
 ///
 /// Facts, not a sentence: whether the footer reads "Total (2 crates)",
@@ -102,19 +170,31 @@ pub struct CountView {
     /// Aggregation key: `total`, `crate`, `module`, or `file`.
     pub aggregation: &'static str,
     /// Enabled column keys, in display order.
-    pub columns: Vec<&'static str>,
+    pub columns: Vec<String>,
     /// Data rows.
     pub rows: Vec<Row<u64>>,
     /// The totals row's values, positionally matching `columns`.
     pub total: Vec<u64>,
     /// Facts behind the footer's wording.
     pub footer: Footer,
+    /// Symbolic links and duplicate paths discovery did not count.
+    pub skipped_links: usize,
+    /// Files skipped as binary, minified or too large.
+    pub skipped: usize,
+    /// Files that could not be counted as they stand.
+    pub diagnostics: DiagnosticCounts,
+    /// Files not detected by their extension.
+    pub detections: Vec<FileDetection>,
+    /// Files decoded from another encoding.
+    pub encodings: Vec<FileEncoding>,
+    /// Whether `--verbose` asked for per-file detail such as `encodings`.
+    pub verbose: bool,
 }
 
 impl CountView {
     /// Build the count table's payload from its canonical response.
     pub fn from_queryset(qs: &CountQuerySet) -> Self {
-        let columns = enabled_columns(&qs.line_types);
+        let columns = enabled_columns(&qs.line_types, qs.total.categories.keys());
         CountView {
             aggregation: aggregation_key(&qs.aggregation),
             rows: qs
@@ -132,7 +212,13 @@ impl CountView {
                 qs.file_count,
                 qs.top_applied,
             ),
-            columns: columns.iter().map(|c| c.key()).collect(),
+            columns: columns.iter().map(|c| c.key().to_string()).collect(),
+            skipped_links: qs.skipped_links.len(),
+            skipped: qs.skipped.len(),
+            diagnostics: DiagnosticCounts::new(&qs.diagnostics),
+            detections: detected(&qs.detected),
+            encodings: transcoded(&qs.transcoded),
+            verbose: false,
         }
     }
 }
@@ -174,7 +260,7 @@ pub struct DiffView {
     /// Aggregation key: `total`, `crate`, `module`, or `file`.
     pub aggregation: &'static str,
     /// Enabled column keys, in display order.
-    pub columns: Vec<&'static str>,
+    pub columns: Vec<String>,
     /// Data rows.
     pub rows: Vec<Row<DiffValue>>,
     /// The totals row's values, positionally matching `columns`.
@@ -190,12 +276,26 @@ pub struct DiffView {
     /// Always present, even when zero: whether a zero summary is worth showing
     /// a reader is the template's call, not this module's.
     pub non_rust: DiffValue,
+    /// Changed files skipped as binary, minified or too large.
+    pub skipped: usize,
+    /// Changed files that could not be counted as they stand.
+    pub diagnostics: DiagnosticCounts,
+    /// Files not detected by their extension.
+    pub detections: Vec<FileDetection>,
+    /// Files decoded from another encoding.
+    pub encodings: Vec<FileEncoding>,
+    /// Whether `--verbose` asked for per-file detail such as `encodings`.
+    pub verbose: bool,
 }
 
 impl DiffView {
     /// Build the diff table's payload from its canonical response.
     pub fn from_queryset(qs: &DiffQuerySet) -> Self {
-        let columns = enabled_columns(&qs.line_types);
+        let categories = qs.total.added.categories.keys();
+        let columns = enabled_columns(
+            &qs.line_types,
+            categories.chain(qs.total.removed.categories.keys()),
+        );
         DiffView {
             aggregation: aggregation_key(&qs.aggregation),
             rows: qs
@@ -216,7 +316,12 @@ impl DiffView {
             from_commit: qs.from_commit.clone(),
             to_commit: qs.to_commit.clone(),
             non_rust: DiffValue::new(qs.non_rust_added, qs.non_rust_removed),
-            columns: columns.iter().map(|c| c.key()).collect(),
+            skipped: qs.skipped.len(),
+            diagnostics: DiagnosticCounts::new(&qs.diagnostics),
+            detections: detected(&qs.detected),
+            encodings: transcoded(&qs.transcoded),
+            verbose: false,
+            columns: columns.iter().map(|c| c.key().to_string()).collect(),
         }
     }
 }
@@ -226,14 +331,19 @@ impl DiffView {
 /// The query set's `line_types` selects which of these appear; this enum is the
 /// single source of truth for their order and per-column accessors, so the
 /// column keys and the cells beneath them can never drift out of alignment.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 enum Column {
     Code,
     Tests,
     Examples,
+    /// A category of the classification rules, after the built-in logic
+    /// line types.
+    Category(String),
     Docs,
     Comments,
     Blanks,
+    Preprocessor,
+    Config,
     Total,
 }
 
@@ -243,51 +353,72 @@ impl Column {
     /// A *data* name — it matches the `Locs` field and the JSON/CSV schemas, and
     /// is deliberately not a display word. The templates map keys to the words a
     /// reader sees, which is why nothing in Rust needs to know that `code` is
-    /// shown as "Code".
-    fn key(self) -> &'static str {
+    /// shown as "Code". A category's key is its name.
+    fn key(&self) -> &str {
         match self {
             Column::Code => "code",
             Column::Tests => "tests",
             Column::Examples => "examples",
+            Column::Category(name) => name,
             Column::Docs => "docs",
             Column::Comments => "comments",
             Column::Blanks => "blanks",
+            Column::Preprocessor => "preprocessor",
+            Column::Config => "config",
             Column::Total => "total",
         }
     }
 
     /// This column's count out of a `Locs`.
-    fn count(self, locs: &Locs) -> u64 {
+    fn count(&self, locs: &Locs) -> u64 {
         match self {
             Column::Code => locs.code,
             Column::Tests => locs.tests,
             Column::Examples => locs.examples,
+            Column::Category(name) => locs.category(name),
             Column::Docs => locs.docs,
             Column::Comments => locs.comments,
             Column::Blanks => locs.blanks,
+            Column::Preprocessor => locs.preprocessor,
+            Column::Config => locs.config,
             // Precomputed by the library, not summed here.
             Column::Total => locs.total,
         }
     }
 
     /// This column's cell out of a `LocsDiff`.
-    fn diff_value(self, diff: &LocsDiff) -> DiffValue {
+    fn diff_value(&self, diff: &LocsDiff) -> DiffValue {
         DiffValue::new(self.count(&diff.added), self.count(&diff.removed))
     }
 }
 
-/// The enabled columns, in display order.
-fn enabled_columns(line_types: &LineTypes) -> Vec<Column> {
+/// The enabled columns, in display order, with one column per category the
+/// counts hold when categories are enabled.
+fn enabled_columns<'a>(
+    line_types: &LineTypes,
+    categories: impl Iterator<Item = &'a String>,
+) -> Vec<Column> {
+    let mut categories: Vec<&String> = categories.collect();
+    categories.sort();
+    categories.dedup();
+    let categories = categories
+        .into_iter()
+        .map(|name| (line_types.categories, Column::Category(name.clone())));
     [
         (line_types.code, Column::Code),
         (line_types.tests, Column::Tests),
         (line_types.examples, Column::Examples),
+    ]
+    .into_iter()
+    .chain(categories)
+    .chain([
         (line_types.docs, Column::Docs),
         (line_types.comments, Column::Comments),
         (line_types.blanks, Column::Blanks),
+        (line_types.preprocessor, Column::Preprocessor),
+        (line_types.config, Column::Config),
         (line_types.total, Column::Total),
-    ]
-    .into_iter()
+    ])
     .filter_map(|(enabled, column)| enabled.then_some(column))
     .collect()
 }
@@ -299,8 +430,10 @@ fn enabled_columns(line_types: &LineTypes) -> Vec<Column> {
 fn aggregation_key(aggregation: &Aggregation) -> &'static str {
     match aggregation {
         Aggregation::Total => "total",
+        Aggregation::ByWorkspace => "workspace",
         Aggregation::ByCrate => "crate",
         Aggregation::ByModule => "module",
+        Aggregation::ByTarget => "target",
         Aggregation::ByFile => "file",
     }
 }
@@ -320,6 +453,9 @@ mod tests {
             docs: 0,
             comments: 0,
             blanks: 0,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: code + tests,
         }
     }
@@ -345,6 +481,13 @@ mod tests {
             ],
             files: vec![],
             modules: vec![],
+            targets: vec![],
+            workspaces: vec![],
+            skipped_links: vec![],
+            skipped: vec![],
+            diagnostics: vec![],
+            detected: vec![],
+            transcoded: vec![],
         }
     }
 
@@ -365,7 +508,17 @@ mod tests {
         // ("Code", "Tests", ...) belong to the template and must not appear.
         assert_eq!(
             view.columns,
-            vec!["code", "tests", "examples", "docs", "comments", "blanks", "total"]
+            vec![
+                "code",
+                "tests",
+                "examples",
+                "docs",
+                "comments",
+                "blanks",
+                "preprocessor",
+                "config",
+                "total"
+            ]
         );
     }
 
@@ -393,22 +546,48 @@ mod tests {
         assert_eq!(view.total, vec![200]);
     }
 
+    #[test]
+    fn categories_get_one_column_each_after_examples() {
+        let mut result = sample_count_result();
+        result.crates[0].stats.code -= 10;
+        result.crates[0]
+            .stats
+            .categories
+            .insert("bench".to_string(), 10);
+        result.total.code -= 10;
+        result.total.categories.insert("bench".to_string(), 10);
+        let qs = CountQuerySet::from_result(
+            &result,
+            Aggregation::ByCrate,
+            LineTypes::everything(),
+            Ordering::default(),
+        );
+        let view = CountView::from_queryset(&qs);
+
+        assert_eq!(&view.columns[..4], ["code", "tests", "examples", "bench"]);
+        assert_eq!(view.rows[0].values[..4], [40, 25, 0, 10]);
+        assert_eq!(view.rows[1].values[3], 0);
+        assert_eq!(view.total[..4], [190, 100, 0, 10]);
+    }
+
     #[test]
     fn values_are_typed_numbers_in_column_order() {
         let view =
             CountView::from_queryset(&queryset(LineTypes::everything(), Ordering::default()));
         // Default ordering is by label ascending: alpha before beta.
         assert_eq!(view.rows[0].label, "alpha");
-        assert_eq!(view.rows[0].values, vec![50, 25, 0, 0, 0, 0, 75]);
-        assert_eq!(view.total, vec![200, 100, 0, 0, 0, 0, 300]);
+        assert_eq!(view.rows[0].values, vec![50, 25, 0, 0, 0, 0, 0, 0, 75]);
+        assert_eq!(view.total, vec![200, 100, 0, 0, 0, 0, 0, 0, 300]);
     }
 
     #[test]
     fn aggregation_is_a_key_not_a_header_word() {
         for (aggregation, expected) in [
             (Aggregation::Total, "total"),
+            (Aggregation::ByWorkspace, "workspace"),
             (Aggregation::ByCrate, "crate"),
             (Aggregation::ByModule, "module"),
+            (Aggregation::ByTarget, "target"),
             (Aggregation::ByFile, "file"),
         ] {
             assert_eq!(aggregation_key(&aggregation), expected);
@@ -495,6 +674,10 @@ mod tests {
             non_rust_removed: 0,
             total_items: 0,
             top_applied: false,
+            skipped: vec![],
+            diagnostics: vec![],
+            detected: vec![],
+            transcoded: vec![],
         };
         let view = DiffView::from_queryset(&qs);
 
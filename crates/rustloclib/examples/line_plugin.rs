@@ -0,0 +1,74 @@
+//! Reference plugin for the external-process backend protocol.
+//!
+//! Classifies a toy line-oriented language: `;;` starts a doc line, `;` a
+//! comment, and lines between `test ...` and `end` are test code. Declare it
+//! in a configuration file with:
+//!
+//! ```toml
+//! [[plugin]]
+//! name = "dsl"
+//! extensions = ["dsl"]
+//! command = "target/debug/examples/line_plugin"
+//! ```
+
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+use rustloclib::{LineClass, LogicContext, PluginRequest, PluginResponse, PLUGIN_PROTOCOL_VERSION};
+
+fn classify(source: &str) -> Vec<LineClass> {
+    let mut in_test = false;
+    source
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                LineClass::Blanks
+            } else if trimmed.starts_with(";;") {
+                LineClass::Docs
+            } else if trimmed.starts_with(';') {
+                LineClass::Comments
+            } else if trimmed.starts_with("test ") {
+                in_test = true;
+                LineClass::Logic(LogicContext::Tests)
+            } else if in_test {
+                in_test = trimmed != "end";
+                LineClass::Logic(LogicContext::Tests)
+            } else {
+                LineClass::Logic(LogicContext::Code)
+            }
+        })
+        .collect()
+}
+
+fn main() -> ExitCode {
+    let mut input = String::new();
+    if let Err(e) = io::stdin().read_to_string(&mut input) {
+        eprintln!("cannot read request: {e}");
+        return ExitCode::FAILURE;
+    }
+    let request: PluginRequest = match serde_json::from_str(&input) {
+        Ok(request) => request,
+        Err(e) => {
+            eprintln!("invalid request: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if request.version != PLUGIN_PROTOCOL_VERSION {
+        eprintln!("unsupported protocol version {}", request.version);
+        return ExitCode::FAILURE;
+    }
+
+    let response = PluginResponse {
+        lines: classify(&request.source),
+    };
+    let mut stdout = io::stdout().lock();
+    match serde_json::to_writer(&mut stdout, &response).map_err(io::Error::from) {
+        Ok(()) if stdout.flush().is_ok() => ExitCode::SUCCESS,
+        Ok(()) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("cannot write response: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
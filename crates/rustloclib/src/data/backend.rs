@@ -1,7 +1,9 @@
 //! Language backend seam for source analysis.
 //!
 //! Backends classify source files into rustloc's shared [`Locs`] model. The
-//! Rust and Python backends provide semantic classification, while the generic
+//! Rust, Python, JVM, C/C++, Zig, and Ruby backends provide semantic
+//! classification, the Markdown backend counts prose documentation, and the
+//! config backend counts configuration and data files, while the generic
 //! backend gives other common source files file-level code/test/example
 //! classification until language-specific backends are added.
 
@@ -14,10 +16,21 @@ use serde::{Deserialize, Serialize};
 
 use crate::{Result, RustlocError};
 
+use super::classify::PathClassifier;
+use super::config::ConfigBackend;
+use super::cpp::CppBackend;
+use super::custom::{CustomBackend, LanguageConfig, LanguageDefinition};
+use super::detect::{ContentHints, Detection, DetectionReason};
+use super::generic::GenericBackend;
+use super::jvm::JvmBackend;
+use super::markdown::{FencedBlock, MarkdownBackend};
+use super::plugin::ExternalBackend;
 use super::python::PythonBackend;
+use super::ruby::RubyBackend;
 use super::stats::Locs;
 use super::typescript::TypeScriptBackend;
-use super::visitor::{gather_analysis, gather_analysis_for_path};
+use super::visitor::gather_analysis;
+use super::zig::ZigBackend;
 
 /// Language identified by a backend.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -25,6 +38,14 @@ pub enum LanguageId {
     Rust,
     Python,
     TypeScript,
+    Java,
+    Kotlin,
+    C,
+    Cpp,
+    Zig,
+    Ruby,
+    Markdown,
+    Config,
     External(String),
     Unknown,
 }
@@ -35,6 +56,12 @@ pub enum LanguageName {
     Rust,
     Python,
     TypeScript,
+    Jvm,
+    Cpp,
+    Zig,
+    Ruby,
+    Markdown,
+    Config,
     Generic,
 }
 
@@ -44,6 +71,12 @@ impl LanguageName {
             Self::Rust => "rust",
             Self::Python => "python",
             Self::TypeScript => "typescript",
+            Self::Jvm => "jvm",
+            Self::Cpp => "cpp",
+            Self::Zig => "zig",
+            Self::Ruby => "ruby",
+            Self::Markdown => "markdown",
+            Self::Config => "config",
             Self::Generic => "generic",
         }
     }
@@ -63,6 +96,12 @@ impl FromStr for LanguageName {
             "rust" | "rs" => Ok(Self::Rust),
             "python" | "py" => Ok(Self::Python),
             "typescript" | "ts" | "tsx" => Ok(Self::TypeScript),
+            "jvm" | "java" | "kotlin" | "kt" => Ok(Self::Jvm),
+            "cpp" | "c++" | "cxx" | "c" => Ok(Self::Cpp),
+            "zig" => Ok(Self::Zig),
+            "ruby" | "rb" => Ok(Self::Ruby),
+            "markdown" | "md" => Ok(Self::Markdown),
+            "config" | "conf" => Ok(Self::Config),
             "generic" => Ok(Self::Generic),
             other => Err(format!(
                 "unknown language '{}'; available languages: {}",
@@ -82,6 +121,12 @@ pub const fn available_languages() -> &'static [LanguageName] {
         LanguageName::Rust,
         LanguageName::Python,
         LanguageName::TypeScript,
+        LanguageName::Jvm,
+        LanguageName::Cpp,
+        LanguageName::Zig,
+        LanguageName::Ruby,
+        LanguageName::Markdown,
+        LanguageName::Config,
         LanguageName::Generic,
     ]
 }
@@ -90,10 +135,14 @@ pub const fn default_languages() -> &'static [LanguageName] {
     &[LanguageName::Rust]
 }
 
-/// Active language backend groups for a count or diff operation.
+/// Active language backends for a count or diff operation.
+///
+/// A selection holds backend names as registered in a [`BackendRegistry`]:
+/// the built-in [`LanguageName`] groups plus any user-defined languages,
+/// plugins, or backends registered through the library.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct LanguageSelection {
-    enabled: BTreeSet<LanguageName>,
+    enabled: BTreeSet<String>,
 }
 
 impl Default for LanguageSelection {
@@ -104,21 +153,38 @@ impl Default for LanguageSelection {
 
 impl LanguageSelection {
     pub fn new(languages: &[LanguageName]) -> Self {
+        Self::from_names(languages.iter().map(|lang| lang.name()))
+    }
+
+    /// Select backends by registered name.
+    pub fn from_names<S: Into<String>>(names: impl IntoIterator<Item = S>) -> Self {
         Self {
-            enabled: languages.iter().copied().collect(),
+            enabled: names.into_iter().map(Into::into).collect(),
         }
     }
 
+    /// Also select the backend registered under `name`.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.enabled.insert(name.into());
+        self
+    }
+
+    /// Every built-in backend group. Use [`BackendRegistry::all_languages`]
+    /// to include registered backends as well.
     pub fn all() -> Self {
         Self::new(available_languages())
     }
 
     pub fn contains(&self, language: LanguageName) -> bool {
-        self.enabled.contains(&language)
+        self.contains_name(language.name())
+    }
+
+    pub fn contains_name(&self, name: &str) -> bool {
+        self.enabled.contains(name)
     }
 
-    pub fn names(&self) -> Vec<&'static str> {
-        self.enabled.iter().map(|lang| lang.name()).collect()
+    pub fn names(&self) -> Vec<&str> {
+        self.enabled.iter().map(String::as_str).collect()
     }
 }
 
@@ -155,6 +221,37 @@ impl LogicContext {
 
         Self::Code
     }
+
+    /// The context's name in classification rules.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Code => "code",
+            Self::Tests => "tests",
+            Self::Example => "examples",
+        }
+    }
+}
+
+impl fmt::Display for LogicContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for LogicContext {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "code" => Ok(Self::Code),
+            "tests" | "test" => Ok(Self::Tests),
+            "examples" | "example" => Ok(Self::Example),
+            other => Err(format!(
+                "unknown context '{}'; available contexts: code, tests, examples",
+                other
+            )),
+        }
+    }
 }
 
 /// Classification for a single source line.
@@ -164,6 +261,8 @@ pub enum LineClass {
     Docs,
     Comments,
     Blanks,
+    Preprocessor,
+    Config,
 }
 
 impl LineClass {
@@ -177,6 +276,8 @@ impl LineClass {
             Self::Docs => stats.docs += 1,
             Self::Comments => stats.comments += 1,
             Self::Blanks => stats.blanks += 1,
+            Self::Preprocessor => stats.preprocessor += 1,
+            Self::Config => stats.config += 1,
         }
     }
 }
@@ -188,21 +289,130 @@ pub struct FileAnalysis {
     pub stats: Locs,
     #[serde(skip)]
     pub line_classes: Vec<LineClass>,
+    /// Set when the parser rejected the file and the stats come from a
+    /// simpler fallback.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parse_failure: Option<ParseFailure>,
+}
+
+impl FileAnalysis {
+    /// Count every logic line as `context`.
+    pub fn set_logic_context(&mut self, context: LogicContext) {
+        for class in &mut self.line_classes {
+            if let LineClass::Logic(line_context) = class {
+                *line_context = context;
+            }
+        }
+        let stats = &mut self.stats;
+        let logic = stats.code + stats.tests + stats.examples;
+        (stats.code, stats.tests, stats.examples) = (0, 0, 0);
+        match context {
+            LogicContext::Code => stats.code = logic,
+            LogicContext::Tests => stats.tests = logic,
+            LogicContext::Example => stats.examples = logic,
+        }
+    }
+}
+
+/// A syntax error a backend counted around instead of failing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseFailure {
+    /// What the parser reported.
+    pub message: String,
+    /// 1-based line of the error.
+    pub line: usize,
+    /// 1-based column of the error, in characters.
+    pub column: usize,
+    /// How the file was classified instead, such as `line-level`.
+    pub fallback: String,
+}
+
+impl ParseFailure {
+    /// A failure at byte `offset` of `source`.
+    pub fn at_offset(
+        source: &str,
+        offset: usize,
+        message: impl Into<String>,
+        fallback: impl Into<String>,
+    ) -> Self {
+        let before = source.get(..offset).unwrap_or(source);
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        Self {
+            message: message.into(),
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            fallback: fallback.into(),
+        }
+    }
+}
+
+/// `path` relative to `root`, or `path` itself when it is not below `root`
+/// or is `root`.
+pub(crate) fn relative_to<'a>(path: &'a Path, root: &Path) -> &'a Path {
+    match path.strip_prefix(root) {
+        Ok(relative) if !relative.as_os_str().is_empty() => relative,
+        _ => path,
+    }
 }
 
 /// Backend interface for language-specific source analysis.
-pub trait LanguageBackend: Sync {
+pub trait LanguageBackend: Send + Sync {
     fn supports_path(&self, path: &Path) -> bool;
 
+    /// Whether files no backend claims by path belong to this backend when
+    /// their content names `hint`: a `#!` interpreter (`python`, `bash`) or a
+    /// modeline filetype (`ruby`, `sh`). See [`ContentHints`].
+    fn supports_hint(&self, _hint: &str) -> bool {
+        false
+    }
+
+    /// Read, decode and analyze a file. Binary and minified files are an
+    /// error; see [`crate::data::content`].
     fn analyze_path(&self, path: &Path) -> Result<FileAnalysis> {
-        let source = std::fs::read_to_string(path).map_err(|e| RustlocError::FileRead {
-            path: path.to_path_buf(),
-            source: e,
-        })?;
-        self.analyze_source(path, &source)
+        match super::content::read_source(path, None)? {
+            Ok(source) => self.analyze_source(path, &source.text),
+            Err(skipped) => Err(RustlocError::SkippedFile {
+                path: skipped.path,
+                reason: skipped.reason,
+            }),
+        }
     }
 
     fn analyze_source(&self, path: &Path, source: &str) -> Result<FileAnalysis>;
+
+    /// Analyze `source` with `context` in place of the context the backend
+    /// would derive from `path`, as set by a classification rule. Backends
+    /// with semantic classification override this so that test functions in
+    /// production files are still counted as tests; the default moves every
+    /// logic line into `context`.
+    fn analyze_source_in_context(
+        &self,
+        path: &Path,
+        source: &str,
+        context: LogicContext,
+    ) -> Result<FileAnalysis> {
+        let mut analysis = self.analyze_source(path, source)?;
+        analysis.set_logic_context(context);
+        Ok(analysis)
+    }
+
+    /// The context configured path globs give `relative`, a path relative
+    /// to the analyzed directory, or `None` to let the backend decide from
+    /// the path it is given. User-defined languages with `test_paths` or
+    /// `example_paths` override this.
+    fn configured_context(&self, _relative: &Path) -> Option<LogicContext> {
+        None
+    }
+
+    /// Blocks of other languages embedded in `source`, such as the fenced
+    /// code of a Markdown file. A [`BackendRegistry`] re-classifies the body
+    /// of each block through the selected backend its language names; lines
+    /// that backend counts as docs, comments or blanks take its class, and
+    /// the rest keep theirs. A backend returning blocks records its stats
+    /// from its line classes.
+    fn embedded_blocks(&self, _source: &str) -> Vec<FencedBlock> {
+        Vec::new()
+    }
 }
 
 /// Rust language backend.
@@ -214,139 +424,21 @@ impl LanguageBackend for RustBackend {
         path.extension().is_some_and(|ext| ext == "rs")
     }
 
-    fn analyze_path(&self, path: &Path) -> Result<FileAnalysis> {
-        gather_analysis_for_path(path)
+    fn supports_hint(&self, hint: &str) -> bool {
+        matches!(hint, "rust" | "rust-script" | "cargo")
     }
 
     fn analyze_source(&self, path: &Path, source: &str) -> Result<FileAnalysis> {
-        let context = LogicContext::from_file_path(path);
-        Ok(gather_analysis(source, context))
-    }
-}
-
-/// File-level backend for common source languages without semantic parsing.
-#[derive(Debug, Default)]
-pub struct GenericBackend;
-
-#[derive(Debug, Clone, Copy)]
-struct GenericLanguage {
-    id: &'static str,
-    line_comments: &'static [&'static str],
-    block_comment: Option<(&'static str, &'static str)>,
-}
-
-impl GenericLanguage {
-    fn for_path(path: &Path) -> Option<Self> {
-        let ext = path.extension()?.to_str()?;
-        let language = if any_ext(ext, &["sh", "bash", "zsh", "fish"]) {
-            Self {
-                id: "Shell",
-                line_comments: &["#"],
-                block_comment: None,
-            }
-        } else if any_ext(ext, &["rb"]) {
-            Self {
-                id: "Ruby",
-                line_comments: &["#"],
-                block_comment: None,
-            }
-        } else if any_ext(ext, &["js", "jsx"]) {
-            Self {
-                id: "JavaScript",
-                line_comments: &["//"],
-                block_comment: Some(("/*", "*/")),
-            }
-        } else if any_ext(ext, &["go"]) {
-            Self {
-                id: "Go",
-                line_comments: &["//"],
-                block_comment: Some(("/*", "*/")),
-            }
-        } else if any_ext(ext, &["java"]) {
-            Self {
-                id: "Java",
-                line_comments: &["//"],
-                block_comment: Some(("/*", "*/")),
-            }
-        } else if any_ext(ext, &["c", "h", "cc", "cpp", "cxx", "hpp", "hh", "hxx"]) {
-            Self {
-                id: "C-like",
-                line_comments: &["//"],
-                block_comment: Some(("/*", "*/")),
-            }
-        } else if any_ext(ext, &["cs"]) {
-            Self {
-                id: "CSharp",
-                line_comments: &["//"],
-                block_comment: Some(("/*", "*/")),
-            }
-        } else if any_ext(ext, &["php"]) {
-            Self {
-                id: "PHP",
-                line_comments: &["//", "#"],
-                block_comment: Some(("/*", "*/")),
-            }
-        } else if any_ext(ext, &["swift"]) {
-            Self {
-                id: "Swift",
-                line_comments: &["//"],
-                block_comment: Some(("/*", "*/")),
-            }
-        } else if any_ext(ext, &["kt", "kts"]) {
-            Self {
-                id: "Kotlin",
-                line_comments: &["//"],
-                block_comment: Some(("/*", "*/")),
-            }
-        } else if any_ext(ext, &["scala"]) {
-            Self {
-                id: "Scala",
-                line_comments: &["//"],
-                block_comment: Some(("/*", "*/")),
-            }
-        } else if any_ext(ext, &["css", "scss", "less"]) {
-            Self {
-                id: "CSS",
-                line_comments: &[],
-                block_comment: Some(("/*", "*/")),
-            }
-        } else {
-            return None;
-        };
-        Some(language)
-    }
-}
-
-fn any_ext(ext: &str, candidates: &[&str]) -> bool {
-    candidates
-        .iter()
-        .any(|candidate| ext.eq_ignore_ascii_case(candidate))
-}
-
-impl LanguageBackend for GenericBackend {
-    fn supports_path(&self, path: &Path) -> bool {
-        GenericLanguage::for_path(path).is_some()
+        self.analyze_source_in_context(path, source, LogicContext::from_file_path(path))
     }
 
-    fn analyze_source(&self, path: &Path, source: &str) -> Result<FileAnalysis> {
-        let language = GenericLanguage::for_path(path)
-            .ok_or_else(|| RustlocError::UnsupportedSourceFile(path.to_path_buf()))?;
-        let context = generic_context_from_path(path);
-        let mut stats = Locs::new();
-        let mut line_classes = Vec::new();
-        let mut in_block_comment = false;
-
-        for line in source.lines() {
-            let class = classify_generic_line(line, language, &mut in_block_comment, context);
-            class.record(&mut stats);
-            line_classes.push(class);
-        }
-
-        Ok(FileAnalysis {
-            language: LanguageId::External(language.id.to_string()),
-            stats,
-            line_classes,
-        })
+    fn analyze_source_in_context(
+        &self,
+        _path: &Path,
+        source: &str,
+        context: LogicContext,
+    ) -> Result<FileAnalysis> {
+        Ok(gather_analysis(source, context))
     }
 }
 
@@ -390,84 +482,425 @@ pub(super) fn generic_context_from_path(path: &Path) -> LogicContext {
     }
 }
 
-fn classify_generic_line(
-    line: &str,
-    language: GenericLanguage,
-    in_block_comment: &mut bool,
-    context: LogicContext,
-) -> LineClass {
-    let trimmed = line.trim();
-    if trimmed.is_empty() {
-        return LineClass::Blanks;
+/// Priority of the built-in backends in a [`BackendRegistry`].
+pub const BUILTIN_PRIORITY: i32 = 0;
+
+/// Priority of languages and plugins from a configuration file, so a selected
+/// definition takes over an extension from a built-in backend.
+pub const CONFIG_PRIORITY: i32 = 100;
+
+/// Extensions of images, archives, media and compiled objects, which no
+/// shebang or modeline can turn into a source file.
+const BINARY_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "ico", "webp", "pdf", "zip", "gz", "tgz", "xz", "bz2",
+    "zst", "7z", "tar", "jar", "war", "class", "o", "a", "so", "dylib", "dll", "exe", "wasm",
+    "pyc", "woff", "woff2", "ttf", "otf", "mp3", "mp4", "mov", "avi", "wav", "bin",
+];
+
+struct RegisteredBackend {
+    name: String,
+    priority: i32,
+    backend: Box<dyn LanguageBackend>,
+    /// Runs an external program; only selected by name.
+    plugin: bool,
+}
+
+impl RegisteredBackend {
+    fn detected(&self, reason: DetectionReason) -> (&dyn LanguageBackend, Detection) {
+        let detection = Detection {
+            backend: self.name.clone(),
+            reason,
+        };
+        (self.backend.as_ref(), detection)
     }
+}
 
-    if *in_block_comment {
-        if let Some((_, end)) = language.block_comment {
-            if trimmed.contains(end) {
-                *in_block_comment = false;
-            }
+/// Registry of language backends available to the analyzer.
+///
+/// Backends are registered under a unique name, which is what a
+/// [`LanguageSelection`] refers to, and a priority. A path is analyzed by the
+/// first selected backend that supports it, trying higher priorities first and
+/// backends of equal priority in registration order.
+///
+/// ```
+/// use std::path::Path;
+/// use rustloclib::{BackendRegistry, FileAnalysis, LanguageBackend, LanguageId, Locs, Result};
+///
+/// struct AsciiDoc;
+///
+/// impl LanguageBackend for AsciiDoc {
+///     fn supports_path(&self, path: &Path) -> bool {
+///         path.extension().is_some_and(|ext| ext == "adoc")
+///     }
+///
+///     fn analyze_source(&self, _path: &Path, source: &str) -> Result<FileAnalysis> {
+///         let mut stats = Locs::new();
+///         stats.docs = source.lines().count() as u64;
+///         stats.total = stats.docs;
+///         Ok(FileAnalysis {
+///             language: LanguageId::External("asciidoc".into()),
+///             stats,
+///             line_classes: Vec::new(),
+///             parse_failure: None,
+///         })
+///     }
+/// }
+///
+/// let registry = BackendRegistry::new().with_backend("asciidoc", 0, AsciiDoc)?;
+/// assert!(registry.supports_path(Path::new("README.adoc")));
+/// # Ok::<(), rustloclib::RustlocError>(())
+/// ```
+pub struct BackendRegistry {
+    backends: Vec<RegisteredBackend>,
+    classifier: PathClassifier,
+}
+
+impl Default for BackendRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for BackendRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.backends
+                    .iter()
+                    .map(|entry| (&entry.name, entry.priority)),
+            )
+            .finish()
+    }
+}
+
+impl BackendRegistry {
+    /// Create a registry with the built-in backends.
+    pub fn new() -> Self {
+        let builtins: [(LanguageName, Box<dyn LanguageBackend>); 10] = [
+            (LanguageName::Rust, Box::new(RustBackend)),
+            (LanguageName::Python, Box::new(PythonBackend)),
+            (LanguageName::TypeScript, Box::new(TypeScriptBackend)),
+            (LanguageName::Jvm, Box::new(JvmBackend)),
+            (LanguageName::Cpp, Box::new(CppBackend)),
+            (LanguageName::Zig, Box::new(ZigBackend)),
+            (LanguageName::Ruby, Box::new(RubyBackend)),
+            (LanguageName::Markdown, Box::new(MarkdownBackend)),
+            (LanguageName::Config, Box::new(ConfigBackend)),
+            (LanguageName::Generic, Box::new(GenericBackend)),
+        ];
+        Self {
+            backends: builtins
+                .into_iter()
+                .map(|(language, backend)| RegisteredBackend {
+                    name: language.name().to_string(),
+                    priority: BUILTIN_PRIORITY,
+                    backend,
+                    plugin: false,
+                })
+                .collect(),
+            classifier: PathClassifier::default(),
         }
-        return LineClass::Comments;
     }
 
-    if language
-        .line_comments
-        .iter()
-        .any(|prefix| trimmed.starts_with(prefix))
-    {
-        return LineClass::Comments;
+    /// Create a registry without any backends.
+    pub fn empty() -> Self {
+        Self {
+            backends: Vec::new(),
+            classifier: PathClassifier::default(),
+        }
     }
 
-    if let Some((start, end)) = language.block_comment {
-        if trimmed.starts_with(start) {
-            if !trimmed.contains(end) {
-                *in_block_comment = true;
-            }
-            return LineClass::Comments;
+    /// Create a registry with the built-in backends plus user-defined
+    /// languages.
+    pub fn with_languages(
+        definitions: impl IntoIterator<Item = LanguageDefinition>,
+    ) -> Result<Self> {
+        Self::with_config(LanguageConfig {
+            languages: definitions.into_iter().collect(),
+            ..LanguageConfig::default()
+        })
+    }
+
+    /// Create a registry with the built-in backends plus the languages and
+    /// plugins of a configuration file, registered at [`CONFIG_PRIORITY`],
+    /// and its classification rules.
+    pub fn with_config(config: LanguageConfig) -> Result<Self> {
+        let mut registry = Self::new().with_classifier(PathClassifier::new(config.classify)?);
+        for definition in config.languages {
+            let backend = CustomBackend::new(definition)?;
+            registry.check_config_name(backend.name())?;
+            registry.register(backend.name().to_string(), CONFIG_PRIORITY, backend)?;
         }
+        for definition in config.plugins {
+            let backend = ExternalBackend::new(definition)?;
+            registry.check_config_name(backend.name())?;
+            registry.insert(
+                backend.name().to_string(),
+                CONFIG_PRIORITY,
+                Box::new(backend),
+                true,
+            )?;
+        }
+        Ok(registry)
     }
 
-    LineClass::Logic(context)
-}
+    fn check_config_name(&self, name: &str) -> Result<()> {
+        if self.contains(name) {
+            return Err(RustlocError::InvalidLanguageConfig(format!(
+                "language '{}' is defined more than once",
+                name
+            )));
+        }
+        Ok(())
+    }
 
-/// Registry of language backends available to the analyzer.
-#[derive(Debug, Default)]
-pub struct BackendRegistry {
-    rust: RustBackend,
-    python: PythonBackend,
-    typescript: TypeScriptBackend,
-    generic: GenericBackend,
-}
+    /// Decide the code, test or example context of matching paths with
+    /// `classifier` instead of each backend's path rules.
+    pub fn with_classifier(mut self, classifier: PathClassifier) -> Self {
+        self.classifier = classifier;
+        self
+    }
 
-impl BackendRegistry {
-    pub fn new() -> Self {
-        Self::default()
+    /// The classification rules applied before analysis.
+    pub fn classifier(&self) -> &PathClassifier {
+        &self.classifier
+    }
+
+    /// Register a backend under a unique `name` with the given `priority`.
+    ///
+    /// Names are matched case-insensitively and `all` is reserved.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        priority: i32,
+        backend: impl LanguageBackend + 'static,
+    ) -> Result<()> {
+        self.insert(name.into(), priority, Box::new(backend), false)
+    }
+
+    fn insert(
+        &mut self,
+        name: String,
+        priority: i32,
+        backend: Box<dyn LanguageBackend>,
+        plugin: bool,
+    ) -> Result<()> {
+        if name.trim().is_empty() || name.eq_ignore_ascii_case("all") {
+            return Err(RustlocError::InvalidBackend(format!(
+                "'{}' is not a valid backend name",
+                name
+            )));
+        }
+        if self.contains(&name) {
+            return Err(RustlocError::InvalidBackend(format!(
+                "a backend named '{}' is already registered",
+                name
+            )));
+        }
+        let index = self
+            .backends
+            .iter()
+            .position(|entry| entry.priority < priority)
+            .unwrap_or(self.backends.len());
+        self.backends.insert(
+            index,
+            RegisteredBackend {
+                name,
+                priority,
+                backend,
+                plugin,
+            },
+        );
+        Ok(())
+    }
+
+    /// Builder form of [`register`](Self::register).
+    pub fn with_backend(
+        mut self,
+        name: impl Into<String>,
+        priority: i32,
+        backend: impl LanguageBackend + 'static,
+    ) -> Result<Self> {
+        self.register(name, priority, backend)?;
+        Ok(self)
+    }
+
+    /// Whether a backend is registered under `name` (case-insensitive).
+    pub fn contains(&self, name: &str) -> bool {
+        self.resolve(name).is_some()
+    }
+
+    /// The registered spelling of `name`, matched case-insensitively. Aliases
+    /// of the built-in groups (`rs`, `py`, `kt`, ...) resolve to their group.
+    pub fn resolve(&self, name: &str) -> Option<&str> {
+        let registered = |candidate: &str| {
+            self.backends
+                .iter()
+                .find(|entry| entry.name.eq_ignore_ascii_case(candidate))
+                .map(|entry| entry.name.as_str())
+        };
+        registered(name).or_else(|| {
+            name.parse::<LanguageName>()
+                .ok()
+                .and_then(|language| registered(language.name()))
+        })
+    }
+
+    /// Registered backend names, in lookup order.
+    pub fn names(&self) -> Vec<&str> {
+        self.backends
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect()
+    }
+
+    /// Names of the registered backends that are not built-in groups, in
+    /// lookup order.
+    pub fn custom_languages(&self) -> Vec<&str> {
+        self.names()
+            .into_iter()
+            .filter(|name| {
+                !available_languages()
+                    .iter()
+                    .any(|language| language.name() == *name)
+            })
+            .collect()
+    }
+
+    /// Names of the registered plugins, which run an external program per
+    /// file, in lookup order.
+    pub fn plugins(&self) -> Vec<&str> {
+        self.backends
+            .iter()
+            .filter(|entry| entry.plugin)
+            .map(|entry| entry.name.as_str())
+            .collect()
+    }
+
+    /// Every registered backend except plugins, which only run when
+    /// selected by name.
+    pub fn all_languages(&self) -> LanguageSelection {
+        LanguageSelection::from_names(
+            self.backends
+                .iter()
+                .filter(|entry| !entry.plugin)
+                .map(|entry| entry.name.as_str()),
+        )
+    }
+
+    /// Every registered backend, plugins included, for the lookups that do
+    /// not take a selection.
+    fn every_backend(&self) -> LanguageSelection {
+        LanguageSelection::from_names(self.names())
     }
 
     pub fn backend_for_path(&self, path: &Path) -> Option<&dyn LanguageBackend> {
-        self.backend_for_path_with_languages(path, &LanguageSelection::all())
+        self.backend_for_path_with_languages(path, &self.every_backend())
     }
 
+    /// The backend claiming `path` by extension or filename, without reading
+    /// the file.
     pub fn backend_for_path_with_languages(
         &self,
         path: &Path,
         languages: &LanguageSelection,
     ) -> Option<&dyn LanguageBackend> {
-        let backends: [(LanguageName, &dyn LanguageBackend); 4] = [
-            (LanguageName::Rust, &self.rust),
-            (LanguageName::Python, &self.python),
-            (LanguageName::TypeScript, &self.typescript),
-            (LanguageName::Generic, &self.generic),
-        ];
-        backends.into_iter().find_map(|(language, backend)| {
-            if languages.contains(language) && backend.supports_path(path) {
-                Some(backend)
+        self.claim_path(path, languages)
+            .map(|entry| entry.backend.as_ref())
+    }
+
+    /// Route `path` by extension or filename only.
+    pub fn detect_path_with_languages(
+        &self,
+        path: &Path,
+        languages: &LanguageSelection,
+    ) -> Option<Detection> {
+        self.detect(path, languages, ContentHints::default)
+            .map(|(_, detection)| detection)
+    }
+
+    /// Route `path` by extension or filename, then by the hints in the file's
+    /// content.
+    pub fn detect_file_with_languages(
+        &self,
+        path: &Path,
+        languages: &LanguageSelection,
+    ) -> Option<Detection> {
+        self.detect(path, languages, || ContentHints::from_path(path))
+            .map(|(_, detection)| detection)
+    }
+
+    /// Route `path` by extension or filename, then by the hints in `source`.
+    pub fn detect_source_with_languages(
+        &self,
+        path: &Path,
+        source: &str,
+        languages: &LanguageSelection,
+    ) -> Option<Detection> {
+        self.detect(path, languages, || ContentHints::from_source(source))
+            .map(|(_, detection)| detection)
+    }
+
+    fn claim_path(&self, path: &Path, languages: &LanguageSelection) -> Option<&RegisteredBackend> {
+        self.backends
+            .iter()
+            .find(|entry| languages.contains_name(&entry.name) && entry.backend.supports_path(path))
+    }
+
+    /// Content is only sniffed when no selected backend claims the path, as
+    /// for scripts such as `bin/deploy` or a Python `tool.cgi`. Files with a
+    /// [`BINARY_EXTENSIONS`] extension are never scripts and are not read.
+    fn detect(
+        &self,
+        path: &Path,
+        languages: &LanguageSelection,
+        hints: impl FnOnce() -> ContentHints,
+    ) -> Option<(&dyn LanguageBackend, Detection)> {
+        if let Some(entry) = self.claim_path(path, languages) {
+            // A backend that accepts any file with this extension claimed it
+            // by extension; otherwise the file name decided.
+            let by_extension = path.extension().is_some_and(|ext| {
+                entry
+                    .backend
+                    .supports_path(&Path::new("_").with_extension(ext))
+            });
+            let reason = if by_extension {
+                DetectionReason::Extension
             } else {
-                None
-            }
+                DetectionReason::Filename
+            };
+            return Some(entry.detected(reason));
+        }
+        if path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                BINARY_EXTENSIONS
+                    .iter()
+                    .any(|binary| ext.eq_ignore_ascii_case(binary))
+            })
+        {
+            return None;
+        }
+
+        let hints = hints();
+        [
+            (hints.modeline, DetectionReason::Modeline),
+            (hints.interpreter, DetectionReason::Shebang),
+        ]
+        .into_iter()
+        .find_map(|(hint, reason)| {
+            let hint = hint?;
+            self.backends
+                .iter()
+                .find(|entry| {
+                    languages.contains_name(&entry.name) && entry.backend.supports_hint(&hint)
+                })
+                .map(|entry| entry.detected(reason))
         })
     }
 
+    /// Whether a selected backend claims `path` by extension or filename.
     pub fn supports_path(&self, path: &Path) -> bool {
         self.backend_for_path(path).is_some()
     }
@@ -477,10 +910,14 @@ impl BackendRegistry {
             .is_some()
     }
 
+    /// Like [`supports_path_with_languages`](Self::supports_path_with_languages),
+    /// but also sniffs the file's content for a shebang or modeline.
+    pub fn supports_file_with_languages(&self, path: &Path, languages: &LanguageSelection) -> bool {
+        self.detect_file_with_languages(path, languages).is_some()
+    }
+
     pub fn analyze_source(&self, path: &Path, source: &str) -> Result<Option<FileAnalysis>> {
-        self.backend_for_path(path)
-            .map(|backend| backend.analyze_source(path, source))
-            .transpose()
+        self.analyze_source_with_languages(path, source, &self.every_backend())
     }
 
     pub fn analyze_source_with_languages(
@@ -489,31 +926,211 @@ impl BackendRegistry {
         source: &str,
         languages: &LanguageSelection,
     ) -> Result<Option<FileAnalysis>> {
-        self.backend_for_path_with_languages(path, languages)
-            .map(|backend| backend.analyze_source(path, source))
+        self.detect(path, languages, || ContentHints::from_source(source))
+            .map(|(backend, _)| self.analyze_with(backend, path, source, languages))
             .transpose()
     }
 
-    pub fn analyze_path(&self, path: &Path) -> Result<Option<FileAnalysis>> {
-        self.backend_for_path(path)
-            .map(|backend| backend.analyze_path(path))
+    /// Analyze already-read source and report which backend analyzed it and
+    /// why.
+    pub fn analyze_source_with_detection(
+        &self,
+        path: &Path,
+        source: &str,
+        languages: &LanguageSelection,
+    ) -> Result<Option<(FileAnalysis, Detection)>> {
+        self.detect(path, languages, || ContentHints::from_source(source))
+            .map(|(backend, detection)| {
+                Ok((
+                    self.analyze_with(backend, path, source, languages)?,
+                    detection,
+                ))
+            })
+            .transpose()
+    }
+
+    /// Analyze source read while counting `root`. Backends see `path` as
+    /// walked, so counting a `tests/` directory directly still counts tests,
+    /// while classification rules and language path globs are matched
+    /// against the path relative to `root`.
+    pub fn analyze_source_below(
+        &self,
+        root: &Path,
+        path: &Path,
+        source: &str,
+        languages: &LanguageSelection,
+    ) -> Result<Option<(FileAnalysis, Detection)>> {
+        let relative = relative_to(path, root);
+        self.detect(path, languages, || ContentHints::from_source(source))
+            .map(|(backend, detection)| {
+                Ok((
+                    self.analyze_at(backend, path, relative, source, languages)?,
+                    detection,
+                ))
+            })
             .transpose()
     }
 
+    /// Analyze already-read source with the backend `detection` names, such
+    /// as the other side of a changed file. `None` if no backend is
+    /// registered under that name.
+    pub fn analyze_source_as(
+        &self,
+        detection: &Detection,
+        path: &Path,
+        source: &str,
+        languages: &LanguageSelection,
+    ) -> Result<Option<FileAnalysis>> {
+        self.backends
+            .iter()
+            .find(|entry| entry.name == detection.backend)
+            .map(|entry| self.analyze_with(entry.backend.as_ref(), path, source, languages))
+            .transpose()
+    }
+
+    pub fn analyze_path(&self, path: &Path) -> Result<Option<FileAnalysis>> {
+        self.analyze_path_with_languages(path, &self.every_backend())
+    }
+
     pub fn analyze_path_with_languages(
         &self,
         path: &Path,
         languages: &LanguageSelection,
     ) -> Result<Option<FileAnalysis>> {
-        self.backend_for_path_with_languages(path, languages)
-            .map(|backend| backend.analyze_path(path))
+        Ok(self
+            .analyze_path_with_detection(path, languages)?
+            .map(|(analysis, _)| analysis))
+    }
+
+    /// Analyze a file and report which backend analyzed it and why.
+    pub fn analyze_path_with_detection(
+        &self,
+        path: &Path,
+        languages: &LanguageSelection,
+    ) -> Result<Option<(FileAnalysis, Detection)>> {
+        self.detect(path, languages, || ContentHints::from_path(path))
+            .map(|(backend, detection)| {
+                let analysis = match super::content::read_source(path, None)? {
+                    Ok(source) => self.analyze_with(backend, path, &source.text, languages)?,
+                    Err(skipped) => {
+                        return Err(RustlocError::SkippedFile {
+                            path: skipped.path,
+                            reason: skipped.reason,
+                        })
+                    }
+                };
+                Ok((analysis, detection))
+            })
             .transpose()
     }
+
+    fn analyze_with(
+        &self,
+        backend: &dyn LanguageBackend,
+        path: &Path,
+        source: &str,
+        languages: &LanguageSelection,
+    ) -> Result<FileAnalysis> {
+        self.analyze_at(backend, path, path, source, languages)
+    }
+
+    /// Analyze `path`, matching classification rules and configured path
+    /// globs against `relative`, and re-classify its embedded blocks through
+    /// the `languages` they name. Code lines of a file a rule assigns to a
+    /// user-defined category are counted under it; its line classes still
+    /// read as code.
+    fn analyze_at(
+        &self,
+        backend: &dyn LanguageBackend,
+        path: &Path,
+        relative: &Path,
+        source: &str,
+        languages: &LanguageSelection,
+    ) -> Result<FileAnalysis> {
+        let context = self
+            .classifier
+            .context_for(relative)
+            .or_else(|| backend.configured_context(relative));
+        let mut analysis = match context {
+            Some(context) => backend.analyze_source_in_context(path, source, context)?,
+            None => backend.analyze_source(path, source)?,
+        };
+        self.reclassify_embedded(backend, source, &mut analysis, languages);
+        if let Some(category) = self.classifier.category_for(relative) {
+            analysis.stats.move_code_to_category(category);
+        }
+        Ok(analysis)
+    }
+
+    /// Re-classify the bodies of the blocks `backend` finds embedded in
+    /// `source` through the selected backends their language names, as
+    /// [`LanguageBackend::embedded_blocks`] describes. A block whose language
+    /// no selected backend knows, or that its backend fails on, is left as
+    /// it is.
+    fn reclassify_embedded(
+        &self,
+        backend: &dyn LanguageBackend,
+        source: &str,
+        analysis: &mut FileAnalysis,
+        languages: &LanguageSelection,
+    ) {
+        let blocks = backend.embedded_blocks(source);
+        if blocks.is_empty() {
+            return;
+        }
+        let lines: Vec<&str> = source.lines().collect();
+        if analysis.line_classes.len() != lines.len() {
+            return;
+        }
+        for block in blocks {
+            let Some(tag) = block.language.as_deref() else {
+                continue;
+            };
+            let snippet = format!("snippet.{tag}");
+            let snippet = Path::new(&snippet);
+            let resolved = self.resolve(tag);
+            let Some(entry) = self.backends.iter().find(|entry| {
+                languages.contains_name(&entry.name)
+                    && (resolved == Some(entry.name.as_str())
+                        || entry.backend.supports_hint(tag)
+                        || entry.backend.supports_path(snippet))
+            }) else {
+                continue;
+            };
+            let Some(body) = lines.get(block.lines.clone()) else {
+                continue;
+            };
+            let text: String = body.iter().flat_map(|line| [*line, "\n"]).collect();
+            let Ok(inner) = entry.backend.analyze_source(snippet, &text) else {
+                continue;
+            };
+            if inner.line_classes.len() != body.len() {
+                continue;
+            }
+            for (class, inner) in analysis.line_classes[block.lines]
+                .iter_mut()
+                .zip(inner.line_classes)
+            {
+                if matches!(
+                    inner,
+                    LineClass::Docs | LineClass::Comments | LineClass::Blanks
+                ) {
+                    *class = inner;
+                }
+            }
+        }
+        let mut stats = Locs::new();
+        for class in &analysis.line_classes {
+            class.record(&mut stats);
+        }
+        analysis.stats = stats;
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::data::classify::ClassifyRule;
 
     #[test]
     fn registry_selects_rust_backend_for_rs_files() {
@@ -527,8 +1144,8 @@ mod tests {
     fn registry_returns_none_for_unsupported_files() {
         let registry = BackendRegistry::new();
 
-        assert!(!registry.supports_path(Path::new("README.md")));
-        assert!(registry.backend_for_path(Path::new("README.md")).is_none());
+        assert!(!registry.supports_path(Path::new("LICENSE")));
+        assert!(registry.backend_for_path(Path::new("notes.txt")).is_none());
     }
 
     #[test]
@@ -642,4 +1259,230 @@ mod tests {
         assert!(analysis.stats.code > 0);
         assert!(analysis.stats.tests > 0);
     }
+
+    #[test]
+    fn registry_routes_selected_user_defined_languages_first() {
+        let definition = |name: &str, ext: &str| LanguageDefinition {
+            name: name.to_string(),
+            extensions: vec![ext.to_string()],
+            line_comments: vec!["#".to_string()],
+            ..LanguageDefinition::default()
+        };
+        let registry = BackendRegistry::with_languages([
+            definition("terraform", "tf"),
+            definition("shellish", "sh"),
+        ])
+        .unwrap();
+        let tf = Path::new("main.tf");
+
+        assert_eq!(registry.custom_languages(), vec!["terraform", "shellish"]);
+        assert!(!registry.supports_path_with_languages(tf, &LanguageSelection::all()));
+        assert!(registry.supports_path(tf));
+
+        let selection = LanguageSelection::new(&[LanguageName::Generic]).with_name("terraform");
+        let analysis = registry
+            .analyze_source_with_languages(tf, "# vars\nx = 1\n", &selection)
+            .unwrap()
+            .unwrap();
+        assert_eq!(analysis.language, LanguageId::External("terraform".into()));
+        assert_eq!(analysis.stats.comments, 1);
+
+        // A selected definition takes over an extension from the generic backend.
+        let script = Path::new("run.sh");
+        let generic = registry
+            .analyze_source_with_languages(script, "echo\n", &selection)
+            .unwrap()
+            .unwrap();
+        assert_eq!(generic.language, LanguageId::External("Shell".into()));
+        let custom = registry
+            .analyze_source_with_languages(script, "echo\n", &selection.with_name("shellish"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(custom.language, LanguageId::External("shellish".into()));
+
+        assert!(BackendRegistry::with_languages([
+            definition("terraform", "tf"),
+            definition("terraform", "tfvars"),
+        ])
+        .is_err());
+    }
+
+    struct Fixed(&'static str);
+
+    impl LanguageBackend for Fixed {
+        fn supports_path(&self, path: &Path) -> bool {
+            path.extension()
+                .is_some_and(|ext| ext == "rs" || ext == "x")
+        }
+
+        fn analyze_source(&self, _path: &Path, _source: &str) -> Result<FileAnalysis> {
+            Ok(FileAnalysis {
+                language: LanguageId::External(self.0.to_string()),
+                stats: Locs::new(),
+                line_classes: Vec::new(),
+                parse_failure: None,
+            })
+        }
+    }
+
+    #[test]
+    fn registered_backends_are_tried_by_priority_then_order() {
+        let registry = BackendRegistry::new()
+            .with_backend("low", -1, Fixed("low"))
+            .unwrap()
+            .with_backend("high", 10, Fixed("high"))
+            .unwrap()
+            .with_backend("also-high", 10, Fixed("also-high"))
+            .unwrap();
+        let language = |selection: &LanguageSelection, path: &str| {
+            registry
+                .analyze_source_with_languages(Path::new(path), "", selection)
+                .unwrap()
+                .map(|analysis| analysis.language)
+        };
+
+        assert_eq!(&registry.names()[..3], ["high", "also-high", "rust"]);
+        assert_eq!(registry.names().last(), Some(&"low"));
+        assert_eq!(
+            registry.custom_languages(),
+            vec!["high", "also-high", "low"]
+        );
+
+        let all = registry.all_languages();
+        assert_eq!(
+            language(&all, "a.rs"),
+            Some(LanguageId::External("high".into()))
+        );
+        let without_high = LanguageSelection::from_names(["also-high", "rust", "low"]);
+        assert_eq!(
+            language(&without_high, "a.rs"),
+            Some(LanguageId::External("also-high".into()))
+        );
+        // Built-ins outrank a lower-priority backend for the paths they claim.
+        let low = LanguageSelection::default().with_name("low");
+        assert_eq!(language(&low, "a.rs"), Some(LanguageId::Rust));
+        assert_eq!(
+            language(&low, "a.x"),
+            Some(LanguageId::External("low".into()))
+        );
+    }
+
+    #[test]
+    fn registration_validates_names() {
+        let mut registry = BackendRegistry::empty();
+        assert!(registry.names().is_empty());
+        assert!(!registry.supports_path(Path::new("src/lib.rs")));
+
+        registry.register("Fixed", 0, Fixed("fixed")).unwrap();
+        assert_eq!(registry.resolve("fixed"), Some("Fixed"));
+        for name in ["fixed", "ALL", " "] {
+            assert!(matches!(
+                registry.register(name, 0, Fixed("again")),
+                Err(RustlocError::InvalidBackend(_))
+            ));
+        }
+
+        // Aliases resolve to the built-in group they name.
+        assert_eq!(BackendRegistry::new().resolve("kt"), Some("jvm"));
+        assert_eq!(registry.resolve("rs"), None);
+    }
+
+    #[test]
+    fn detects_well_known_filenames_shebangs_and_modelines() {
+        let registry = BackendRegistry::new();
+        let all = registry.all_languages();
+        let detect = |path: &str, source: &str| {
+            registry
+                .detect_source_with_languages(Path::new(path), source, &all)
+                .map(|detection| (detection.backend, detection.reason.name()))
+        };
+        let expect = |backend: &str, reason: &'static str| Some((backend.to_string(), reason));
+
+        assert_eq!(detect("src/lib.rs", ""), expect("rust", "extension"));
+        assert_eq!(detect("Rakefile", ""), expect("ruby", "filename"));
+        assert_eq!(detect("Dockerfile", ""), expect("generic", "filename"));
+        assert_eq!(detect("justfile", ""), expect("generic", "filename"));
+        assert_eq!(
+            detect("bin/deploy", "#!/usr/bin/env python3\n"),
+            expect("python", "shebang")
+        );
+        assert_eq!(
+            detect("bin/tool", "#!/bin/sh\n# vim: ft=ruby\n"),
+            expect("ruby", "modeline")
+        );
+        assert_eq!(detect("bin/deploy", "no hints\n"), None);
+        // An extension no backend claims does not stop the sniffing; a
+        // binary one does.
+        assert_eq!(
+            detect("tool.cgi", "#!/usr/bin/env python3\n"),
+            expect("python", "shebang")
+        );
+        assert_eq!(
+            detect("notes.txt", "# vim: ft=ruby\n"),
+            expect("ruby", "modeline")
+        );
+        assert_eq!(detect("logo.PNG", "#!/usr/bin/env python3\n"), None);
+        // Content never overrides a backend that claims the path.
+        assert_eq!(
+            detect("main.py", "#!/usr/bin/env ruby\n"),
+            expect("python", "extension")
+        );
+
+        let script = registry
+            .analyze_source(Path::new("bin/deploy"), "#!/bin/bash\n# step\necho hi\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(script.language, LanguageId::External("Shell".into()));
+        assert_eq!(script.stats.comments, 2);
+        assert_eq!(script.stats.code, 1);
+
+        // Hints only route to selected backends.
+        let rust_only = LanguageSelection::default();
+        assert!(registry
+            .detect_source_with_languages(Path::new("bin/deploy"), "#!/bin/sh\n", &rust_only)
+            .is_none());
+    }
+
+    #[test]
+    fn classify_rules_set_the_context_of_every_backend() {
+        let registry = BackendRegistry::with_config(LanguageConfig {
+            classify: vec![
+                ClassifyRule::new("src/testing/**", "tests"),
+                ClassifyRule::new("demos/**", "examples"),
+            ],
+            ..LanguageConfig::default()
+        })
+        .unwrap();
+        let analyze = |path: &str, source: &str| {
+            registry
+                .analyze_source(Path::new(path), source)
+                .unwrap()
+                .unwrap()
+                .stats
+        };
+
+        let helpers = analyze(
+            "src/testing/helpers.rs",
+            "pub fn fixture() -> u32 {\n    1\n}\n",
+        );
+        assert_eq!((helpers.code, helpers.tests), (0, 3));
+
+        // Semantic classification still applies inside a file the rules make
+        // code or examples.
+        let demo = analyze(
+            "demos/app.rs",
+            "fn main() {}\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn t() {}\n}\n",
+        );
+        assert_eq!((demo.code, demo.examples, demo.tests), (0, 1, 5));
+        let demo = analyze(
+            "demos/app.py",
+            "def main():\n    pass\n\ndef test_main():\n    main()\n",
+        );
+        assert_eq!((demo.code, demo.examples, demo.tests), (0, 2, 2));
+        assert_eq!(analyze("demos/app.go", "package main\n").examples, 1);
+
+        // Fenced Markdown code is an example wherever the file lives.
+        let guide = analyze("src/testing/guide.md", "```rust\nfn main() {}\n```\n");
+        assert_eq!((guide.tests, guide.examples), (0, 1));
+    }
 }
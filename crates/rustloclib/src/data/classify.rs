@@ -0,0 +1,340 @@
+//! User-defined path classification rules.
+//!
+//! Each backend decides from a file's path whether its logic lines are code,
+//! tests or examples (`tests/`, `test_*.py`, `examples/`, ...). Projects with
+//! other layouts add ordered `glob=context` rules, such as
+//! `src/testing/**=tests`; the first rule matching a file sets its context
+//! for every backend, ahead of the kind of the Cargo target holding it, and
+//! semantic classification such as `#[test]` functions still applies on top.
+//!
+//! A context other than `code`, `tests` or `examples` names a new category,
+//! as in `crates/*/fixtures/**=fixtures`: its files are analyzed as
+//! production code, and their code lines are counted under the category
+//! (see [`Locs::categories`](super::stats::Locs::categories)).
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+
+use crate::error::RustlocError;
+use crate::Result;
+
+use super::backend::LogicContext;
+
+/// One `[[classify]]` table or `--classify GLOB=CONTEXT` argument.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClassifyRule {
+    /// Glob matched against file paths, such as `src/testing/**`.
+    pub path: String,
+    /// Context of the matching files: `code`, `tests`, `examples`, or the
+    /// name of a new category.
+    pub context: String,
+}
+
+impl ClassifyRule {
+    pub fn new(path: impl Into<String>, context: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            context: context.into(),
+        }
+    }
+}
+
+impl FromStr for ClassifyRule {
+    type Err = RustlocError;
+
+    /// Parse `GLOB=CONTEXT`, splitting at the last `=`.
+    fn from_str(value: &str) -> Result<Self> {
+        match value.rsplit_once('=') {
+            Some((path, context)) if !path.trim().is_empty() && !context.trim().is_empty() => {
+                Ok(Self::new(path.trim(), context.trim()))
+            }
+            _ => Err(RustlocError::InvalidClassifyRule(format!(
+                "'{}' is not GLOB=CONTEXT",
+                value
+            ))),
+        }
+    }
+}
+
+/// Where a rule puts the files it matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    Context(LogicContext),
+    Category(String),
+}
+
+/// Line type names a category cannot take.
+const RESERVED: &[&str] = &[
+    "docs",
+    "comments",
+    "blanks",
+    "preprocessor",
+    "config",
+    "total",
+    "label",
+];
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        if let Ok(context) = value.parse() {
+            return Ok(Self::Context(context));
+        }
+        let name = value.trim().to_ascii_lowercase();
+        let valid = name.starts_with(|c: char| c.is_ascii_lowercase())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+        if !valid {
+            Err(format!(
+                "'{}' is not a category name; use letters, digits, '-' and '_'",
+                value
+            ))
+        } else if RESERVED.contains(&name.as_str()) {
+            Err(format!("'{}' is a line type, not a category", name))
+        } else {
+            Ok(Self::Category(name))
+        }
+    }
+}
+
+/// Ordered classification rules, compiled.
+///
+/// Globs are anchored at the root of the analysis: `demos/**` matches
+/// `demos/app.rs` but not `tools/demos/app.rs`, while a leading `**/` matches
+/// anywhere, as in `**/fixtures/**`. A `*` never crosses a `/`, so
+/// `crates/*/fixtures/**` matches one crate directory deep. Paths beneath
+/// the root given to [`with_root`](Self::with_root) are matched relative to
+/// it, and other paths, such as the repository-relative paths of a diff, as
+/// they are.
+///
+/// ```
+/// use std::path::Path;
+/// use rustloclib::{ClassifyRule, LogicContext, PathClassifier};
+///
+/// let classifier = PathClassifier::new([
+///     ClassifyRule::new("src/testing/**", "tests"),
+///     ClassifyRule::new("**/demos/**", "examples"),
+///     ClassifyRule::new("crates/*/fixtures/**", "fixtures"),
+/// ])?
+/// .with_root("/repo");
+/// assert_eq!(
+///     classifier.context_for(Path::new("/repo/src/testing/mock.rs")),
+///     Some(LogicContext::Tests)
+/// );
+/// assert_eq!(
+///     classifier.context_for(Path::new("/repo/crates/app/src/testing/mock.rs")),
+///     None
+/// );
+/// assert_eq!(
+///     classifier.context_for(Path::new("tools/demos/app.rs")),
+///     Some(LogicContext::Example)
+/// );
+/// assert_eq!(
+///     classifier.category_for(Path::new("/repo/crates/cli/fixtures/input.rs")),
+///     Some("fixtures")
+/// );
+/// # Ok::<(), rustloclib::RustlocError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PathClassifier {
+    rules: Vec<(Pattern, Target)>,
+    roots: Vec<PathBuf>,
+}
+
+impl PathClassifier {
+    /// Compile `rules`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`RustlocError::InvalidClassifyRule`] for a category name
+    /// that is not a lowercase identifier or is a built-in line type such as
+    /// `docs`, rather than counting its files somewhere the rule did not ask
+    /// for.
+    pub fn new(rules: impl IntoIterator<Item = ClassifyRule>) -> Result<Self> {
+        let rules = rules
+            .into_iter()
+            .map(|rule| {
+                let pattern = Pattern::new(&rule.path).map_err(|e| RustlocError::InvalidGlob {
+                    pattern: rule.path.clone(),
+                    message: e.to_string(),
+                })?;
+                let target = rule.context.parse().map_err(|message| {
+                    RustlocError::InvalidClassifyRule(format!(
+                        "'{}={}': {}",
+                        rule.path, rule.context, message
+                    ))
+                })?;
+                Ok((pattern, target))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            rules,
+            roots: Vec::new(),
+        })
+    }
+
+    /// Match paths beneath `root`, as given or canonicalized, relative to it.
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        self.roots = root.canonicalize().into_iter().chain([root]).collect();
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The user-defined categories, in rule order.
+    pub fn categories(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for (_, target) in &self.rules {
+            if let Target::Category(name) = target {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+
+    /// The context set by the first rule matching `path`, if any. Files of a
+    /// user-defined category are production code until their code lines are
+    /// moved to it.
+    pub fn context_for(&self, path: &Path) -> Option<LogicContext> {
+        self.target_for(path).map(|target| match target {
+            Target::Context(context) => *context,
+            Target::Category(_) => LogicContext::Code,
+        })
+    }
+
+    /// The user-defined category of the first rule matching `path`, if that
+    /// rule names one.
+    pub fn category_for(&self, path: &Path) -> Option<&str> {
+        match self.target_for(path)? {
+            Target::Category(name) => Some(name),
+            Target::Context(_) => None,
+        }
+    }
+
+    fn target_for(&self, path: &Path) -> Option<&Target> {
+        if self.rules.is_empty() {
+            return None;
+        }
+        let path = self
+            .roots
+            .iter()
+            .find_map(|root| path.strip_prefix(root).ok())
+            .unwrap_or(path);
+        let path = path.strip_prefix(".").unwrap_or(path);
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        self.rules
+            .iter()
+            .find(|(pattern, _)| pattern.matches_path_with(path, options))
+            .map(|(_, target)| target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classifier(rules: &[&str]) -> PathClassifier {
+        PathClassifier::new(rules.iter().map(|rule| rule.parse().unwrap())).unwrap()
+    }
+
+    #[test]
+    fn rules_parse_from_glob_and_context() {
+        let rule: ClassifyRule = "src/testing/** = tests".parse().unwrap();
+        assert_eq!(rule, ClassifyRule::new("src/testing/**", "tests"));
+        assert!("tests".parse::<ClassifyRule>().is_err());
+        assert!("=tests".parse::<ClassifyRule>().is_err());
+        assert!("src/**=".parse::<ClassifyRule>().is_err());
+    }
+
+    #[test]
+    fn other_contexts_name_categories() {
+        let classifier = classifier(&[
+            "crates/*/fixtures/**=Fixtures",
+            "benches/**=bench-suite",
+            "src/**=tests",
+        ]);
+        assert_eq!(classifier.categories(), ["fixtures", "bench-suite"]);
+        let fixture = Path::new("crates/cli/fixtures/input.rs");
+        assert_eq!(classifier.category_for(fixture), Some("fixtures"));
+        assert_eq!(classifier.context_for(fixture), Some(LogicContext::Code));
+        assert_eq!(classifier.category_for(Path::new("src/lib.rs")), None);
+
+        for rule in ["demos/**=docs", "demos/**=2fast", "demos/**=a b"] {
+            match PathClassifier::new([rule.parse::<ClassifyRule>().unwrap()]) {
+                Err(RustlocError::InvalidClassifyRule(message)) => {
+                    assert!(message.contains(rule), "{message}");
+                }
+                other => panic!("expected an invalid rule for {rule}, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn rules_are_anchored_at_the_root_unless_they_start_with_globstar() {
+        let classifier = classifier(&[
+            "crates/*/fixtures/**=tests",
+            "demos/**=examples",
+            "**/bench/**=tests",
+        ])
+        .with_root("/repo");
+        for path in ["demos/app.rs", "./demos/app.rs", "/repo/demos/app.rs"] {
+            assert_eq!(
+                classifier.context_for(Path::new(path)),
+                Some(LogicContext::Example),
+                "{}",
+                path
+            );
+        }
+        assert_eq!(
+            classifier.context_for(Path::new("/repo/crates/cli/fixtures/input.rs")),
+            Some(LogicContext::Tests)
+        );
+        for path in [
+            "/repo/tools/demos/app.py",
+            "/elsewhere/demos/app.rs",
+            "src/demos.rs",
+            "/repo/crates/cli/nested/fixtures/input.rs",
+        ] {
+            assert_eq!(classifier.context_for(Path::new(path)), None, "{}", path);
+        }
+        for path in [
+            "bench/a.rs",
+            "/repo/crates/cli/bench/a.rs",
+            "/elsewhere/bench/a.rs",
+        ] {
+            assert_eq!(
+                classifier.context_for(Path::new(path)),
+                Some(LogicContext::Tests),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn the_first_matching_rule_wins() {
+        let classifier = classifier(&["src/testing/real.rs=code", "src/testing/**=tests"]);
+        assert_eq!(
+            classifier.context_for(Path::new("src/testing/real.rs")),
+            Some(LogicContext::Code)
+        );
+        assert_eq!(
+            classifier.context_for(Path::new("src/testing/mock.rs")),
+            Some(LogicContext::Tests)
+        );
+    }
+}
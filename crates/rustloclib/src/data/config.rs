@@ -0,0 +1,175 @@
+use std::path::Path;
+
+use crate::Result;
+
+use super::backend::{FileAnalysis, LanguageBackend, LanguageId, LineClass, LogicContext};
+use super::generic::{escaped, literal, multiline, spec, verbatim, GenericLanguage, C_BLOCK};
+use super::lexer::lex;
+use super::stats::Locs;
+
+/// Backend for configuration and data files: TOML, YAML, JSON, INI and SQL.
+///
+/// Comments and blanks are classified from each format's comment syntax;
+/// every other line is counted as config rather than code, whatever the
+/// file's location.
+#[derive(Debug, Default)]
+pub struct ConfigBackend;
+
+const CONFIG_LANGUAGES: &[GenericLanguage] = &[
+    GenericLanguage {
+        id: "TOML",
+        extensions: &["toml"],
+        filenames: &["Pipfile"],
+        hints: &["toml"],
+        spec: spec(
+            &["#"],
+            &[],
+            &[
+                multiline("\"\"\""),
+                verbatim("'''", "'''"),
+                escaped("\""),
+                literal("'"),
+            ],
+        ),
+    },
+    GenericLanguage {
+        id: "YAML",
+        extensions: &["yaml", "yml"],
+        filenames: &[".clang-format", ".clang-tidy"],
+        hints: &["yaml"],
+        spec: spec(&["#"], &[], &[escaped("\""), literal("'")]),
+    },
+    GenericLanguage {
+        id: "JSON",
+        extensions: &["json", "jsonc", "json5"],
+        filenames: &[".babelrc", ".eslintrc", ".prettierrc"],
+        hints: &["json", "jsonc", "json5"],
+        // Plain JSON has no comments; JSONC and JSON5 borrow JavaScript's.
+        spec: spec(&["//"], &[C_BLOCK], &[escaped("\"")]),
+    },
+    GenericLanguage {
+        id: "INI",
+        extensions: &["ini", "cfg", "conf", "properties"],
+        filenames: &[".editorconfig", ".gitconfig", ".npmrc"],
+        hints: &["ini", "dosini", "conf", "cfg", "properties"],
+        spec: spec(&[";", "#"], &[], &[escaped("\"")]),
+    },
+    GenericLanguage {
+        id: "SQL",
+        extensions: &["sql"],
+        filenames: &[],
+        hints: &["sql", "mysql", "plsql", "pgsql"],
+        spec: spec(&["--"], &[C_BLOCK], &[verbatim("'", "'"), escaped("\"")]),
+    },
+];
+
+/// Generated lock files, which would drown hand-written config.
+const GENERATED: &[&str] = &["package-lock.json", "npm-shrinkwrap.json", "pnpm-lock.yaml"];
+
+impl LanguageBackend for ConfigBackend {
+    fn supports_path(&self, path: &Path) -> bool {
+        let generated = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| GENERATED.contains(&name));
+        !generated && GenericLanguage::for_path(CONFIG_LANGUAGES, path).is_some()
+    }
+
+    fn supports_hint(&self, hint: &str) -> bool {
+        GenericLanguage::for_hint(CONFIG_LANGUAGES, hint).is_some()
+    }
+
+    fn analyze_source(&self, path: &Path, source: &str) -> Result<FileAnalysis> {
+        let language = GenericLanguage::for_file(CONFIG_LANGUAGES, path, source)?;
+        let mut stats = Locs::new();
+        let mut line_classes = Vec::new();
+
+        for line in lex(source, &language.spec) {
+            let class = match line.class(LogicContext::Code) {
+                LineClass::Logic(_) => LineClass::Config,
+                class => class,
+            };
+            class.record(&mut stats);
+            line_classes.push(class);
+        }
+
+        Ok(FileAnalysis {
+            language: LanguageId::Config,
+            stats,
+            line_classes,
+            parse_failure: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analyze(path: &str, source: &str) -> Locs {
+        ConfigBackend
+            .analyze_source(Path::new(path), source)
+            .unwrap()
+            .stats
+    }
+
+    #[test]
+    fn data_lines_are_config_not_code() {
+        let stats = analyze(
+            "tests/fixtures/Cargo.toml",
+            "# Manifest\n[package]\nname = \"demo\"\n\n[dependencies]\n",
+        );
+        assert_eq!(stats.config, 3);
+        assert_eq!(stats.comments, 1);
+        assert_eq!(stats.blanks, 1);
+        assert_eq!(stats.code + stats.tests + stats.examples, 0);
+
+        let stats = analyze(
+            ".github/workflows/ci.yml",
+            "# CI\non: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n",
+        );
+        assert_eq!((stats.config, stats.comments), (4, 1));
+
+        let stats = analyze("setup.cfg", "; legacy\n# modern\n[metadata]\nname = demo\n");
+        assert_eq!((stats.config, stats.comments), (2, 2));
+    }
+
+    #[test]
+    fn sql_and_jsonc_comments_are_comments() {
+        let stats = analyze(
+            "migrations/0001_init.sql",
+            "-- Users\n/* owned by\n   auth */\nCREATE TABLE users (\n  id INT\n);\n",
+        );
+        assert_eq!((stats.config, stats.comments), (3, 3));
+
+        let stats = analyze(
+            "tsconfig.json",
+            "{\n  // strict mode\n  \"strict\": true\n}\n",
+        );
+        assert_eq!((stats.config, stats.comments), (3, 1));
+    }
+
+    #[test]
+    fn routes_known_files_but_not_lock_files() {
+        for path in [
+            "Cargo.toml",
+            "pyproject.toml",
+            "Pipfile",
+            "config.yaml",
+            "schema.json",
+            ".editorconfig",
+            "db/schema.sql",
+        ] {
+            assert!(ConfigBackend.supports_path(Path::new(path)), "{path}");
+        }
+        for path in [
+            "package-lock.json",
+            "pnpm-lock.yaml",
+            "Cargo.lock",
+            "main.rs",
+        ] {
+            assert!(!ConfigBackend.supports_path(Path::new(path)), "{path}");
+        }
+        assert!(ConfigBackend.supports_hint("yaml"));
+    }
+}
@@ -0,0 +1,388 @@
+//! Content checks and decoding run before a file is analyzed.
+//!
+//! A file that matches a backend by name is not necessarily source code a
+//! reader wrote: it can be a binary with a source extension, a minified
+//! bundle whose one line holds a whole library, or a multi-megabyte generated
+//! table. Such files are skipped with a [`SkipReason`] instead of failing the
+//! run or inflating the counts.
+//!
+//! Everything else is decoded to UTF-8 first. Older C# and Java trees often
+//! hold UTF-16 or Latin-1 sources, so the encoding is taken from a byte order
+//! mark, from the NUL pattern of BOM-less UTF-16, or, for bytes that are not
+//! UTF-8, assumed to be Windows-1252 (a superset of Latin-1). Counting and
+//! diffing share [`decode_source`], so both read a file the same way.
+
+use std::path::{Path, PathBuf};
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use serde::{Deserialize, Serialize};
+
+use crate::error::RustlocError;
+use crate::Result;
+
+/// Bytes inspected for NUL when deciding whether content is binary, as git does.
+pub const BINARY_CHECK_LENGTH: usize = 8000;
+
+/// A line at least this long, in bytes, marks content as possibly minified.
+pub const MINIFIED_LINE_LENGTH: usize = 1000;
+
+/// Content whose average line is at least this long, in bytes, and which has a
+/// line of [`MINIFIED_LINE_LENGTH`], is minified.
+pub const MINIFIED_AVERAGE_LINE_LENGTH: usize = 200;
+
+/// Why a file was not analyzed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SkipReason {
+    /// Larger than the configured maximum file size.
+    TooLarge,
+    /// Contains a NUL byte, or control characters text does not, in every
+    /// encoding tried.
+    Binary,
+    /// Very long lines with few line breaks, as in minified bundles.
+    Minified,
+}
+
+impl SkipReason {
+    /// The reason's name in structured output.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::TooLarge => "too-large",
+            Self::Binary => "binary",
+            Self::Minified => "minified",
+        }
+    }
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The text encoding a source file was decoded from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SourceEncoding {
+    /// UTF-8 without a byte order mark.
+    #[default]
+    #[serde(rename = "utf-8")]
+    Utf8,
+    /// UTF-8 with a byte order mark.
+    #[serde(rename = "utf-8-bom")]
+    Utf8Bom,
+    /// Little-endian UTF-16, with or without a byte order mark.
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+    /// Big-endian UTF-16, with or without a byte order mark.
+    #[serde(rename = "utf-16be")]
+    Utf16Be,
+    /// Windows-1252, assumed for text that is not valid UTF-8.
+    #[serde(rename = "windows-1252")]
+    Windows1252,
+}
+
+impl SourceEncoding {
+    /// The encoding's name in structured and verbose output.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Utf8Bom => "utf-8-bom",
+            Self::Utf16Le => "utf-16le",
+            Self::Utf16Be => "utf-16be",
+            Self::Windows1252 => "windows-1252",
+        }
+    }
+
+    /// Whether the file was plain UTF-8 and needed no transcoding.
+    pub const fn is_utf8(self) -> bool {
+        matches!(self, Self::Utf8)
+    }
+}
+
+impl std::fmt::Display for SourceEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Source text decoded to UTF-8, with the encoding it was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceText {
+    /// The decoded text, without a byte order mark.
+    pub text: String,
+    /// The encoding detected in the raw bytes.
+    pub encoding: SourceEncoding,
+}
+
+/// A file that matched the filter but was not analyzed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedFile {
+    /// Path to the file.
+    pub path: PathBuf,
+    /// Why it was skipped.
+    pub reason: SkipReason,
+    /// Size of the content in bytes.
+    pub size: u64,
+}
+
+impl SkippedFile {
+    /// Create a skipped file record.
+    pub fn new(path: impl Into<PathBuf>, reason: SkipReason, size: u64) -> Self {
+        Self {
+            path: path.into(),
+            reason,
+            size,
+        }
+    }
+}
+
+/// A file that was decoded from an encoding other than plain UTF-8.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscodedFile {
+    /// Path to the file.
+    pub path: PathBuf,
+    /// The encoding the file was decoded from.
+    pub encoding: SourceEncoding,
+}
+
+impl TranscodedFile {
+    /// Record `encoding` for `path`, unless it is plain UTF-8.
+    pub fn unless_utf8(path: &Path, encoding: Option<SourceEncoding>) -> Option<Self> {
+        let encoding = encoding.filter(|e| !e.is_utf8())?;
+        Some(Self {
+            path: path.to_path_buf(),
+            encoding,
+        })
+    }
+}
+
+/// Decode raw bytes to text, or `None` if they look binary.
+///
+/// A byte order mark decides the encoding when present; otherwise a NUL in
+/// every other byte marks BOM-less UTF-16, valid UTF-8 is taken as is, and
+/// anything else is read as Windows-1252.
+pub fn decode(bytes: &[u8]) -> Option<SourceText> {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        return transcode(&bytes[bom_length..], bom_encoding(encoding));
+    }
+    if let Some(encoding) = utf16_without_bom(bytes) {
+        return transcode(bytes, encoding);
+    }
+    if head(bytes).contains(&0) {
+        return None;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some(SourceText {
+            text: text.to_owned(),
+            encoding: SourceEncoding::Utf8,
+        }),
+        Err(_) => transcode(bytes, SourceEncoding::Windows1252),
+    }
+}
+
+/// Decode raw bytes to text for line counting, falling back to lossy UTF-8
+/// for content [`decode`] considers binary.
+pub fn decode_lossy(bytes: &[u8]) -> String {
+    match decode(bytes) {
+        Some(source) => source.text,
+        None => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// Decode `bytes` for analysis, or the reason they are skipped.
+pub fn decode_source(
+    bytes: &[u8],
+    max_file_size: Option<u64>,
+) -> std::result::Result<SourceText, SkipReason> {
+    if max_file_size.is_some_and(|max| bytes.len() as u64 > max) {
+        return Err(SkipReason::TooLarge);
+    }
+    let source = decode(bytes).ok_or(SkipReason::Binary)?;
+    match check_source(&source.text, None) {
+        Some(reason) => Err(reason),
+        None => Ok(source),
+    }
+}
+
+/// Why already-decoded `source` should not be analyzed, if at all.
+pub fn check_source(source: &str, max_file_size: Option<u64>) -> Option<SkipReason> {
+    if max_file_size.is_some_and(|max| source.len() as u64 > max) {
+        return Some(SkipReason::TooLarge);
+    }
+    if head(source.as_bytes()).contains(&0) {
+        return Some(SkipReason::Binary);
+    }
+    is_minified(source).then_some(SkipReason::Minified)
+}
+
+fn head(bytes: &[u8]) -> &[u8] {
+    &bytes[..bytes.len().min(BINARY_CHECK_LENGTH)]
+}
+
+fn bom_encoding(encoding: &'static Encoding) -> SourceEncoding {
+    if encoding == UTF_16LE {
+        SourceEncoding::Utf16Le
+    } else if encoding == UTF_16BE {
+        SourceEncoding::Utf16Be
+    } else {
+        SourceEncoding::Utf8Bom
+    }
+}
+
+fn transcode(bytes: &[u8], encoding: SourceEncoding) -> Option<SourceText> {
+    let decoder = match encoding {
+        SourceEncoding::Utf8 | SourceEncoding::Utf8Bom => encoding_rs::UTF_8,
+        SourceEncoding::Utf16Le => UTF_16LE,
+        SourceEncoding::Utf16Be => UTF_16BE,
+        SourceEncoding::Windows1252 => WINDOWS_1252,
+    };
+    let text = decoder.decode_without_bom_handling(bytes).0;
+    (!has_binary_controls(&text)).then(|| SourceText {
+        text: text.into_owned(),
+        encoding,
+    })
+}
+
+/// BOM-less UTF-16 in mostly-ASCII text has a NUL in nearly every high byte
+/// and almost none in the low bytes.
+fn utf16_without_bom(bytes: &[u8]) -> Option<SourceEncoding> {
+    let head = head(bytes);
+    let units = head.len() / 2;
+    if units < 2 {
+        return None;
+    }
+    let zeros = |offset: usize| {
+        head.iter()
+            .skip(offset)
+            .step_by(2)
+            .filter(|&&byte| byte == 0)
+            .count()
+    };
+    let (even, odd) = (zeros(0), zeros(1));
+    let mostly = |count: usize| count * 10 >= units * 7;
+    let rarely = |count: usize| count * 10 < units;
+    if mostly(odd) && rarely(even) {
+        Some(SourceEncoding::Utf16Le)
+    } else if mostly(even) && rarely(odd) {
+        Some(SourceEncoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+/// Text has NUL or other C0 controls only by accident; more than a few in the
+/// head mean the bytes were not text in the guessed encoding.
+fn has_binary_controls(text: &str) -> bool {
+    let mut chars = 0;
+    let mut controls = 0;
+    for c in text.chars().take(BINARY_CHECK_LENGTH) {
+        chars += 1;
+        match c {
+            '\0' => return true,
+            '\t' | '\n' | '\r' | '\x0c' | '\x1b' => {}
+            c if c.is_ascii_control() => controls += 1,
+            _ => {}
+        }
+    }
+    controls * 100 > chars
+}
+
+fn is_minified(source: &str) -> bool {
+    if source.len() < MINIFIED_LINE_LENGTH {
+        return false;
+    }
+    let lines = source.lines().count().max(1);
+    source.len() / lines >= MINIFIED_AVERAGE_LINE_LENGTH
+        && source
+            .lines()
+            .any(|line| line.len() >= MINIFIED_LINE_LENGTH)
+}
+
+/// Read a file for analysis, or the reason it is skipped. Files over
+/// `max_file_size` are not read at all.
+pub(crate) fn read_source(
+    path: &Path,
+    max_file_size: Option<u64>,
+) -> Result<std::result::Result<SourceText, SkippedFile>> {
+    let read_error = |source| RustlocError::FileRead {
+        path: path.to_path_buf(),
+        source,
+    };
+    let size = std::fs::metadata(path).map_err(read_error)?.len();
+    if max_file_size.is_some_and(|max| size > max) {
+        return Ok(Err(SkippedFile::new(path, SkipReason::TooLarge, size)));
+    }
+    let bytes = std::fs::read(path).map_err(read_error)?;
+    Ok(decode_source(&bytes, None)
+        .map_err(|reason| SkippedFile::new(path, reason, bytes.len() as u64)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn utf16be(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_be_bytes).collect()
+    }
+
+    #[test]
+    fn source_files_pass_every_check() {
+        let source = "fn main() {\n    println!(\"hi\");\n}\n".repeat(100);
+        let decoded = decode_source(source.as_bytes(), Some(1 << 20)).unwrap();
+        assert_eq!(decoded.text, source);
+        assert_eq!(decoded.encoding, SourceEncoding::Utf8);
+    }
+
+    #[test]
+    fn each_check_names_its_reason() {
+        assert_eq!(decode_source(b"abc", Some(2)), Err(SkipReason::TooLarge));
+        assert_eq!(decode_source(b"\x7fELF\0\0", None), Err(SkipReason::Binary));
+        assert_eq!(
+            decode_source(b"\x01\x02\x03\x04\xff\xfe\x05", None),
+            Err(SkipReason::Binary)
+        );
+
+        let bundle = format!("!function(){{{}}}();\n", "var a=1;".repeat(200));
+        assert_eq!(check_source(&bundle, None), Some(SkipReason::Minified));
+    }
+
+    #[test]
+    fn one_long_line_in_an_ordinary_file_is_not_minified() {
+        let source = format!(
+            "{}const TABLE: &str = \"{}\";\n",
+            "fn f() {}\n".repeat(50),
+            "x".repeat(MINIFIED_LINE_LENGTH)
+        );
+        assert_eq!(check_source(&source, None), None);
+    }
+
+    #[test]
+    fn encodings_are_detected_from_boms_and_content() {
+        let text = "class Café {}\n";
+        let cases = [
+            (
+                [b"\xef\xbb\xbf".as_slice(), text.as_bytes()].concat(),
+                SourceEncoding::Utf8Bom,
+            ),
+            (
+                [b"\xff\xfe".as_slice(), &utf16le(text)].concat(),
+                SourceEncoding::Utf16Le,
+            ),
+            (
+                [b"\xfe\xff".as_slice(), &utf16be(text)].concat(),
+                SourceEncoding::Utf16Be,
+            ),
+            (utf16le(text), SourceEncoding::Utf16Le),
+            (utf16be(text), SourceEncoding::Utf16Be),
+            (b"class Caf\xe9 {}\n".to_vec(), SourceEncoding::Windows1252),
+        ];
+        for (bytes, encoding) in cases {
+            let decoded = decode(&bytes).unwrap();
+            assert_eq!((decoded.text.as_str(), decoded.encoding), (text, encoding));
+        }
+    }
+}
@@ -5,15 +5,28 @@
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use crate::error::RustlocError;
 use crate::query::options::{Aggregation, LineTypes};
-use crate::source::filter::{discover_files, discover_files_in_dirs, FilterConfig};
-use crate::source::workspace::{CrateInfo, WorkspaceInfo};
+use crate::source::dependencies::{discover_dependencies, DependencyQuery};
+use crate::source::filter::{
+    discover_files_in_dirs_with_report, discover_files_pruned, discover_files_with_report,
+    DiscoveredFiles, FilterConfig, SkippedLink,
+};
+use crate::source::manifest::normalize;
+use crate::source::modules::{discover_build_modules, BuildModule};
+use crate::source::project::{Package, PackageProvider, ProjectModel, PythonProvider};
+use crate::source::workspace::{
+    CrateInfo, TargetInfo, TargetKind, WorkspaceDiscovery, WorkspaceInfo,
+};
 use crate::Result;
 
-use super::backend::BackendRegistry;
-use super::stats::{CrateStats, FileStats, Locs, ModuleStats};
+use super::backend::{relative_to, BackendRegistry, LanguageName, LanguageSelection};
+use super::content::{read_source, SkippedFile, TranscodedFile};
+use super::detect::DetectedFile;
+use super::diagnostics::{check_parse, recover, Diagnostic};
+use super::stats::{CrateStats, FileStats, Locs, ModuleStats, TargetStats, WorkspaceStats};
 
 /// Options for counting LOC.
 #[derive(Debug, Clone)]
@@ -26,6 +39,8 @@ pub struct CountOptions {
     pub aggregation: Aggregation,
     /// Which line types to include in results
     pub line_types: LineTypes,
+    /// How the workspace's crates and targets are discovered
+    pub workspace_discovery: WorkspaceDiscovery,
 }
 
 impl Default for CountOptions {
@@ -35,6 +50,7 @@ impl Default for CountOptions {
             file_filter: FilterConfig::new(),
             aggregation: Aggregation::Total,
             line_types: LineTypes::default(),
+            workspace_discovery: WorkspaceDiscovery::default(),
         }
     }
 }
@@ -57,6 +73,15 @@ impl CountOptions {
         self
     }
 
+    /// Set the backend registry files are analyzed with.
+    ///
+    /// [`filter`](Self::filter) replaces the whole file filter, registry
+    /// included, so call this afterwards.
+    pub fn registry(mut self, registry: impl Into<Arc<BackendRegistry>>) -> Self {
+        self.file_filter.registry = registry.into();
+        self
+    }
+
     /// Set aggregation level.
     pub fn aggregation(mut self, level: Aggregation) -> Self {
         self.aggregation = level;
@@ -68,6 +93,12 @@ impl CountOptions {
         self.line_types = types;
         self
     }
+
+    /// Set how the workspace is discovered.
+    pub fn workspace_discovery(mut self, discovery: WorkspaceDiscovery) -> Self {
+        self.workspace_discovery = discovery;
+        self
+    }
 }
 
 /// Result of counting LOC in a workspace or directory.
@@ -85,6 +116,27 @@ pub struct CountResult {
     pub files: Vec<FileStats>,
     /// Per-module statistics (if requested)
     pub modules: Vec<ModuleStats>,
+    /// Per-target statistics (if requested)
+    #[serde(default)]
+    pub targets: Vec<TargetStats>,
+    /// Per-workspace statistics (if requested from [`count_workspaces`])
+    #[serde(default)]
+    pub workspaces: Vec<WorkspaceStats>,
+    /// Symbolic links and duplicate paths discovery did not count
+    #[serde(default)]
+    pub skipped_links: Vec<SkippedLink>,
+    /// Files that matched the filter but failed a content check
+    #[serde(default)]
+    pub skipped: Vec<SkippedFile>,
+    /// Files that could not be read, failed to parse, or were skipped
+    #[serde(default)]
+    pub diagnostics: Vec<Diagnostic>,
+    /// Files not detected by their extension, whatever the aggregation
+    #[serde(default)]
+    pub detected: Vec<DetectedFile>,
+    /// Files decoded from another encoding, whatever the aggregation
+    #[serde(default)]
+    pub transcoded: Vec<TranscodedFile>,
 }
 
 impl CountResult {
@@ -102,8 +154,26 @@ impl CountResult {
             crates: self.crates.iter().map(|c| c.filter(types)).collect(),
             files: self.files.iter().map(|f| f.filter(types)).collect(),
             modules: self.modules.iter().map(|m| m.filter(types)).collect(),
+            targets: self.targets.iter().map(|t| t.filter(types)).collect(),
+            workspaces: self.workspaces.iter().map(|w| w.filter(types)).collect(),
+            skipped_links: self.skipped_links.clone(),
+            skipped: self.skipped.clone(),
+            diagnostics: self.diagnostics.clone(),
+            detected: self.detected.clone(),
+            transcoded: self.transcoded.clone(),
         }
     }
+
+    /// Record how `file` was detected and decoded, where that is not the
+    /// common case.
+    fn note(&mut self, file: &FileStats) {
+        self.detected.extend(DetectedFile::unless_by_extension(
+            &file.path,
+            file.detection.as_ref(),
+        ));
+        self.transcoded
+            .extend(TranscodedFile::unless_utf8(&file.path, file.encoding));
+    }
 }
 
 /// Count LOC in a Cargo workspace.
@@ -114,6 +184,16 @@ impl CountResult {
 /// 3. Applies glob filters to files
 /// 4. Parses all matching files and aggregates statistics
 ///
+/// With [`Aggregation::ByCrate`] or a crate filter, packages other build tools
+/// declare beneath the workspace (a `pyproject.toml`, `package.json` or
+/// `go.mod` outside every crate) are counted alongside the crates, each from
+/// every selected file beneath its root, so mixed repositories get a row per
+/// package.
+///
+/// A member whose `Cargo.toml` cannot be read or parsed is left out with a
+/// diagnostic, or fails the count when the filter is
+/// [`strict`](FilterConfig::strict).
+///
 /// # Example
 ///
 /// ```rust
@@ -141,35 +221,270 @@ impl CountResult {
 /// let result = count_workspace(dir.path(), CountOptions::new().filter(filter)).unwrap();
 /// ```
 pub fn count_workspace(path: impl AsRef<Path>, options: CountOptions) -> Result<CountResult> {
-    let workspace = WorkspaceInfo::discover(path)?;
+    let mut diagnostics = Vec::new();
+    let workspace = WorkspaceInfo::discover_reporting(
+        path,
+        options.workspace_discovery,
+        options.file_filter.strict,
+        &mut diagnostics,
+    )?;
+    let packages = workspace_packages(&workspace, &options)?;
+    let selected =
+        |c: &CrateInfo| options.crate_filter.is_empty() || options.crate_filter.contains(&c.name);
+
+    let mut crates: Vec<(&CrateInfo, Walk)> = workspace
+        .crates
+        .iter()
+        .filter(|c| selected(c))
+        .map(|c| (c, Walk::Targets))
+        .collect();
+    crates.extend(
+        packages
+            .iter()
+            .filter(|c| selected(c))
+            .map(|c| (c, Walk::Root)),
+    );
+    // A package at the root already covers the root's own files.
+    let root_files = workspace_root_files(&workspace, &options)
+        .filter(|_| !packages.iter().any(|p| p.root == workspace.root));
+    crates.extend(root_files.as_ref().map(|c| (c, Walk::Targets)));
+
+    let crate_roots: Vec<&Path> = workspace
+        .crates
+        .iter()
+        .chain(&packages)
+        .map(|c| c.root.as_path())
+        .collect();
+    let mut result = count_crates(workspace.root.clone(), &crates, &crate_roots, &options)?;
+    diagnostics.append(&mut result.diagnostics);
+    result.diagnostics = diagnostics;
+    Ok(result)
+}
 
-    // Filter crates if specified
-    let crates: Vec<&CrateInfo> = if options.crate_filter.is_empty() {
-        workspace.crates.iter().collect()
-    } else {
-        let names: Vec<&str> = options.crate_filter.iter().map(|s| s.as_str()).collect();
-        workspace
+/// The packages other build tools declare beneath a Cargo workspace, such as
+/// the Python bindings or web frontend of a mixed repository, as crates
+/// without targets. Packages in a crate's own directory are left out.
+///
+/// Packages only matter for per-package rows or a package filter; otherwise
+/// the manifest walk is skipped and there are none.
+fn workspace_packages(workspace: &WorkspaceInfo, options: &CountOptions) -> Result<Vec<CrateInfo>> {
+    if options.aggregation != Aggregation::ByCrate && options.crate_filter.is_empty() {
+        return Ok(Vec::new());
+    }
+    let model = ProjectModel::discover_with(
+        &workspace.root,
+        &ProjectModel::non_cargo_providers(),
+        &options.file_filter,
+    )?;
+    Ok(model
+        .packages
+        .into_iter()
+        .filter(|package| !workspace.crates.iter().any(|c| c.root == package.root))
+        .map(|package| CrateInfo {
+            name: package.name,
+            root: package.root,
+            targets: Vec::new(),
+        })
+        .collect())
+}
+
+/// Where a counted crate's files come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Walk {
+    /// A Cargo crate: its target directories, and the crate-wide languages
+    /// from the rest of its root.
+    Targets,
+    /// A package of another build tool: every selected file beneath its root.
+    Root,
+}
+
+/// Row name for the files of a workspace root that is not itself a package,
+/// such as its README or docs.
+pub const WORKSPACE_ROOT_NAME: &str = "(workspace root)";
+
+/// Languages counted from anywhere in a crate rather than only its target
+/// directories: READMEs, `docs/`, `Cargo.toml`, CI workflows and the like.
+const CRATE_WIDE_LANGUAGES: &[LanguageName] = &[LanguageName::Markdown, LanguageName::Config];
+
+/// `filter` narrowed to the selected crate-wide languages, or `None` if none
+/// is selected.
+fn crate_wide_filter(filter: &FilterConfig) -> Option<FilterConfig> {
+    let selected: Vec<&str> = CRATE_WIDE_LANGUAGES
+        .iter()
+        .map(|language| language.name())
+        .filter(|name| filter.languages.contains_name(name))
+        .collect();
+    (!selected.is_empty()).then(|| {
+        filter
+            .clone()
+            .languages(LanguageSelection::from_names(selected))
+    })
+}
+
+/// A pseudo-crate holding the crate-wide files of a workspace root that is
+/// not a package, when every crate is counted.
+fn workspace_root_files(workspace: &WorkspaceInfo, options: &CountOptions) -> Option<CrateInfo> {
+    let is_package = workspace.crates.iter().any(|c| c.root == workspace.root);
+    (options.crate_filter.is_empty()
+        && !is_package
+        && crate_wide_filter(&options.file_filter).is_some())
+    .then(|| CrateInfo {
+        name: WORKSPACE_ROOT_NAME.to_string(),
+        root: workspace.root.clone(),
+        targets: Vec::new(),
+    })
+}
+
+/// Count every Cargo workspace at or below a directory in one report.
+///
+/// Workspaces are found with [`WorkspaceInfo::discover_all`], so independent
+/// workspaces and standalone crates of a monorepo are each counted with their
+/// own crate structure. Crate names are qualified with their workspace
+/// (`services/api:server`) because separate workspaces may reuse a name; the
+/// crate filter accepts either form.
+///
+/// [`Aggregation::ByWorkspace`] gives one row per workspace, labelled by its
+/// root relative to `path` (`.` for `path` itself).
+pub fn count_workspaces(path: impl AsRef<Path>, options: CountOptions) -> Result<CountResult> {
+    let path = path.as_ref();
+    let discovered =
+        WorkspaceInfo::discover_all(path, options.workspace_discovery, &options.file_filter)?;
+    let workspaces = discovered.workspaces;
+
+    let base = normalize(path)?;
+    let mut result = CountResult::new();
+    result.root = base.clone();
+    result.diagnostics = discovered.diagnostics;
+    // Every walk stays out of the other workspaces and crates below it.
+    let crate_roots: Vec<PathBuf> = workspaces
+        .iter()
+        .flat_map(|w| std::iter::once(&w.root).chain(w.crates.iter().map(|c| &c.root)))
+        .cloned()
+        .collect();
+    let crate_roots: Vec<&Path> = crate_roots.iter().map(|root| root.as_path()).collect();
+
+    for workspace in workspaces {
+        let label = match workspace.root.strip_prefix(&base) {
+            Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+            Ok(relative) => relative.to_string_lossy().into_owned(),
+            Err(_) => workspace.root.to_string_lossy().into_owned(),
+        };
+        let root_files = workspace_root_files(&workspace, &options).map(|c| CrateInfo {
+            name: format!("{}:{}", label, c.name),
+            ..c
+        });
+        let crates: Vec<CrateInfo> = workspace
             .crates
-            .iter()
-            .filter(|c| names.contains(&c.name.as_str()))
-            .collect()
-    };
+            .into_iter()
+            .map(|c| CrateInfo {
+                name: format!("{}:{}", label, c.name),
+                ..c
+            })
+            .filter(|c| {
+                options.crate_filter.is_empty()
+                    || options.crate_filter.iter().any(|name| {
+                        *name == c.name || c.name.rsplit_once(':').is_some_and(|(_, n)| n == name)
+                    })
+            })
+            .collect();
+        let crates: Vec<CrateInfo> = crates.into_iter().chain(root_files).collect();
+        if crates.is_empty() {
+            continue;
+        }
+        let crate_refs: Vec<(&CrateInfo, Walk)> =
+            crates.iter().map(|c| (c, Walk::Targets)).collect();
+        let counted = count_crates(workspace.root.clone(), &crate_refs, &crate_roots, &options)?;
+
+        result.total += &counted.total;
+        result.file_count += counted.file_count;
+        result.crates.extend(counted.crates);
+        result.files.extend(counted.files);
+        result.modules.extend(counted.modules);
+        result.targets.extend(counted.targets);
+        result.skipped_links.extend(counted.skipped_links);
+        result.skipped.extend(counted.skipped);
+        result.diagnostics.extend(counted.diagnostics);
+        result.detected.extend(counted.detected);
+        result.transcoded.extend(counted.transcoded);
+        if matches!(options.aggregation, Aggregation::ByWorkspace) {
+            result.workspaces.push(WorkspaceStats {
+                name: label,
+                path: workspace.root,
+                stats: counted.total,
+                crates: crates.into_iter().map(|c| c.name).collect(),
+            });
+        }
+    }
+
+    result.modules.sort_by(|a, b| a.name.cmp(&b.name));
+    result.targets.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(result)
+}
+
+/// Count the dependencies of the workspace at `path`.
+///
+/// Resolves the dependency graph offline (see [`discover_dependencies`]) and
+/// counts each dependency's sources like a workspace crate, labelled
+/// `name@version`. `options.crate_filter` is not consulted: select the
+/// members whose dependencies are counted with [`DependencyQuery::members`].
+///
+/// With [`Aggregation::ByCrate`] there is one row per dependency; `total` is
+/// the sum over all of them.
+pub fn count_dependencies(
+    path: impl AsRef<Path>,
+    query: &DependencyQuery,
+    options: CountOptions,
+) -> Result<CountResult> {
+    let dependencies = discover_dependencies(&path, query)?;
+    let crates: Vec<CrateInfo> = dependencies
+        .into_iter()
+        .map(|dependency| CrateInfo {
+            name: dependency.label(),
+            ..dependency.crate_info
+        })
+        .collect();
+    let crate_roots: Vec<&Path> = crates.iter().map(|c| c.root.as_path()).collect();
+    let crates: Vec<(&CrateInfo, Walk)> = crates.iter().map(|c| (c, Walk::Targets)).collect();
+
+    count_crates(path.as_ref().to_path_buf(), &crates, &crate_roots, &options)
+}
 
+/// Count `crates` and aggregate them at the level `options` asks for.
+/// `crate_roots` are the roots of every crate of the workspace, counted or
+/// not, which a crate's own walk stays out of.
+fn count_crates(
+    root: PathBuf,
+    crates: &[(&CrateInfo, Walk)],
+    crate_roots: &[&Path],
+    options: &CountOptions,
+) -> Result<CountResult> {
     let mut result = CountResult::new();
-    result.root = workspace.root.clone();
+    result.root = root;
 
     // Determine what to include based on aggregation level
     let include_files = matches!(options.aggregation, Aggregation::ByFile);
     let include_modules = matches!(options.aggregation, Aggregation::ByModule);
+    let include_targets = matches!(options.aggregation, Aggregation::ByTarget);
     let include_crates = matches!(
         options.aggregation,
-        Aggregation::ByCrate | Aggregation::ByModule | Aggregation::ByFile
+        Aggregation::ByCrate | Aggregation::ByModule | Aggregation::ByTarget | Aggregation::ByFile
     );
 
-    for crate_info in &crates {
-        let crate_stats = count_crate(crate_info, &options)?;
-        result.total += crate_stats.stats;
+    for &(crate_info, walk) in crates {
+        let CountedCrate {
+            stats: crate_stats,
+            skipped_links,
+            skipped,
+            diagnostics,
+        } = count_crate(crate_info, walk, &result.root, crate_roots, options)?;
+        result.skipped_links.extend(skipped_links);
+        result.skipped.extend(skipped);
+        result.diagnostics.extend(diagnostics);
+        result.total += &crate_stats.stats;
         result.file_count += crate_stats.files.len();
+        for file in &crate_stats.files {
+            result.note(file);
+        }
 
         if include_files {
             result.files.extend(crate_stats.files.clone());
@@ -181,7 +496,16 @@ pub fn count_workspace(path: impl AsRef<Path>, options: CountOptions) -> Result<
             result.modules.extend(crate_modules);
         }
 
-        if include_crates {
+        if include_targets {
+            result
+                .targets
+                .extend(aggregate_targets(&crate_stats.files, crate_info));
+        }
+
+        // A workspace root pseudo-crate or another build tool's package only
+        // gets a row for files it holds.
+        let pseudo = crate_info.targets.is_empty() && crate_stats.files.is_empty();
+        if include_crates && !pseudo {
             result.crates.push(crate_stats);
         }
     }
@@ -190,6 +514,9 @@ pub fn count_workspace(path: impl AsRef<Path>, options: CountOptions) -> Result<
     if include_modules {
         result.modules.sort_by(|a, b| a.name.cmp(&b.name));
     }
+    if include_targets {
+        result.targets.sort_by(|a, b| a.name.cmp(&b.name));
+    }
 
     // Apply line type filter
     Ok(result.filter(options.line_types))
@@ -248,6 +575,41 @@ pub fn compute_module_name(file_path: &Path, src_root: &Path) -> String {
     }
 }
 
+/// Compute the dotted import name of a Python file relative to its import root.
+///
+/// Unlike [`compute_module_name`], every Python file is its own module:
+/// - `app.py` → "app"
+/// - `pkg/__init__.py` → "pkg"
+/// - `pkg/sub/worker.py` → "pkg.sub.worker"
+/// - `__init__.py` in the import root → "" (the root)
+pub fn python_module_name(file_path: &Path, import_root: &Path) -> String {
+    let relative = file_path.strip_prefix(import_root).unwrap_or(file_path);
+    let mut components: Vec<&str> = relative
+        .components()
+        .filter_map(|c| c.as_os_str().to_str())
+        .collect();
+
+    if let Some(filename) = components.pop() {
+        let stem = Path::new(filename)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(filename);
+        if stem != "__init__" {
+            components.push(stem);
+        }
+    }
+    components.join(".")
+}
+
+/// Whether a path is a Python source or stub file, named by import path
+/// rather than by directory.
+pub(crate) fn is_python_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("py" | "pyi")
+    )
+}
+
 /// Aggregate file stats into modules for a specific crate.
 fn aggregate_modules(
     files: &[FileStats],
@@ -258,11 +620,11 @@ fn aggregate_modules(
 
     for file in files {
         // Find the appropriate src root for this file
-        let src_root = crate_info
-            .src_dirs
+        let src_dirs = crate_info.src_dirs();
+        let src_root = src_dirs
             .iter()
             .find(|dir| file.path.starts_with(dir))
-            .map(|p| p.as_path())
+            .copied()
             .unwrap_or(&crate_info.root);
 
         let local_module = compute_module_name(&file.path, src_root);
@@ -278,18 +640,60 @@ fn aggregate_modules(
             .entry(full_module_name.clone())
             .or_insert_with(|| ModuleStats::new(full_module_name));
 
-        module.add_file(file.path.clone(), file.stats);
+        module.add_file(file.path.clone(), file.stats.clone());
     }
 
     module_map.into_values().collect()
 }
 
+/// Aggregate file stats into the build targets of a specific crate.
+///
+/// Files no target claims are grouped under `<crate> (other)`.
+fn aggregate_targets(files: &[FileStats], crate_info: &CrateInfo) -> Vec<TargetStats> {
+    let mut target_map: HashMap<String, TargetStats> = HashMap::new();
+
+    for file in files {
+        let target = crate_info.target_for_path(&file.path, &crate_info.root);
+        let name = target_label(&crate_info.name, target);
+        target_map
+            .entry(name.clone())
+            .or_insert_with(|| {
+                let path = target.map_or(&crate_info.root, |t| &t.src_path);
+                TargetStats::new(name, target.map(|t| t.kind), path.clone())
+            })
+            .add_file(file.path.clone(), file.stats.clone());
+    }
+
+    target_map.into_values().collect()
+}
+
 /// Aggregate file stats into directory/module groups for a non-workspace tree.
-fn aggregate_directory_modules(files: &[FileStats], root: &Path) -> Vec<ModuleStats> {
+///
+/// Files inside a declared build module (see [`discover_build_modules`]) are
+/// grouped under the most specific module's name. Python files take their
+/// dotted import name (see [`python_module_name`]), resolved from the source
+/// root of the distribution that owns them. Other files fall back to
+/// directory-based module names.
+fn aggregate_directory_modules(
+    files: &[FileStats],
+    root: &Path,
+    build_modules: &[BuildModule],
+    python_packages: &[Package],
+) -> Vec<ModuleStats> {
     let mut module_map: HashMap<String, ModuleStats> = HashMap::new();
 
     for file in files {
-        let module_name = compute_module_name(&file.path, root);
+        let build_module = build_modules
+            .iter()
+            .find(|module| file.path.starts_with(&module.root));
+        let module_name = match build_module {
+            Some(module) => module.name.clone(),
+            None if is_python_file(&file.path) => python_module_name(
+                &file.path,
+                python_import_root(&file.path, python_packages).unwrap_or(root),
+            ),
+            None => compute_module_name(&file.path, root),
+        };
         let display_name = if module_name.is_empty() {
             "(root)".to_string()
         } else {
@@ -298,7 +702,7 @@ fn aggregate_directory_modules(files: &[FileStats], root: &Path) -> Vec<ModuleSt
         let module = module_map
             .entry(display_name.clone())
             .or_insert_with(|| ModuleStats::new(display_name));
-        module.add_file(file.path.clone(), file.stats);
+        module.add_file(file.path.clone(), file.stats.clone());
     }
 
     let mut modules: Vec<_> = module_map.into_values().collect();
@@ -306,22 +710,185 @@ fn aggregate_directory_modules(files: &[FileStats], root: &Path) -> Vec<ModuleSt
     modules
 }
 
+/// The directory a Python file's import name resolves from: the source root
+/// of the most specific of `python_packages` (deepest first) owning it, or
+/// that package's root for files outside its source root.
+pub(crate) fn python_import_root<'a>(
+    path: &Path,
+    python_packages: &'a [Package],
+) -> Option<&'a Path> {
+    let package = python_packages
+        .iter()
+        .find(|package| path.starts_with(&package.root))?;
+    Some(if path.starts_with(&package.source_root) {
+        &package.source_root
+    } else {
+        &package.root
+    })
+}
+
+/// Python distributions beneath `root`, deepest first, for naming modules.
+pub(crate) fn deepest_python_packages(root: &Path, filter: &FilterConfig) -> Result<Vec<Package>> {
+    let mut packages = PythonProvider.discover(root, filter)?;
+    packages.sort_by_key(|package| std::cmp::Reverse(package.root.components().count()));
+    Ok(packages)
+}
+
+/// Group a directory's files by the package that owns them. Files outside
+/// every package land in an `(other)` row, listed last.
+fn aggregate_directory_packages(
+    files: Vec<FileStats>,
+    root: &Path,
+    model: &ProjectModel,
+) -> Vec<CrateStats> {
+    let mut packages: Vec<CrateStats> = Vec::new();
+    let mut other = CrateStats::new("(other)".to_string(), root.to_path_buf());
+
+    for file in files {
+        let relative = file.path.strip_prefix(root).unwrap_or(&file.path);
+        let Some(package) = model.package_for_path(relative) else {
+            other.add_file(file);
+            continue;
+        };
+        match packages.iter_mut().find(|p| p.path == package.root) {
+            Some(stats) => stats.add_file(file),
+            None => {
+                let mut stats = CrateStats::new(package.name.clone(), package.root.clone());
+                stats.add_file(file);
+                packages.push(stats);
+            }
+        }
+    }
+
+    packages.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.path.cmp(&b.path)));
+    if !other.files.is_empty() {
+        packages.push(other);
+    }
+    packages
+}
+
+/// A crate's statistics and the paths counting it passed over.
+struct CountedCrate {
+    stats: CrateStats,
+    skipped_links: Vec<SkippedLink>,
+    skipped: Vec<SkippedFile>,
+    diagnostics: Vec<Diagnostic>,
+}
+
 /// Count LOC in a single crate.
-fn count_crate(crate_info: &CrateInfo, options: &CountOptions) -> Result<CrateStats> {
-    let dirs: Vec<&Path> = crate_info.all_dirs();
-    let files = discover_files_in_dirs(&dirs, &options.file_filter)?;
-    let registry = BackendRegistry::new();
+///
+/// For [`Walk::Targets`], source files come from the crate's target
+/// directories, and files of the crate-wide languages (Markdown and config)
+/// are also collected from the rest of the crate root. [`Walk::Root`] takes
+/// every selected file beneath the root. Neither walk enters `target/` or
+/// the roots of nested crates.
+///
+/// Logic lines in files owned by a test or example target count as tests or
+/// examples, wherever those files live. Classification rules and language
+/// path globs see paths relative to `root`, or to the crate's own root when
+/// it lies elsewhere (a dependency).
+fn count_crate(
+    crate_info: &CrateInfo,
+    walk: Walk,
+    root: &Path,
+    crate_roots: &[&Path],
+    options: &CountOptions,
+) -> Result<CountedCrate> {
+    let root = if crate_info.root.starts_with(root) {
+        root
+    } else {
+        &crate_info.root
+    };
+    let nested = crate_roots
+        .iter()
+        .copied()
+        .filter(|other| *other != crate_info.root && other.starts_with(&crate_info.root));
+    let DiscoveredFiles {
+        files,
+        skipped_links,
+        mut diagnostics,
+    } = match walk {
+        Walk::Targets => {
+            let dirs = crate_info.all_dirs();
+            let dirs: Vec<&Path> = dirs.iter().map(|dir| dir.as_path()).collect();
+            let mut discovered = discover_files_in_dirs_with_report(&dirs, &options.file_filter)?;
+            if let Some(filter) = crate_wide_filter(&options.file_filter) {
+                let prune: Vec<&Path> = dirs
+                    .iter()
+                    .copied()
+                    .filter(|dir| dir.is_dir())
+                    .chain(nested)
+                    .collect();
+                let outside = discover_files_pruned(&crate_info.root, &filter, &prune)?;
+                discovered.files.extend(outside.files);
+                discovered.files.sort();
+                discovered.files.dedup();
+                discovered.skipped_links.extend(outside.skipped_links);
+                discovered.diagnostics.extend(outside.diagnostics);
+            }
+            discovered
+        }
+        Walk::Root => {
+            let prune: Vec<&Path> = nested.collect();
+            discover_files_pruned(&crate_info.root, &options.file_filter, &prune)?
+        }
+    };
 
     let mut crate_stats = CrateStats::new(crate_info.name.clone(), crate_info.root.clone());
+    let mut skipped = Vec::new();
 
     for file_path in files {
-        if let Some(stats) = analyze_file_stats(&registry, &file_path)? {
-            let file_stats = FileStats::new(file_path, stats);
+        if let Some(mut file_stats) = analyze_file(
+            &options.file_filter,
+            file_path,
+            root,
+            &mut skipped,
+            &mut diagnostics,
+        )? {
+            let classified = options
+                .file_filter
+                .registry
+                .classifier()
+                .context_for(relative_to(&file_stats.path, root))
+                .is_some();
+            let target = crate_info.target_for_path(&file_stats.path, &crate_info.root);
+            if let Some(target) = target.filter(|_| !classified) {
+                apply_target_kind(&mut file_stats.stats, target.kind);
+            }
             crate_stats.add_file(file_stats);
         }
     }
 
-    Ok(crate_stats)
+    Ok(CountedCrate {
+        stats: crate_stats,
+        skipped_links,
+        skipped,
+        diagnostics,
+    })
+}
+
+/// Row label for a crate's target, or for the crate's unclaimed files.
+pub(crate) fn target_label(crate_name: &str, target: Option<&TargetInfo>) -> String {
+    match target {
+        Some(target) => target.label(crate_name),
+        None => format!("{} (other)", crate_name),
+    }
+}
+
+/// Move production code lines to the context a target kind implies. Files a
+/// classification rule matched keep the context the rule gave them.
+pub(crate) fn apply_target_kind(stats: &mut Locs, kind: TargetKind) {
+    match kind {
+        TargetKind::Test => {
+            stats.tests += stats.code;
+            stats.code = 0;
+        }
+        TargetKind::Example => {
+            stats.examples += stats.code;
+            stats.code = 0;
+        }
+        _ => {}
+    }
 }
 
 /// Count LOC in a directory (non-workspace mode).
@@ -367,31 +934,78 @@ pub fn count_directory_with_options(
         return Err(RustlocError::PathNotFound(path.to_path_buf()));
     }
 
-    let files = discover_files(path, &options.file_filter)?;
-    let registry = BackendRegistry::new();
+    let DiscoveredFiles {
+        files,
+        skipped_links,
+        diagnostics,
+    } = discover_files_with_report(path, &options.file_filter)?;
 
     let mut result = CountResult::new();
     result.root = path.to_path_buf();
+    result.skipped_links = skipped_links;
+    result.diagnostics = diagnostics;
     let include_files = matches!(
         options.aggregation,
-        Aggregation::ByFile | Aggregation::ByModule
+        Aggregation::ByFile | Aggregation::ByModule | Aggregation::ByCrate
     );
 
+    // Packages only matter for per-package rows or a package filter.
+    let model = if options.aggregation == Aggregation::ByCrate || !options.crate_filter.is_empty() {
+        Some(ProjectModel::discover(
+            path,
+            options.workspace_discovery,
+            &options.file_filter,
+        )?)
+    } else {
+        None
+    };
+    let selected = |file_path: &Path| match &model {
+        Some(model) if !options.crate_filter.is_empty() => {
+            let relative = file_path.strip_prefix(path).unwrap_or(file_path);
+            model
+                .package_for_path(relative)
+                .is_some_and(|package| options.crate_filter.contains(&package.name))
+        }
+        _ => true,
+    };
+
     for file_path in files {
-        if let Some(stats) = analyze_file_stats(&registry, &file_path)? {
-            result.total += stats;
+        if !selected(&file_path) {
+            continue;
+        }
+        if let Some(file_stats) = analyze_file(
+            &options.file_filter,
+            file_path,
+            path,
+            &mut result.skipped,
+            &mut result.diagnostics,
+        )? {
+            result.total += &file_stats.stats;
             result.file_count += 1;
+            result.note(&file_stats);
             if include_files {
-                result.files.push(FileStats::new(file_path, stats));
+                result.files.push(file_stats);
             }
         }
     }
 
     if matches!(options.aggregation, Aggregation::ByModule) {
-        result.modules = aggregate_directory_modules(&result.files, path);
+        let build_modules = discover_build_modules(path, &options.file_filter)?;
+        let python_packages = if options.file_filter.languages.contains(LanguageName::Python) {
+            deepest_python_packages(path, &options.file_filter)?
+        } else {
+            Vec::new()
+        };
+        result.modules =
+            aggregate_directory_modules(&result.files, path, &build_modules, &python_packages);
         result.files.clear();
     }
 
+    if let (Aggregation::ByCrate, Some(model)) = (options.aggregation, &model) {
+        result.crates =
+            aggregate_directory_packages(std::mem::take(&mut result.files), path, model);
+    }
+
     Ok(result.filter(options.line_types))
 }
 
@@ -417,19 +1031,76 @@ pub fn count_file(path: impl AsRef<Path>) -> Result<Locs> {
 
 /// Count LOC in a single file if it matches the provided filter.
 pub fn count_file_with_filter(path: impl AsRef<Path>, filter: &FilterConfig) -> Result<Locs> {
-    let registry = BackendRegistry::new();
     let path = path.as_ref();
-    if !filter.matches(path) {
+    if !filter.matches_file(path) {
         return Err(RustlocError::UnsupportedSourceFile(path.to_path_buf()));
     }
-    analyze_file_stats(&registry, path)?
-        .ok_or_else(|| RustlocError::UnsupportedSourceFile(path.to_path_buf()))
+    let mut skipped = Vec::new();
+    match try_analyze_file(filter, path, Path::new(""), &mut skipped, &mut Vec::new())? {
+        Some(file) => Ok(file.stats),
+        None => Err(match skipped.pop() {
+            Some(file) => RustlocError::SkippedFile {
+                path: file.path,
+                reason: file.reason,
+            },
+            None => RustlocError::UnsupportedSourceFile(path.to_path_buf()),
+        }),
+    }
+}
+
+/// Analyze a file, or record it in `skipped` if it fails a content check.
+///
+/// A file that cannot be read or analyzed is recorded in `diagnostics` and
+/// left out, unless the filter is strict.
+fn analyze_file(
+    filter: &FilterConfig,
+    path: PathBuf,
+    root: &Path,
+    skipped: &mut Vec<SkippedFile>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Option<FileStats>> {
+    let analyzed = try_analyze_file(filter, &path, root, skipped, diagnostics);
+    recover(&path, analyzed, filter.strict, diagnostics)
 }
 
-fn analyze_file_stats(registry: &BackendRegistry, path: &Path) -> Result<Option<Locs>> {
-    registry
-        .analyze_path(path)
-        .map(|analysis| analysis.map(|analysis| analysis.stats))
+/// Analyze a file, failing if it cannot be read or analyzed.
+///
+/// Classification rules and language path globs see the path relative to
+/// the analyzed `root`, as in a diff, so the directories above it never
+/// decide whether a file matches a configured glob.
+fn try_analyze_file(
+    filter: &FilterConfig,
+    path: &Path,
+    root: &Path,
+    skipped: &mut Vec<SkippedFile>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Option<FileStats>> {
+    let source = match read_source(path, filter.max_file_size)? {
+        Ok(source) => source,
+        Err(file) => {
+            diagnostics.push(Diagnostic::skipped(&file));
+            skipped.push(file);
+            return Ok(None);
+        }
+    };
+    let Some((analysis, detection)) =
+        filter
+            .registry
+            .analyze_source_below(root, path, &source.text, &filter.languages)?
+    else {
+        return Ok(None);
+    };
+    check_parse(
+        path,
+        analysis.parse_failure.as_ref(),
+        filter.strict,
+        diagnostics,
+    )?;
+    Ok(Some(
+        FileStats::new(path.to_path_buf(), analysis.stats)
+            .with_detection(detection)
+            .with_encoding(source.encoding),
+    ))
 }
 
 #[cfg(test)]
@@ -559,6 +1230,39 @@ edition = "2021"
         assert_eq!(result.total.code, 3);
     }
 
+    #[test]
+    fn test_count_workspace_skips_files_failing_content_checks() {
+        use crate::data::SkipReason;
+
+        let temp = tempdir().unwrap();
+        create_simple_project(temp.path());
+        fs::write(temp.path().join("src/data.rs"), b"\x89PNG\r\n\x1a\n\0\0").unwrap();
+        fs::write(temp.path().join("src/bundle.rs"), "let a = 1;".repeat(200)).unwrap();
+
+        let result = count_workspace(temp.path(), CountOptions::new()).unwrap();
+        let reasons: Vec<_> = result
+            .skipped
+            .iter()
+            .map(|file| (file.path.file_name().unwrap().to_owned(), file.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                ("bundle.rs".into(), SkipReason::Minified),
+                ("data.rs".into(), SkipReason::Binary),
+            ]
+        );
+
+        let error = count_file(temp.path().join("src/data.rs")).unwrap_err();
+        assert!(matches!(
+            error,
+            RustlocError::SkippedFile {
+                reason: SkipReason::Binary,
+                ..
+            }
+        ));
+    }
+
     #[test]
     fn test_count_directory_includes_selected_non_rust_source_files() {
         let temp = tempdir().unwrap();
@@ -597,6 +1301,200 @@ edition = "2021"
         assert_eq!(result.total.tests, 3);
     }
 
+    #[test]
+    fn test_count_directory_matches_path_globs_below_the_analyzed_root() {
+        let temp = tempdir().unwrap();
+        // The analyzed directory itself lives under a `spec/` directory.
+        let root = temp.path().join("spec/project");
+        fs::create_dir_all(root.join("spec")).unwrap();
+        fs::write(root.join("main.lua"), "print(1)\n").unwrap();
+        fs::write(root.join("spec/main_spec.lua"), "assert(1)\n").unwrap();
+        let registry = BackendRegistry::with_languages([crate::data::LanguageDefinition {
+            name: "lua".into(),
+            extensions: vec!["lua".into()],
+            test_paths: vec!["**/spec/**".into()],
+            ..crate::data::LanguageDefinition::default()
+        }])
+        .unwrap();
+
+        let result = count_directory(
+            &root,
+            &FilterConfig::new()
+                .languages(crate::data::LanguageSelection::from_names(["lua"]))
+                .registry(registry),
+        )
+        .unwrap();
+
+        assert_eq!((result.total.code, result.total.tests), (1, 1));
+    }
+
+    #[test]
+    fn test_count_directory_keeps_the_context_of_a_counted_tests_directory() {
+        let temp = tempdir().unwrap();
+        let tests = temp.path().join("tests");
+        create_rust_file(&tests.join("common/mod.rs"), "pub fn setup() {}\n");
+        fs::write(tests.join("helpers.py"), "def helper():\n    pass\n").unwrap();
+        let examples = temp.path().join("examples");
+        create_rust_file(&examples.join("demo.rs"), "fn main() {}\n");
+
+        let filter = FilterConfig::new().languages(crate::data::LanguageSelection::from_names([
+            "rust", "python",
+        ]));
+        let result = count_directory(&tests, &filter).unwrap();
+        assert_eq!((result.total.code, result.total.tests), (0, 3));
+        let result = count_directory(&examples, &filter).unwrap();
+        assert_eq!((result.total.code, result.total.examples), (0, 1));
+    }
+
+    #[test]
+    fn test_count_options_registry_adds_library_backends() {
+        use crate::data::{FileAnalysis, LanguageBackend, LanguageId, LanguageSelection};
+
+        struct AsciiDoc;
+
+        impl LanguageBackend for AsciiDoc {
+            fn supports_path(&self, path: &Path) -> bool {
+                path.extension().is_some_and(|ext| ext == "adoc")
+            }
+
+            fn analyze_source(&self, _path: &Path, source: &str) -> Result<FileAnalysis> {
+                let mut stats = Locs::new();
+                stats.docs = source.lines().count() as u64;
+                stats.total = stats.docs;
+                Ok(FileAnalysis {
+                    language: LanguageId::External("asciidoc".to_string()),
+                    stats,
+                    line_classes: Vec::new(),
+                    parse_failure: None,
+                })
+            }
+        }
+
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("README.adoc"), "# Title\n\nText\n").unwrap();
+        fs::write(temp.path().join("lib.rs"), "fn main() {}\n").unwrap();
+
+        let registry = BackendRegistry::new()
+            .with_backend("asciidoc", 0, AsciiDoc)
+            .unwrap();
+        let options = CountOptions::new()
+            .filter(
+                FilterConfig::new().languages(LanguageSelection::default().with_name("asciidoc")),
+            )
+            .registry(registry);
+        let result = count_directory_with_options(temp.path(), options).unwrap();
+
+        assert_eq!(result.file_count, 2);
+        assert_eq!(result.total.docs, 3);
+        assert_eq!(result.total.code, 1);
+    }
+
+    #[test]
+    fn test_count_directory_records_diagnostics_and_counts_the_rest() {
+        use crate::data::{DiagnosticKind, FileAnalysis, LanguageBackend, LanguageSelection};
+
+        struct Failing;
+
+        impl LanguageBackend for Failing {
+            fn supports_path(&self, path: &Path) -> bool {
+                path.extension().is_some_and(|ext| ext == "bad")
+            }
+
+            fn analyze_source(&self, path: &Path, _source: &str) -> Result<FileAnalysis> {
+                Err(RustlocError::UnsupportedSourceFile(path.to_path_buf()))
+            }
+        }
+
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("input.bad"), "?\n").unwrap();
+        fs::write(temp.path().join("broken.py"), "def broken(\n").unwrap();
+        fs::write(temp.path().join("lib.rs"), "fn main() {}\n").unwrap();
+        let options = |strict| {
+            CountOptions::new()
+                .filter(
+                    FilterConfig::new()
+                        .languages(LanguageSelection::all().with_name("failing"))
+                        .strict(strict),
+                )
+                .registry(
+                    BackendRegistry::new()
+                        .with_backend("failing", 0, Failing)
+                        .unwrap(),
+                )
+        };
+
+        let result = count_directory_with_options(temp.path(), options(false)).unwrap();
+        assert_eq!(result.file_count, 2);
+        let kinds: Vec<_> = result
+            .diagnostics
+            .iter()
+            .map(|d| (d.path.file_name().unwrap().to_str().unwrap(), d.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("broken.py", DiagnosticKind::ParseError),
+                ("input.bad", DiagnosticKind::AnalysisError),
+            ]
+        );
+        assert_eq!(result.diagnostics[0].line, Some(2));
+
+        let error = count_directory_with_options(temp.path(), options(true)).unwrap_err();
+        assert!(matches!(error, RustlocError::Parse { .. }), "{error}");
+    }
+
+    #[test]
+    fn test_count_directory_reports_detection_per_file() {
+        use crate::data::{DetectionReason, LanguageSelection};
+
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("bin")).unwrap();
+        fs::write(
+            root.join("bin/deploy"),
+            "#!/usr/bin/env bash\necho deploy\n",
+        )
+        .unwrap();
+        fs::write(root.join("bin/notes"), "nothing to see\n").unwrap();
+        fs::write(root.join("Makefile"), "all:\n\techo build\n").unwrap();
+        fs::write(root.join("lib.rs"), "fn main() {}\n").unwrap();
+
+        let filter = FilterConfig::new().languages(LanguageSelection::new(&[
+            crate::data::LanguageName::Rust,
+            crate::data::LanguageName::Generic,
+        ]));
+        let result = count_directory(root, &filter).unwrap();
+        let reasons: Vec<_> = result
+            .files
+            .iter()
+            .map(|file| {
+                let detection = file.detection.as_ref().unwrap();
+                (
+                    file.path.strip_prefix(root).unwrap().to_path_buf(),
+                    detection.backend.as_str(),
+                    detection.reason,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            reasons,
+            vec![
+                (
+                    PathBuf::from("Makefile"),
+                    "generic",
+                    DetectionReason::Filename
+                ),
+                (
+                    PathBuf::from("bin/deploy"),
+                    "generic",
+                    DetectionReason::Shebang
+                ),
+                (PathBuf::from("lib.rs"), "rust", DetectionReason::Extension),
+            ]
+        );
+    }
+
     #[test]
     fn test_count_directory_defaults_to_rust_language_only() {
         let temp = tempdir().unwrap();
@@ -636,7 +1534,106 @@ edition = "2021"
 
         let module_names: Vec<_> = result.modules.iter().map(|m| m.name.as_str()).collect();
         assert!(module_names.contains(&"pkg"));
-        assert!(module_names.contains(&"pkg::sub"));
+        assert!(module_names.contains(&"pkg.sub.worker"));
+    }
+
+    #[test]
+    fn test_count_directory_by_module_resolves_python_src_layouts() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        let write = |file: &str, content: &str| {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write("api/pyproject.toml", "[project]\nname = \"acme-api\"\n");
+        write("api/src/acme_api/__init__.py", "VALUE = 1\n");
+        write(
+            "api/src/acme_api/routes/users.py",
+            "def get():\n    return 1\n",
+        );
+        write("api/tests/test_users.py", "def test_get():\n    pass\n");
+        write(
+            "lib/pyproject.toml",
+            "[tool.poetry]\nname = \"acme-lib\"\npackages = [{ include = \"acme_lib\", from = \"python\" }]\n",
+        );
+        write("lib/python/acme_lib/core.py", "X = 1\n");
+
+        let options = || {
+            CountOptions::new().filter(FilterConfig::new().languages(
+                crate::data::LanguageSelection::new(&[crate::data::LanguageName::Python]),
+            ))
+        };
+
+        let result =
+            count_directory_with_options(root, options().aggregation(Aggregation::ByModule))
+                .unwrap();
+        let mut module_names: Vec<_> = result.modules.iter().map(|m| m.name.as_str()).collect();
+        module_names.sort();
+        assert_eq!(
+            module_names,
+            [
+                "acme_api",
+                "acme_api.routes.users",
+                "acme_lib.core",
+                "tests.test_users"
+            ]
+        );
+
+        let result =
+            count_directory_with_options(root, options().aggregation(Aggregation::ByCrate))
+                .unwrap();
+        let crate_names: Vec<_> = result.crates.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(crate_names, ["acme-api", "acme-lib"]);
+    }
+
+    #[test]
+    fn test_python_module_name() {
+        let root = Path::new("/project/src");
+        let name = |path: &str| python_module_name(Path::new(path), root);
+        assert_eq!(name("/project/src/app.py"), "app");
+        assert_eq!(name("/project/src/__init__.py"), "");
+        assert_eq!(name("/project/src/pkg/__init__.py"), "pkg");
+        assert_eq!(name("/project/src/pkg/sub/worker.py"), "pkg.sub.worker");
+        assert_eq!(name("/project/src/pkg/types.pyi"), "pkg.types");
+    }
+
+    #[test]
+    fn test_count_directory_by_module_groups_gradle_projects() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("core/src/main/java/demo")).unwrap();
+        fs::create_dir_all(root.join("core/src/test/java/demo")).unwrap();
+        fs::create_dir_all(root.join("tools")).unwrap();
+        fs::write(root.join("settings.gradle"), "include ':core'\n").unwrap();
+        fs::write(
+            root.join("core/src/main/java/demo/Core.java"),
+            "class Core {}\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("core/src/test/java/demo/CoreTest.java"),
+            "class CoreTest {}\n",
+        )
+        .unwrap();
+        fs::write(root.join("tools/Gen.kt"), "fun main() {}\n").unwrap();
+
+        let result = count_directory_with_options(
+            root,
+            CountOptions::new()
+                .filter(
+                    FilterConfig::new().languages(crate::data::LanguageSelection::new(&[
+                        crate::data::LanguageName::Jvm,
+                    ])),
+                )
+                .aggregation(Aggregation::ByModule),
+        )
+        .unwrap();
+
+        let module_names: Vec<_> = result.modules.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(module_names, vec!["core", "tools"]);
+        assert_eq!(result.modules[0].stats.code, 1);
+        assert_eq!(result.modules[0].stats.tests, 1);
     }
 
     #[test]
@@ -713,6 +1710,341 @@ fn foo() {
         assert_eq!(result.files.len(), 2);
     }
 
+    #[test]
+    fn test_count_workspace_counts_root_and_crate_docs_when_markdown_is_selected() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        create_workspace(root);
+        fs::write(root.join("README.md"), "# Workspace\n\nIntro.\n").unwrap();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/guide.md"), "Guide.\n").unwrap();
+        fs::write(root.join("crate-a/README.md"), "Crate A.\n").unwrap();
+        fs::write(root.join("crate-a/src/notes.md"), "Notes.\n").unwrap();
+        fs::create_dir_all(root.join("target/doc")).unwrap();
+        fs::write(root.join("target/doc/out.md"), "Generated.\n").unwrap();
+
+        let markdown = FilterConfig::new().languages(LanguageSelection::new(&[
+            LanguageName::Rust,
+            LanguageName::Markdown,
+        ]));
+        let result = count_workspace(
+            root,
+            CountOptions::new()
+                .filter(markdown)
+                .aggregation(Aggregation::ByCrate),
+        )
+        .unwrap();
+
+        let files = |name: &str| -> Vec<PathBuf> {
+            let stats = result.crates.iter().find(|c| c.name == name).unwrap();
+            stats
+                .files
+                .iter()
+                .map(|f| f.path.strip_prefix(root).unwrap().to_path_buf())
+                .collect()
+        };
+        assert_eq!(
+            files(WORKSPACE_ROOT_NAME),
+            [PathBuf::from("README.md"), PathBuf::from("docs/guide.md")]
+        );
+        assert_eq!(
+            files("crate-a"),
+            [
+                PathBuf::from("crate-a/README.md"),
+                PathBuf::from("crate-a/src/lib.rs"),
+                PathBuf::from("crate-a/src/notes.md"),
+            ]
+        );
+        assert_eq!(result.file_count, 6);
+
+        // Rust alone walks only the target directories, and a crate filter
+        // leaves the workspace root out.
+        let result =
+            count_workspace(root, CountOptions::new().aggregation(Aggregation::ByCrate)).unwrap();
+        assert_eq!(result.crates.len(), 2);
+        assert_eq!(result.file_count, 2);
+        let markdown =
+            FilterConfig::new().languages(LanguageSelection::new(&[LanguageName::Markdown]));
+        let result = count_workspace(
+            root,
+            CountOptions::new()
+                .filter(markdown)
+                .crates(vec!["crate-b".to_string()]),
+        )
+        .unwrap();
+        assert_eq!(result.file_count, 0);
+    }
+
+    #[test]
+    fn test_count_workspace_counts_manifests_and_workflows_when_config_is_selected() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        create_workspace(root);
+        fs::create_dir_all(root.join(".github/workflows")).unwrap();
+        fs::write(root.join(".github/workflows/ci.yml"), "on: push\n").unwrap();
+
+        let config = FilterConfig::new().languages(LanguageSelection::new(&[LanguageName::Config]));
+        let result = count_workspace(
+            root,
+            CountOptions::new()
+                .filter(config)
+                .line_types(LineTypes::default().with_config())
+                .aggregation(Aggregation::ByCrate),
+        )
+        .unwrap();
+
+        let files = |name: &str| -> Vec<PathBuf> {
+            let stats = result.crates.iter().find(|c| c.name == name).unwrap();
+            stats
+                .files
+                .iter()
+                .map(|f| f.path.strip_prefix(root).unwrap().to_path_buf())
+                .collect()
+        };
+        assert_eq!(
+            files(WORKSPACE_ROOT_NAME),
+            [
+                PathBuf::from(".github/workflows/ci.yml"),
+                PathBuf::from("Cargo.toml"),
+            ]
+        );
+        assert_eq!(files("crate-a"), [PathBuf::from("crate-a/Cargo.toml")]);
+        assert_eq!(files("crate-b"), [PathBuf::from("crate-b/Cargo.toml")]);
+        assert!(result.total.config > 0);
+        assert_eq!(result.total.code, 0);
+    }
+
+    #[test]
+    fn test_count_workspace_counts_the_packages_of_other_build_tools() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        create_workspace(root);
+        fs::create_dir_all(root.join("bindings/src/acme")).unwrap();
+        fs::write(
+            root.join("bindings/pyproject.toml"),
+            "[project]\nname = \"acme-py\"\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("bindings/src/acme/__init__.py"),
+            "def hello():\n    return 1\n",
+        )
+        .unwrap();
+        // Beside a crate's manifest, the crate wins.
+        fs::write(
+            root.join("crate-a/pyproject.toml"),
+            "[project]\nname = \"crate-a-py\"\n",
+        )
+        .unwrap();
+
+        let filter = FilterConfig::new().languages(LanguageSelection::new(&[
+            LanguageName::Rust,
+            LanguageName::Python,
+        ]));
+        let options = CountOptions::new()
+            .filter(filter)
+            .aggregation(Aggregation::ByCrate);
+        let result = count_workspace(root, options.clone()).unwrap();
+        let mut names: Vec<_> = result.crates.iter().map(|c| c.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["acme-py", "crate-a", "crate-b"]);
+        assert_eq!(result.file_count, 3);
+        let package = result.crates.iter().find(|c| c.name == "acme-py").unwrap();
+        assert_eq!(package.stats.code, 2);
+
+        let totals = CountOptions::new().filter(options.file_filter.clone());
+        let result = count_workspace(root, options.crates(vec!["acme-py".to_string()])).unwrap();
+        assert_eq!(result.crates.len(), 1);
+        assert_eq!(result.file_count, 1);
+
+        // Without per-package rows or a package filter, packages are not
+        // looked for.
+        assert_eq!(count_workspace(root, totals).unwrap().file_count, 2);
+
+        // Rust alone finds nothing in the Python package, which gets no row.
+        let result =
+            count_workspace(root, CountOptions::new().aggregation(Aggregation::ByCrate)).unwrap();
+        assert_eq!(result.crates.len(), 2);
+    }
+
+    #[test]
+    fn test_count_workspaces_finds_every_workspace_beneath_a_directory() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        let manifest = |dir: &str, body: &str| {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("Cargo.toml"), body).unwrap();
+        };
+        let package = |name: &str| {
+            format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n")
+        };
+        manifest("services", "[workspace]\nmembers = [\"api\", \"worker\"]\n");
+        manifest("services/api", &package("server"));
+        create_rust_file(&root.join("services/api/src/lib.rs"), "pub fn a() {}\n");
+        manifest("services/worker", &package("worker"));
+        create_rust_file(
+            &root.join("services/worker/src/main.rs"),
+            "fn main() {}\nfn b() {}\n",
+        );
+        manifest("tools/server", &package("server"));
+        create_rust_file(&root.join("tools/server/src/main.rs"), "fn main() {}\n");
+        // Neither a vendored package nor build output is a workspace.
+        manifest("vendor/dep", &package("dep"));
+        fs::write(root.join("vendor/dep/.cargo-checksum.json"), "{}").unwrap();
+        create_rust_file(&root.join("vendor/dep/src/lib.rs"), "pub fn v() {}\n");
+        manifest("target/package/x", &package("x"));
+
+        let options = CountOptions::new().aggregation(Aggregation::ByWorkspace);
+        let result = count_workspaces(root, options).unwrap();
+        let rows: Vec<_> = result
+            .workspaces
+            .iter()
+            .map(|w| (w.name.as_str(), w.stats.code, w.crates.len()))
+            .collect();
+        assert_eq!(rows, [("services", 3, 2), ("tools/server", 1, 1)]);
+        assert_eq!(result.total.code, 4);
+
+        // Crate names are qualified; the filter takes the bare name too.
+        let options = CountOptions::new()
+            .aggregation(Aggregation::ByCrate)
+            .crates(vec!["server".to_string()]);
+        let result = count_workspaces(root, options).unwrap();
+        let names: Vec<_> = result.crates.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["services:server", "tools/server:server"]);
+    }
+
+    #[test]
+    fn test_count_dependencies_labels_rows_by_version() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        create_rust_file(&root.join("app/src/lib.rs"), "pub fn app() {}\n");
+        fs::write(
+            root.join("app/Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+             [dependencies]\nshared = { path = \"../shared\" }\n",
+        )
+        .unwrap();
+        create_rust_file(
+            &root.join("shared/src/lib.rs"),
+            "pub fn a() {}\n\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn t() {}\n}\n",
+        );
+        fs::write(
+            root.join("shared/Cargo.toml"),
+            "[package]\nname = \"shared\"\nversion = \"1.0.0\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+
+        let options = CountOptions::new().aggregation(Aggregation::ByCrate);
+        let result =
+            count_dependencies(root.join("app"), &DependencyQuery::new(), options).unwrap();
+
+        assert_eq!(result.crates.len(), 1);
+        assert_eq!(result.crates[0].name, "shared@1.0.0");
+        assert_eq!(result.total.code, 1);
+        assert!(result.total.tests > 0);
+    }
+
+    #[test]
+    fn test_count_workspace_by_target_follows_the_manifest() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            r#"[package]
+name = "custom"
+version = "0.1.0"
+edition = "2021"
+autotests = false
+
+[lib]
+path = "core/entry.rs"
+
+[[bin]]
+name = "tool"
+path = "cli/tool.rs"
+
+[[test]]
+name = "checks"
+path = "checks/run.rs"
+"#,
+        )
+        .unwrap();
+        create_rust_file(
+            &root.join("core/entry.rs"),
+            "pub fn a() {}\npub fn b() {}\n",
+        );
+        create_rust_file(&root.join("cli/tool.rs"), "fn main() {}\n");
+        create_rust_file(&root.join("checks/run.rs"), "fn check() {}\n");
+        create_rust_file(&root.join("checks/common/mod.rs"), "pub fn setup() {}\n");
+        // `autotests = false`: not a target, so not counted.
+        create_rust_file(&root.join("tests/stale.rs"), "fn stale() {}\n");
+
+        let options = CountOptions::new().aggregation(Aggregation::ByTarget);
+        let result = count_workspace(root, options).unwrap();
+
+        assert_eq!(result.file_count, 4);
+        assert_eq!((result.total.code, result.total.tests), (3, 2));
+        let targets: Vec<_> = result
+            .targets
+            .iter()
+            .map(|t| (t.name.as_str(), t.kind, t.stats.code + t.stats.tests))
+            .collect();
+        assert_eq!(
+            targets,
+            [
+                ("custom (bin tool)", Some(TargetKind::Bin), 1),
+                ("custom (lib)", Some(TargetKind::Lib), 2),
+                ("custom (test checks)", Some(TargetKind::Test), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_count_workspace_counts_modules_of_a_root_level_lib() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            r#"[package]
+name = "flat"
+version = "0.1.0"
+edition = "2021"
+autotests = false
+
+[lib]
+path = "lib.rs"
+
+[[test]]
+name = "auto"
+path = "tests/auto.rs"
+"#,
+        )
+        .unwrap();
+        create_rust_file(&root.join("lib.rs"), "mod util;\npub fn a() {}\n");
+        create_rust_file(&root.join("util.rs"), "pub fn b() {}\n");
+        create_rust_file(&root.join("tests/auto.rs"), "fn check() {}\n");
+        create_rust_file(&root.join("scratch.rs"), "fn unused() {}\n");
+
+        for discovery in [
+            WorkspaceDiscovery::Manifest,
+            WorkspaceDiscovery::CargoMetadata,
+        ] {
+            let options = CountOptions::new()
+                .aggregation(Aggregation::ByTarget)
+                .workspace_discovery(discovery);
+            let result = count_workspace(root, options).unwrap();
+
+            assert_eq!(result.file_count, 3, "{:?}", discovery);
+            assert_eq!((result.total.code, result.total.tests), (3, 1));
+            let targets: Vec<_> = result
+                .targets
+                .iter()
+                .map(|t| (t.name.as_str(), t.stats.code + t.stats.tests))
+                .collect();
+            assert_eq!(targets, [("flat (lib)", 3), ("flat (test auto)", 1)]);
+        }
+    }
+
     #[test]
     fn test_count_mixed_code_and_tests() {
         let temp = tempdir().unwrap();
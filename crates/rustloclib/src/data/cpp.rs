@@ -0,0 +1,258 @@
+use std::path::Path;
+
+use crate::Result;
+
+use super::backend::{
+    generic_context_from_path, FileAnalysis, LanguageBackend, LanguageId, LineClass, LogicContext,
+};
+use super::lexer::{brace_regions, lex, BlockComment, LexSpec, StringDelim};
+use super::stats::Locs;
+
+/// C and C++ backend with Doxygen docs, preprocessor directives and
+/// GoogleTest/Catch2/doctest awareness.
+#[derive(Debug, Default)]
+pub struct CppBackend;
+
+const C_FAMILY: LexSpec = LexSpec {
+    doc_line_comments: &["///", "//!"],
+    line_comments: &["//"],
+    block_comments: &[
+        BlockComment {
+            open: "/**",
+            close: "*/",
+            doc: true,
+            nested: false,
+        },
+        BlockComment {
+            open: "/*!",
+            close: "*/",
+            doc: true,
+            nested: false,
+        },
+        BlockComment {
+            open: "/*",
+            close: "*/",
+            doc: false,
+            nested: false,
+        },
+    ],
+    strings: &[
+        StringDelim {
+            open: "\"",
+            close: "\"",
+            escape: Some('\\'),
+            multiline: false,
+        },
+        StringDelim {
+            open: "'",
+            close: "'",
+            escape: Some('\\'),
+            multiline: false,
+        },
+    ],
+    raw_strings: true,
+    code_prefixes: &[],
+};
+
+const C_EXTENSIONS: &[&str] = &["c", "h"];
+const CPP_EXTENSIONS: &[&str] = &[
+    "cc", "cpp", "cxx", "c++", "hpp", "hh", "hxx", "h++", "ipp", "tpp", "inl",
+];
+
+/// Test-defining macros whose body is test code: GoogleTest, Catch2 and
+/// doctest.
+const TEST_MACROS: &[&str] = &[
+    // GoogleTest
+    "TEST",
+    "TEST_F",
+    "TEST_P",
+    "TYPED_TEST",
+    "TYPED_TEST_P",
+    // Catch2
+    "TEST_CASE",
+    "TEST_CASE_METHOD",
+    "TEMPLATE_TEST_CASE",
+    "TEMPLATE_PRODUCT_TEST_CASE",
+    "SCENARIO",
+    // doctest
+    "TEST_SUITE",
+    "TEST_CASE_FIXTURE",
+    "TEST_CASE_TEMPLATE",
+];
+
+impl LanguageBackend for CppBackend {
+    fn supports_path(&self, path: &Path) -> bool {
+        c_language(path).is_some()
+    }
+
+    fn supports_hint(&self, hint: &str) -> bool {
+        matches!(hint, "c" | "cpp")
+    }
+
+    fn analyze_source(&self, path: &Path, source: &str) -> Result<FileAnalysis> {
+        self.analyze_source_in_context(path, source, generic_context_from_path(path))
+    }
+
+    fn analyze_source_in_context(
+        &self,
+        path: &Path,
+        source: &str,
+        context: LogicContext,
+    ) -> Result<FileAnalysis> {
+        let language = c_language(path).unwrap_or(LanguageId::Cpp);
+        let lines = lex(source, &C_FAMILY);
+
+        let mut in_directive = false;
+        let directives: Vec<bool> = lines
+            .iter()
+            .map(|line| {
+                let code = line.code.trim();
+                let directive = in_directive || code.starts_with('#');
+                in_directive = directive && code.ends_with('\\');
+                directive
+            })
+            .collect();
+        let codes =
+            lines.iter().zip(&directives).map(
+                |(line, &directive)| {
+                    if directive {
+                        ""
+                    } else {
+                        line.code.as_str()
+                    }
+                },
+            );
+        let tests = brace_regions(codes, |_, ident, after| {
+            TEST_MACROS.contains(&ident) && after.trim_start().starts_with('(')
+        });
+
+        let mut stats = Locs::new();
+        let mut line_classes = Vec::with_capacity(lines.len());
+        for ((line, directive), is_test) in lines.iter().zip(directives).zip(tests) {
+            let class = if directive && line.has_code {
+                LineClass::Preprocessor
+            } else if is_test {
+                line.class(LogicContext::Tests)
+            } else {
+                line.class(context)
+            };
+            class.record(&mut stats);
+            line_classes.push(class);
+        }
+
+        Ok(FileAnalysis {
+            language,
+            stats,
+            line_classes,
+            parse_failure: None,
+        })
+    }
+}
+
+fn c_language(path: &Path) -> Option<LanguageId> {
+    let ext = path.extension()?.to_str()?;
+    let matches = |candidates: &[&str]| {
+        candidates
+            .iter()
+            .any(|candidate| ext.eq_ignore_ascii_case(candidate))
+    };
+    if matches(C_EXTENSIONS) {
+        Some(LanguageId::C)
+    } else if matches(CPP_EXTENSIONS) {
+        Some(LanguageId::Cpp)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analyze(path: &str, source: &str) -> FileAnalysis {
+        CppBackend.analyze_source(Path::new(path), source).unwrap()
+    }
+
+    #[test]
+    fn classifies_doxygen_docs_comments_and_strings() {
+        let analysis = analyze(
+            "src/parser.cpp",
+            r#"/// Parses input.
+//! File docs.
+/*! Qt-style
+ *  docs */
+/** Javadoc-style */
+int parse(const char *s) { // trailing comment
+    const char *url = "http://example.invalid"; /* after code */
+    /* plain */
+    auto raw = R"(// not a comment
+/* still not */)";
+
+    return 0;
+}
+"#,
+        );
+
+        assert_eq!(analysis.language, LanguageId::Cpp);
+        assert_eq!(analysis.stats.docs, 5);
+        assert_eq!(analysis.stats.comments, 1);
+        assert_eq!(analysis.stats.code, 6);
+        assert_eq!(analysis.stats.blanks, 1);
+    }
+
+    #[test]
+    fn preprocessor_directives_are_their_own_category() {
+        let analysis = analyze(
+            "src/config.h",
+            "#include <stdio.h>\n#define MAX(a, b) \\\n    ((a) > (b) ? (a) : (b))\n  # pragma once\n\nint x; // #define in a comment\n",
+        );
+
+        assert_eq!(analysis.language, LanguageId::C);
+        assert_eq!(analysis.stats.preprocessor, 4);
+        assert_eq!(analysis.stats.code, 1);
+        assert_eq!(analysis.stats.blanks, 1);
+        assert_eq!(analysis.stats.total, 6);
+    }
+
+    #[test]
+    fn googletest_catch2_and_doctest_bodies_are_tests() {
+        let analysis = analyze(
+            "src/math.cpp",
+            r#"#include <gtest/gtest.h>
+
+int add(int a, int b) { return a + b; }
+
+TEST(Math, Adds) {
+    EXPECT_EQ(add(1, 2), 3);
+}
+
+TEST_CASE("adds", "[math]") {
+    SECTION("zero") { REQUIRE(add(0, 0) == 0); }
+}
+
+TEST_SUITE("math") {
+    TEST_CASE_FIXTURE(Fixture, "fixture") {
+        CHECK(value == 1);
+    }
+}
+"#,
+        );
+
+        assert_eq!(analysis.stats.preprocessor, 1);
+        assert_eq!(analysis.stats.code, 1);
+        assert_eq!(analysis.stats.tests, 11);
+        assert_eq!(analysis.stats.blanks, 4);
+    }
+
+    #[test]
+    fn test_paths_count_as_tests() {
+        let analysis = analyze(
+            "tests/parser_test.cc",
+            "#include \"parser.h\"\nint helper() { return 1; }\n",
+        );
+
+        assert_eq!(analysis.stats.preprocessor, 1);
+        assert_eq!(analysis.stats.tests, 1);
+        assert_eq!(analysis.stats.code, 0);
+    }
+}
@@ -0,0 +1,474 @@
+//! User-defined languages loaded from a configuration file.
+//!
+//! Languages without a built-in backend can be declared in TOML and counted
+//! through the shared line lexer, so adding Terraform or Lua does not require
+//! a rustloc release:
+//!
+//! ```toml
+//! [[language]]
+//! name = "lua"
+//! extensions = ["lua"]
+//! shebangs = ["lua", "luajit"]
+//! line_comments = ["--"]
+//! block_comments = [["--[[", "]]"]]
+//! doc_comments = ["---"]
+//! quotes = ["\"", "'"]
+//! test_paths = ["spec/**", "**/*_spec.lua"]
+//! ```
+//!
+//! Path globs are matched against the path relative to the analyzed directory
+//! (the repository in a diff): `spec/**` is the top-level `spec/` directory,
+//! `**/*_spec.lua` a spec file anywhere.
+
+use std::path::Path;
+
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+
+use crate::error::RustlocError;
+use crate::Result;
+
+use super::backend::{
+    generic_context_from_path, FileAnalysis, LanguageBackend, LanguageId, LanguageName,
+    LogicContext,
+};
+use super::classify::ClassifyRule;
+use super::lexer::{lex, BlockComment, LexSpec, StringDelim};
+use super::plugin::PluginDefinition;
+use super::stats::Locs;
+
+/// A language declared in a configuration file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LanguageDefinition {
+    /// Name used with `--lang` and reported as the file language.
+    pub name: String,
+    /// File extensions without the dot (`tf`, `proto`).
+    pub extensions: Vec<String>,
+    /// Exact file names (`Tiltfile`).
+    pub filenames: Vec<String>,
+    /// Interpreters that identify scripts no backend claims by path (`lua`
+    /// matches `#!/usr/bin/env lua` and `#!/usr/bin/lua5.4`). Modelines
+    /// naming the language's `name` are recognized as well.
+    pub shebangs: Vec<String>,
+    /// Line comment prefixes (`#`, `//`).
+    pub line_comments: Vec<String>,
+    /// Block comment `[open, close]` pairs.
+    pub block_comments: Vec<(String, String)>,
+    /// Line prefixes that mark documentation comments (`///`, `---`).
+    pub doc_comments: Vec<String>,
+    /// Block comments nest (`/* /* */ */` closes twice).
+    pub nested_comments: bool,
+    /// String quote characters; comment markers inside strings are code.
+    pub quotes: Vec<String>,
+    /// Globs for test files, relative to the analyzed directory; replaces
+    /// the default `tests/`-style path rules.
+    pub test_paths: Vec<String>,
+    /// Globs for example files, relative to the analyzed directory; replaces
+    /// the default `examples/`-style rules.
+    pub example_paths: Vec<String>,
+}
+
+impl Default for LanguageDefinition {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            extensions: Vec::new(),
+            filenames: Vec::new(),
+            shebangs: Vec::new(),
+            line_comments: Vec::new(),
+            block_comments: Vec::new(),
+            doc_comments: Vec::new(),
+            nested_comments: false,
+            quotes: vec!["\"".to_string()],
+            test_paths: Vec::new(),
+            example_paths: Vec::new(),
+        }
+    }
+}
+
+/// Language definitions, plugins and classification rules read from a
+/// configuration file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LanguageConfig {
+    /// One entry per `[[language]]` table.
+    #[serde(rename = "language")]
+    pub languages: Vec<LanguageDefinition>,
+    /// One entry per `[[plugin]]` table.
+    #[serde(rename = "plugin")]
+    pub plugins: Vec<PluginDefinition>,
+    /// One entry per `[[classify]]` table, in the order they are tried.
+    #[serde(rename = "classify")]
+    pub classify: Vec<ClassifyRule>,
+}
+
+impl LanguageConfig {
+    /// Parse a TOML configuration.
+    pub fn from_toml(source: &str) -> Result<Self> {
+        toml::from_str(source).map_err(|e| RustlocError::InvalidLanguageConfig(e.to_string()))
+    }
+
+    /// Read and parse a TOML configuration file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|e| RustlocError::FileRead {
+            path: path.to_path_buf(),
+            source: e,
+        })?;
+        let mut config = Self::from_toml(&source).map_err(|e| match e {
+            RustlocError::InvalidLanguageConfig(message) => {
+                RustlocError::InvalidLanguageConfig(format!("{}: {}", path.display(), message))
+            }
+            other => other,
+        })?;
+
+        // `tools/dsl-loc` means next to the config file, not the working
+        // directory; bare names are left for `PATH` lookup.
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for plugin in &mut config.plugins {
+            let command = Path::new(&plugin.command);
+            if command.is_relative() && command.components().count() > 1 {
+                plugin.command = dir.join(command).to_string_lossy().into_owned();
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// A user-defined language name must be present and must not shadow a built-in
+/// `--lang` value.
+pub(super) fn check_name(name: &str) -> std::result::Result<(), String> {
+    if name.is_empty() {
+        Err("missing name".to_string())
+    } else if name.eq_ignore_ascii_case("all") || name.parse::<LanguageName>().is_ok() {
+        Err("name is reserved by a built-in language".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// Backend for a [`LanguageDefinition`], built on the shared line lexer.
+#[derive(Debug)]
+pub struct CustomBackend {
+    definition: LanguageDefinition,
+    test_paths: Vec<Pattern>,
+    example_paths: Vec<Pattern>,
+}
+
+impl CustomBackend {
+    /// Validate a definition and compile its path globs.
+    pub fn new(definition: LanguageDefinition) -> Result<Self> {
+        let invalid = |message: String| {
+            Err(RustlocError::InvalidLanguageConfig(format!(
+                "language '{}': {}",
+                definition.name, message
+            )))
+        };
+        if let Err(message) = check_name(&definition.name) {
+            return invalid(message);
+        }
+        if definition.extensions.is_empty()
+            && definition.filenames.is_empty()
+            && definition.shebangs.is_empty()
+        {
+            return invalid("needs at least one extension, filename or shebang".to_string());
+        }
+        let empty_delimiter = definition
+            .line_comments
+            .iter()
+            .chain(&definition.doc_comments)
+            .chain(&definition.quotes)
+            .chain(
+                definition
+                    .block_comments
+                    .iter()
+                    .flat_map(|(open, close)| [open, close]),
+            )
+            .any(|delimiter| delimiter.is_empty());
+        if empty_delimiter {
+            return invalid("comment and quote delimiters cannot be empty".to_string());
+        }
+
+        let compile = |globs: &[String]| {
+            globs
+                .iter()
+                .map(|glob| {
+                    Pattern::new(glob).map_err(|e| RustlocError::InvalidGlob {
+                        pattern: glob.clone(),
+                        message: e.to_string(),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        let test_paths = compile(&definition.test_paths)?;
+        let example_paths = compile(&definition.example_paths)?;
+
+        Ok(Self {
+            definition,
+            test_paths,
+            example_paths,
+        })
+    }
+
+    /// The name this language is selected by.
+    pub fn name(&self) -> &str {
+        &self.definition.name
+    }
+
+    /// The definition this backend was built from.
+    pub fn definition(&self) -> &LanguageDefinition {
+        &self.definition
+    }
+
+    fn context(&self, path: &Path) -> LogicContext {
+        self.configured_context(path)
+            .unwrap_or_else(|| generic_context_from_path(path))
+    }
+}
+
+impl LanguageBackend for CustomBackend {
+    fn supports_path(&self, path: &Path) -> bool {
+        let definition = &self.definition;
+        let by_extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                definition
+                    .extensions
+                    .iter()
+                    .any(|candidate| ext.eq_ignore_ascii_case(candidate))
+            });
+        let by_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| definition.filenames.iter().any(|f| f == name));
+        by_extension || by_name
+    }
+
+    fn supports_hint(&self, hint: &str) -> bool {
+        self.definition.name.eq_ignore_ascii_case(hint)
+            || self
+                .definition
+                .shebangs
+                .iter()
+                .any(|shebang| shebang.eq_ignore_ascii_case(hint))
+    }
+
+    fn analyze_source(&self, path: &Path, source: &str) -> Result<FileAnalysis> {
+        self.analyze_source_in_context(path, source, self.context(path))
+    }
+
+    fn configured_context(&self, relative: &Path) -> Option<LogicContext> {
+        if self.test_paths.is_empty() && self.example_paths.is_empty() {
+            return None;
+        }
+        let path = relative.to_string_lossy();
+        Some(if self.test_paths.iter().any(|glob| glob.matches(&path)) {
+            LogicContext::Tests
+        } else if self.example_paths.iter().any(|glob| glob.matches(&path)) {
+            LogicContext::Example
+        } else {
+            LogicContext::Code
+        })
+    }
+
+    fn analyze_source_in_context(
+        &self,
+        _path: &Path,
+        source: &str,
+        context: LogicContext,
+    ) -> Result<FileAnalysis> {
+        let definition = &self.definition;
+        let doc_line_comments: Vec<&str> =
+            definition.doc_comments.iter().map(String::as_str).collect();
+        let line_comments: Vec<&str> = definition
+            .line_comments
+            .iter()
+            .map(String::as_str)
+            .collect();
+        let mut block_comments: Vec<BlockComment> = definition
+            .block_comments
+            .iter()
+            .map(|(open, close)| BlockComment {
+                open,
+                close,
+                doc: false,
+                nested: definition.nested_comments,
+            })
+            .collect();
+        block_comments.sort_by_key(|block| std::cmp::Reverse(block.open.len()));
+        let strings: Vec<StringDelim> = definition
+            .quotes
+            .iter()
+            .map(|quote| StringDelim {
+                open: quote,
+                close: quote,
+                escape: Some('\\'),
+                multiline: false,
+            })
+            .collect();
+        let spec = LexSpec {
+            doc_line_comments: &doc_line_comments,
+            line_comments: &line_comments,
+            block_comments: &block_comments,
+            strings: &strings,
+            raw_strings: false,
+            code_prefixes: &[],
+        };
+
+        let mut stats = Locs::new();
+        let mut line_classes = Vec::new();
+        for line in lex(source, &spec) {
+            let class = line.class(context);
+            class.record(&mut stats);
+            line_classes.push(class);
+        }
+
+        Ok(FileAnalysis {
+            language: LanguageId::External(definition.name.clone()),
+            stats,
+            line_classes,
+            parse_failure: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r##"
+[[language]]
+name = "terraform"
+extensions = ["tf", "tfvars"]
+line_comments = ["#", "//"]
+block_comments = [["/*", "*/"]]
+
+[[language]]
+name = "lua"
+extensions = ["lua"]
+shebangs = ["lua"]
+line_comments = ["--"]
+block_comments = [["--[[", "]]"]]
+doc_comments = ["---"]
+quotes = ["\"", "'"]
+test_paths = ["**/spec/**"]
+"##;
+
+    fn backends() -> Vec<CustomBackend> {
+        LanguageConfig::from_toml(CONFIG)
+            .unwrap()
+            .languages
+            .into_iter()
+            .map(|definition| CustomBackend::new(definition).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn parses_language_tables() {
+        let config = LanguageConfig::from_toml(CONFIG).unwrap();
+
+        assert_eq!(config.languages.len(), 2);
+        assert_eq!(config.languages[0].name, "terraform");
+        assert_eq!(config.languages[0].quotes, vec!["\""]);
+        assert_eq!(
+            config.languages[1].block_comments,
+            vec![("--[[".to_string(), "]]".to_string())]
+        );
+    }
+
+    #[test]
+    fn classifies_with_the_declared_syntax() {
+        let backends = backends();
+        let analysis = backends[0]
+            .analyze_source(
+                Path::new("main.tf"),
+                "# comment\nsource = \"git::https://example.invalid//mod\"\n/* block\n*/\n\n// note\n",
+            )
+            .unwrap();
+
+        assert_eq!(
+            analysis.language,
+            LanguageId::External("terraform".to_string())
+        );
+        assert_eq!(analysis.stats.code, 1);
+        assert_eq!(analysis.stats.comments, 4);
+        assert_eq!(analysis.stats.blanks, 1);
+    }
+
+    #[test]
+    fn docs_and_test_globs() {
+        let backends = backends();
+        let lua = &backends[1];
+        let source =
+            "--- Adds numbers.\nlocal function add(a, b) return a + b end\n--[[\nnotes\n]]\n";
+
+        let analysis = lua
+            .analyze_source(Path::new("src/math.lua"), source)
+            .unwrap();
+        assert_eq!(analysis.stats.docs, 1);
+        assert_eq!(analysis.stats.code, 1);
+        assert_eq!(analysis.stats.comments, 3);
+
+        let analysis = lua
+            .analyze_source(Path::new("app/spec/math_spec.lua"), source)
+            .unwrap();
+        assert_eq!(analysis.stats.tests, 1);
+
+        // Test globs replace the default path rules.
+        let analysis = lua
+            .analyze_source(Path::new("tests/math.lua"), source)
+            .unwrap();
+        assert_eq!(analysis.stats.code, 1);
+    }
+
+    #[test]
+    fn matches_extensions_filenames_and_hints() {
+        let backends = backends();
+
+        assert!(backends[0].supports_path(Path::new("vars.TFVARS")));
+        assert!(!backends[0].supports_path(Path::new("main.rs")));
+        assert!(!backends[1].supports_path(Path::new("build")));
+        assert!(backends[0].supports_hint("terraform"));
+        assert!(backends[1].supports_hint("lua"));
+        assert!(!backends[1].supports_hint("sh"));
+    }
+
+    #[test]
+    fn resolves_plugin_commands_against_the_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".rustloc.toml");
+        std::fs::write(
+            &path,
+            "[[plugin]]\nname = \"a\"\nextensions = [\"a\"]\ncommand = \"tools/a-loc\"\n\n\
+             [[plugin]]\nname = \"b\"\nextensions = [\"b\"]\ncommand = \"b-loc\"\n",
+        )
+        .unwrap();
+
+        let config = LanguageConfig::load(&path).unwrap();
+        assert_eq!(
+            Path::new(&config.plugins[0].command),
+            dir.path().join("tools/a-loc")
+        );
+        assert_eq!(config.plugins[1].command, "b-loc");
+    }
+
+    #[test]
+    fn rejects_invalid_definitions() {
+        for config in [
+            "[[language]]\nextensions = [\"x\"]\n",
+            "[[language]]\nname = \"rust\"\nextensions = [\"x\"]\n",
+            "[[language]]\nname = \"x\"\n",
+            "[[language]]\nname = \"x\"\nextensions = [\"x\"]\ntest_paths = [\"[\"]\n",
+        ] {
+            let definition = LanguageConfig::from_toml(config)
+                .unwrap()
+                .languages
+                .remove(0);
+            assert!(CustomBackend::new(definition).is_err(), "{config}");
+        }
+
+        assert!(LanguageConfig::from_toml("[[language]]\nname = \"x\"\ncomments = []\n").is_err());
+    }
+}
@@ -0,0 +1,238 @@
+//! Language detection beyond file extensions.
+//!
+//! [`BackendRegistry`](super::BackendRegistry) first routes a file by its
+//! path: a backend's extensions and well-known filenames (`Rakefile`,
+//! `Dockerfile`, ...). Files no selected backend claims that way are sniffed
+//! for [`ContentHints`]: a vim or emacs modeline naming the filetype, then the
+//! interpreter of a `#!` line. Backends opt into hints with
+//! [`LanguageBackend::supports_hint`](super::LanguageBackend::supports_hint).
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Bytes read from each end of a file when sniffing it.
+const SNIFF_BYTES: u64 = 1024;
+
+/// Lines at each end of a file searched for a modeline, as vim does.
+const MODELINE_LINES: usize = 5;
+
+/// Why a file was routed to its backend.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DetectionReason {
+    /// The file extension.
+    Extension,
+    /// A well-known file name.
+    Filename,
+    /// A vim or emacs modeline.
+    Modeline,
+    /// The interpreter on a `#!` line.
+    Shebang,
+}
+
+impl DetectionReason {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Extension => "extension",
+            Self::Filename => "filename",
+            Self::Modeline => "modeline",
+            Self::Shebang => "shebang",
+        }
+    }
+}
+
+/// The backend a file was routed to and why.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Detection {
+    /// Registered name of the backend.
+    pub backend: String,
+    pub reason: DetectionReason,
+}
+
+/// A file routed to its backend by something other than its extension.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DetectedFile {
+    /// Path to the file.
+    pub path: PathBuf,
+    /// The backend the file was routed to and why.
+    pub detection: Detection,
+}
+
+impl DetectedFile {
+    /// Record `detection` for `path`, unless it came from the extension.
+    pub fn unless_by_extension(path: &Path, detection: Option<&Detection>) -> Option<Self> {
+        let detection = detection.filter(|d| d.reason != DetectionReason::Extension)?;
+        Some(Self {
+            path: path.to_path_buf(),
+            detection: detection.clone(),
+        })
+    }
+}
+
+/// Language hints found in a file's content.
+///
+/// Hints are lowercase; interpreter names lose their directory and version
+/// suffix (`/usr/bin/python3.12` is `python`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentHints {
+    /// Filetype from a vim (`vim: ft=ruby`) or emacs (`-*- mode: ruby -*-`)
+    /// modeline.
+    pub modeline: Option<String>,
+    /// Interpreter from a `#!` first line, looking through `env`.
+    pub interpreter: Option<String>,
+}
+
+impl ContentHints {
+    /// Hints in a source string.
+    pub fn from_source(source: &str) -> Self {
+        Self::from_ends(source, source)
+    }
+
+    /// Hints in the first and last kilobyte of a file. Unreadable files have
+    /// no hints.
+    pub fn from_path(path: &Path) -> Self {
+        Self::read(path).unwrap_or_default()
+    }
+
+    fn read(path: &Path) -> std::io::Result<Self> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+
+        let mut head = Vec::new();
+        (&mut file).take(SNIFF_BYTES).read_to_end(&mut head)?;
+        let head = String::from_utf8_lossy(&head);
+        if len <= SNIFF_BYTES {
+            return Ok(Self::from_source(&head));
+        }
+
+        let mut tail = Vec::new();
+        file.seek(SeekFrom::Start(len.saturating_sub(SNIFF_BYTES)))?;
+        file.read_to_end(&mut tail)?;
+        let tail = String::from_utf8_lossy(&tail);
+        // The first tail line is most likely cut off.
+        let tail = tail.split_once('\n').map_or("", |(_, rest)| rest);
+        Ok(Self::from_ends(&head, tail))
+    }
+
+    fn from_ends(head: &str, tail: &str) -> Self {
+        Self {
+            modeline: head
+                .lines()
+                .take(MODELINE_LINES)
+                .chain(tail.lines().rev().take(MODELINE_LINES))
+                .find_map(modeline),
+            interpreter: head.lines().next().and_then(interpreter),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modeline.is_none() && self.interpreter.is_none()
+    }
+}
+
+/// Interpreter named by a `#!` line. Rust's `#![attr]` is not a shebang.
+fn interpreter(line: &str) -> Option<String> {
+    let command = line.strip_prefix("#!")?;
+    if command.trim_start().starts_with('[') {
+        return None;
+    }
+    let mut words = command.split_whitespace();
+    let mut program = words.next()?;
+    if program.rsplit('/').next() == Some("env") {
+        program = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+    }
+    let name = program.rsplit('/').next()?;
+    let name = name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    (!name.is_empty()).then(|| name.to_ascii_lowercase())
+}
+
+/// Filetype named by a vim or emacs modeline.
+fn modeline(line: &str) -> Option<String> {
+    emacs_mode(line).or_else(|| vim_filetype(line))
+}
+
+fn emacs_mode(line: &str) -> Option<String> {
+    let (_, rest) = line.split_once("-*-")?;
+    let (inner, _) = rest.split_once("-*-")?;
+    let inner = inner.trim();
+    let mode = if inner.contains(':') {
+        inner.split(';').find_map(|pair| {
+            let (key, value) = pair.split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case("mode")
+                .then(|| value.trim())
+        })?
+    } else {
+        inner
+    };
+    let mode = mode.strip_suffix("-mode").unwrap_or(mode);
+    (!mode.is_empty()).then(|| mode.to_ascii_lowercase())
+}
+
+fn vim_filetype(line: &str) -> Option<String> {
+    let start = ["vim:", "vi:", "ex:"].iter().find_map(|marker| {
+        line.match_indices(marker)
+            .find(|(at, _)| *at == 0 || line[..*at].ends_with(char::is_whitespace))
+            .map(|(at, _)| at + marker.len())
+    })?;
+    line[start..]
+        .split(|c: char| c == ':' || c.is_whitespace())
+        .find_map(|option| {
+            let (key, value) = option.split_once('=')?;
+            matches!(key, "ft" | "filetype" | "syn" | "syntax").then(|| value.to_ascii_lowercase())
+        })
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_interpreters_from_shebangs() {
+        for (line, expected) in [
+            ("#!/bin/sh", Some("sh")),
+            ("#!/usr/bin/env python3.12", Some("python")),
+            ("#!/usr/bin/env -S ruby -w", Some("ruby")),
+            ("#!/usr/bin/env -S cargo +nightly -Zscript", Some("cargo")),
+            ("#! /usr/local/bin/bash -e", Some("bash")),
+            ("#![allow(dead_code)]", None),
+            ("# not a shebang", None),
+        ] {
+            assert_eq!(interpreter(line).as_deref(), expected, "{line}");
+        }
+    }
+
+    #[test]
+    fn reads_vim_and_emacs_modelines() {
+        for (line, expected) in [
+            ("# vim: set ft=ruby :", Some("ruby")),
+            ("// vim:filetype=typescript:ts=2", Some("typescript")),
+            ("/* vi: syntax=c */", Some("c")),
+            ("# -*- mode: python; coding: utf-8 -*-", Some("python")),
+            ("; -*- shell-script -*-", Some("shell-script")),
+            (";; -*- Emacs-Lisp-mode -*-", Some("emacs-lisp")),
+            ("# -*- coding: utf-8 -*-", None),
+            ("let navi: ft=rust", None),
+            ("plain text", None),
+        ] {
+            assert_eq!(modeline(line).as_deref(), expected, "{line}");
+        }
+    }
+
+    #[test]
+    fn sniffs_both_ends_of_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deploy");
+        let body = "echo step\n".repeat(500);
+        std::fs::write(&path, format!("#!/bin/bash\n{body}# vim: ft=zsh\n")).unwrap();
+
+        let hints = ContentHints::from_path(&path);
+        assert_eq!(hints.interpreter.as_deref(), Some("bash"));
+        assert_eq!(hints.modeline.as_deref(), Some("zsh"));
+        assert!(ContentHints::from_path(&dir.path().join("missing")).is_empty());
+    }
+}
@@ -0,0 +1,203 @@
+//! Per-file diagnostics collected while counting and diffing.
+//!
+//! One unreadable file, a plugin that chokes on one input, or a syntax error
+//! in one module should not cost the whole report. Counting and diffing record
+//! each such problem as a [`Diagnostic`] and carry on with the remaining files;
+//! [`FilterConfig::strict`](crate::FilterConfig::strict) turns read, analysis
+//! and parse failures back into errors.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::RustlocError;
+use crate::Result;
+
+use super::backend::ParseFailure;
+use super::content::SkippedFile;
+
+/// What kind of problem a file had.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiagnosticKind {
+    /// The file could not be read; it was not counted.
+    ReadError,
+    /// The backend failed on the file; it was not counted.
+    AnalysisError,
+    /// The parser rejected the file; it was counted with a fallback.
+    ParseError,
+    /// The file failed a content check; it was not counted.
+    Skipped,
+}
+
+impl DiagnosticKind {
+    /// The kind's name in structured output.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::ReadError => "read-error",
+            Self::AnalysisError => "analysis-error",
+            Self::ParseError => "parse-error",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+impl std::fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A problem with one file, and what was done about it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// Path to the file.
+    pub path: PathBuf,
+    /// What went wrong.
+    pub kind: DiagnosticKind,
+    /// The underlying error or skip reason.
+    pub message: String,
+    /// 1-based line of a parse error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// 1-based column of a parse error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    /// How the file was counted despite the problem, such as `line-level`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<String>,
+}
+
+impl Diagnostic {
+    /// Create a diagnostic with no position or fallback.
+    pub fn new(path: impl Into<PathBuf>, kind: DiagnosticKind, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            kind,
+            message: message.into(),
+            line: None,
+            column: None,
+            fallback: None,
+        }
+    }
+
+    /// A file that could not be read or analyzed because of `error`.
+    pub fn from_error(path: impl Into<PathBuf>, error: &RustlocError) -> Self {
+        match error {
+            RustlocError::FileRead { source, .. } | RustlocError::Io(source) => {
+                Self::new(path, DiagnosticKind::ReadError, source.to_string())
+            }
+            other => Self::new(path, DiagnosticKind::AnalysisError, other.to_string()),
+        }
+    }
+
+    /// A file counted with a fallback after its parser rejected it.
+    pub fn parse_failure(path: impl Into<PathBuf>, failure: &ParseFailure) -> Self {
+        Self {
+            line: Some(failure.line),
+            column: Some(failure.column),
+            fallback: Some(failure.fallback.clone()),
+            ..Self::new(path, DiagnosticKind::ParseError, failure.message.clone())
+        }
+    }
+
+    /// A file that failed a content check.
+    pub fn skipped(file: &SkippedFile) -> Self {
+        Self::new(&file.path, DiagnosticKind::Skipped, file.reason.name())
+    }
+}
+
+/// Record a file's parse failure, or fail with it when `strict`.
+pub(crate) fn check_parse(
+    path: &Path,
+    failure: Option<&ParseFailure>,
+    strict: bool,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<()> {
+    let Some(failure) = failure else {
+        return Ok(());
+    };
+    if strict {
+        return Err(RustlocError::Parse {
+            path: path.to_path_buf(),
+            line: failure.line,
+            column: failure.column,
+            message: failure.message.clone(),
+        });
+    }
+    diagnostics.push(Diagnostic::parse_failure(path, failure));
+    Ok(())
+}
+
+/// Turn a failed file into a diagnostic and `None`, unless `strict`.
+pub(crate) fn recover<T>(
+    path: &Path,
+    result: Result<Option<T>>,
+    strict: bool,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Option<T>> {
+    match result {
+        Err(error) if !strict => {
+            diagnostics.push(Diagnostic::from_error(path, &error));
+            Ok(None)
+        }
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::content::SkipReason;
+
+    fn failure() -> ParseFailure {
+        ParseFailure::at_offset("ok\nbad line", 7, "unexpected token", "line-level")
+    }
+
+    #[test]
+    fn errors_are_classified_by_cause() {
+        let read = RustlocError::FileRead {
+            path: "a.rs".into(),
+            source: std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied"),
+        };
+        let diagnostic = Diagnostic::from_error("a.rs", &read);
+        assert_eq!(diagnostic.kind, DiagnosticKind::ReadError);
+        assert_eq!(diagnostic.message, "denied");
+
+        let plugin = RustlocError::Plugin {
+            name: "asciidoc".into(),
+            message: "exited with 1".into(),
+        };
+        let diagnostic = Diagnostic::from_error("a.adoc", &plugin);
+        assert_eq!(diagnostic.kind, DiagnosticKind::AnalysisError);
+
+        let skipped = SkippedFile::new("b.rs", SkipReason::Binary, 10);
+        assert_eq!(Diagnostic::skipped(&skipped).message, "binary");
+    }
+
+    #[test]
+    fn parse_failures_carry_their_position_and_fallback() {
+        let mut diagnostics = Vec::new();
+        check_parse(Path::new("a.py"), Some(&failure()), false, &mut diagnostics).unwrap();
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.kind, DiagnosticKind::ParseError);
+        assert_eq!((diagnostic.line, diagnostic.column), (Some(2), Some(5)));
+        assert_eq!(diagnostic.fallback.as_deref(), Some("line-level"));
+    }
+
+    #[test]
+    fn strict_turns_failures_back_into_errors() {
+        let mut diagnostics = Vec::new();
+        let error = check_parse(Path::new("a.py"), Some(&failure()), true, &mut diagnostics);
+        assert!(matches!(error, Err(RustlocError::Parse { line: 2, .. })));
+
+        let failed: Result<Option<()>> = Err(RustlocError::PathNotFound("a.rs".into()));
+        assert!(recover(Path::new("a.rs"), failed, true, &mut diagnostics).is_err());
+        let failed: Result<Option<()>> = Err(RustlocError::PathNotFound("a.rs".into()));
+        assert_eq!(
+            recover(Path::new("a.rs"), failed, false, &mut diagnostics).unwrap(),
+            None
+        );
+        assert_eq!(diagnostics.len(), 1);
+    }
+}
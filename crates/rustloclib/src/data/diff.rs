@@ -27,6 +27,7 @@
 use std::collections::HashMap;
 use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use imara_diff::intern::InternedInput;
 use imara_diff::{diff, Algorithm};
@@ -35,16 +36,21 @@ use serde::{Deserialize, Serialize};
 use crate::error::RustlocError;
 use crate::query::options::{Aggregation, LineTypes};
 use crate::source::filter::FilterConfig;
-use crate::source::workspace::WorkspaceInfo;
+use crate::source::project::ProjectModel;
+use crate::source::workspace::{TargetKind, WorkspaceDiscovery, WorkspaceInfo};
 use crate::Result;
 
-use super::backend::{BackendRegistry, FileAnalysis, LineClass};
+use super::backend::{BackendRegistry, FileAnalysis, LineClass, ParseFailure};
+use super::content::{decode_lossy, decode_source, SkippedFile, SourceEncoding, TranscodedFile};
+use super::counter::{apply_target_kind, target_label};
+use super::detect::{DetectedFile, Detection};
+use super::diagnostics::{check_parse, recover, Diagnostic};
 use super::stats::Locs;
 
 /// Lines of code diff (added vs removed).
 ///
 /// Tracks additions and removals for each of the 6 line types.
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LocsDiff {
     /// Lines added
     pub added: Locs,
@@ -107,6 +113,16 @@ impl LocsDiff {
         sat_sub_u64(self.added.blanks, self.removed.blanks)
     }
 
+    /// Net change for preprocessor directive lines.
+    pub fn net_preprocessor(&self) -> i64 {
+        sat_sub_u64(self.added.preprocessor, self.removed.preprocessor)
+    }
+
+    /// Net change for embedded configuration lines.
+    pub fn net_config(&self) -> i64 {
+        sat_sub_u64(self.added.config, self.removed.config)
+    }
+
     /// Net change for total lines.
     pub fn net_total(&self) -> i64 {
         sat_sub_u64(self.added.total(), self.removed.total())
@@ -134,8 +150,14 @@ impl std::ops::Add for LocsDiff {
 
 impl std::ops::AddAssign for LocsDiff {
     fn add_assign(&mut self, other: Self) {
-        self.added += other.added;
-        self.removed += other.removed;
+        *self += &other;
+    }
+}
+
+impl std::ops::AddAssign<&LocsDiff> for LocsDiff {
+    fn add_assign(&mut self, other: &LocsDiff) {
+        self.added += &other.added;
+        self.removed += &other.removed;
     }
 }
 
@@ -148,6 +170,14 @@ pub struct FileDiffStats {
     pub change_type: FileChangeType,
     /// LOC diff for this file.
     pub diff: LocsDiff,
+    /// Backend that analyzed both sides of the change and why, detected
+    /// from the newer side (the older side for a deletion).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detection: Option<Detection>,
+    /// Encoding of the newer side of the change (the older side for a
+    /// deletion).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<SourceEncoding>,
 }
 
 impl FileDiffStats {
@@ -157,6 +187,8 @@ impl FileDiffStats {
             path: self.path.clone(),
             change_type: self.change_type,
             diff: self.diff.filter(types),
+            detection: self.detection.clone(),
+            encoding: self.encoding,
         }
     }
 }
@@ -198,7 +230,7 @@ impl CrateDiffStats {
 
     /// Add file diff to this crate.
     pub fn add_file(&mut self, file_diff: FileDiffStats) {
-        self.diff += file_diff.diff;
+        self.diff += &file_diff.diff;
         self.files.push(file_diff);
     }
 
@@ -213,6 +245,31 @@ impl CrateDiffStats {
     }
 }
 
+/// Diff statistics for a Cargo build target.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetDiffStats {
+    /// Row label, e.g. `rustloc (bin rustloc)`.
+    pub name: String,
+    /// Target kind, or `None` for the crate's files no target claims.
+    pub kind: Option<TargetKind>,
+    /// Root source file of the target (the crate root for unclaimed files).
+    pub path: PathBuf,
+    /// Aggregated LOC diff.
+    pub diff: LocsDiff,
+}
+
+impl TargetDiffStats {
+    /// Return a filtered copy with only the specified line types included.
+    pub fn filter(&self, types: LineTypes) -> Self {
+        Self {
+            name: self.name.clone(),
+            kind: self.kind,
+            path: self.path.clone(),
+            diff: self.diff.filter(types),
+        }
+    }
+}
+
 /// Result of a diff operation between two commits.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DiffResult {
@@ -228,12 +285,27 @@ pub struct DiffResult {
     pub crates: Vec<CrateDiffStats>,
     /// Per-file diff (optional, for detailed output).
     pub files: Vec<FileDiffStats>,
+    /// Per-target diff breakdown.
+    #[serde(default)]
+    pub targets: Vec<TargetDiffStats>,
     /// Lines added in files skipped by the active language selection.
     #[serde(default)]
     pub non_rust_added: u64,
     /// Lines removed in files skipped by the active language selection.
     #[serde(default)]
     pub non_rust_removed: u64,
+    /// Changed files that failed a content check on either side.
+    #[serde(default)]
+    pub skipped: Vec<SkippedFile>,
+    /// Changed files that could not be read, failed to parse, or were skipped.
+    #[serde(default)]
+    pub diagnostics: Vec<Diagnostic>,
+    /// Changed files not detected by their extension, whatever the aggregation.
+    #[serde(default)]
+    pub detected: Vec<DetectedFile>,
+    /// Changed files decoded from another encoding, whatever the aggregation.
+    #[serde(default)]
+    pub transcoded: Vec<TranscodedFile>,
 }
 
 impl DiffResult {
@@ -246,8 +318,13 @@ impl DiffResult {
             total: self.total.filter(types),
             crates: self.crates.iter().map(|c| c.filter(types)).collect(),
             files: self.files.iter().map(|f| f.filter(types)).collect(),
+            targets: self.targets.iter().map(|t| t.filter(types)).collect(),
             non_rust_added: self.non_rust_added,
             non_rust_removed: self.non_rust_removed,
+            skipped: self.skipped.clone(),
+            diagnostics: self.diagnostics.clone(),
+            detected: self.detected.clone(),
+            transcoded: self.transcoded.clone(),
         }
     }
 }
@@ -263,6 +340,8 @@ pub struct DiffOptions {
     pub aggregation: Aggregation,
     /// Which line types to include in results.
     pub line_types: LineTypes,
+    /// How the workspace's crates and targets are discovered.
+    pub workspace_discovery: WorkspaceDiscovery,
 }
 
 impl Default for DiffOptions {
@@ -272,6 +351,7 @@ impl Default for DiffOptions {
             file_filter: FilterConfig::new(),
             aggregation: Aggregation::Total,
             line_types: LineTypes::default(),
+            workspace_discovery: WorkspaceDiscovery::default(),
         }
     }
 }
@@ -294,6 +374,15 @@ impl DiffOptions {
         self
     }
 
+    /// Set the backend registry files are analyzed with.
+    ///
+    /// [`filter`](Self::filter) replaces the whole file filter, registry
+    /// included, so call this afterwards.
+    pub fn registry(mut self, registry: impl Into<Arc<BackendRegistry>>) -> Self {
+        self.file_filter.registry = registry.into();
+        self
+    }
+
     /// Set aggregation level.
     pub fn aggregation(mut self, level: Aggregation) -> Self {
         self.aggregation = level;
@@ -305,6 +394,12 @@ impl DiffOptions {
         self.line_types = types;
         self
     }
+
+    /// Set how the workspace is discovered.
+    pub fn workspace_discovery(mut self, discovery: WorkspaceDiscovery) -> Self {
+        self.workspace_discovery = discovery;
+        self
+    }
 }
 
 /// Mode for working directory diff.
@@ -319,6 +414,52 @@ pub enum WorkdirDiffMode {
     Staged,
 }
 
+/// The crate grouping for a repository: its Cargo workspace, joined for
+/// `--by-crate` or a crate filter by the packages other build tools declare.
+/// Outside a Cargo workspace those are every package of the project model.
+///
+/// Members whose manifest cannot be read or parsed are recorded in
+/// `diagnostics`; when the filter is strict, they and a root `Cargo.toml`
+/// that cannot be read fail the diff instead.
+fn discover_grouping(
+    repo_root: &Path,
+    options: &DiffOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Option<WorkspaceInfo>> {
+    let strict = options.file_filter.strict;
+    let workspace = match WorkspaceInfo::discover_reporting(
+        repo_root,
+        options.workspace_discovery,
+        strict,
+        diagnostics,
+    ) {
+        Ok(workspace) => Some(workspace),
+        Err(err) if strict && repo_root.join("Cargo.toml").is_file() => return Err(err),
+        Err(_) => None,
+    };
+    if options.aggregation != Aggregation::ByCrate && options.crate_filter.is_empty() {
+        return Ok(workspace);
+    }
+    let (workspace, providers) = match workspace {
+        Some(workspace) => (workspace, ProjectModel::non_cargo_providers()),
+        None => (
+            WorkspaceInfo {
+                root: repo_root.to_path_buf(),
+                crates: Vec::new(),
+                default_members: Vec::new(),
+            },
+            ProjectModel::providers(options.workspace_discovery),
+        ),
+    };
+    Ok(
+        match ProjectModel::discover_with(repo_root, &providers, &options.file_filter) {
+            Ok(model) => Some(workspace.with_packages(model.packages)),
+            Err(_) if workspace.crates.is_empty() => None,
+            Err(_) => Some(workspace),
+        },
+    )
+}
+
 /// Compute LOC diff for working directory changes.
 pub fn diff_workdir(
     repo_path: impl AsRef<Path>,
@@ -351,17 +492,22 @@ pub fn diff_workdir(
         .map_err(|e| RustlocError::GitError(format!("Failed to read index: {}", e)))?;
 
     // Collect changes based on mode
+    let mut diagnostics = Vec::new();
     let (changes, non_rust_added, non_rust_removed) = match mode {
         WorkdirDiffMode::Staged => {
             collect_staged_changes(&repo, &head_tree, &index, &options.file_filter)?
         }
-        WorkdirDiffMode::All => {
-            collect_workdir_changes(&repo, &head_tree, &repo_root, &options.file_filter)?
-        }
+        WorkdirDiffMode::All => collect_workdir_changes(
+            &repo,
+            &head_tree,
+            &repo_root,
+            &options.file_filter,
+            &mut diagnostics,
+        )?,
     };
 
     // Try to discover workspace info for crate grouping
-    let workspace = WorkspaceInfo::discover(&repo_root).ok();
+    let workspace = discover_grouping(&repo_root, &options, &mut diagnostics)?;
 
     // Apply crate filter if specified
     let filtered_workspace = workspace.as_ref().map(|ws| {
@@ -385,14 +531,19 @@ pub fn diff_workdir(
     );
     let include_crates = matches!(
         options.aggregation,
-        Aggregation::ByCrate | Aggregation::ByModule | Aggregation::ByFile
+        Aggregation::ByCrate | Aggregation::ByModule | Aggregation::ByTarget | Aggregation::ByFile
     );
+    let include_targets = matches!(options.aggregation, Aggregation::ByTarget);
+    let mut target_stats: HashMap<String, TargetDiffStats> = HashMap::new();
+    let mut skipped = Vec::new();
+    let mut detected = Vec::new();
+    let mut transcoded = Vec::new();
 
     for change in changes {
         let path = change.path.clone();
 
-        // Apply glob filter
-        if !options.file_filter.matches(&path) {
+        // Apply glob filter; the language was checked with the content at hand.
+        if !options.file_filter.matches_patterns(&path) {
             continue;
         }
 
@@ -407,10 +558,43 @@ pub fn diff_workdir(
         }
 
         // Compute file diff
-        let file_diff = compute_workdir_file_diff(&change, &path)?;
+        let Some(mut file_diff) = compute_workdir_file_diff(
+            &change,
+            &path,
+            &options.file_filter,
+            &mut skipped,
+            &mut diagnostics,
+        )?
+        else {
+            continue;
+        };
+        let target = filtered_workspace
+            .as_ref()
+            .zip(crate_info)
+            .and_then(|(ws, c)| c.target_for_path(&path, &ws.root));
+        let classifier = options.file_filter.registry.classifier();
+        let classified = classifier.context_for(&path).is_some();
+        if let Some(target) = target.filter(|_| !classified) {
+            apply_target_kind(&mut file_diff.diff.added, target.kind);
+            apply_target_kind(&mut file_diff.diff.removed, target.kind);
+        }
+        // Changed lines are recorded by line class, which reads as code in
+        // a file of a user-defined category.
+        if let Some(category) = classifier.category_for(&path) {
+            file_diff.diff.added.move_code_to_category(category);
+            file_diff.diff.removed.move_code_to_category(category);
+        }
 
         // Aggregate into total
-        total += file_diff.diff;
+        total += &file_diff.diff;
+        detected.extend(DetectedFile::unless_by_extension(
+            &file_diff.path,
+            file_diff.detection.as_ref(),
+        ));
+        transcoded.extend(TranscodedFile::unless_utf8(
+            &file_diff.path,
+            file_diff.encoding,
+        ));
 
         // Aggregate into crate stats if applicable
         if include_crates {
@@ -425,12 +609,27 @@ pub fn diff_workdir(
                 if include_files {
                     crate_stats_entry.add_file(file_diff.clone());
                 } else {
-                    crate_stats_entry.diff += file_diff.diff;
+                    crate_stats_entry.diff += &file_diff.diff;
                 }
             }
         }
 
         // Collect file stats if requested
+        if include_targets {
+            if let Some(crate_info) = crate_info {
+                let name = target_label(&crate_info.name, target);
+                target_stats
+                    .entry(name.clone())
+                    .or_insert_with(|| TargetDiffStats {
+                        name,
+                        kind: target.map(|t| t.kind),
+                        path: target.map_or(&crate_info.root, |t| &t.src_path).clone(),
+                        diff: LocsDiff::new(),
+                    })
+                    .diff += &file_diff.diff;
+            }
+        }
+
         if include_files {
             files.push(file_diff);
         }
@@ -452,8 +651,13 @@ pub fn diff_workdir(
         total,
         crates,
         files,
+        targets: target_stats.into_values().collect(),
         non_rust_added,
         non_rust_removed,
+        skipped,
+        diagnostics,
+        detected,
+        transcoded,
     };
 
     Ok(result.filter(options.line_types))
@@ -463,8 +667,8 @@ pub fn diff_workdir(
 struct WorkdirFileChange {
     path: PathBuf,
     change_type: FileChangeType,
-    old_content: Option<String>,
-    new_content: Option<String>,
+    old_content: Option<Vec<u8>>,
+    new_content: Option<Vec<u8>>,
 }
 
 /// Collect staged changes (HEAD vs index)
@@ -488,69 +692,74 @@ fn collect_staged_changes(
     // Check each entry in the index against HEAD
     for entry in index.entries() {
         let path = PathBuf::from(gix::path::from_bstr(entry.path(index)));
+        seen_paths.insert(path.clone());
 
-        if !is_analyzed_source_path(&path, filter) {
-            // Track source lines outside the command's active language set.
-            let index_oid = entry.id;
-            if let Some(&head_oid) = head_entries.get(&path) {
-                if head_oid != index_oid {
-                    let old_lines = count_lines(&read_blob(repo, head_oid)?);
-                    let new_lines = count_lines(&read_blob(repo, index_oid)?);
-                    non_rust_added += new_lines.saturating_sub(old_lines);
-                    non_rust_removed += old_lines.saturating_sub(new_lines);
-                }
-            } else {
-                non_rust_added += count_lines(&read_blob(repo, index_oid)?);
-            }
-            seen_paths.insert(path);
+        let index_oid = entry.id;
+        let head_oid = head_entries.get(&path).copied();
+        if head_oid == Some(index_oid) {
             continue;
         }
+        let new_content = read_blob_bytes(repo, index_oid)?;
 
-        seen_paths.insert(path.clone());
-        let index_oid = entry.id;
+        if !is_analyzed_source(&path, &new_content, filter) {
+            // Track source lines outside the command's active language set.
+            let new_lines = count_lines(&decode_lossy(&new_content));
+            let old_lines = match head_oid {
+                Some(head_oid) => count_lines(&read_blob(repo, head_oid)?),
+                None => 0,
+            };
+            non_rust_added += new_lines.saturating_sub(old_lines);
+            non_rust_removed += old_lines.saturating_sub(new_lines);
+            continue;
+        }
 
-        if let Some(&head_oid) = head_entries.get(&path) {
-            if head_oid != index_oid {
-                let old_content = read_blob(repo, head_oid)?;
-                let new_content = read_blob(repo, index_oid)?;
-                changes.push(WorkdirFileChange {
-                    path,
-                    change_type: FileChangeType::Modified,
-                    old_content: Some(old_content),
-                    new_content: Some(new_content),
-                });
-            }
-        } else {
-            let new_content = read_blob(repo, index_oid)?;
-            changes.push(WorkdirFileChange {
+        match head_oid {
+            Some(head_oid) => changes.push(WorkdirFileChange {
+                path,
+                change_type: FileChangeType::Modified,
+                old_content: Some(read_blob_bytes(repo, head_oid)?),
+                new_content: Some(new_content),
+            }),
+            None => changes.push(WorkdirFileChange {
                 path,
                 change_type: FileChangeType::Added,
                 old_content: None,
                 new_content: Some(new_content),
-            });
+            }),
         }
     }
 
-    // Check for deleted files
+    collect_deleted_files(repo, head_entries, &seen_paths, filter, &mut changes)
+        .map(|removed| (changes, non_rust_added, non_rust_removed + removed))
+}
+
+/// Record the HEAD files missing from `seen_paths` as deletions, returning
+/// the lines removed from files outside the active language set.
+fn collect_deleted_files(
+    repo: &gix::Repository,
+    head_entries: HashMap<PathBuf, gix::ObjectId>,
+    seen_paths: &std::collections::HashSet<PathBuf>,
+    filter: &FilterConfig,
+    changes: &mut Vec<WorkdirFileChange>,
+) -> Result<u64> {
+    let mut non_rust_removed = 0;
     for (path, head_oid) in head_entries {
-        if !is_analyzed_source_path(&path, filter) {
-            if !seen_paths.contains(&path) {
-                non_rust_removed += count_lines(&read_blob(repo, head_oid)?);
-            }
+        if seen_paths.contains(&path) {
             continue;
         }
-        if !seen_paths.contains(&path) {
-            let old_content = read_blob(repo, head_oid)?;
-            changes.push(WorkdirFileChange {
-                path,
-                change_type: FileChangeType::Deleted,
-                old_content: Some(old_content),
-                new_content: None,
-            });
+        let old_content = read_blob_bytes(repo, head_oid)?;
+        if !is_analyzed_source(&path, &old_content, filter) {
+            non_rust_removed += count_lines(&decode_lossy(&old_content));
+            continue;
         }
+        changes.push(WorkdirFileChange {
+            path,
+            change_type: FileChangeType::Deleted,
+            old_content: Some(old_content),
+            new_content: None,
+        });
     }
-
-    Ok((changes, non_rust_added, non_rust_removed))
+    Ok(non_rust_removed)
 }
 
 /// Collect all uncommitted changes (HEAD vs working directory)
@@ -559,6 +768,7 @@ fn collect_workdir_changes(
     head_tree: &gix::Tree<'_>,
     repo_root: &Path,
     filter: &FilterConfig,
+    diagnostics: &mut Vec<Diagnostic>,
 ) -> Result<(Vec<WorkdirFileChange>, u64, u64)> {
     use std::collections::HashSet;
 
@@ -605,35 +815,36 @@ fn collect_workdir_changes(
             continue;
         }
 
-        if !is_analyzed_source_path(abs_path, filter) {
-            // Track source lines outside the command's active language set.
-            seen_paths.insert(rel_path.clone());
-            let workdir_content = match std::fs::read_to_string(abs_path) {
-                Ok(content) => content,
-                Err(_) => continue,
-            };
-            let new_lines = count_lines(&workdir_content);
-            if let Some(&head_oid) = head_entries.get(&rel_path) {
-                let old_lines = count_lines(&read_blob(repo, head_oid)?);
-                if old_lines != new_lines {
-                    non_rust_added += new_lines.saturating_sub(old_lines);
-                    non_rust_removed += old_lines.saturating_sub(new_lines);
-                }
-            } else {
-                non_rust_added += new_lines;
-            }
-            continue;
-        }
-
         seen_paths.insert(rel_path.clone());
 
-        let workdir_content = match std::fs::read_to_string(abs_path) {
+        let workdir_content = match std::fs::read(abs_path) {
             Ok(content) => content,
+            // Without content only the path can tell whether it is analyzed.
+            Err(source) if filter.matches(&rel_path) => {
+                let error = RustlocError::FileRead {
+                    path: abs_path.to_path_buf(),
+                    source,
+                };
+                recover::<()>(&rel_path, Err(error), filter.strict, diagnostics)?;
+                continue;
+            }
             Err(_) => continue,
         };
 
+        if !is_analyzed_source(&rel_path, &workdir_content, filter) {
+            // Track source lines outside the command's active language set.
+            let new_lines = count_lines(&decode_lossy(&workdir_content));
+            let old_lines = match head_entries.get(&rel_path) {
+                Some(&head_oid) => count_lines(&read_blob(repo, head_oid)?),
+                None => 0,
+            };
+            non_rust_added += new_lines.saturating_sub(old_lines);
+            non_rust_removed += old_lines.saturating_sub(new_lines);
+            continue;
+        }
+
         if let Some(&head_oid) = head_entries.get(&rel_path) {
-            let head_content = read_blob(repo, head_oid)?;
+            let head_content = read_blob_bytes(repo, head_oid)?;
             if head_content != workdir_content {
                 changes.push(WorkdirFileChange {
                     path: rel_path,
@@ -652,26 +863,8 @@ fn collect_workdir_changes(
         }
     }
 
-    // Check for deleted files
-    for (path, head_oid) in head_entries {
-        if !is_analyzed_source_path(&path, filter) {
-            if !seen_paths.contains(&path) {
-                non_rust_removed += count_lines(&read_blob(repo, head_oid)?);
-            }
-            continue;
-        }
-        if !seen_paths.contains(&path) {
-            let old_content = read_blob(repo, head_oid)?;
-            changes.push(WorkdirFileChange {
-                path,
-                change_type: FileChangeType::Deleted,
-                old_content: Some(old_content),
-                new_content: None,
-            });
-        }
-    }
-
-    Ok((changes, non_rust_added, non_rust_removed))
+    collect_deleted_files(repo, head_entries, &seen_paths, filter, &mut changes)
+        .map(|removed| (changes, non_rust_added, non_rust_removed + removed))
 }
 
 /// Recursively collect all blob entries from a tree
@@ -702,29 +895,24 @@ fn collect_tree_entries(
     Ok(())
 }
 
-/// Compute the LOC diff for a working directory file change
-fn compute_workdir_file_diff(change: &WorkdirFileChange, path: &Path) -> Result<FileDiffStats> {
-    let diff = match change.change_type {
-        FileChangeType::Added => {
-            let stats = analyze_content_stats(path, change.new_content.as_ref().unwrap())?;
-            compute_locs_diff(&Locs::new(), &stats)
-        }
-        FileChangeType::Deleted => {
-            let stats = analyze_content_stats(path, change.old_content.as_ref().unwrap())?;
-            compute_locs_diff(&stats, &Locs::new())
-        }
-        FileChangeType::Modified => compute_modified_locs_diff(
-            path,
-            change.old_content.as_ref().unwrap(),
-            change.new_content.as_ref().unwrap(),
-        )?,
-    };
-
-    Ok(FileDiffStats {
-        path: path.to_path_buf(),
-        change_type: change.change_type,
-        diff,
-    })
+/// Compute the LOC diff for a working directory file change, or `None` if
+/// the file is skipped.
+fn compute_workdir_file_diff(
+    change: &WorkdirFileChange,
+    path: &Path,
+    filter: &FilterConfig,
+    skipped: &mut Vec<SkippedFile>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Option<FileDiffStats>> {
+    compute_content_diff(
+        change.change_type,
+        path,
+        change.old_content.as_deref(),
+        change.new_content.as_deref(),
+        filter,
+        skipped,
+        diagnostics,
+    )
 }
 
 /// Compute LOC diff from a git revision specification.
@@ -786,7 +974,8 @@ pub fn diff_revspec(
     let changes = compute_tree_diff(&from_tree, &to_tree)?;
 
     // Try to discover workspace info
-    let workspace = WorkspaceInfo::discover(&repo_root).ok();
+    let mut diagnostics = Vec::new();
+    let workspace = discover_grouping(&repo_root, &options, &mut diagnostics)?;
 
     // Apply crate filter
     let filtered_workspace = workspace.as_ref().map(|ws| {
@@ -804,6 +993,9 @@ pub fn diff_revspec(
     let mut crate_stats: HashMap<String, CrateDiffStats> = HashMap::new();
     let mut non_rust_added: u64 = 0;
     let mut non_rust_removed: u64 = 0;
+    let mut skipped = Vec::new();
+    let mut detected = Vec::new();
+    let mut transcoded = Vec::new();
 
     let include_files = matches!(
         options.aggregation,
@@ -811,28 +1003,37 @@ pub fn diff_revspec(
     );
     let include_crates = matches!(
         options.aggregation,
-        Aggregation::ByCrate | Aggregation::ByModule | Aggregation::ByFile
+        Aggregation::ByCrate | Aggregation::ByModule | Aggregation::ByTarget | Aggregation::ByFile
     );
+    let include_targets = matches!(options.aggregation, Aggregation::ByTarget);
+    let mut target_stats: HashMap<String, TargetDiffStats> = HashMap::new();
 
     for change in changes {
         let path = change.path.clone();
+        let old_content = change
+            .old_oid
+            .map(|oid| read_blob_bytes(&repo, oid))
+            .transpose()?;
+        let new_content = change
+            .new_oid
+            .map(|oid| read_blob_bytes(&repo, oid))
+            .transpose()?;
 
         // Track source lines outside the command's active language set.
-        if !is_analyzed_source_path(&path, &options.file_filter) {
-            let old_lines = change
-                .old_oid
-                .and_then(|oid| read_blob(&repo, oid).ok().map(|c| count_lines(&c)))
-                .unwrap_or(0);
-            let new_lines = change
-                .new_oid
-                .and_then(|oid| read_blob(&repo, oid).ok().map(|c| count_lines(&c)))
-                .unwrap_or(0);
+        let changed = new_content.as_deref().or(old_content.as_deref());
+        if !changed.is_some_and(|content| is_analyzed_source(&path, content, &options.file_filter))
+        {
+            let lines =
+                |content: Option<&[u8]>| content.map_or(0, |c| count_lines(&decode_lossy(c)));
+            let old_lines = lines(old_content.as_deref());
+            let new_lines = lines(new_content.as_deref());
             non_rust_added += new_lines.saturating_sub(old_lines);
             non_rust_removed += old_lines.saturating_sub(new_lines);
             continue;
         }
 
-        if !options.file_filter.matches(&path) {
+        // The language was checked above, with the content at hand.
+        if !options.file_filter.matches_patterns(&path) {
             continue;
         }
 
@@ -844,9 +1045,44 @@ pub fn diff_revspec(
             continue;
         }
 
-        let file_diff = compute_file_diff(&repo, &change, &path)?;
+        let Some(mut file_diff) = compute_content_diff(
+            change.change_type,
+            &path,
+            old_content.as_deref(),
+            new_content.as_deref(),
+            &options.file_filter,
+            &mut skipped,
+            &mut diagnostics,
+        )?
+        else {
+            continue;
+        };
+        let target = filtered_workspace
+            .as_ref()
+            .zip(crate_info)
+            .and_then(|(ws, c)| c.target_for_path(&path, &ws.root));
+        let classifier = options.file_filter.registry.classifier();
+        let classified = classifier.context_for(&path).is_some();
+        if let Some(target) = target.filter(|_| !classified) {
+            apply_target_kind(&mut file_diff.diff.added, target.kind);
+            apply_target_kind(&mut file_diff.diff.removed, target.kind);
+        }
+        // Changed lines are recorded by line class, which reads as code in
+        // a file of a user-defined category.
+        if let Some(category) = classifier.category_for(&path) {
+            file_diff.diff.added.move_code_to_category(category);
+            file_diff.diff.removed.move_code_to_category(category);
+        }
 
-        total += file_diff.diff;
+        total += &file_diff.diff;
+        detected.extend(DetectedFile::unless_by_extension(
+            &file_diff.path,
+            file_diff.detection.as_ref(),
+        ));
+        transcoded.extend(TranscodedFile::unless_utf8(
+            &file_diff.path,
+            file_diff.encoding,
+        ));
 
         if include_crates {
             if let Some(crate_info) = crate_info {
@@ -860,11 +1096,26 @@ pub fn diff_revspec(
                 if include_files {
                     crate_stats_entry.add_file(file_diff.clone());
                 } else {
-                    crate_stats_entry.diff += file_diff.diff;
+                    crate_stats_entry.diff += &file_diff.diff;
                 }
             }
         }
 
+        if include_targets {
+            if let Some(crate_info) = crate_info {
+                let name = target_label(&crate_info.name, target);
+                target_stats
+                    .entry(name.clone())
+                    .or_insert_with(|| TargetDiffStats {
+                        name,
+                        kind: target.map(|t| t.kind),
+                        path: target.map_or(&crate_info.root, |t| &t.src_path).clone(),
+                        diff: LocsDiff::new(),
+                    })
+                    .diff += &file_diff.diff;
+            }
+        }
+
         if include_files {
             files.push(file_diff);
         }
@@ -879,8 +1130,13 @@ pub fn diff_revspec(
         total,
         crates,
         files,
+        targets: target_stats.into_values().collect(),
         non_rust_added,
         non_rust_removed,
+        skipped,
+        diagnostics,
+        detected,
+        transcoded,
     };
 
     Ok(result.filter(options.line_types))
@@ -1095,58 +1351,133 @@ fn compute_tree_diff(
     Ok(changes)
 }
 
-/// Compute the LOC diff for a single file
-fn compute_file_diff(
-    repo: &gix::Repository,
-    change: &FileChange,
+/// Decode both sides of a change and diff them, or record the file in
+/// `skipped` if either side fails a content check.
+///
+/// A file the backend fails on is recorded in `diagnostics` and left out,
+/// unless the filter is strict.
+fn compute_content_diff(
+    change_type: FileChangeType,
     path: &Path,
-) -> Result<FileDiffStats> {
-    let diff = match change.change_type {
-        FileChangeType::Added => {
-            let content = read_blob(repo, change.new_oid.unwrap())?;
-            let stats = analyze_content_stats(path, &content)?;
-            compute_locs_diff(&Locs::new(), &stats)
-        }
-        FileChangeType::Deleted => {
-            let content = read_blob(repo, change.old_oid.unwrap())?;
-            let stats = analyze_content_stats(path, &content)?;
-            compute_locs_diff(&stats, &Locs::new())
-        }
-        FileChangeType::Modified => {
-            let old_content = read_blob(repo, change.old_oid.unwrap())?;
-            let new_content = read_blob(repo, change.new_oid.unwrap())?;
-            compute_modified_locs_diff(path, &old_content, &new_content)?
+    old: Option<&[u8]>,
+    new: Option<&[u8]>,
+    filter: &FilterConfig,
+    skipped: &mut Vec<SkippedFile>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Option<FileDiffStats>> {
+    let decode = |bytes: &[u8]| {
+        decode_source(bytes, filter.max_file_size)
+            .map_err(|reason| SkippedFile::new(path, reason, bytes.len() as u64))
+    };
+    let (old, new) = match (old.map(decode).transpose(), new.map(decode).transpose()) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(file), _) | (_, Err(file)) => {
+            diagnostics.push(Diagnostic::skipped(&file));
+            skipped.push(file);
+            return Ok(None);
         }
     };
+    let old_text = old.as_ref().map(|source| source.text.as_str());
+    let new_text = new.as_ref().map(|source| source.text.as_str());
+    let Some(detection) = new_text.or(old_text).and_then(|text| {
+        filter
+            .registry
+            .detect_source_with_languages(path, text, &filter.languages)
+    }) else {
+        return Ok(None);
+    };
+    let diffed =
+        compute_change_diff(change_type, path, &detection, old_text, new_text, filter).map(Some);
+    let Some((diff, parse_failures)) = recover(path, diffed, filter.strict, diagnostics)? else {
+        return Ok(None);
+    };
+    for failure in &parse_failures {
+        check_parse(path, Some(failure), filter.strict, diagnostics)?;
+    }
 
-    Ok(FileDiffStats {
+    Ok(Some(FileDiffStats {
         path: path.to_path_buf(),
-        change_type: change.change_type,
+        change_type,
         diff,
-    })
+        detection: Some(detection),
+        encoding: new.as_ref().or(old.as_ref()).map(|source| source.encoding),
+    }))
 }
 
-fn is_analyzed_source_path(path: &Path, filter: &FilterConfig) -> bool {
-    BackendRegistry::new().supports_path_with_languages(path, &filter.languages)
+/// Compute the LOC diff between the decoded sides of a change, both analyzed
+/// by the backend `detection` names, with the parse failures of every side.
+fn compute_change_diff(
+    change_type: FileChangeType,
+    path: &Path,
+    detection: &Detection,
+    old: Option<&str>,
+    new: Option<&str>,
+    filter: &FilterConfig,
+) -> Result<(LocsDiff, Vec<ParseFailure>)> {
+    Ok(match change_type {
+        FileChangeType::Added => {
+            let analysis = analyze_content(path, detection, new.unwrap_or_default(), filter)?;
+            (
+                compute_locs_diff(&Locs::new(), &analysis.stats),
+                analysis.parse_failure.into_iter().collect(),
+            )
+        }
+        FileChangeType::Deleted => {
+            let analysis = analyze_content(path, detection, old.unwrap_or_default(), filter)?;
+            (
+                compute_locs_diff(&analysis.stats, &Locs::new()),
+                analysis.parse_failure.into_iter().collect(),
+            )
+        }
+        FileChangeType::Modified => compute_modified_locs_diff(
+            path,
+            detection,
+            old.unwrap_or_default(),
+            new.unwrap_or_default(),
+            filter,
+        )?,
+    })
 }
 
-fn analyze_content_stats(path: &Path, source: &str) -> Result<Locs> {
-    Ok(analyze_content(path, source)?.stats)
+/// Whether a selected backend analyzes `path`, judged by its path and, failing
+/// that, by the shebang or modeline of `content`: the changed blob, which is
+/// the older one for a deletion.
+fn is_analyzed_source(path: &Path, content: &[u8], filter: &FilterConfig) -> bool {
+    let registry = &filter.registry;
+    registry.supports_path_with_languages(path, &filter.languages)
+        || registry
+            .detect_source_with_languages(path, &decode_lossy(content), &filter.languages)
+            .is_some()
 }
 
-fn analyze_content(path: &Path, source: &str) -> Result<FileAnalysis> {
-    Ok(BackendRegistry::new()
-        .analyze_source(path, source)?
+fn analyze_content(
+    path: &Path,
+    detection: &Detection,
+    source: &str,
+    filter: &FilterConfig,
+) -> Result<FileAnalysis> {
+    Ok(filter
+        .registry
+        .analyze_source_as(detection, path, source, &filter.languages)?
         .unwrap_or_else(|| FileAnalysis {
             language: super::backend::LanguageId::Unknown,
             stats: Locs::new(),
             line_classes: Vec::new(),
+            parse_failure: None,
         }))
 }
 
-fn compute_modified_locs_diff(path: &Path, old: &str, new: &str) -> Result<LocsDiff> {
-    let old_analysis = analyze_content(path, old)?;
-    let new_analysis = analyze_content(path, new)?;
+/// Diff the two sides of a modification line by line. A parse failure of the
+/// older side says so in its message, as its position refers to that side.
+fn compute_modified_locs_diff(
+    path: &Path,
+    detection: &Detection,
+    old: &str,
+    new: &str,
+    filter: &FilterConfig,
+) -> Result<(LocsDiff, Vec<ParseFailure>)> {
+    let old_analysis = analyze_content(path, detection, old, filter)?;
+    let new_analysis = analyze_content(path, detection, new, filter)?;
     let mut line_diff = LocsDiff::new();
 
     let input = InternedInput::new(old, new);
@@ -1163,7 +1494,15 @@ fn compute_modified_locs_diff(path: &Path, old: &str, new: &str) -> Result<LocsD
         },
     );
 
-    Ok(line_diff)
+    let old_failure = old_analysis.parse_failure.map(|failure| ParseFailure {
+        message: format!("before the change: {}", failure.message),
+        ..failure
+    });
+    let failures = old_failure
+        .into_iter()
+        .chain(new_analysis.parse_failure)
+        .collect();
+    Ok((line_diff, failures))
 }
 
 fn record_changed_classes(classes: &[LineClass], range: Range<u32>, stats: &mut Locs) {
@@ -1180,44 +1519,11 @@ fn record_changed_classes(classes: &[LineClass], range: Range<u32>, stats: &mut
 
 /// Compute the diff between two Locs
 fn compute_locs_diff(old: &Locs, new: &Locs) -> LocsDiff {
-    let added = Locs {
-        code: new.code.saturating_sub(old.code),
-        tests: new.tests.saturating_sub(old.tests),
-        examples: new.examples.saturating_sub(old.examples),
-        docs: new.docs.saturating_sub(old.docs),
-        comments: new.comments.saturating_sub(old.comments),
-        blanks: new.blanks.saturating_sub(old.blanks),
-        total: 0,
-    };
-    let removed = Locs {
-        code: old.code.saturating_sub(new.code),
-        tests: old.tests.saturating_sub(new.tests),
-        examples: old.examples.saturating_sub(new.examples),
-        docs: old.docs.saturating_sub(new.docs),
-        comments: old.comments.saturating_sub(new.comments),
-        blanks: old.blanks.saturating_sub(new.blanks),
-        total: 0,
-    };
-    LocsDiff {
-        added: Locs {
-            total: added.code
-                + added.tests
-                + added.examples
-                + added.docs
-                + added.comments
-                + added.blanks,
-            ..added
-        },
-        removed: Locs {
-            total: removed.code
-                + removed.tests
-                + removed.examples
-                + removed.docs
-                + removed.comments
-                + removed.blanks,
-            ..removed
-        },
-    }
+    let mut added = new.clone() - old.clone();
+    let mut removed = old.clone() - new.clone();
+    added.recompute_total();
+    removed.recompute_total();
+    LocsDiff { added, removed }
 }
 
 /// Count lines in a text string.
@@ -1225,8 +1531,13 @@ fn count_lines(content: &str) -> u64 {
     content.lines().count() as u64
 }
 
-/// Read a blob's content as a UTF-8 string
+/// Read a blob's content as text, decoded like an analyzed source
 fn read_blob(repo: &gix::Repository, oid: gix::ObjectId) -> Result<String> {
+    Ok(decode_lossy(&read_blob_bytes(repo, oid)?))
+}
+
+/// Read a blob's raw content
+fn read_blob_bytes(repo: &gix::Repository, oid: gix::ObjectId) -> Result<Vec<u8>> {
     let object = repo
         .find_object(oid)
         .map_err(|e| RustlocError::GitError(format!("Failed to find object {}: {}", oid, e)))?;
@@ -1235,8 +1546,7 @@ fn read_blob(repo: &gix::Repository, oid: gix::ObjectId) -> Result<String> {
         .try_into_blob()
         .map_err(|_| RustlocError::GitError(format!("Object {} is not a blob", oid)))?;
 
-    String::from_utf8(blob.data.to_vec())
-        .or_else(|e| Ok(String::from_utf8_lossy(&e.into_bytes()).into_owned()))
+    Ok(blob.data.to_vec())
 }
 
 #[cfg(test)]
@@ -1246,6 +1556,24 @@ mod tests {
     use std::sync::OnceLock;
     use tempfile::TempDir;
 
+    fn all_languages() -> FilterConfig {
+        FilterConfig::new().languages(crate::data::LanguageSelection::all())
+    }
+
+    /// Diff two versions of `path` with the backend detected from `new`.
+    fn modified_locs_diff(
+        path: &Path,
+        old: &str,
+        new: &str,
+    ) -> Result<(LocsDiff, Vec<ParseFailure>)> {
+        let filter = all_languages();
+        let detection = filter
+            .registry
+            .detect_source_with_languages(path, new, &filter.languages)
+            .unwrap();
+        compute_modified_locs_diff(path, &detection, old, new, &filter)
+    }
+
     /// Hermetic two-commit git fixture for diff_revspec round-trip tests.
     ///
     /// Earlier versions of `test_diff_revspec_same_commit_range` and
@@ -1320,6 +1648,9 @@ mod tests {
                 docs: 10,
                 comments: 5,
                 blanks: 15,
+                preprocessor: 0,
+                config: 0,
+                categories: Default::default(),
                 total: 200,
             },
             removed: Locs {
@@ -1329,6 +1660,9 @@ mod tests {
                 docs: 2,
                 comments: 1,
                 blanks: 5,
+                preprocessor: 0,
+                config: 0,
+                categories: Default::default(),
                 total: 68,
             },
         };
@@ -1357,6 +1691,9 @@ mod tests {
                 docs: 0,
                 comments: 0,
                 blanks: 0,
+                preprocessor: 0,
+                config: 0,
+                categories: Default::default(),
                 total: u64::MAX,
             },
             removed: Locs::new(),
@@ -1376,6 +1713,9 @@ mod tests {
                 docs: 1,
                 comments: 1,
                 blanks: 1,
+                preprocessor: 0,
+                config: 0,
+                categories: Default::default(),
                 total: 20,
             },
             removed: Locs {
@@ -1385,6 +1725,9 @@ mod tests {
                 docs: 0,
                 comments: 0,
                 blanks: 0,
+                preprocessor: 0,
+                config: 0,
+                categories: Default::default(),
                 total: 8,
             },
         };
@@ -1396,6 +1739,9 @@ mod tests {
                 docs: 2,
                 comments: 2,
                 blanks: 2,
+                preprocessor: 0,
+                config: 0,
+                categories: Default::default(),
                 total: 40,
             },
             removed: Locs {
@@ -1405,6 +1751,9 @@ mod tests {
                 docs: 1,
                 comments: 1,
                 blanks: 1,
+                preprocessor: 0,
+                config: 0,
+                categories: Default::default(),
                 total: 20,
             },
         };
@@ -1435,6 +1784,9 @@ mod tests {
             docs: 10,
             comments: 5,
             blanks: 20,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 135,
         };
 
@@ -1454,6 +1806,9 @@ mod tests {
             docs: 5,
             comments: 2,
             blanks: 10,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 67,
         };
         let new = Locs::new();
@@ -1472,6 +1827,9 @@ mod tests {
             docs: 10,
             comments: 5,
             blanks: 20,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 135,
         };
         let new = Locs {
@@ -1481,6 +1839,9 @@ mod tests {
             docs: 8,
             comments: 5,
             blanks: 25,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 158,
         };
 
@@ -1493,8 +1854,8 @@ mod tests {
 
     #[test]
     fn test_compute_modified_locs_diff_counts_replaced_lines() {
-        let diff = compute_modified_locs_diff(Path::new("a.rs"), "fn old() {}\n", "fn new() {}\n")
-            .unwrap();
+        let (diff, _) =
+            modified_locs_diff(Path::new("a.rs"), "fn old() {}\n", "fn new() {}\n").unwrap();
 
         assert_eq!(diff.added.code, 1);
         assert_eq!(diff.removed.code, 1);
@@ -1504,7 +1865,7 @@ mod tests {
 
     #[test]
     fn test_compute_modified_locs_diff_ignores_unchanged_lines() {
-        let diff = compute_modified_locs_diff(
+        let (diff, _) = modified_locs_diff(
             Path::new("a.rs"),
             "/// docs\nfn a() {}\n",
             "/// docs\nfn a() {}\nfn b() {}\n",
@@ -1519,7 +1880,7 @@ mod tests {
 
     #[test]
     fn test_compute_modified_locs_diff_uses_python_backend_classes() {
-        let diff = compute_modified_locs_diff(
+        let (diff, _) = modified_locs_diff(
             Path::new("tests/test_app.py"),
             "# old comment\ndef test_old():\n    assert False\n",
             "\"\"\"Module docs.\"\"\"\n# new comment\ndef test_new():\n    assert True\n",
@@ -1534,9 +1895,43 @@ mod tests {
         assert_eq!(diff.added.code, 0);
     }
 
+    #[test]
+    fn test_compute_content_diff_reports_parse_failures_of_both_sides() {
+        let path = Path::new("app.py");
+        let old = b"x = 1\ndef broken(\n";
+        let new = b"x = 2\ndef still_broken(\n";
+        let diff = |strict: bool, diagnostics: &mut Vec<Diagnostic>| {
+            compute_content_diff(
+                FileChangeType::Modified,
+                path,
+                Some(old),
+                Some(new),
+                &all_languages().strict(strict),
+                &mut Vec::new(),
+                diagnostics,
+            )
+        };
+
+        let mut diagnostics = Vec::new();
+        assert!(diff(false, &mut diagnostics).unwrap().is_some());
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages.len(), 2, "{messages:?}");
+        assert!(
+            messages[0].starts_with("before the change: "),
+            "{messages:?}"
+        );
+        assert!(
+            !messages[1].starts_with("before the change: "),
+            "{messages:?}"
+        );
+
+        let error = diff(true, &mut Vec::new()).unwrap_err();
+        assert!(error.to_string().contains("before the change"), "{error}");
+    }
+
     #[test]
     fn test_compute_modified_locs_diff_uses_typescript_backend_classes() {
-        let diff = compute_modified_locs_diff(
+        let (diff, _) = modified_locs_diff(
             Path::new("src/app.test.ts"),
             "// old comment\nconst oldValue = 1;\n",
             "/** public docs */\n// new comment\nconst newValue = 2;\n",
@@ -1767,6 +2162,9 @@ mod tests {
                 docs: 2,
                 comments: 1,
                 blanks: 4,
+                preprocessor: 0,
+                config: 0,
+                categories: Default::default(),
                 total: 25,
             },
             removed: Locs {
@@ -1776,6 +2174,9 @@ mod tests {
                 docs: 1,
                 comments: 1,
                 blanks: 1,
+                preprocessor: 0,
+                config: 0,
+                categories: Default::default(),
                 total: 6,
             },
         };
@@ -1835,6 +2236,8 @@ mod tests {
                 },
                 removed: Locs::default(),
             },
+            detection: None,
+            encoding: None,
         };
         let filtered = stats.filter(LineTypes::tests_only());
         assert_eq!(filtered.path, PathBuf::from("src/a.rs"));
@@ -1866,6 +2269,8 @@ mod tests {
                 },
                 removed: Locs::default(),
             },
+            detection: None,
+            encoding: None,
         });
         c.add_file(FileDiffStats {
             path: PathBuf::from("b.rs"),
@@ -1882,6 +2287,8 @@ mod tests {
                     ..Locs::default()
                 },
             },
+            detection: None,
+            encoding: None,
         });
         assert_eq!(c.files.len(), 2);
         assert_eq!(c.diff.added.code, 15);
@@ -1904,6 +2311,8 @@ mod tests {
                 },
                 removed: Locs::default(),
             },
+            detection: None,
+            encoding: None,
         });
         let filtered = c.filter(LineTypes::code_only());
         assert_eq!(filtered.files.len(), 1);
@@ -1943,9 +2352,27 @@ mod tests {
                     },
                     removed: Locs::default(),
                 },
+                detection: None,
+                encoding: None,
             }],
+            targets: vec![],
             non_rust_added: 7,
             non_rust_removed: 3,
+            skipped: vec![SkippedFile::new(
+                "app.min.js",
+                crate::data::SkipReason::Minified,
+                4096,
+            )],
+            diagnostics: vec![Diagnostic::new(
+                "b.rs",
+                crate::data::DiagnosticKind::ReadError,
+                "permission denied",
+            )],
+            detected: vec![],
+            transcoded: vec![TranscodedFile {
+                path: PathBuf::from("a.rs"),
+                encoding: SourceEncoding::Utf16Le,
+            }],
         };
         let filtered = result.filter(LineTypes::code_only());
         // Metadata preserved
@@ -1954,6 +2381,9 @@ mod tests {
         assert_eq!(filtered.to_commit, "b");
         assert_eq!(filtered.non_rust_added, 7);
         assert_eq!(filtered.non_rust_removed, 3);
+        assert_eq!(filtered.skipped, result.skipped);
+        assert_eq!(filtered.diagnostics, result.diagnostics);
+        assert_eq!(filtered.transcoded, result.transcoded);
         // Filter applied recursively
         assert_eq!(filtered.total.added.code, 10);
         assert_eq!(filtered.total.added.docs, 0);
@@ -2225,6 +2655,30 @@ mod tests {
         assert_eq!(result.non_rust_removed, 0);
     }
 
+    #[test]
+    fn test_diff_workdir_skips_binary_and_oversized_changes() {
+        let dir = workdir_repo(&[("a.rs", "fn a() {}\n")]);
+        std::fs::write(dir.path().join("blob.rs"), b"\0\x01\x02").unwrap();
+        std::fs::write(dir.path().join("a.rs"), "fn a() {}\n".repeat(20)).unwrap();
+        let options = DiffOptions::new().filter(FilterConfig::new().max_file_size(Some(100)));
+        git_add(dir.path(), "blob.rs");
+        let result = diff_workdir(dir.path(), WorkdirDiffMode::All, options).unwrap();
+
+        let reasons: Vec<_> = result
+            .skipped
+            .iter()
+            .map(|file| (file.path.to_str().unwrap(), file.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                ("a.rs", crate::data::SkipReason::TooLarge),
+                ("blob.rs", crate::data::SkipReason::Binary),
+            ]
+        );
+        assert_eq!(result.total.added.total, 0);
+    }
+
     #[test]
     fn test_diff_workdir_staged_non_rust_deleted() {
         let dir = workdir_repo(&[("notes.txt", "one\ntwo\nthree\n")]);
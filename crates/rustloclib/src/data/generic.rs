@@ -0,0 +1,467 @@
+use std::path::Path;
+
+use crate::{Result, RustlocError};
+
+use super::backend::{
+    generic_context_from_path, FileAnalysis, LanguageBackend, LanguageId, LogicContext,
+};
+use super::detect::ContentHints;
+use super::lexer::{lex, BlockComment, LexSpec, StringDelim};
+use super::stats::Locs;
+
+/// File-level backend for common source languages without semantic parsing.
+///
+/// Each language is lexed with its own comment and string syntax, so comment
+/// markers inside strings, code around block comments, and nested comments
+/// are classified correctly; test and example context comes from the path.
+#[derive(Debug, Default)]
+pub struct GenericBackend;
+
+/// A language classified line by line from its comment and string syntax.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct GenericLanguage {
+    pub(super) id: &'static str,
+    pub(super) extensions: &'static [&'static str],
+    pub(super) filenames: &'static [&'static str],
+    /// Interpreter and modeline names, see [`LanguageBackend::supports_hint`].
+    pub(super) hints: &'static [&'static str],
+    pub(super) spec: LexSpec<'static>,
+}
+
+pub(super) const C_BLOCK: BlockComment = BlockComment {
+    open: "/*",
+    close: "*/",
+    doc: false,
+    nested: false,
+};
+
+/// `/* */` comments that nest, as in Swift, Kotlin and Scala.
+const NESTED_C_BLOCK: BlockComment = BlockComment {
+    nested: true,
+    ..C_BLOCK
+};
+
+/// A single-line string closed by `quote`, with backslash escapes.
+pub(super) const fn escaped(quote: &'static str) -> StringDelim<'static> {
+    StringDelim {
+        open: quote,
+        close: quote,
+        escape: Some('\\'),
+        multiline: false,
+    }
+}
+
+/// A single-line string closed by `quote`, without escapes.
+pub(super) const fn literal(quote: &'static str) -> StringDelim<'static> {
+    StringDelim {
+        escape: None,
+        ..escaped(quote)
+    }
+}
+
+/// A string that may span lines and has no escapes (raw strings, text blocks).
+pub(super) const fn verbatim(open: &'static str, close: &'static str) -> StringDelim<'static> {
+    StringDelim {
+        open,
+        close,
+        escape: None,
+        multiline: true,
+    }
+}
+
+/// A string that may span lines and has backslash escapes.
+pub(super) const fn multiline(quote: &'static str) -> StringDelim<'static> {
+    StringDelim {
+        escape: Some('\\'),
+        ..verbatim(quote, quote)
+    }
+}
+
+/// Comment and string syntax without doc comments or raw strings.
+pub(super) const fn spec(
+    line_comments: &'static [&'static str],
+    block_comments: &'static [BlockComment<'static>],
+    strings: &'static [StringDelim<'static>],
+) -> LexSpec<'static> {
+    LexSpec {
+        doc_line_comments: &[],
+        line_comments,
+        block_comments,
+        strings,
+        raw_strings: false,
+        code_prefixes: &[],
+    }
+}
+
+const HASH: &[&str] = &["#"];
+const SLASHES: &[&str] = &["//"];
+
+const GENERIC_LANGUAGES: &[GenericLanguage] = &[
+    GenericLanguage {
+        id: "Shell",
+        extensions: &["sh", "bash", "zsh", "fish"],
+        filenames: &[".bashrc", ".bash_profile", ".zshrc", ".profile"],
+        hints: &["sh", "bash", "zsh", "fish", "dash", "ksh", "shell-script"],
+        spec: spec(HASH, &[], &[multiline("\""), verbatim("'", "'")]),
+    },
+    GenericLanguage {
+        id: "Ruby",
+        extensions: &["rb"],
+        filenames: &[],
+        hints: &[],
+        spec: spec(HASH, &[], &[multiline("\""), multiline("'")]),
+    },
+    GenericLanguage {
+        id: "JavaScript",
+        extensions: &["js", "jsx"],
+        filenames: &[],
+        hints: &["node", "javascript"],
+        spec: spec(
+            SLASHES,
+            &[C_BLOCK],
+            &[escaped("\""), escaped("'"), multiline("`")],
+        ),
+    },
+    GenericLanguage {
+        id: "Go",
+        extensions: &["go"],
+        filenames: &[],
+        hints: &["go"],
+        spec: spec(
+            SLASHES,
+            &[C_BLOCK],
+            &[escaped("\""), escaped("'"), verbatim("`", "`")],
+        ),
+    },
+    GenericLanguage {
+        id: "Java",
+        extensions: &["java"],
+        filenames: &[],
+        hints: &[],
+        spec: spec(
+            SLASHES,
+            &[C_BLOCK],
+            &[multiline("\"\"\""), escaped("\""), escaped("'")],
+        ),
+    },
+    GenericLanguage {
+        id: "C-like",
+        extensions: &["c", "h", "cc", "cpp", "cxx", "hpp", "hh", "hxx"],
+        filenames: &[],
+        hints: &[],
+        spec: LexSpec {
+            raw_strings: true,
+            ..spec(SLASHES, &[C_BLOCK], &[escaped("\""), escaped("'")])
+        },
+    },
+    GenericLanguage {
+        id: "CSharp",
+        extensions: &["cs"],
+        filenames: &[],
+        hints: &["cs"],
+        spec: spec(
+            SLASHES,
+            &[C_BLOCK],
+            &[
+                verbatim("\"\"\"", "\"\"\""),
+                verbatim("@\"", "\""),
+                verbatim("$@\"", "\""),
+                escaped("\""),
+                escaped("'"),
+            ],
+        ),
+    },
+    GenericLanguage {
+        id: "PHP",
+        extensions: &["php"],
+        filenames: &[],
+        hints: &["php"],
+        spec: LexSpec {
+            // PHP 8 attributes, not `#` comments.
+            code_prefixes: &["#["],
+            ..spec(&["//", "#"], &[C_BLOCK], &[multiline("\""), multiline("'")])
+        },
+    },
+    GenericLanguage {
+        id: "Swift",
+        extensions: &["swift"],
+        filenames: &[],
+        hints: &["swift"],
+        spec: spec(
+            SLASHES,
+            &[NESTED_C_BLOCK],
+            &[multiline("\"\"\""), escaped("\"")],
+        ),
+    },
+    GenericLanguage {
+        id: "Kotlin",
+        extensions: &["kt", "kts"],
+        filenames: &[],
+        hints: &[],
+        spec: spec(
+            SLASHES,
+            &[NESTED_C_BLOCK],
+            &[verbatim("\"\"\"", "\"\"\""), escaped("\""), escaped("'")],
+        ),
+    },
+    GenericLanguage {
+        id: "Scala",
+        extensions: &["scala"],
+        filenames: &[],
+        hints: &["scala"],
+        spec: spec(
+            SLASHES,
+            &[NESTED_C_BLOCK],
+            &[verbatim("\"\"\"", "\"\"\""), escaped("\""), escaped("'")],
+        ),
+    },
+    GenericLanguage {
+        id: "CSS",
+        extensions: &["css", "scss", "less"],
+        filenames: &[],
+        hints: &["css", "scss", "less"],
+        spec: spec(&[], &[C_BLOCK], &[escaped("\""), escaped("'")]),
+    },
+    GenericLanguage {
+        id: "Dockerfile",
+        extensions: &["dockerfile"],
+        filenames: &["Dockerfile", "Containerfile"],
+        hints: &["dockerfile"],
+        spec: spec(HASH, &[], &[]),
+    },
+    GenericLanguage {
+        id: "Make",
+        extensions: &["mk", "mak"],
+        filenames: &["Makefile", "makefile", "GNUmakefile"],
+        hints: &["make", "makefile"],
+        spec: spec(HASH, &[], &[]),
+    },
+    GenericLanguage {
+        id: "Just",
+        extensions: &["just"],
+        filenames: &["justfile", "Justfile", ".justfile"],
+        hints: &["just"],
+        spec: spec(HASH, &[], &[escaped("\""), literal("'")]),
+    },
+];
+
+impl GenericLanguage {
+    /// The language in `table` claiming `path` by file name or extension.
+    pub(super) fn for_path(table: &[Self], path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        let ext = path.extension().and_then(|ext| ext.to_str());
+        table
+            .iter()
+            .find(|language| {
+                language.filenames.contains(&name)
+                    || ext.is_some_and(|ext| any_ext(ext, language.extensions))
+            })
+            .copied()
+    }
+
+    pub(super) fn for_hint(table: &[Self], hint: &str) -> Option<Self> {
+        table
+            .iter()
+            .find(|language| language.hints.contains(&hint))
+            .copied()
+    }
+
+    /// The language in `table` named by the content hints of `source`.
+    pub(super) fn for_source(table: &[Self], source: &str) -> Option<Self> {
+        let hints = ContentHints::from_source(source);
+        [hints.modeline, hints.interpreter]
+            .into_iter()
+            .flatten()
+            .find_map(|hint: String| Self::for_hint(table, &hint))
+    }
+
+    /// The language in `table` for a file, by path and then by content.
+    pub(super) fn for_file(table: &[Self], path: &Path, source: &str) -> Result<Self> {
+        Self::for_path(table, path)
+            .or_else(|| Self::for_source(table, source))
+            .ok_or_else(|| RustlocError::UnsupportedSourceFile(path.to_path_buf()))
+    }
+}
+
+fn any_ext(ext: &str, candidates: &[&str]) -> bool {
+    candidates
+        .iter()
+        .any(|candidate| ext.eq_ignore_ascii_case(candidate))
+}
+
+impl LanguageBackend for GenericBackend {
+    fn supports_path(&self, path: &Path) -> bool {
+        GenericLanguage::for_path(GENERIC_LANGUAGES, path).is_some()
+    }
+
+    fn supports_hint(&self, hint: &str) -> bool {
+        GenericLanguage::for_hint(GENERIC_LANGUAGES, hint).is_some()
+    }
+
+    fn analyze_source(&self, path: &Path, source: &str) -> Result<FileAnalysis> {
+        self.analyze_source_in_context(path, source, generic_context_from_path(path))
+    }
+
+    fn analyze_source_in_context(
+        &self,
+        path: &Path,
+        source: &str,
+        context: LogicContext,
+    ) -> Result<FileAnalysis> {
+        let language = GenericLanguage::for_file(GENERIC_LANGUAGES, path, source)?;
+        let mut stats = Locs::new();
+        let mut line_classes = Vec::new();
+
+        for line in lex(source, &language.spec) {
+            let class = line.class(context);
+            class.record(&mut stats);
+            line_classes.push(class);
+        }
+
+        Ok(FileAnalysis {
+            language: LanguageId::External(language.id.to_string()),
+            stats,
+            line_classes,
+            parse_failure: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::backend::{LineClass, LogicContext};
+
+    const CODE: LineClass = LineClass::Logic(LogicContext::Code);
+    const COMMENT: LineClass = LineClass::Comments;
+    const BLANK: LineClass = LineClass::Blanks;
+
+    fn classes(path: &str, source: &str) -> Vec<LineClass> {
+        GenericBackend
+            .analyze_source(Path::new(path), source)
+            .unwrap()
+            .line_classes
+    }
+
+    /// One regression case per language: comment markers inside strings,
+    /// comments before and after code on the same line, and block comments
+    /// that span lines or nest.
+    #[test]
+    fn lexes_each_language_with_its_own_syntax() {
+        let cases: &[(&str, &str, &[LineClass])] = &[
+            (
+                "run.sh",
+                "# setup\necho \"# not a comment\" # note\nURL='http://x'\n",
+                &[COMMENT, CODE, CODE],
+            ),
+            (
+                "tool.rb",
+                "# helper\nputs \"#{name}\" # note\n",
+                &[COMMENT, CODE],
+            ),
+            (
+                "app.js",
+                "const url = \"http://example.com\";\nx = 1 /* note */\n/* a\n*/ code();\nconst s = `\n// text\n`;\n",
+                &[CODE, CODE, COMMENT, CODE, CODE, CODE, CODE],
+            ),
+            (
+                "main.go",
+                "s := `\n/* not a comment\n`\n// done\n",
+                &[CODE, CODE, CODE, COMMENT],
+            ),
+            (
+                "Main.java",
+                "String s = \"\"\"\n  // text block\n  \"\"\";\n/* comment */\n",
+                &[CODE, CODE, CODE, COMMENT],
+            ),
+            (
+                "main.c",
+                "char *s = \"/* not a comment\";\nauto r = R\"x(\n// raw\n)x\";\n",
+                &[CODE, CODE, CODE, CODE],
+            ),
+            (
+                "Program.cs",
+                "var p = @\"C:\\dir\\\";\n// comment\nvar q = \"\\\"//\";\n",
+                &[CODE, COMMENT, CODE],
+            ),
+            (
+                "index.php",
+                "#[Route('/home')]\n# comment\n// comment\n$a = '# not'; # note\n",
+                &[CODE, COMMENT, COMMENT, CODE],
+            ),
+            (
+                "App.swift",
+                "/* outer /* inner */\nstill comment */\nlet s = \"/*\"\n",
+                &[COMMENT, COMMENT, CODE],
+            ),
+            (
+                "Main.kt",
+                "/* a /* b */ c */ val x = 1\nval raw = \"\"\"\\\"\"\"\n\n",
+                &[CODE, CODE, BLANK],
+            ),
+            (
+                "Main.scala",
+                "/* a /* b\n*/ still */\nval s = \"*/\"\n",
+                &[COMMENT, COMMENT, CODE],
+            ),
+            (
+                "site.css",
+                "a { content: \"/*\"; }\n/* note */\n",
+                &[CODE, COMMENT],
+            ),
+            (
+                "Dockerfile",
+                "# base\nFROM rust # pinned\n",
+                &[COMMENT, CODE],
+            ),
+            ("Makefile", "# build\nall:\n\tcargo build\n", &[COMMENT, CODE, CODE]),
+            (
+                "justfile",
+                "# test\ntest:\n    echo '# not'\n",
+                &[COMMENT, CODE, CODE],
+            ),
+        ];
+
+        for (path, source, expected) in cases {
+            assert_eq!(classes(path, source), *expected, "{path}");
+        }
+    }
+
+    #[test]
+    fn every_language_has_a_regression_case() {
+        let covered = [
+            "Shell",
+            "Ruby",
+            "JavaScript",
+            "Go",
+            "Java",
+            "C-like",
+            "CSharp",
+            "PHP",
+            "Swift",
+            "Kotlin",
+            "Scala",
+            "CSS",
+            "Dockerfile",
+            "Make",
+            "Just",
+        ];
+        let ids: Vec<_> = GENERIC_LANGUAGES
+            .iter()
+            .map(|language| language.id)
+            .collect();
+        assert_eq!(ids, covered);
+    }
+
+    #[test]
+    fn context_comes_from_the_path() {
+        assert_eq!(
+            classes("tests/app.test.js", "run();\n"),
+            [LineClass::Logic(LogicContext::Tests)]
+        );
+        assert_eq!(
+            classes("examples/demo.go", "run()\n"),
+            [LineClass::Logic(LogicContext::Example)]
+        );
+    }
+}
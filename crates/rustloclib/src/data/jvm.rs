@@ -0,0 +1,557 @@
+use std::path::Path;
+
+use crate::Result;
+
+use super::backend::{
+    generic_context_from_path, FileAnalysis, LanguageBackend, LanguageId, LogicContext,
+};
+use super::lexer::{
+    brace_regions_with, lex, BlockComment, Bodiless, LexSpec, LexedLine, StringDelim,
+};
+use super::stats::Locs;
+
+/// Java and Kotlin backend with Maven/Gradle source-set and JUnit/TestNG awareness.
+#[derive(Debug, Default)]
+pub struct JvmBackend;
+
+const JAVA: LexSpec = LexSpec {
+    doc_line_comments: &[],
+    line_comments: &["//"],
+    block_comments: &[
+        BlockComment {
+            open: "/**",
+            close: "*/",
+            doc: true,
+            nested: false,
+        },
+        BlockComment {
+            open: "/*",
+            close: "*/",
+            doc: false,
+            nested: false,
+        },
+    ],
+    strings: &[
+        StringDelim {
+            open: "\"\"\"",
+            close: "\"\"\"",
+            escape: Some('\\'),
+            multiline: true,
+        },
+        StringDelim {
+            open: "\"",
+            close: "\"",
+            escape: Some('\\'),
+            multiline: false,
+        },
+        StringDelim {
+            open: "'",
+            close: "'",
+            escape: Some('\\'),
+            multiline: false,
+        },
+    ],
+    raw_strings: false,
+    code_prefixes: &[],
+};
+
+const KOTLIN: LexSpec = LexSpec {
+    doc_line_comments: &[],
+    line_comments: &["//"],
+    block_comments: &[
+        BlockComment {
+            open: "/**",
+            close: "*/",
+            doc: true,
+            nested: true,
+        },
+        BlockComment {
+            open: "/*",
+            close: "*/",
+            doc: false,
+            nested: true,
+        },
+    ],
+    strings: &[
+        StringDelim {
+            open: "\"\"\"",
+            close: "\"\"\"",
+            escape: None,
+            multiline: true,
+        },
+        StringDelim {
+            open: "\"",
+            close: "\"",
+            escape: Some('\\'),
+            multiline: false,
+        },
+        StringDelim {
+            open: "'",
+            close: "'",
+            escape: Some('\\'),
+            multiline: false,
+        },
+    ],
+    raw_strings: false,
+    code_prefixes: &[],
+};
+
+/// JUnit 4/5 and TestNG annotations whose target declaration is test code.
+/// Outside those frameworks these names are rare enough to match on sight.
+const TEST_ANNOTATIONS: &[&str] = &[
+    "Test",
+    "ParameterizedTest",
+    "RepeatedTest",
+    "TestFactory",
+    "TestTemplate",
+    "BeforeEach",
+    "AfterEach",
+    "BeforeAll",
+    "AfterAll",
+    "BeforeMethod",
+    "AfterMethod",
+    "BeforeTest",
+    "AfterTest",
+    "BeforeSuite",
+    "AfterSuite",
+    "BeforeGroups",
+    "AfterGroups",
+];
+
+/// JUnit and TestNG annotations that production frameworks use too (AspectJ
+/// and Spring `@Before`/`@After` advice, Micronaut `@Factory`). They mark tests
+/// only in a file that imports `org.junit` or `org.testng`, or when written
+/// fully qualified.
+const FRAMEWORK_TEST_ANNOTATIONS: &[&str] = &[
+    "Nested",
+    "Before",
+    "After",
+    "BeforeClass",
+    "AfterClass",
+    "DataProvider",
+    "Factory",
+];
+
+/// Packages of the test frameworks whose annotations are recognized.
+const TEST_FRAMEWORK_PACKAGES: &[&str] = &["org.junit.", "org.testng."];
+
+impl LanguageBackend for JvmBackend {
+    fn supports_path(&self, path: &Path) -> bool {
+        jvm_language(path).is_some()
+    }
+
+    fn supports_hint(&self, hint: &str) -> bool {
+        hint == "java"
+    }
+
+    fn analyze_source(&self, path: &Path, source: &str) -> Result<FileAnalysis> {
+        self.analyze_source_in_context(path, source, jvm_context_from_path(path))
+    }
+
+    fn analyze_source_in_context(
+        &self,
+        path: &Path,
+        source: &str,
+        context: LogicContext,
+    ) -> Result<FileAnalysis> {
+        let (language, spec) = jvm_language(path).unwrap_or((LanguageId::Java, &JAVA));
+        let lines = lex(source, spec);
+        let tests = test_lines(&lines, language == LanguageId::Kotlin);
+
+        let mut stats = Locs::new();
+        let mut line_classes = Vec::with_capacity(lines.len());
+        for (line, is_test) in lines.iter().zip(tests) {
+            let context = if is_test {
+                LogicContext::Tests
+            } else {
+                context
+            };
+            let class = line.class(context);
+            class.record(&mut stats);
+            line_classes.push(class);
+        }
+
+        Ok(FileAnalysis {
+            language,
+            stats,
+            line_classes,
+            parse_failure: None,
+        })
+    }
+}
+
+fn jvm_language(path: &Path) -> Option<(LanguageId, &'static LexSpec<'static>)> {
+    let ext = path.extension()?.to_str()?;
+    if ext.eq_ignore_ascii_case("java") {
+        Some((LanguageId::Java, &JAVA))
+    } else if ext.eq_ignore_ascii_case("kt") || ext.eq_ignore_ascii_case("kts") {
+        Some((LanguageId::Kotlin, &KOTLIN))
+    } else {
+        None
+    }
+}
+
+/// Determine the logic context from a Maven/Gradle source-set layout.
+///
+/// `src/<set>/...` decides when present: `main` is production code, `test`,
+/// `testFixtures`, `integrationTest` and any other `*Test`/`*Tests` set is test
+/// code. This runs before the generic path rules so a `test` package under
+/// `src/main` stays production code.
+fn jvm_context_from_path(path: &Path) -> LogicContext {
+    let components: Vec<&str> = path
+        .components()
+        .filter_map(|component| component.as_os_str().to_str())
+        .collect();
+
+    for pair in components.windows(2).rev() {
+        if pair[0] != "src" {
+            continue;
+        }
+        let set = pair[1];
+        let lower = set.to_ascii_lowercase();
+        if set == "main" {
+            return LogicContext::Code;
+        }
+        // `test`, `tests`, `testFixtures`, or a camelCase `integrationTest` —
+        // not `latest`.
+        if lower.starts_with("test") || set.ends_with("Test") || set.ends_with("Tests") {
+            return LogicContext::Tests;
+        }
+        if lower.contains("example") || lower.contains("sample") {
+            return LogicContext::Example;
+        }
+    }
+
+    generic_context_from_path(path)
+}
+
+/// Mark the lines of declarations annotated with a test annotation, and of
+/// JUnit 3 `extends TestCase` classes, as tests.
+fn test_lines(lines: &[LexedLine], kotlin: bool) -> Vec<bool> {
+    let imports_framework = lines.iter().any(|line| {
+        let code = line.code.trim_start();
+        code.strip_prefix("import ")
+            .map(|name| name.trim_start())
+            .map(|name| name.strip_prefix("static ").unwrap_or(name).trim_start())
+            .is_some_and(|name| {
+                TEST_FRAMEWORK_PACKAGES
+                    .iter()
+                    .any(|package| name.starts_with(package))
+            })
+    });
+    // Kotlin expression bodies (`fun x() = check()`) have no braces and no
+    // semicolon; the declaration ends with its line.
+    let expression_body = |code: &str| {
+        let trimmed = code.trim();
+        kotlin
+            && !strip_annotations(trimmed).is_empty()
+            && trimmed.contains(" = ")
+            && !trimmed.ends_with(['=', '(', ',', '.'])
+    };
+    brace_regions_with(
+        lines.iter().map(|line| line.code.as_str()),
+        |before, ident, after| {
+            if before.ends_with('@') {
+                is_test_annotation(ident, after, imports_framework)
+            } else {
+                ident == "extends" && extends_test_case(after)
+            }
+        },
+        Bodiless::Mark(&expression_body),
+    )
+}
+
+/// Whether the superclass after `extends` is JUnit 3's `TestCase`, plain or
+/// fully qualified — not `TestCaseHelper` or `TestCases`.
+fn extends_test_case(after: &str) -> bool {
+    let after = after.trim_start();
+    let after = after.strip_prefix("junit.framework.").unwrap_or(after);
+    after.strip_prefix("TestCase").is_some_and(|rest| {
+        !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_' || c == '$')
+    })
+}
+
+/// The part of a line after any leading annotations (`@Test @Tag("x") fun a()`
+/// → `fun a()`).
+fn strip_annotations(mut line: &str) -> &str {
+    while let Some(rest) = line.strip_prefix('@') {
+        let name_end = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.' || c == ':'))
+            .unwrap_or(rest.len());
+        line = rest[name_end..].trim_start();
+        if line.starts_with('(') {
+            let mut depth = 0usize;
+            let mut end = line.len();
+            for (offset, ch) in line.char_indices() {
+                match ch {
+                    '(' => depth += 1,
+                    ')' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            end = offset + 1;
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            line = line[end..].trim_start();
+        }
+    }
+    line
+}
+
+/// Whether the annotation `ident` starts (`after` is the rest of its line)
+/// marks a test: any name in [`TEST_ANNOTATIONS`], and a name in
+/// [`FRAMEWORK_TEST_ANNOTATIONS`] when the file imports a test framework or
+/// the annotation is qualified with one's package.
+fn is_test_annotation(ident: &str, after: &str, imports_framework: bool) -> bool {
+    let name = annotation_name(ident, after);
+    if TEST_ANNOTATIONS.contains(&name) {
+        return true;
+    }
+    let qualified = format!("{ident}{after}");
+    FRAMEWORK_TEST_ANNOTATIONS.contains(&name)
+        && (imports_framework
+            || TEST_FRAMEWORK_PACKAGES
+                .iter()
+                .any(|package| qualified.starts_with(package)))
+}
+
+/// The simple name of the annotation `ident` starts (`Test` for
+/// `org.junit.Test`, with `after` = `.junit.Test`).
+fn annotation_name<'a>(ident: &'a str, after: &'a str) -> &'a str {
+    let Some(qualified) = after.strip_prefix('.') else {
+        return ident;
+    };
+    let end = qualified
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+        .unwrap_or(qualified.len());
+    qualified[..end].rsplit('.').next().unwrap_or(ident)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analyze(path: &str, source: &str) -> FileAnalysis {
+        JvmBackend.analyze_source(Path::new(path), source).unwrap()
+    }
+
+    #[test]
+    fn maven_source_sets_decide_context() {
+        assert_eq!(
+            jvm_context_from_path(Path::new("core/src/main/java/com/acme/test/Util.java")),
+            LogicContext::Code
+        );
+        assert_eq!(
+            jvm_context_from_path(Path::new("core/src/test/java/com/acme/UtilTest.java")),
+            LogicContext::Tests
+        );
+        assert_eq!(
+            jvm_context_from_path(Path::new("lib/src/testFixtures/kotlin/Fakes.kt")),
+            LogicContext::Tests
+        );
+        assert_eq!(
+            jvm_context_from_path(Path::new("app/src/integrationTest/java/FlowIT.java")),
+            LogicContext::Tests
+        );
+        assert_eq!(
+            jvm_context_from_path(Path::new("app/src/functionalTests/java/Flow.java")),
+            LogicContext::Tests
+        );
+        assert_eq!(
+            jvm_context_from_path(Path::new("app/src/latest/java/com/acme/Feed.java")),
+            LogicContext::Code
+        );
+    }
+
+    #[test]
+    fn classifies_javadoc_comments_blanks_and_code() {
+        let analysis = analyze(
+            "src/main/java/com/acme/Service.java",
+            r#"/**
+ * Public service.
+ */
+public class Service {
+    // implementation note
+    private final String url = "http://example.invalid"; // trailing
+
+    /* block */
+    public int run() { return 1; }
+}
+"#,
+        );
+
+        assert_eq!(analysis.language, LanguageId::Java);
+        assert_eq!(analysis.stats.docs, 3);
+        assert_eq!(analysis.stats.comments, 2);
+        assert_eq!(analysis.stats.blanks, 1);
+        assert_eq!(analysis.stats.code, 4);
+        assert_eq!(analysis.stats.tests, 0);
+    }
+
+    #[test]
+    fn junit_annotated_methods_in_main_sources_are_tests() {
+        let analysis = analyze(
+            "src/main/java/com/acme/Service.java",
+            r#"import org.junit.jupiter.api.Test;
+
+public class Service {
+    int run() {
+        return 1;
+    }
+
+    @Test
+    void runs() {
+        assertEquals(1, new Service().run());
+    }
+
+    @org.junit.jupiter.params.ParameterizedTest
+    @ValueSource(ints = {1, 2})
+    void many(int value) {
+        assertTrue(value > 0);
+    }
+}
+"#,
+        );
+
+        assert_eq!(analysis.stats.tests, 9);
+        assert_eq!(analysis.stats.code, 6);
+        assert_eq!(analysis.stats.blanks, 3);
+    }
+
+    #[test]
+    fn aspectj_advice_is_not_a_test() {
+        let analysis = analyze(
+            "src/main/java/Aspect.java",
+            r#"import org.aspectj.lang.annotation.After;
+import org.aspectj.lang.annotation.Before;
+
+@io.micronaut.context.annotation.Factory
+public class Audit {
+    @Before("execution(* x.*(..))")
+    public void enter() {
+        log("enter");
+    }
+
+    @After("execution(* x.*(..))")
+    public void leave() {
+        log("leave");
+    }
+}
+"#,
+        );
+
+        assert_eq!(analysis.stats.tests, 0);
+        assert_eq!(analysis.stats.code, 13);
+    }
+
+    #[test]
+    fn junit4_lifecycle_annotations_need_a_junit_import_or_qualified_name() {
+        let imported = analyze(
+            "src/main/java/Checks.java",
+            r#"import org.junit.Before;
+
+class Checks {
+    @Before
+    public void setUp() {}
+}
+"#,
+        );
+        assert_eq!(imported.stats.tests, 2);
+
+        let qualified = analyze(
+            "src/main/java/Checks.java",
+            r#"class Checks {
+    @org.testng.annotations.BeforeClass
+    public void setUp() {}
+}
+"#,
+        );
+        assert_eq!(qualified.stats.tests, 2);
+    }
+
+    #[test]
+    fn testng_class_annotation_marks_the_whole_class() {
+        let analysis = analyze(
+            "src/main/java/Checks.java",
+            r#"@Test(groups = "fast")
+public class Checks {
+    public void first() {
+        assert true;
+    }
+}
+"#,
+        );
+
+        assert_eq!(analysis.stats.tests, 6);
+        assert_eq!(analysis.stats.code, 0);
+    }
+
+    #[test]
+    fn junit3_test_case_classes_are_tests() {
+        let analysis = analyze(
+            "src/main/java/LegacyTest.java",
+            r#"public class LegacyTest extends TestCase {
+    public void testIt() {}
+}
+"#,
+        );
+
+        assert_eq!(analysis.stats.tests, 3);
+    }
+
+    #[test]
+    fn junit3_test_case_lookalikes_are_not_tests() {
+        let analysis = analyze(
+            "src/main/java/Fixtures.java",
+            r#"public class Fixtures extends TestCaseHelper {
+    public void build() {}
+}
+class Suite extends TestCases {
+}
+class Qualified extends junit.framework.TestCase {
+}
+"#,
+        );
+
+        assert_eq!(analysis.stats.code, 5);
+        assert_eq!(analysis.stats.tests, 2);
+    }
+
+    #[test]
+    fn kotlin_kdoc_nested_comments_and_expression_body_tests() {
+        let analysis = analyze(
+            "src/main/kotlin/Calc.kt",
+            r#"/** KDoc. */
+fun add(a: Int, b: Int) = a + b
+
+/* outer /* inner */ still comment */
+@Test fun adds() = assertEquals(3, add(1, 2))
+
+val raw = """
+// not a comment
+"""
+"#,
+        );
+
+        assert_eq!(analysis.language, LanguageId::Kotlin);
+        assert_eq!(analysis.stats.docs, 1);
+        assert_eq!(analysis.stats.comments, 1);
+        assert_eq!(analysis.stats.tests, 1);
+        assert_eq!(analysis.stats.code, 4);
+        assert_eq!(analysis.stats.blanks, 2);
+    }
+
+    #[test]
+    fn test_source_set_counts_everything_as_tests() {
+        let analysis = analyze(
+            "src/test/kotlin/CalcTest.kt",
+            "class CalcTest {\n    fun helper() = 1\n}\n",
+        );
+
+        assert_eq!(analysis.stats.tests, 3);
+        assert_eq!(analysis.stats.code, 0);
+    }
+}
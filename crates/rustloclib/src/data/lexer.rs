@@ -0,0 +1,558 @@
+//! Line lexer shared by the backends without a full parser.
+//!
+//! Backends without a full parser still need to know, per line, whether code,
+//! a comment, or a doc comment appears on it — and they need to know that
+//! without being fooled by `//` inside a string literal or by a comment that
+//! opens after code. [`lex`] walks a source once with a small table of comment
+//! and string delimiters ([`LexSpec`]) and reports those facts per line,
+//! together with the line's code text with comments removed and string
+//! contents blanked, so backends can look for test constructs without
+//! re-implementing string and comment handling.
+
+use super::backend::{LineClass, LogicContext};
+
+/// A block comment delimiter pair.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct BlockComment<'a> {
+    pub open: &'a str,
+    pub close: &'a str,
+    /// Lines inside this comment count as documentation.
+    pub doc: bool,
+    /// Openers inside the comment nest (Kotlin, Swift, Scala).
+    pub nested: bool,
+}
+
+/// A string literal delimiter pair.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct StringDelim<'a> {
+    pub open: &'a str,
+    pub close: &'a str,
+    /// Escape character that protects the next character, if any.
+    pub escape: Option<char>,
+    /// The literal may span lines (text blocks, raw strings).
+    pub multiline: bool,
+}
+
+/// Comment and string syntax for one language.
+///
+/// Delimiters of one kind are tried in declaration order, so longer openers
+/// that share a prefix with shorter ones (`///` before `//`, `/**` before `/*`,
+/// `"""` before `"`) must be listed first. A block comment opener wins over a
+/// shorter line comment prefix (`--[[` over `--`).
+#[derive(Debug, Clone, Copy)]
+pub(super) struct LexSpec<'a> {
+    pub doc_line_comments: &'a [&'a str],
+    pub line_comments: &'a [&'a str],
+    pub block_comments: &'a [BlockComment<'a>],
+    pub strings: &'a [StringDelim<'a>],
+    /// C++11 raw string literals, `R"delim(...)delim"`, with an optional
+    /// `u8`/`u`/`U`/`L` encoding prefix.
+    pub raw_strings: bool,
+    /// Tokens that start like a comment but are code, such as PHP's `#[`
+    /// attributes next to `#` line comments.
+    pub code_prefixes: &'a [&'a str],
+}
+
+/// What one source line contains once comments and strings are understood.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(super) struct LexedLine {
+    /// Code text with comments removed and string contents blanked to `""`.
+    pub code: String,
+    /// Any code (including string literal content) appears on the line.
+    pub has_code: bool,
+    /// Any ordinary comment appears on the line.
+    pub has_comment: bool,
+    /// Any doc comment appears on the line.
+    pub has_doc: bool,
+}
+
+impl LexedLine {
+    /// Classify the line: code wins over comments, docs win over comments.
+    pub fn class(&self, context: LogicContext) -> LineClass {
+        if self.has_code {
+            LineClass::Logic(context)
+        } else if self.has_doc {
+            LineClass::Docs
+        } else if self.has_comment {
+            LineClass::Comments
+        } else {
+            LineClass::Blanks
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Mode {
+    Code,
+    Block { index: usize, depth: usize },
+    Str { index: usize },
+    RawStr { close: String },
+}
+
+/// Lex `source` line by line according to `spec`.
+pub(super) fn lex(source: &str, spec: &LexSpec) -> Vec<LexedLine> {
+    let mut mode = Mode::Code;
+    let mut lines = Vec::new();
+
+    for line in source.lines() {
+        let mut lexed = LexedLine::default();
+        match mode {
+            Mode::Block { index, .. } => mark_comment(&mut lexed, spec.block_comments[index].doc),
+            Mode::Str { .. } | Mode::RawStr { .. } => lexed.has_code = true,
+            Mode::Code => {}
+        }
+
+        let mut rest = line;
+        while let Some(ch) = rest.chars().next() {
+            match &mode {
+                Mode::Code => {
+                    if ch.is_whitespace() {
+                        lexed.code.push(ch);
+                        rest = &rest[ch.len_utf8()..];
+                        continue;
+                    }
+                    if let Some(prefix) = spec.code_prefixes.iter().find(|p| rest.starts_with(**p))
+                    {
+                        lexed.has_code = true;
+                        lexed.code.push_str(prefix);
+                        rest = &rest[prefix.len()..];
+                        continue;
+                    }
+                    let block = spec
+                        .block_comments
+                        .iter()
+                        .enumerate()
+                        .find(|(_, block)| rest.starts_with(block.open));
+                    let block_len = block.map_or(0, |(_, block)| block.open.len());
+                    let line_prefix = |prefixes: &[&str]| {
+                        prefixes
+                            .iter()
+                            .any(|p| rest.starts_with(p) && p.len() > block_len)
+                    };
+                    if line_prefix(spec.doc_line_comments) {
+                        lexed.has_doc = true;
+                        break;
+                    }
+                    if line_prefix(spec.line_comments) {
+                        lexed.has_comment = true;
+                        break;
+                    }
+                    if let Some((index, block)) = block {
+                        // `/**/` is an empty ordinary comment, not an unterminated
+                        // doc comment: the doc opener's last char starts the close.
+                        let overlap = block.open.len() - 1;
+                        if block.doc && rest[overlap..].starts_with(block.close) {
+                            lexed.has_comment = true;
+                            rest = &rest[overlap + block.close.len()..];
+                            continue;
+                        }
+                        mark_comment(&mut lexed, block.doc);
+                        mode = Mode::Block { index, depth: 1 };
+                        rest = &rest[block.open.len()..];
+                        continue;
+                    }
+                    if spec.raw_strings && ch == 'R' && raw_string_prefix(&lexed.code) {
+                        if let Some((delimiter, open_len)) = raw_string_delimiter(rest) {
+                            lexed.has_code = true;
+                            lexed.code.push('"');
+                            mode = Mode::RawStr {
+                                close: format!("){}\"", delimiter),
+                            };
+                            rest = &rest[open_len..];
+                            continue;
+                        }
+                    }
+                    if let Some((index, delim)) = spec
+                        .strings
+                        .iter()
+                        .enumerate()
+                        .find(|(_, delim)| rest.starts_with(delim.open))
+                    {
+                        lexed.has_code = true;
+                        lexed.code.push('"');
+                        mode = Mode::Str { index };
+                        rest = &rest[delim.open.len()..];
+                        continue;
+                    }
+                    lexed.has_code = true;
+                    lexed.code.push(ch);
+                    rest = &rest[ch.len_utf8()..];
+                }
+                &Mode::Block { index, depth } => {
+                    let block = spec.block_comments[index];
+                    mark_comment(&mut lexed, block.doc);
+                    if rest.starts_with(block.close) {
+                        rest = &rest[block.close.len()..];
+                        mode = if depth == 1 {
+                            Mode::Code
+                        } else {
+                            Mode::Block {
+                                index,
+                                depth: depth - 1,
+                            }
+                        };
+                        continue;
+                    }
+                    if block.nested {
+                        if let Some(nested) = spec.block_comments.iter().find(|other| {
+                            other.close == block.close && rest.starts_with(other.open)
+                        }) {
+                            rest = &rest[nested.open.len()..];
+                            mode = Mode::Block {
+                                index,
+                                depth: depth + 1,
+                            };
+                            continue;
+                        }
+                    }
+                    rest = &rest[ch.len_utf8()..];
+                }
+                &Mode::Str { index } => {
+                    let delim = spec.strings[index];
+                    lexed.has_code = true;
+                    if delim.escape == Some(ch) {
+                        rest = &rest[ch.len_utf8()..];
+                        if let Some(escaped) = rest.chars().next() {
+                            rest = &rest[escaped.len_utf8()..];
+                        }
+                        continue;
+                    }
+                    if rest.starts_with(delim.close) {
+                        lexed.code.push('"');
+                        rest = &rest[delim.close.len()..];
+                        mode = Mode::Code;
+                        continue;
+                    }
+                    rest = &rest[ch.len_utf8()..];
+                }
+                Mode::RawStr { close } => {
+                    lexed.has_code = true;
+                    if let Some(after) = rest.strip_prefix(close.as_str()) {
+                        lexed.code.push('"');
+                        rest = after;
+                        mode = Mode::Code;
+                        continue;
+                    }
+                    rest = &rest[ch.len_utf8()..];
+                }
+            }
+        }
+
+        // An unterminated single-line literal does not swallow the next line.
+        if let Mode::Str { index } = mode {
+            if !spec.strings[index].multiline {
+                mode = Mode::Code;
+            }
+        }
+        lines.push(lexed);
+    }
+
+    lines
+}
+
+/// Mark the lines spanned by brace-delimited blocks that `opens` introduces.
+///
+/// `codes` is each line's code text (as in [`LexedLine::code`]). For every
+/// identifier, `opens(before, ident, after)` decides whether it introduces a
+/// block (`TEST(...)`, `test "name"`); the block then runs from that line
+/// through the `}` matching the next top-level `{`. A `;` before the `{`
+/// cancels it, since the identifier was then only declared or called.
+pub(super) fn brace_regions<'a>(
+    codes: impl IntoIterator<Item = &'a str>,
+    opens: impl Fn(&str, &str, &str) -> bool,
+) -> Vec<bool> {
+    brace_regions_with(codes, opens, Bodiless::Cancel)
+}
+
+/// What [`brace_regions_with`] does with a block opener that reaches a `;`
+/// before a `{`.
+#[derive(Clone, Copy)]
+pub(super) enum Bodiless<'f> {
+    /// Drop it: the identifier was only declared or called.
+    Cancel,
+    /// Mark the declaration, which has no body (an annotated abstract
+    /// method). It also ends, marked, at the end of any line the function
+    /// accepts (a Kotlin expression body, `fun a() = b()`).
+    Mark(&'f dyn Fn(&str) -> bool),
+}
+
+/// [`brace_regions`] with a choice of what a declaration without a body
+/// does. A block opener whose enclosing scope closes first is dropped.
+pub(super) fn brace_regions_with<'a>(
+    codes: impl IntoIterator<Item = &'a str>,
+    opens: impl Fn(&str, &str, &str) -> bool,
+    bodiless: Bodiless<'_>,
+) -> Vec<bool> {
+    let mut marked = Vec::new();
+    let mut depth = 0usize;
+    let mut parens = 0usize;
+    // (start line, brace depth) of a block whose `{` has not been seen yet
+    let mut pending: Option<(usize, usize)> = None;
+    // (start line, brace depth the block closes back to)
+    let mut open_region: Option<(usize, usize)> = None;
+
+    for (index, code) in codes.into_iter().enumerate() {
+        marked.push(false);
+        let mut offset = 0;
+        while let Some(ch) = code[offset..].chars().next() {
+            if ch.is_alphabetic() || ch == '_' {
+                let end = code[offset..]
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .map_or(code.len(), |len| offset + len);
+                if open_region.is_none()
+                    && pending.is_none()
+                    && opens(&code[..offset], &code[offset..end], &code[end..])
+                {
+                    pending = Some((index, depth));
+                }
+                offset = end;
+                continue;
+            }
+
+            match ch {
+                '(' => parens += 1,
+                ')' => parens = parens.saturating_sub(1),
+                '{' => {
+                    if parens == 0 {
+                        if let Some(start) = pending.take() {
+                            open_region = Some(start);
+                        }
+                    }
+                    depth += 1;
+                }
+                '}' => {
+                    depth = depth.saturating_sub(1);
+                    if let Some((start, region_depth)) = open_region {
+                        if depth == region_depth {
+                            marked[start..=index].fill(true);
+                            open_region = None;
+                        }
+                    }
+                    if pending.is_some_and(|(_, pending_depth)| depth < pending_depth) {
+                        pending = None;
+                    }
+                }
+                ';' if parens == 0 => {
+                    if let (Some((start, _)), Bodiless::Mark(_)) = (pending, bodiless) {
+                        marked[start..=index].fill(true);
+                    }
+                    pending = None;
+                }
+                _ => {}
+            }
+            offset += ch.len_utf8();
+        }
+
+        if let (Some((start, _)), Bodiless::Mark(ends_line)) = (pending, bodiless) {
+            if parens == 0 && ends_line(code) {
+                marked[start..=index].fill(true);
+                pending = None;
+            }
+        }
+    }
+
+    if let Some((start, _)) = open_region {
+        marked[start..].fill(true);
+    }
+
+    marked
+}
+
+/// Whether an `R` at the end of `code_so_far` starts a raw string literal: it
+/// must begin a token, or follow one of the encoding prefixes.
+fn raw_string_prefix(code_so_far: &str) -> bool {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let token_start = code_so_far
+        .rfind(|c: char| !is_ident(c))
+        .map_or(0, |index| index + 1);
+    matches!(&code_so_far[token_start..], "" | "u8" | "u" | "U" | "L")
+}
+
+/// Parse `R"delim(` at the start of `rest`, returning the delimiter and the
+/// length of the opener.
+fn raw_string_delimiter(rest: &str) -> Option<(&str, usize)> {
+    let body = rest.strip_prefix("R\"")?;
+    let paren = body.find('(')?;
+    let delimiter = &body[..paren];
+    // The standard caps delimiters at 16 characters and forbids spaces,
+    // parentheses and backslashes.
+    if delimiter.len() > 16 || delimiter.contains([' ', ')', '\\', '"']) {
+        return None;
+    }
+    Some((delimiter, 2 + paren + 1))
+}
+
+fn mark_comment(line: &mut LexedLine, doc: bool) {
+    if doc {
+        line.has_doc = true;
+    } else {
+        line.has_comment = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: LexSpec = LexSpec {
+        doc_line_comments: &["///"],
+        line_comments: &["//"],
+        block_comments: &[
+            BlockComment {
+                open: "/**",
+                close: "*/",
+                doc: true,
+                nested: true,
+            },
+            BlockComment {
+                open: "/*",
+                close: "*/",
+                doc: false,
+                nested: true,
+            },
+        ],
+        strings: &[
+            StringDelim {
+                open: "\"\"\"",
+                close: "\"\"\"",
+                escape: None,
+                multiline: true,
+            },
+            StringDelim {
+                open: "\"",
+                close: "\"",
+                escape: Some('\\'),
+                multiline: false,
+            },
+        ],
+        raw_strings: true,
+        code_prefixes: &[],
+    };
+
+    fn classes(source: &str) -> Vec<LineClass> {
+        lex(source, &SPEC)
+            .iter()
+            .map(|line| line.class(LogicContext::Code))
+            .collect()
+    }
+
+    #[test]
+    fn comment_markers_inside_strings_are_code() {
+        assert_eq!(
+            classes("let a = \"// not a comment\";\nlet b = \"\\\" /* still string\";\n"),
+            vec![LineClass::Logic(LogicContext::Code); 2]
+        );
+    }
+
+    #[test]
+    fn code_before_or_after_a_comment_is_code() {
+        assert_eq!(
+            classes("x = 1; /* note */\n/* note */ y();\n/* a\n*/ z();\n"),
+            vec![
+                LineClass::Logic(LogicContext::Code),
+                LineClass::Logic(LogicContext::Code),
+                LineClass::Comments,
+                LineClass::Logic(LogicContext::Code),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_block_comments_close_at_the_matching_depth() {
+        assert_eq!(
+            classes("/* outer /* inner */ still comment */\ncode()\n"),
+            vec![LineClass::Comments, LineClass::Logic(LogicContext::Code)]
+        );
+    }
+
+    #[test]
+    fn doc_comments_and_empty_block_comments() {
+        assert_eq!(
+            classes("/**\n\n * docs\n */\n/**/\n/// line docs\n"),
+            vec![
+                LineClass::Docs,
+                LineClass::Docs,
+                LineClass::Docs,
+                LineClass::Docs,
+                LineClass::Comments,
+                LineClass::Docs,
+            ]
+        );
+    }
+
+    #[test]
+    fn raw_strings_end_only_at_their_own_delimiter() {
+        let lines = lex(
+            "auto s = u8R\"sql(\n/* not a comment \")\" */\n)sql\"; // done\nR(x);\n",
+            &SPEC,
+        );
+        assert!(lines[..3].iter().all(|line| line.has_code));
+        assert!(!lines[1].has_comment);
+        assert!(lines[2].has_comment);
+        assert_eq!(lines[2].code, "\"; ");
+        assert_eq!(lines[3].code, "R(x);");
+    }
+
+    #[test]
+    fn brace_regions_span_from_the_opener_to_the_matching_brace() {
+        let codes = [
+            "fn a() {}",
+            "CHECK(x);",
+            "CHECK(y)",
+            "{",
+            "    if (x) { y(); }",
+            "}",
+            "z();",
+        ];
+        let marked = brace_regions(codes, |_, ident, after| {
+            ident == "CHECK" && after.starts_with('(')
+        });
+        assert_eq!(marked, vec![false, false, true, true, true, true, false]);
+    }
+
+    #[test]
+    fn multiline_strings_keep_comment_like_lines_as_code() {
+        let lines = lex("val s = \"\"\"\n// text\n\n\"\"\"\n", &SPEC);
+        assert!(lines.iter().all(|line| line.has_code));
+        assert_eq!(lines[0].code, "val s = \"");
+    }
+
+    #[test]
+    fn block_openers_win_over_shorter_line_prefixes() {
+        let spec = LexSpec {
+            doc_line_comments: &["---"],
+            line_comments: &["--"],
+            block_comments: &[BlockComment {
+                open: "--[[",
+                close: "]]",
+                doc: false,
+                nested: false,
+            }],
+            strings: &[],
+            raw_strings: false,
+            code_prefixes: &[],
+        };
+        let classes: Vec<LineClass> = lex(
+            "--[[ block
+still ]]
+-- line
+--- docs
+x()
+",
+            &spec,
+        )
+        .iter()
+        .map(|line| line.class(LogicContext::Code))
+        .collect();
+        assert_eq!(
+            classes,
+            vec![
+                LineClass::Comments,
+                LineClass::Comments,
+                LineClass::Comments,
+                LineClass::Docs,
+                LineClass::Logic(LogicContext::Code),
+            ]
+        );
+    }
+}
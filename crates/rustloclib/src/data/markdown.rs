@@ -0,0 +1,354 @@
+use std::ops::Range;
+use std::path::Path;
+
+use crate::Result;
+
+use super::backend::{FileAnalysis, LanguageBackend, LanguageId, LineClass, LogicContext};
+use super::stats::Locs;
+
+/// Markdown backend for prose documentation.
+///
+/// Prose is counted as docs, fenced and indented code blocks as examples,
+/// HTML comments as comments, and YAML (`---`) or TOML (`+++`) front matter
+/// as config. The fence lines themselves are markup and count as docs. The
+/// fenced blocks are [embedded blocks](LanguageBackend::embedded_blocks): when
+/// a [`BackendRegistry`](super::backend::BackendRegistry) has a selected
+/// backend for a block's fence language, built-in, user-defined or
+/// registered, the block body is re-classified through it so comments and
+/// blank lines inside examples are not counted as example code.
+#[derive(Debug, Default)]
+pub struct MarkdownBackend;
+
+/// A fenced code block in a Markdown document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FencedBlock {
+    /// First word of the info string, lowercased (`rust` for ```` ```rust,ignore ````).
+    pub language: Option<String>,
+    /// Zero-based indices of the body lines, excluding both fences.
+    pub lines: Range<usize>,
+}
+
+impl MarkdownBackend {
+    /// The fenced code blocks of `source`, tagged with their fence language.
+    /// Front matter is skipped; an unclosed fence runs to the end of the file.
+    pub fn fenced_blocks(source: &str) -> Vec<FencedBlock> {
+        scan(&source.lines().collect::<Vec<_>>()).1
+    }
+}
+
+impl LanguageBackend for MarkdownBackend {
+    fn supports_path(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                ["md", "markdown", "mdown", "mkd"]
+                    .iter()
+                    .any(|known| ext.eq_ignore_ascii_case(known))
+            })
+    }
+
+    fn supports_hint(&self, hint: &str) -> bool {
+        hint == "markdown"
+    }
+
+    /// Code in fenced blocks is always an example, wherever the file lives.
+    fn analyze_source_in_context(
+        &self,
+        path: &Path,
+        source: &str,
+        _context: LogicContext,
+    ) -> Result<FileAnalysis> {
+        self.analyze_source(path, source)
+    }
+
+    fn analyze_source(&self, _path: &Path, source: &str) -> Result<FileAnalysis> {
+        let (line_classes, _) = scan(&source.lines().collect::<Vec<_>>());
+        let mut stats = Locs::new();
+        for class in &line_classes {
+            class.record(&mut stats);
+        }
+        Ok(FileAnalysis {
+            language: LanguageId::Markdown,
+            stats,
+            line_classes,
+            parse_failure: None,
+        })
+    }
+
+    fn embedded_blocks(&self, source: &str) -> Vec<FencedBlock> {
+        Self::fenced_blocks(source)
+    }
+}
+
+/// Classify every line without consulting other backends, and collect the
+/// fenced blocks. Indented code blocks are examples too, but have no language
+/// and are not collected.
+fn scan(lines: &[&str]) -> (Vec<LineClass>, Vec<FencedBlock>) {
+    let front_matter = front_matter_len(lines);
+    let mut classes = vec![LineClass::Config; front_matter];
+    let mut blocks = Vec::new();
+    let mut fence: Option<(Fence, FencedBlock)> = None;
+    let mut in_comment = false;
+    // An indented line continues a paragraph or list item rather than
+    // starting a code block.
+    let mut in_paragraph = false;
+    let mut in_list = false;
+
+    for (index, line) in lines.iter().enumerate().skip(front_matter) {
+        let trimmed = line.trim();
+        let class = if let Some((open, _)) = &fence {
+            if open.is_closed_by(trimmed) {
+                let (_, mut block) = fence.take().unwrap();
+                block.lines.end = index;
+                blocks.push(block);
+                LineClass::Docs
+            } else if trimmed.is_empty() {
+                LineClass::Blanks
+            } else {
+                LineClass::Logic(LogicContext::Example)
+            }
+        } else if in_comment {
+            in_comment = !trimmed.contains("-->");
+            LineClass::Comments
+        } else if trimmed.is_empty() {
+            in_paragraph = false;
+            LineClass::Blanks
+        } else if is_indented(line) && !in_paragraph && !in_list {
+            LineClass::Logic(LogicContext::Example)
+        } else if let Some((open, language)) = Fence::open(trimmed) {
+            let block = FencedBlock {
+                language,
+                lines: index + 1..lines.len(),
+            };
+            fence = Some((open, block));
+            in_paragraph = false;
+            LineClass::Docs
+        } else if let Some(rest) = trimmed.strip_prefix("<!--") {
+            in_comment = !rest.contains("-->");
+            in_paragraph = false;
+            LineClass::Comments
+        } else {
+            if is_list_item(trimmed) {
+                in_list = true;
+            } else if !is_indented(line) && !in_paragraph {
+                in_list = false;
+            }
+            in_paragraph = !trimmed.starts_with('#');
+            LineClass::Docs
+        };
+        classes.push(class);
+    }
+
+    blocks.extend(fence.map(|(_, block)| block));
+    (classes, blocks)
+}
+
+/// Whether `line` is indented by four columns, enough for a code block.
+fn is_indented(line: &str) -> bool {
+    line.starts_with("    ") || line.trim_start_matches(' ').starts_with('\t')
+}
+
+/// Whether `line`, trimmed, starts a bullet or ordered list item.
+fn is_list_item(line: &str) -> bool {
+    let marker_end = if line.starts_with(['-', '*', '+']) {
+        1
+    } else {
+        let digits = line.chars().take_while(char::is_ascii_digit).count();
+        if digits == 0 || !line[digits..].starts_with(['.', ')']) {
+            return false;
+        }
+        digits + 1
+    };
+    line[marker_end..].is_empty() || line[marker_end..].starts_with([' ', '\t'])
+}
+
+/// Number of lines in a front matter block at the very start of the file, or
+/// zero if there is none or it is never closed.
+fn front_matter_len(lines: &[&str]) -> usize {
+    let Some(delimiter) = lines.first().map(|line| line.trim_end()) else {
+        return 0;
+    };
+    if delimiter != "---" && delimiter != "+++" {
+        return 0;
+    }
+    lines
+        .iter()
+        .skip(1)
+        .position(|line| {
+            let line = line.trim_end();
+            line == delimiter || (delimiter == "---" && line == "...")
+        })
+        .map_or(0, |close| close + 2)
+}
+
+/// An open code fence: its character and length.
+struct Fence {
+    marker: char,
+    len: usize,
+}
+
+impl Fence {
+    /// A line opening a fence of three or more backticks or tildes, and the
+    /// fence language from its info string.
+    fn open(line: &str) -> Option<(Self, Option<String>)> {
+        let marker = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+        let len = line.chars().take_while(|c| *c == marker).count();
+        let info = &line[len..];
+        if len < 3 || (marker == '`' && info.contains('`')) {
+            return None;
+        }
+        let language = info
+            .trim_start_matches(|c: char| c.is_whitespace() || c == '{' || c == '.')
+            .split(|c: char| c.is_whitespace() || matches!(c, ',' | '{' | '}'))
+            .next()
+            .filter(|word| !word.is_empty())
+            .map(str::to_ascii_lowercase);
+        Some((Self { marker, len }, language))
+    }
+
+    fn is_closed_by(&self, line: &str) -> bool {
+        line.len() >= self.len && line.chars().all(|c| c == self.marker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::backend::{BackendRegistry, LanguageName, LanguageSelection};
+    use crate::data::custom::LanguageDefinition;
+
+    const GUIDE: &str = "docs/guide.md";
+
+    fn analyze(source: &str) -> FileAnalysis {
+        BackendRegistry::new()
+            .analyze_source(Path::new(GUIDE), source)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn prose_is_docs_and_front_matter_is_config() {
+        let analysis = analyze(
+            r#"---
+title: Guide
+tags: [intro]
+---
+# Guide
+
+Some prose.
+<!-- TODO: expand
+     this section -->
+---
+"#,
+        );
+
+        assert_eq!(analysis.language, LanguageId::Markdown);
+        assert_eq!(analysis.stats.config, 4);
+        assert_eq!(analysis.stats.docs, 3);
+        assert_eq!(analysis.stats.comments, 2);
+        assert_eq!(analysis.stats.blanks, 1);
+        assert_eq!(analysis.stats.total, 10);
+    }
+
+    #[test]
+    fn unclosed_front_matter_is_prose() {
+        let analysis = analyze("+++\ntitle = \"x\"\n");
+        assert_eq!(analysis.stats.config, 0);
+        assert_eq!(analysis.stats.docs, 2);
+
+        let analysis = analyze("+++\ntitle = \"x\"\n+++\nBody\n");
+        assert_eq!(analysis.stats.config, 3);
+    }
+
+    #[test]
+    fn fenced_blocks_are_examples_reclassified_by_their_language() {
+        let source = r#"Usage:
+
+```rust,ignore
+// Count the current directory.
+let result = count(".");
+
+println!("{result}");
+```
+
+~~~python
+# comment
+print("hi")
+~~~
+
+````text
+``` not a closing fence
+plain
+````
+"#;
+        let analysis = analyze(source);
+
+        assert_eq!(analysis.stats.examples, 5);
+        assert_eq!(analysis.stats.comments, 2);
+        // Prose line and six fence lines.
+        assert_eq!(analysis.stats.docs, 7);
+        assert_eq!(analysis.stats.blanks, 4);
+        assert_eq!(analysis.stats.code, 0);
+
+        let blocks = MarkdownBackend::fenced_blocks(source);
+        let languages: Vec<_> = blocks.iter().map(|b| b.language.as_deref()).collect();
+        assert_eq!(languages, [Some("rust"), Some("python"), Some("text")]);
+        assert_eq!(blocks[0].lines, 3..7);
+        assert_eq!(blocks[2].lines, 15..17);
+    }
+
+    #[test]
+    fn fences_are_reclassified_by_the_selected_backends_of_the_registry() {
+        let source = "```hcl\n# comment\nx = 1\n```\n\n```python\n# comment\nx = 1\n```\n";
+        let registry = BackendRegistry::with_languages([LanguageDefinition {
+            name: "hcl".to_string(),
+            extensions: vec!["hcl".to_string()],
+            line_comments: vec!["#".to_string()],
+            ..LanguageDefinition::default()
+        }])
+        .unwrap();
+        let analyze = |languages: &LanguageSelection| {
+            registry
+                .analyze_source_with_languages(Path::new(GUIDE), source, languages)
+                .unwrap()
+                .unwrap()
+                .stats
+        };
+
+        let stats = analyze(&registry.all_languages());
+        assert_eq!((stats.examples, stats.comments), (2, 2));
+        // `--lang markdown` leaves the Python fence alone too.
+        let stats = analyze(&LanguageSelection::new(&[LanguageName::Markdown]));
+        assert_eq!((stats.examples, stats.comments), (4, 0));
+        let stats = analyze(&LanguageSelection::new(&[LanguageName::Markdown]).with_name("hcl"));
+        assert_eq!((stats.examples, stats.comments), (3, 1));
+    }
+
+    #[test]
+    fn indented_code_blocks_are_examples() {
+        let analysis = analyze(
+            "Run it:\n\n    cargo run\n\n    cargo test\nA paragraph\n    continued lazily\n\n- item\n\n    more of the item\n",
+        );
+
+        assert_eq!(analysis.stats.examples, 2);
+        assert_eq!(analysis.stats.docs, 5);
+        assert_eq!(analysis.stats.blanks, 4);
+        // Indented code has no language to collect it under.
+        assert!(MarkdownBackend::fenced_blocks("    let x = 1;\n").is_empty());
+    }
+
+    #[test]
+    fn unclosed_fence_runs_to_the_end() {
+        let source = "```\ncode\nmore\n";
+        let analysis = analyze(source);
+
+        assert_eq!(analysis.stats.examples, 2);
+        assert_eq!(
+            MarkdownBackend::fenced_blocks(source),
+            [FencedBlock {
+                language: None,
+                lines: 1..3,
+            }]
+        );
+    }
+}
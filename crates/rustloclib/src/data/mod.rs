@@ -6,6 +6,13 @@
 //! - **Parsing**: AST-aware visitor that categorizes lines
 //! - **Statistics**: Core data structures (`Locs`, `FileStats`, etc.)
 //! - **Counting**: High-level API (`count_workspace`, `count_file`)
+//! - **Content checks**: Binary, minified and oversized files are skipped with
+//!   a reason (`SkippedFile`); other sources are decoded from UTF-8, UTF-16 or
+//!   Windows-1252 (`SourceEncoding`)
+//! - **Diagnostics**: Unreadable, unparsable and skipped files are recorded
+//!   per file (`Diagnostic`) while the rest are counted
+//! - **Classification rules**: Ordered `glob=context` rules (`PathClassifier`)
+//!   decide which files are code, tests or examples for every backend
 //! - **Diffing**: Git diff analysis (`diff_revspec`, `diff_workdir`)
 //!
 //! ## Example
@@ -18,25 +25,55 @@
 //! ```
 
 pub mod backend;
+pub mod classify;
+mod config;
+pub mod content;
 pub mod counter;
+mod cpp;
+pub mod custom;
+pub mod detect;
+pub mod diagnostics;
 pub mod diff;
+mod generic;
+mod jvm;
+mod lexer;
+mod markdown;
+pub mod plugin;
 mod python;
+mod ruby;
 pub mod stats;
 mod typescript;
 pub mod visitor;
+mod zig;
 
 pub use backend::{
-    available_languages, default_languages, BackendRegistry, FileAnalysis, GenericBackend,
-    LanguageBackend, LanguageId, LanguageName, LanguageSelection, LineClass, LogicContext,
-    RustBackend,
+    available_languages, default_languages, BackendRegistry, FileAnalysis, LanguageBackend,
+    LanguageId, LanguageName, LanguageSelection, LineClass, LogicContext, ParseFailure,
+    RustBackend, BUILTIN_PRIORITY, CONFIG_PRIORITY,
 };
+pub use classify::{ClassifyRule, PathClassifier};
+pub use config::ConfigBackend;
+pub use content::{SkipReason, SkippedFile, SourceEncoding, SourceText, TranscodedFile};
 pub use counter::{
-    compute_module_name, count_directory, count_directory_with_options, count_file,
-    count_file_with_filter, count_workspace, CountOptions, CountResult,
+    compute_module_name, count_dependencies, count_directory, count_directory_with_options,
+    count_file, count_file_with_filter, count_workspace, count_workspaces, python_module_name,
+    CountOptions, CountResult, WORKSPACE_ROOT_NAME,
 };
+pub use cpp::CppBackend;
+pub use custom::{CustomBackend, LanguageConfig, LanguageDefinition};
+pub use detect::{ContentHints, DetectedFile, Detection, DetectionReason};
+pub use diagnostics::{Diagnostic, DiagnosticKind};
 pub use diff::{
     diff_revspec, diff_workdir, sat_sub_u64, CrateDiffStats, DiffOptions, DiffResult,
-    FileChangeType, FileDiffStats, LocsDiff, WorkdirDiffMode,
+    FileChangeType, FileDiffStats, LocsDiff, TargetDiffStats, WorkdirDiffMode,
 };
-pub use stats::{CrateStats, FileStats, Locs, ModuleStats};
+pub use generic::GenericBackend;
+pub use jvm::JvmBackend;
+pub use markdown::{FencedBlock, MarkdownBackend};
+pub use plugin::{
+    ExternalBackend, PluginDefinition, PluginRequest, PluginResponse, PLUGIN_PROTOCOL_VERSION,
+};
+pub use ruby::RubyBackend;
+pub use stats::{CrateStats, FileStats, Locs, ModuleStats, TargetStats, WorkspaceStats};
 pub use visitor::{gather_stats, gather_stats_for_path, VisitorContext};
+pub use zig::ZigBackend;
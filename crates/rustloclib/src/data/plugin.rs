@@ -0,0 +1,343 @@
+//! External-process language analyzers.
+//!
+//! A plugin is a local executable that classifies lines for a language rustloc
+//! has no backend for. It is declared next to user-defined languages:
+//!
+//! ```toml
+//! [[plugin]]
+//! name = "dsl"
+//! extensions = ["dsl"]
+//! command = "tools/dsl-loc"
+//! args = ["--strict"]
+//! timeout = 30
+//! ```
+//!
+//! rustloc runs the command once per file, writes a [`PluginRequest`] as JSON
+//! to its stdin and reads a [`PluginResponse`] from its stdout:
+//!
+//! ```text
+//! -> {"version":1,"path":"src/main.dsl","source":"; note\nrun()\n"}
+//! <- {"lines":["Comments",{"Logic":"Code"}]}
+//! ```
+//!
+//! `lines` holds one [`LineClass`] per source line, in the same JSON form the
+//! library serializes them in. A non-zero exit status, malformed JSON or a
+//! line count that does not match the source fails the analysis with the
+//! plugin's stderr in the error, and a plugin still running after its
+//! timeout is killed. See `examples/line_plugin.rs` for a reference plugin.
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::RustlocError;
+use crate::Result;
+
+use super::backend::{FileAnalysis, LanguageBackend, LanguageId, LineClass};
+use super::custom::check_name;
+use super::stats::Locs;
+
+/// Version of the request/response format sent to plugins.
+pub const PLUGIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Seconds a plugin may spend on one file when its definition sets no
+/// `timeout`.
+pub const DEFAULT_PLUGIN_TIMEOUT: u64 = 10;
+
+/// What rustloc writes to a plugin's stdin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginRequest {
+    /// [`PLUGIN_PROTOCOL_VERSION`] at the time rustloc was built.
+    pub version: u32,
+    /// Path of the file being analyzed, as rustloc sees it.
+    pub path: PathBuf,
+    /// Full file contents.
+    pub source: String,
+}
+
+/// What a plugin writes to its stdout.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginResponse {
+    /// One classification per source line.
+    pub lines: Vec<LineClass>,
+}
+
+/// A plugin declared in a configuration file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PluginDefinition {
+    /// Name used with `--lang` and reported as the file language.
+    pub name: String,
+    /// File extensions without the dot.
+    pub extensions: Vec<String>,
+    /// Exact file names.
+    pub filenames: Vec<String>,
+    /// Executable to run. Relative paths with a directory part are resolved
+    /// against the configuration file's directory; bare names use `PATH`.
+    pub command: String,
+    /// Extra arguments passed to the command.
+    pub args: Vec<String>,
+    /// Seconds the command may run per file before it is killed; defaults to
+    /// [`DEFAULT_PLUGIN_TIMEOUT`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+/// Backend that delegates line classification to a [`PluginDefinition`]'s
+/// executable.
+#[derive(Debug)]
+pub struct ExternalBackend {
+    definition: PluginDefinition,
+}
+
+impl ExternalBackend {
+    /// Validate a plugin definition.
+    pub fn new(definition: PluginDefinition) -> Result<Self> {
+        let message = if let Err(message) = check_name(&definition.name) {
+            Some(message)
+        } else if definition.command.is_empty() {
+            Some("missing command".to_string())
+        } else if definition.extensions.is_empty() && definition.filenames.is_empty() {
+            Some("needs at least one extension or filename".to_string())
+        } else if definition.timeout == Some(0) {
+            Some("timeout must be at least one second".to_string())
+        } else {
+            None
+        };
+        if let Some(message) = message {
+            return Err(RustlocError::InvalidLanguageConfig(format!(
+                "plugin '{}': {}",
+                definition.name, message
+            )));
+        }
+        Ok(Self { definition })
+    }
+
+    /// The name this plugin is selected by.
+    pub fn name(&self) -> &str {
+        &self.definition.name
+    }
+
+    /// The definition this backend was built from.
+    pub fn definition(&self) -> &PluginDefinition {
+        &self.definition
+    }
+
+    fn run(&self, path: &Path, source: &str) -> Result<PluginResponse> {
+        let failed = |message: String| RustlocError::Plugin {
+            name: self.definition.name.clone(),
+            message,
+        };
+        let request = serde_json::to_vec(&PluginRequest {
+            version: PLUGIN_PROTOCOL_VERSION,
+            path: path.to_path_buf(),
+            source: source.to_string(),
+        })
+        .map_err(|e| failed(e.to_string()))?;
+
+        let mut child = Command::new(&self.definition.command)
+            .args(&self.definition.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| failed(format!("cannot run '{}': {}", self.definition.command, e)))?;
+
+        // Feed stdin and drain stdout/stderr from separate threads so a
+        // plugin that answers before it has read all of its input cannot
+        // deadlock on a full pipe, and so the wait below can time out.
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let writer = thread::spawn(move || stdin.write_all(&request));
+        let stdout = drain(child.stdout.take().expect("stdout is piped"));
+        let stderr = drain(child.stderr.take().expect("stderr is piped"));
+
+        let timeout = self.definition.timeout.unwrap_or(DEFAULT_PLUGIN_TIMEOUT);
+        let deadline = Instant::now() + Duration::from_secs(timeout);
+        let mut pause = Duration::from_millis(1);
+        let status = loop {
+            if let Some(status) = child.try_wait().map_err(|e| failed(e.to_string()))? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(failed(format!(
+                    "timed out after {}s on {}",
+                    timeout,
+                    path.display()
+                )));
+            }
+            thread::sleep(pause);
+            pause = (pause * 2).min(Duration::from_millis(50));
+        };
+        // A plugin may legitimately exit without reading everything; only its
+        // own status and output decide success.
+        let _ = writer.join();
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+
+        if !status.success() {
+            return Err(failed(format!(
+                "exited with {}: {}",
+                status,
+                String::from_utf8_lossy(&stderr).trim()
+            )));
+        }
+        serde_json::from_slice(&stdout).map_err(|e| failed(format!("invalid response: {}", e)))
+    }
+}
+
+/// Read `pipe` to its end on a separate thread.
+fn drain(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = pipe.read_to_end(&mut bytes);
+        bytes
+    })
+}
+
+impl LanguageBackend for ExternalBackend {
+    fn supports_path(&self, path: &Path) -> bool {
+        let by_extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                self.definition
+                    .extensions
+                    .iter()
+                    .any(|candidate| ext.eq_ignore_ascii_case(candidate))
+            });
+        by_extension
+            || path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| self.definition.filenames.iter().any(|f| f == name))
+    }
+
+    fn supports_hint(&self, hint: &str) -> bool {
+        self.definition.name.eq_ignore_ascii_case(hint)
+    }
+
+    fn analyze_source(&self, path: &Path, source: &str) -> Result<FileAnalysis> {
+        let response = self.run(path, source)?;
+        let expected = source.lines().count();
+        if response.lines.len() != expected {
+            return Err(RustlocError::Plugin {
+                name: self.definition.name.clone(),
+                message: format!(
+                    "returned {} line classes for {} lines in {}",
+                    response.lines.len(),
+                    expected,
+                    path.display()
+                ),
+            });
+        }
+
+        let mut stats = Locs::new();
+        for class in &response.lines {
+            class.record(&mut stats);
+        }
+        Ok(FileAnalysis {
+            language: LanguageId::External(self.definition.name.clone()),
+            stats,
+            line_classes: response.lines,
+            parse_failure: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::backend::LogicContext;
+    use super::*;
+
+    #[test]
+    fn line_classes_use_the_library_json_form() {
+        let response: PluginResponse =
+            serde_json::from_str(r#"{"lines":["Comments",{"Logic":"Tests"},"Blanks"]}"#).unwrap();
+
+        assert_eq!(
+            response.lines,
+            vec![
+                LineClass::Comments,
+                LineClass::Logic(LogicContext::Tests),
+                LineClass::Blanks,
+            ]
+        );
+    }
+
+    #[test]
+    fn validates_definitions() {
+        let definition = PluginDefinition {
+            name: "dsl".to_string(),
+            extensions: vec!["dsl".to_string()],
+            command: "dsl-loc".to_string(),
+            ..PluginDefinition::default()
+        };
+        let backend = ExternalBackend::new(definition.clone()).unwrap();
+        assert!(backend.supports_path(Path::new("a/b.DSL")));
+        assert!(!backend.supports_path(Path::new("a/b.rs")));
+
+        for invalid in [
+            PluginDefinition {
+                command: String::new(),
+                ..definition.clone()
+            },
+            PluginDefinition {
+                name: "python".to_string(),
+                ..definition.clone()
+            },
+            PluginDefinition {
+                extensions: Vec::new(),
+                ..definition.clone()
+            },
+            PluginDefinition {
+                timeout: Some(0),
+                ..definition
+            },
+        ] {
+            assert!(ExternalBackend::new(invalid).is_err());
+        }
+    }
+
+    #[test]
+    fn a_missing_command_is_a_plugin_error() {
+        let backend = ExternalBackend::new(PluginDefinition {
+            name: "dsl".to_string(),
+            extensions: vec!["dsl".to_string()],
+            command: "rustloc-plugin-that-does-not-exist".to_string(),
+            ..PluginDefinition::default()
+        })
+        .unwrap();
+
+        let error = backend
+            .analyze_source(Path::new("a.dsl"), "x\n")
+            .unwrap_err();
+        assert!(matches!(error, RustlocError::Plugin { .. }), "{error}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_plugin_past_its_timeout_is_killed() {
+        let backend = ExternalBackend::new(PluginDefinition {
+            name: "dsl".to_string(),
+            extensions: vec!["dsl".to_string()],
+            command: "sleep".to_string(),
+            args: vec!["30".to_string()],
+            timeout: Some(1),
+            ..PluginDefinition::default()
+        })
+        .unwrap();
+
+        let started = Instant::now();
+        let error = backend
+            .analyze_source(Path::new("a.dsl"), "x\n")
+            .unwrap_err();
+        assert!(error.to_string().contains("timed out after 1s"), "{error}");
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+}
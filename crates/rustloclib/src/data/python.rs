@@ -10,7 +10,9 @@ use ruff_text_size::{Ranged, TextRange};
 
 use crate::Result;
 
-use super::backend::{FileAnalysis, LanguageBackend, LanguageId, LineClass, LogicContext};
+use super::backend::{
+    FileAnalysis, LanguageBackend, LanguageId, LineClass, LogicContext, ParseFailure,
+};
 use super::stats::Locs;
 
 /// Python backend using Ruff syntax ranges for same-file semantic classification.
@@ -24,14 +26,36 @@ impl LanguageBackend for PythonBackend {
             .is_some_and(|ext| ext.eq_ignore_ascii_case("py") || ext.eq_ignore_ascii_case("pyw"))
     }
 
+    fn supports_hint(&self, hint: &str) -> bool {
+        matches!(hint, "python" | "pypy")
+    }
+
     fn analyze_source(&self, path: &Path, source: &str) -> Result<FileAnalysis> {
-        let default_context = python_context_from_path(path);
+        self.analyze_source_in_context(path, source, python_context_from_path(path))
+    }
+
+    fn analyze_source_in_context(
+        &self,
+        _path: &Path,
+        source: &str,
+        default_context: LogicContext,
+    ) -> Result<FileAnalysis> {
         let mut line_classes = classify_python_lines(source, default_context);
 
-        if let Ok(parsed) = parse_module(source) {
-            let mut classifier = PythonSemanticClassifier::new(source, &mut line_classes);
-            classifier.visit_suite(parsed.suite(), default_context);
-        }
+        // A file Ruff cannot parse keeps the line-level classification above.
+        let parse_failure = match parse_module(source) {
+            Ok(parsed) => {
+                let mut classifier = PythonSemanticClassifier::new(source, &mut line_classes);
+                classifier.visit_suite(parsed.suite(), default_context);
+                None
+            }
+            Err(error) => Some(ParseFailure::at_offset(
+                source,
+                error.location.start().to_usize(),
+                error.error.to_string(),
+                "line-level",
+            )),
+        };
 
         let mut stats = Locs::new();
         for class in &line_classes {
@@ -42,6 +66,7 @@ impl LanguageBackend for PythonBackend {
             language: LanguageId::Python,
             stats,
             line_classes,
+            parse_failure,
         })
     }
 }
@@ -508,6 +533,22 @@ def test_bad(
         assert_eq!(stats.tests, 2);
     }
 
+    #[test]
+    fn reports_where_parsing_failed_and_the_fallback_used() {
+        let source = "import os\n\ndef broken(\n    assert True\n";
+        let analysis = PythonBackend
+            .analyze_source(Path::new("src/broken.py"), source)
+            .unwrap();
+        let failure = analysis.parse_failure.expect("a parse failure");
+        assert_eq!((failure.line, failure.column), (3, 12));
+        assert_eq!(failure.fallback, "line-level");
+
+        let analysis = PythonBackend
+            .analyze_source(Path::new("src/ok.py"), "import os\n")
+            .unwrap();
+        assert_eq!(analysis.parse_failure, None);
+    }
+
     #[test]
     fn classifies_example_paths() {
         let stats = analyze(
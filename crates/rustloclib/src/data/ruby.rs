@@ -0,0 +1,743 @@
+use std::path::Path;
+
+use crate::Result;
+
+use super::backend::{
+    generic_context_from_path, FileAnalysis, LanguageBackend, LanguageId, LineClass, LogicContext,
+};
+use super::lexer::LexedLine;
+use super::stats::Locs;
+
+/// Ruby backend with RSpec/Minitest, YARD and `=begin`/`=end` awareness.
+#[derive(Debug, Default)]
+pub struct RubyBackend;
+
+const RUBY_EXTENSIONS: &[&str] = &["rb", "rake", "gemspec", "ru"];
+const RUBY_FILENAMES: &[&str] = &[
+    "Rakefile",
+    "Gemfile",
+    "Guardfile",
+    "Vagrantfile",
+    "Podfile",
+    "Brewfile",
+    "Capfile",
+    "Berksfile",
+];
+
+/// RSpec example-group and example methods whose block is test code.
+const SPEC_BLOCKS: &[&str] = &[
+    "describe",
+    "context",
+    "it",
+    "specify",
+    "example",
+    "feature",
+    "scenario",
+    "shared_examples",
+    "shared_examples_for",
+    "shared_context",
+];
+
+/// Superclasses that make a class a Minitest/Test::Unit test case.
+const TEST_SUPERCLASSES: &[&str] = &[
+    "Minitest::Test",
+    "Minitest::Spec",
+    "Test::Unit::TestCase",
+    "ActiveSupport::TestCase",
+    "ActionDispatch::IntegrationTest",
+];
+
+/// Leading tokens of a declaration that a YARD comment block documents.
+const DEFINITIONS: &[&str] = &[
+    "def",
+    "class",
+    "module",
+    "attr_reader",
+    "attr_writer",
+    "attr_accessor",
+    "define_method",
+];
+
+impl LanguageBackend for RubyBackend {
+    fn supports_path(&self, path: &Path) -> bool {
+        let by_extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                RUBY_EXTENSIONS
+                    .iter()
+                    .any(|candidate| ext.eq_ignore_ascii_case(candidate))
+            });
+        let by_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| RUBY_FILENAMES.contains(&name));
+        by_extension || by_name
+    }
+
+    fn supports_hint(&self, hint: &str) -> bool {
+        matches!(hint, "ruby" | "jruby" | "truffleruby")
+    }
+
+    fn analyze_source(&self, path: &Path, source: &str) -> Result<FileAnalysis> {
+        self.analyze_source_in_context(path, source, generic_context_from_path(path))
+    }
+
+    fn analyze_source_in_context(
+        &self,
+        _path: &Path,
+        source: &str,
+        context: LogicContext,
+    ) -> Result<FileAnalysis> {
+        let raw: Vec<&str> = source.lines().collect();
+        let lines = lex_ruby(&raw);
+        let docs = yard_docs(&raw, &lines);
+        let tests = test_lines(&lines);
+
+        let mut stats = Locs::new();
+        let mut line_classes = Vec::with_capacity(lines.len());
+        for ((line, is_doc), is_test) in lines.iter().zip(docs).zip(tests) {
+            let class = if is_doc {
+                LineClass::Docs
+            } else if is_test {
+                line.class(LogicContext::Tests)
+            } else {
+                line.class(context)
+            };
+            class.record(&mut stats);
+            line_classes.push(class);
+        }
+
+        Ok(FileAnalysis {
+            language: LanguageId::Ruby,
+            stats,
+            line_classes,
+            parse_failure: None,
+        })
+    }
+}
+
+/// A heredoc whose body starts on the next line.
+struct Heredoc {
+    terminator: String,
+    /// `<<-` and `<<~` allow an indented terminator.
+    indented: bool,
+}
+
+/// Lex line by line, handling the line-anchored constructs first:
+/// `=begin`/`=end` comments and heredoc bodies. Literals may span lines, so a
+/// `=begin` inside one is not a comment.
+fn lex_ruby(raw: &[&str]) -> Vec<LexedLine> {
+    let mut lines = Vec::with_capacity(raw.len());
+    let mut mode = Mode::Code;
+    let mut in_block_comment = false;
+    let mut heredocs: Vec<Heredoc> = Vec::new();
+
+    for line in raw {
+        if in_block_comment {
+            in_block_comment = !is_marker(line, "=end");
+            lines.push(LexedLine {
+                has_comment: true,
+                ..LexedLine::default()
+            });
+            continue;
+        }
+        if let Some(heredoc) = heredocs.first() {
+            let candidate = if heredoc.indented { line.trim() } else { line };
+            if candidate == heredoc.terminator {
+                heredocs.remove(0);
+            }
+            lines.push(LexedLine {
+                has_code: true,
+                ..LexedLine::default()
+            });
+            continue;
+        }
+        if mode == Mode::Code && is_marker(line, "=begin") {
+            in_block_comment = true;
+            lines.push(LexedLine {
+                has_comment: true,
+                ..LexedLine::default()
+            });
+            continue;
+        }
+
+        let lexed = lex_line(line, &mut mode);
+        heredocs.extend(heredoc_openers(&lexed.code, line));
+        lines.push(lexed);
+    }
+
+    lines
+}
+
+/// Where the lexer is at the end of a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Code,
+    /// Inside a literal closed by `close`. Percent literals with paired
+    /// delimiters nest (`%w[a [b] c]`), so `open` raises `depth`.
+    Literal {
+        open: Option<char>,
+        close: char,
+        depth: usize,
+    },
+}
+
+/// Keywords after which an expression starts, so `/` opens a regex and `%` a
+/// percent literal rather than dividing.
+const VALUE_KEYWORDS: &[&str] = &[
+    "if", "unless", "elsif", "while", "until", "when", "in", "case", "and", "or", "not", "return",
+    "then", "do", "else", "yield",
+];
+
+/// Lex one line the way the shared lexer does, with Ruby's literals: quoted
+/// strings, percent literals (`%w[...]`, `%q(...)`, `%r{...}`), `?x`
+/// character literals and `/.../` regexes. Literal contents are blanked to
+/// `""` in `code`.
+fn lex_line(line: &str, mode: &mut Mode) -> LexedLine {
+    let mut lexed = LexedLine {
+        has_code: *mode != Mode::Code,
+        ..LexedLine::default()
+    };
+    let mut rest = line;
+    while let Some(ch) = rest.chars().next() {
+        let after = &rest[ch.len_utf8()..];
+        let Mode::Literal { open, close, depth } = *mode else {
+            if ch.is_whitespace() {
+                lexed.code.push(ch);
+                rest = after;
+                continue;
+            }
+            if ch == '#' {
+                lexed.has_comment = true;
+                break;
+            }
+            lexed.has_code = true;
+            let literal = match ch {
+                '"' | '\'' | '`' => Some((None, ch, after)),
+                '/' if expects_value(&lexed.code, after) => Some((None, '/', after)),
+                '%' if expects_value(&lexed.code, after) => percent_literal(after),
+                _ => None,
+            };
+            if let Some((open, close, body)) = literal {
+                lexed.code.push('"');
+                *mode = Mode::Literal {
+                    open,
+                    close,
+                    depth: 1,
+                };
+                rest = body;
+                continue;
+            }
+            rest = match (ch, after.chars().next()) {
+                // `?a`, `?'`, `?\n`: a one-character string.
+                ('?', Some(next)) if !next.is_whitespace() && expects_value(&lexed.code, after) => {
+                    let len = match next {
+                        '\\' => 1 + after[1..].chars().next().map_or(0, char::len_utf8),
+                        _ => next.len_utf8(),
+                    };
+                    if after[len..].starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+                        lexed.code.push(ch);
+                        after
+                    } else {
+                        lexed.code.push_str("\"\"");
+                        &after[len..]
+                    }
+                }
+                // `$'`, `$"` and the other punctuation globals.
+                ('$', Some(next)) if !(next.is_alphanumeric() || next == '_') => {
+                    lexed.code.push(ch);
+                    lexed.code.push(next);
+                    &after[next.len_utf8()..]
+                }
+                _ => {
+                    lexed.code.push(ch);
+                    after
+                }
+            };
+            continue;
+        };
+
+        lexed.has_code = true;
+        if ch == '\\' {
+            rest = after
+                .get(after.chars().next().map_or(0, char::len_utf8)..)
+                .unwrap_or("");
+            continue;
+        }
+        if ch == close {
+            if depth == 1 {
+                lexed.code.push('"');
+                *mode = Mode::Code;
+            } else {
+                *mode = Mode::Literal {
+                    open,
+                    close,
+                    depth: depth - 1,
+                };
+            }
+        } else if Some(ch) == open {
+            *mode = Mode::Literal {
+                open,
+                close,
+                depth: depth + 1,
+            };
+        }
+        rest = after;
+    }
+    lexed
+}
+
+/// Whether an expression may start here, given the line's code so far and the
+/// text after the `/`, `%` or `?` in question. After an identifier that is not
+/// a keyword, only `foo /x/` (space before, none after) starts one, as in Ruby.
+fn expects_value(code: &str, after: &str) -> bool {
+    let before = code.trim_end();
+    let Some(last) = before.chars().next_back() else {
+        return true;
+    };
+    if matches!(last, ')' | ']' | '}' | '"') {
+        return false;
+    }
+    if !(last.is_alphanumeric() || matches!(last, '_' | '?' | '!')) {
+        return true;
+    }
+    let word = &before[before
+        .rfind(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '?' | '!')))
+        .map_or(0, |index| index + 1)..];
+    if VALUE_KEYWORDS.contains(&word) {
+        return true;
+    }
+    !word.starts_with(|c: char| c.is_ascii_digit())
+        && before.len() < code.len()
+        && !after.starts_with(char::is_whitespace)
+}
+
+/// The delimiters and body of a percent literal, given the text after `%`:
+/// an optional type letter, then a bracket that pairs with its closer or any
+/// other punctuation that closes itself.
+fn percent_literal(after: &str) -> Option<(Option<char>, char, &str)> {
+    let body = after
+        .strip_prefix(['q', 'Q', 'w', 'W', 'i', 'I', 'r', 's', 'x'])
+        .unwrap_or(after);
+    let delimiter = body.chars().next()?;
+    // `%=` is modulo assignment.
+    if delimiter.is_alphanumeric() || delimiter.is_whitespace() || delimiter == '=' {
+        return None;
+    }
+    let close = match delimiter {
+        '(' => ')',
+        '[' => ']',
+        '{' => '}',
+        '<' => '>',
+        other => other,
+    };
+    let open = (close != delimiter).then_some(delimiter);
+    Some((open, close, &body[delimiter.len_utf8()..]))
+}
+
+/// `=begin`/`=end` only count at the very start of a line.
+fn is_marker(line: &str, marker: &str) -> bool {
+    line.strip_prefix(marker)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+}
+
+/// Heredocs opened on a line: `<<~ID`, `<<-ID`, `<<ID` and their quoted forms.
+///
+/// Openers are looked for in the line's `code`, so a `<<ID` inside a string or
+/// comment opens nothing. The lexer blanks a quoted identifier to `""`; the
+/// `n`th quoted opener in `code` takes its identifier from the `n`th one in the
+/// `raw` line.
+fn heredoc_openers(code: &str, raw: &str) -> Vec<Heredoc> {
+    let mut quoted_ids = openers(raw).filter_map(|(_, quoted, id)| quoted.then_some(id));
+    openers(code)
+        .filter_map(|(indented, quoted, id)| {
+            let id = if quoted { quoted_ids.next()? } else { id };
+            // A bare `<<ID` must look like a heredoc (`ID` upper-case) so that
+            // `list <<value` style appends are not mistaken for one.
+            let plausible = quoted || indented || id.starts_with(|c: char| c.is_ascii_uppercase());
+            (!id.is_empty() && plausible).then(|| Heredoc {
+                terminator: id.to_string(),
+                indented,
+            })
+        })
+        .collect()
+}
+
+/// Each `<<` in `text` that could open a heredoc, as `(indented, quoted, id)`;
+/// a quoted identifier must be closed by the same quote.
+fn openers(text: &str) -> impl Iterator<Item = (bool, bool, &str)> {
+    text.match_indices("<<").filter_map(|(start, _)| {
+        let rest = &text[start + 2..];
+        let indented = rest.starts_with(['~', '-']);
+        let body = if indented { &rest[1..] } else { rest };
+        let (quote, body) = match body.chars().next() {
+            Some(quote @ ('\'' | '"' | '`')) => (Some(quote), &body[1..]),
+            _ => (None, body),
+        };
+        let end = body
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(body.len());
+        let closed = quote.is_none_or(|quote| body[end..].starts_with(quote));
+        closed.then_some((indented, quote.is_some(), &body[..end]))
+    })
+}
+
+/// Mark YARD doc blocks: runs of `#` comment lines directly above a definition.
+fn yard_docs(raw: &[&str], lines: &[LexedLine]) -> Vec<bool> {
+    let comment_only = |index: usize| {
+        let line = &lines[index];
+        line.has_comment && !line.has_code && raw[index].trim_start().starts_with('#')
+    };
+
+    let mut docs = vec![false; lines.len()];
+    let mut index = 0;
+    while index < lines.len() {
+        if !comment_only(index) {
+            index += 1;
+            continue;
+        }
+        let start = index;
+        while index < lines.len() && comment_only(index) {
+            index += 1;
+        }
+        if index < lines.len() && is_definition(&lines[index].code) {
+            for (offset, doc) in docs[start..index].iter_mut().enumerate() {
+                *doc = !is_directive(raw[start + offset]);
+            }
+        }
+    }
+    docs
+}
+
+fn is_definition(code: &str) -> bool {
+    let mut tokens = code.split_whitespace();
+    let first = tokens.next().unwrap_or_default();
+    let first = if matches!(
+        first,
+        "private" | "protected" | "public" | "module_function"
+    ) {
+        tokens.next().unwrap_or_default()
+    } else {
+        first
+    };
+    DEFINITIONS.contains(&first.trim_end_matches(['(', ':']))
+}
+
+/// Magic comments and tool directives are not documentation.
+fn is_directive(line: &str) -> bool {
+    let comment = line.trim_start();
+    comment.starts_with("#!")
+        || ["frozen_string_literal:", "encoding:", "rubocop:"]
+            .iter()
+            .any(|directive| {
+                comment
+                    .trim_start_matches(['#', ' '])
+                    .starts_with(directive)
+            })
+}
+
+/// Mark RSpec example blocks and Minitest test classes, tracking `do`/`end`,
+/// keyword blocks and braces to find where each one ends.
+fn test_lines(lines: &[LexedLine]) -> Vec<bool> {
+    let mut tests = vec![false; lines.len()];
+    let mut depth = 0usize;
+    // (start line, depth the block closes back to)
+    let mut open_region: Option<(usize, usize)> = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        let code = line.code.as_str();
+        let mut opener: Option<usize> = None;
+        let mut statement_start = true;
+        let mut loop_opened = false;
+        let mut offset = 0;
+
+        while let Some(ch) = code[offset..].chars().next() {
+            if !(ch.is_alphabetic() || ch == '_') {
+                match ch {
+                    '{' => depth += 1,
+                    '}' => close(&mut depth, &mut open_region, &mut tests, index),
+                    _ => {}
+                }
+                if !ch.is_whitespace() {
+                    statement_start = matches!(ch, ';' | '=' | '(' | '{');
+                }
+                offset += ch.len_utf8();
+                continue;
+            }
+
+            let end = code[offset..]
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '?' || c == '!'))
+                .map_or(code.len(), |len| offset + len);
+            let ident = &code[offset..end];
+            let before = code[..offset].chars().next_back();
+            let after = &code[end..];
+            offset = end;
+
+            // Method calls (`range.end`), symbols (`:end`) and hash keys
+            // (`if: :ready?`) are not keywords.
+            let hash_key = after.starts_with(':') && !after.starts_with("::");
+            let keyword = !matches!(before, Some('.' | ':')) && !hash_key;
+            if !keyword {
+                statement_start = false;
+                continue;
+            }
+
+            if statement_start && open_region.is_none() && opener.is_none() {
+                let spec = SPEC_BLOCKS.contains(&ident)
+                    && !after.trim_start().starts_with(['=', '.', ','])
+                    || ident == "RSpec" && after.starts_with(".describe");
+                let test_case = ident == "class"
+                    && after
+                        .split_once('<')
+                        .is_some_and(|(_, parent)| TEST_SUPERCLASSES.contains(&parent.trim()));
+                if spec || test_case {
+                    opener = Some(depth);
+                }
+            }
+
+            let mut next_statement = false;
+            match ident {
+                "class" | "module" | "case" | "begin" => depth += 1,
+                "def" if !is_endless_def(after) => depth += 1,
+                "if" | "unless" | "while" | "until" if statement_start => {
+                    depth += 1;
+                    loop_opened |= matches!(ident, "while" | "until");
+                }
+                "for" => {
+                    depth += 1;
+                    loop_opened = true;
+                }
+                // `while x do` shares the loop's `end`.
+                "do" if loop_opened => loop_opened = false,
+                "do" => {
+                    depth += 1;
+                    next_statement = true;
+                }
+                "end" => close(&mut depth, &mut open_region, &mut tests, index),
+                "then" | "else" | "elsif" | "rescue" | "ensure" => next_statement = true,
+                // `RSpec.describe` continues the statement.
+                "RSpec" => next_statement = statement_start,
+                _ => {}
+            }
+            statement_start = next_statement;
+        }
+
+        if let Some(opener_depth) = opener {
+            if depth > opener_depth {
+                open_region = Some((index, opener_depth));
+            } else {
+                tests[index] = true;
+            }
+        }
+    }
+
+    if let Some((start, _)) = open_region {
+        tests[start..].fill(true);
+    }
+
+    tests
+}
+
+fn close(
+    depth: &mut usize,
+    open_region: &mut Option<(usize, usize)>,
+    tests: &mut [bool],
+    index: usize,
+) {
+    *depth = depth.saturating_sub(1);
+    if let Some((start, region_depth)) = *open_region {
+        if *depth == region_depth {
+            tests[start..=index].fill(true);
+            *open_region = None;
+        }
+    }
+}
+
+/// `def name(args) = expr` has no `end`.
+fn is_endless_def(after: &str) -> bool {
+    let rest = after.trim_start();
+    let name_end = rest
+        .find(|c: char| c.is_whitespace() || c == '(')
+        .unwrap_or(rest.len());
+    let mut rest = &rest[name_end..];
+    if rest.starts_with('(') {
+        let mut parens = 0usize;
+        for (offset, ch) in rest.char_indices() {
+            match ch {
+                '(' => parens += 1,
+                ')' => {
+                    parens -= 1;
+                    if parens == 0 {
+                        rest = &rest[offset + 1..];
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    let rest = rest.trim_start();
+    rest.starts_with('=') && !rest.starts_with("==")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analyze(path: &str, source: &str) -> FileAnalysis {
+        RubyBackend.analyze_source(Path::new(path), source).unwrap()
+    }
+
+    #[test]
+    fn yard_docs_block_comments_and_heredocs() {
+        let analysis = analyze(
+            "lib/greeter.rb",
+            r#"# frozen_string_literal: true
+
+=begin
+Long-form notes.
+=end
+
+# Greets people.
+# @param name [String]
+def greet(name)
+  # a plain comment
+  <<~TEXT
+    # not a comment
+    Hello, #{name}!
+  TEXT
+end
+
+def shout = greet("x").upcase # endless
+"#,
+        );
+
+        assert_eq!(analysis.language, LanguageId::Ruby);
+        assert_eq!(analysis.stats.docs, 2);
+        assert_eq!(analysis.stats.comments, 5);
+        assert_eq!(analysis.stats.code, 7);
+        assert_eq!(analysis.stats.blanks, 3);
+        assert_eq!(analysis.stats.tests, 0);
+    }
+
+    #[test]
+    fn strings_span_lines_and_heredocs_open_only_in_code() {
+        let analysis = analyze(
+            "lib/query.rb",
+            r#"USAGE = "usage:
+# not a comment
+=begin
+  run it"
+note = "append with <<END" # or <<~DONE
+sql = <<~'SQL'
+  # not a comment
+SQL
+$stdout.puts $' if $"
+"#,
+        );
+
+        assert_eq!(analysis.stats.comments, 0);
+        assert_eq!(analysis.stats.code, 9);
+    }
+
+    #[test]
+    fn rspec_blocks_are_tests() {
+        let analysis = analyze(
+            "lib/calculator.rb",
+            r#"class Calculator
+  def add(a, b)
+    if a.nil? then 0 else a + b end
+  end
+end
+
+RSpec.describe Calculator do
+  let(:calc) { Calculator.new }
+
+  context "with numbers" do
+    it "adds" do
+      expect(calc.add(1, 2)).to eq(3)
+    end
+  end
+
+  it "is pending"
+end
+
+puts Calculator.new.add(1, 2)
+"#,
+        );
+
+        assert_eq!(analysis.stats.code, 6);
+        assert_eq!(analysis.stats.tests, 9);
+        assert_eq!(analysis.stats.blanks, 4);
+    }
+
+    /// Each literal holds an apostrophe that, lexed as a quote, would open a
+    /// string swallowing the rest of the file.
+    #[test]
+    fn percent_character_and_regex_literals_hold_their_quotes() {
+        for literal in [
+            "%w[it's fine]",
+            "%q(it's (nested) fine)",
+            "%i{it's}",
+            "%r{it's}i",
+            "%<it's>",
+            "%|it's|",
+            "?'",
+            "/it's/",
+            "line.match?(/it's/)",
+            "x if /it's/ =~ line",
+        ] {
+            let source = format!(
+                "WORDS = {literal}\n\n# Docs here.\ndef words\n  WORDS\nend\n\nRSpec.describe do\n  it \"works\" do\n    expect(words).to be\n  end\nend\n"
+            );
+            let analysis = analyze("lib/words.rb", &source);
+            assert_eq!(analysis.stats.docs, 1, "{literal}");
+            assert_eq!(analysis.stats.code, 4, "{literal}");
+            assert_eq!(analysis.stats.tests, 5, "{literal}");
+            assert_eq!(analysis.stats.blanks, 2, "{literal}");
+        }
+    }
+
+    #[test]
+    fn percent_literals_span_lines_and_division_is_not_a_regex() {
+        let analysis = analyze(
+            "lib/math.rb",
+            "LIST = %w[\n  a's\n  # not a comment\n]\nhalf = total / 2 # it's half\nrest = total % 3 # it's the rest\nratio = a/b\n",
+        );
+
+        assert_eq!(analysis.stats.code, 7);
+        assert_eq!(analysis.stats.comments, 0);
+    }
+
+    #[test]
+    fn minitest_classes_are_tests() {
+        let analysis = analyze(
+            "lib/widget.rb",
+            r#"class Widget
+  def size = 3
+end
+
+class WidgetTest < Minitest::Test
+  def test_size
+    while false do
+    end
+    assert_equal 3, Widget.new.size
+  end
+end
+"#,
+        );
+
+        assert_eq!(analysis.stats.code, 3);
+        assert_eq!(analysis.stats.tests, 7);
+    }
+
+    #[test]
+    fn spec_and_test_suites_count_as_tests() {
+        let analysis = analyze("spec/models/user_spec.rb", "require \"user\"\n");
+        assert_eq!(analysis.stats.tests, 1);
+
+        let analysis = analyze("test/user_test.rb", "require \"user\"\n");
+        assert_eq!(analysis.stats.tests, 1);
+    }
+}
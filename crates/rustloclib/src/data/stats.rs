@@ -1,7 +1,7 @@
 //! Core data structures for LOC statistics.
 //!
 //! This module provides the fundamental types for representing line counts
-//! in Rust source files. The design uses a single flat structure with 6 line types:
+//! in source files. The design uses a single flat structure with 8 line types:
 //!
 //! - **code**: Logic lines in production code (src/, not in tests)
 //! - **tests**: Logic lines in test code (#[test], #[cfg(test)], tests/)
@@ -9,25 +9,39 @@
 //! - **docs**: Documentation comments (///, //!, /** */, /*! */) - anywhere
 //! - **comments**: Regular comments (//, /* */) - anywhere
 //! - **blanks**: Blank/whitespace-only lines - anywhere
+//! - **preprocessor**: Preprocessor directives (`#include`, `#define`, ...) - anywhere
+//! - **config**: Whole config and data files (TOML, YAML, JSON, INI, SQL), and
+//!   configuration embedded in other files (Markdown front matter) - anywhere
+//!
+//! Classification rules can add categories of their own (`fixtures`,
+//! `benchmarks`), which take the production code lines of the files they
+//! match.
 //!
 //! The key insight: only actual code lines need context (code/tests/examples),
 //! because that's the meaningful distinction. A blank is a blank, a comment is
 //! a comment - where they appear doesn't matter for most analysis.
 
 use crate::query::options::LineTypes;
+use crate::source::workspace::TargetKind;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::ops::{Add, AddAssign, Sub, SubAssign};
 use std::path::PathBuf;
 
-/// Lines of code counts with 7 line types.
+use super::content::SourceEncoding;
+use super::detect::Detection;
+
+/// Lines of code counts with 9 line types.
 ///
 /// This is the fundamental unit of measurement in rustloc. Each field counts
 /// a specific type of line:
 ///
 /// - `code`, `tests`, `examples`: Actual executable/logic lines, distinguished by context
-/// - `docs`, `comments`, `blanks`: Metadata lines, counted regardless of location
+/// - `docs`, `comments`, `blanks`, `preprocessor`, `config`: Non-logic lines,
+///   counted regardless of location
+/// - `categories`: Logic lines of user-defined categories, by name
 /// - `total`: Precomputed sum of all line types (total line count)
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Locs {
     /// Logic lines in production code (src/, not in test blocks)
     pub code: u64,
@@ -41,6 +55,16 @@ pub struct Locs {
     pub comments: u64,
     /// Blank lines (whitespace only)
     pub blanks: u64,
+    /// Preprocessor directive lines (C/C++ `#include`, `#define`, ...)
+    pub preprocessor: u64,
+    /// Configuration lines: the non-comment, non-blank lines of whole config
+    /// and data files (TOML, YAML, JSON, INI, SQL), plus configuration
+    /// embedded in other files (Markdown front matter)
+    pub config: u64,
+    /// Logic lines of the categories classification rules define, such as
+    /// `fixtures`, by category name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub categories: BTreeMap<String, u64>,
     /// Total line count (sum of all types)
     pub total: u64,
 }
@@ -56,16 +80,37 @@ impl Locs {
         self.total
     }
 
-    /// Total logic lines (code + tests + examples).
+    /// Total logic lines (code + tests + examples + categories).
     pub fn total_logic(&self) -> u64 {
-        self.code + self.tests + self.examples
+        self.code + self.tests + self.examples + self.categories.values().sum::<u64>()
+    }
+
+    /// Logic lines of the user-defined `category`.
+    pub fn category(&self, category: &str) -> u64 {
+        self.categories.get(category).copied().unwrap_or(0)
+    }
+
+    /// Move the production code lines into the user-defined `category`, as
+    /// for a file a classification rule assigns to it.
+    pub fn move_code_to_category(&mut self, category: &str) {
+        let code = std::mem::take(&mut self.code);
+        if code > 0 {
+            *self.categories.entry(category.to_string()).or_default() += code;
+        }
     }
 
     /// Recompute the `total` field from individual line types.
     /// Call this after manually setting individual fields.
     pub fn recompute_total(&mut self) {
-        self.total =
-            self.code + self.tests + self.examples + self.docs + self.comments + self.blanks;
+        self.total = self.code
+            + self.tests
+            + self.examples
+            + self.docs
+            + self.comments
+            + self.blanks
+            + self.preprocessor
+            + self.config
+            + self.categories.values().sum::<u64>();
     }
 
     /// Return a filtered copy with only the specified line types included.
@@ -78,6 +123,17 @@ impl Locs {
             docs: if types.docs { self.docs } else { 0 },
             comments: if types.comments { self.comments } else { 0 },
             blanks: if types.blanks { self.blanks } else { 0 },
+            preprocessor: if types.preprocessor {
+                self.preprocessor
+            } else {
+                0
+            },
+            config: if types.config { self.config } else { 0 },
+            categories: if types.categories {
+                self.categories.clone()
+            } else {
+                BTreeMap::new()
+            },
             total: self.total, // Always preserved
         }
     }
@@ -86,27 +142,31 @@ impl Locs {
 impl Add for Locs {
     type Output = Self;
 
-    fn add(self, other: Self) -> Self {
-        Self {
-            code: self.code + other.code,
-            tests: self.tests + other.tests,
-            examples: self.examples + other.examples,
-            docs: self.docs + other.docs,
-            comments: self.comments + other.comments,
-            blanks: self.blanks + other.blanks,
-            total: self.total + other.total,
-        }
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
     }
 }
 
 impl AddAssign for Locs {
     fn add_assign(&mut self, other: Self) {
+        *self += &other;
+    }
+}
+
+impl AddAssign<&Locs> for Locs {
+    fn add_assign(&mut self, other: &Locs) {
         self.code += other.code;
         self.tests += other.tests;
         self.examples += other.examples;
         self.docs += other.docs;
         self.comments += other.comments;
         self.blanks += other.blanks;
+        self.preprocessor += other.preprocessor;
+        self.config += other.config;
+        for (category, lines) in &other.categories {
+            *self.categories.entry(category.clone()).or_default() += lines;
+        }
         self.total += other.total;
     }
 }
@@ -114,16 +174,9 @@ impl AddAssign for Locs {
 impl Sub for Locs {
     type Output = Self;
 
-    fn sub(self, other: Self) -> Self {
-        Self {
-            code: self.code.saturating_sub(other.code),
-            tests: self.tests.saturating_sub(other.tests),
-            examples: self.examples.saturating_sub(other.examples),
-            docs: self.docs.saturating_sub(other.docs),
-            comments: self.comments.saturating_sub(other.comments),
-            blanks: self.blanks.saturating_sub(other.blanks),
-            total: self.total.saturating_sub(other.total),
-        }
+    fn sub(mut self, other: Self) -> Self {
+        self -= other;
+        self
     }
 }
 
@@ -135,6 +188,13 @@ impl SubAssign for Locs {
         self.docs = self.docs.saturating_sub(other.docs);
         self.comments = self.comments.saturating_sub(other.comments);
         self.blanks = self.blanks.saturating_sub(other.blanks);
+        self.preprocessor = self.preprocessor.saturating_sub(other.preprocessor);
+        self.config = self.config.saturating_sub(other.config);
+        for (category, lines) in &other.categories {
+            if let Some(own) = self.categories.get_mut(category) {
+                *own = own.saturating_sub(*lines);
+            }
+        }
         self.total = self.total.saturating_sub(other.total);
     }
 }
@@ -146,12 +206,35 @@ pub struct FileStats {
     pub path: PathBuf,
     /// LOC statistics for this file.
     pub stats: Locs,
+    /// Backend that analyzed the file and why it was chosen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detection: Option<Detection>,
+    /// Encoding the file was decoded from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<SourceEncoding>,
 }
 
 impl FileStats {
     /// Create new file stats.
     pub fn new(path: PathBuf, stats: Locs) -> Self {
-        Self { path, stats }
+        Self {
+            path,
+            stats,
+            detection: None,
+            encoding: None,
+        }
+    }
+
+    /// Record how the file was routed to its backend.
+    pub fn with_detection(mut self, detection: Detection) -> Self {
+        self.detection = Some(detection);
+        self
+    }
+
+    /// Record the encoding the file was decoded from.
+    pub fn with_encoding(mut self, encoding: SourceEncoding) -> Self {
+        self.encoding = Some(encoding);
+        self
     }
 
     /// Return a filtered copy with only the specified line types included.
@@ -159,6 +242,8 @@ impl FileStats {
         Self {
             path: self.path.clone(),
             stats: self.stats.filter(types),
+            detection: self.detection.clone(),
+            encoding: self.encoding,
         }
     }
 }
@@ -204,6 +289,79 @@ impl ModuleStats {
     }
 }
 
+/// Statistics for a Cargo build target (a library, binary, test, example,
+/// bench or build script).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetStats {
+    /// Row label, e.g. `rustloc (bin rustloc)` (see [`TargetInfo::label`]).
+    ///
+    /// [`TargetInfo::label`]: crate::source::workspace::TargetInfo::label
+    pub name: String,
+    /// Target kind, or `None` for the crate's files no target claims.
+    pub kind: Option<TargetKind>,
+    /// Root source file of the target (the crate root for unclaimed files).
+    pub path: PathBuf,
+    /// Aggregated LOC statistics.
+    pub stats: Locs,
+    /// Files belonging to this target.
+    pub files: Vec<PathBuf>,
+}
+
+impl TargetStats {
+    /// Create new target stats.
+    pub fn new(name: String, kind: Option<TargetKind>, path: PathBuf) -> Self {
+        Self {
+            name,
+            kind,
+            path,
+            stats: Locs::new(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Add stats from a file to this target.
+    pub fn add_file(&mut self, path: PathBuf, stats: Locs) {
+        self.stats += stats;
+        self.files.push(path);
+    }
+
+    /// Return a filtered copy with only the specified line types included.
+    pub fn filter(&self, types: LineTypes) -> Self {
+        Self {
+            name: self.name.clone(),
+            kind: self.kind,
+            path: self.path.clone(),
+            stats: self.stats.filter(types),
+            files: self.files.clone(),
+        }
+    }
+}
+
+/// Statistics for one Cargo workspace when several are counted together.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceStats {
+    /// Workspace root relative to the counted directory (`.` for itself).
+    pub name: String,
+    /// Root path of the workspace.
+    pub path: PathBuf,
+    /// Aggregated LOC statistics.
+    pub stats: Locs,
+    /// Names of the crates counted in this workspace.
+    pub crates: Vec<String>,
+}
+
+impl WorkspaceStats {
+    /// Return a filtered copy with only the specified line types included.
+    pub fn filter(&self, types: LineTypes) -> Self {
+        Self {
+            name: self.name.clone(),
+            path: self.path.clone(),
+            stats: self.stats.filter(types),
+            crates: self.crates.clone(),
+        }
+    }
+}
+
 /// Statistics for a crate within a workspace.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CrateStats {
@@ -230,7 +388,7 @@ impl CrateStats {
 
     /// Add file stats to this crate.
     pub fn add_file(&mut self, file_stats: FileStats) {
-        self.stats += file_stats.stats;
+        self.stats += &file_stats.stats;
         self.files.push(file_stats);
     }
 
@@ -271,6 +429,9 @@ mod tests {
             docs: 30,
             comments: 10,
             blanks: 15,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 225,
         };
         assert_eq!(locs.total(), 225);
@@ -286,6 +447,9 @@ mod tests {
             docs: 30,
             comments: 10,
             blanks: 15,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 225,
         };
         let b = Locs {
@@ -295,6 +459,9 @@ mod tests {
             docs: 15,
             comments: 5,
             blanks: 10,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 115,
         };
         let sum = a + b;
@@ -316,6 +483,9 @@ mod tests {
             docs: 30,
             comments: 10,
             blanks: 15,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 225,
         };
 
@@ -346,6 +516,9 @@ mod tests {
             docs: 30,
             comments: 10,
             blanks: 15,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 0, // Intentionally wrong
         };
         locs.recompute_total();
@@ -361,6 +534,9 @@ mod tests {
             docs: 3,
             comments: 1,
             blanks: 4,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 25,
         };
         a += Locs {
@@ -370,6 +546,9 @@ mod tests {
             docs: 4,
             comments: 5,
             blanks: 6,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 21,
         };
         assert_eq!(a.code, 11);
@@ -390,6 +569,9 @@ mod tests {
             docs: 30,
             comments: 10,
             blanks: 15,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 225,
         };
         let b = Locs {
@@ -399,6 +581,9 @@ mod tests {
             docs: 10,
             comments: 4,
             blanks: 5,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 74,
         };
         let diff = a - b;
@@ -423,6 +608,9 @@ mod tests {
             docs: 1,
             comments: 1,
             blanks: 1,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 6,
         };
         let large = Locs {
@@ -432,6 +620,9 @@ mod tests {
             docs: 100,
             comments: 100,
             blanks: 100,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 600,
         };
         let diff = small - large;
@@ -447,6 +638,9 @@ mod tests {
             docs: 0,
             comments: 2,
             blanks: 0,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 10,
         };
         a -= Locs {
@@ -456,6 +650,9 @@ mod tests {
             docs: 7, // larger than a.docs -> clamps to 0
             comments: 2,
             blanks: 0,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 20, // larger than a.total -> clamps to 0
         };
         assert_eq!(a.code, 0);
@@ -480,6 +677,9 @@ mod tests {
             docs: 1,
             comments: 2,
             blanks: 3,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 30,
         };
         let filtered = locs.filter(LineTypes::new());
@@ -501,6 +701,9 @@ mod tests {
             docs: 2,
             comments: 1,
             blanks: 3,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 21,
         };
         let fs = FileStats::new(PathBuf::from("src/lib.rs"), locs);
@@ -524,6 +727,9 @@ mod tests {
             docs: 0,
             comments: 0,
             blanks: 2,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 12,
         };
         let b = Locs {
@@ -533,6 +739,9 @@ mod tests {
             docs: 1,
             comments: 0,
             blanks: 0,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: 10,
         };
         module.add_file(PathBuf::from("foo/a.rs"), a);
@@ -563,6 +772,9 @@ mod tests {
                 docs: 5,
                 comments: 0,
                 blanks: 0,
+                preprocessor: 0,
+                config: 0,
+                categories: Default::default(),
                 total: 25,
             },
         );
@@ -575,6 +787,9 @@ mod tests {
                 docs: 0,
                 comments: 0,
                 blanks: 0,
+                preprocessor: 0,
+                config: 0,
+                categories: Default::default(),
                 total: 30,
             },
         );
@@ -605,6 +820,9 @@ mod tests {
                 docs: 5,
                 comments: 0,
                 blanks: 0,
+                preprocessor: 0,
+                config: 0,
+                categories: Default::default(),
                 total: 32,
             },
         ));
@@ -22,8 +22,20 @@ impl LanguageBackend for TypeScriptBackend {
             .is_some_and(|ext| ext.eq_ignore_ascii_case("ts") || ext.eq_ignore_ascii_case("tsx"))
     }
 
+    fn supports_hint(&self, hint: &str) -> bool {
+        matches!(hint, "typescript" | "ts-node" | "tsx" | "deno")
+    }
+
     fn analyze_source(&self, path: &Path, source: &str) -> Result<FileAnalysis> {
-        let context = generic_context_from_path(path);
+        self.analyze_source_in_context(path, source, generic_context_from_path(path))
+    }
+
+    fn analyze_source_in_context(
+        &self,
+        path: &Path,
+        source: &str,
+        context: LogicContext,
+    ) -> Result<FileAnalysis> {
         let line_classes = classify_typescript_lines(path, source, context);
 
         let mut stats = Locs::new();
@@ -35,6 +47,7 @@ impl LanguageBackend for TypeScriptBackend {
             language: LanguageId::TypeScript,
             stats,
             line_classes,
+            parse_failure: None,
         })
     }
 }
@@ -28,6 +28,7 @@ use crate::error::RustlocError;
 use crate::Result;
 
 use super::backend::{LineClass, LogicContext};
+use super::content::read_source;
 use super::stats::Locs;
 
 /// Backwards-compatible name for the logic context used by the Rust visitor.
@@ -473,7 +474,17 @@ impl<T: Read> Visitor<T> {
 /// assert_eq!(stats.code, 3);
 /// ```
 pub fn gather_stats_for_path(path: impl AsRef<Path>) -> Result<Locs> {
-    Ok(gather_analysis_for_path(path)?.stats)
+    let path = path.as_ref();
+    match read_source(path, None)? {
+        Ok(source) => Ok(gather_stats(
+            &source.text,
+            VisitorContext::from_file_path(path),
+        )),
+        Err(skipped) => Err(RustlocError::SkippedFile {
+            path: skipped.path,
+            reason: skipped.reason,
+        }),
+    }
 }
 
 /// Gather LOC statistics from a string of Rust source code.
@@ -503,18 +514,6 @@ pub fn gather_stats(source: &str, context: VisitorContext) -> Locs {
     gather_analysis(source, context).stats
 }
 
-pub(crate) fn gather_analysis_for_path(
-    path: impl AsRef<Path>,
-) -> Result<super::backend::FileAnalysis> {
-    let visitor = Visitor::new(path)?;
-    let (stats, line_classes) = visitor.visit_file_analysis();
-    Ok(super::backend::FileAnalysis {
-        language: super::backend::LanguageId::Rust,
-        stats,
-        line_classes,
-    })
-}
-
 pub(crate) fn gather_analysis(
     source: &str,
     context: VisitorContext,
@@ -525,6 +524,7 @@ pub(crate) fn gather_analysis(
         language: super::backend::LanguageId::Rust,
         stats,
         line_classes,
+        parse_failure: None,
     }
 }
 
@@ -842,4 +842,21 @@ fn foo() {
         assert_eq!(test_stats.blanks, 1);
         assert_eq!(example_stats.blanks, 1);
     }
+
+    #[test]
+    fn stats_for_path_transcode_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wide.rs");
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(
+            "// café\nfn a() {}\n"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+        std::fs::write(&path, bytes).unwrap();
+
+        let stats = gather_stats_for_path(&path).unwrap();
+        assert_eq!(stats.code, 1);
+        assert_eq!(stats.comments, 1);
+    }
 }
@@ -0,0 +1,229 @@
+use std::path::Path;
+
+use crate::Result;
+
+use super::backend::{
+    generic_context_from_path, FileAnalysis, LanguageBackend, LanguageId, LogicContext,
+};
+use super::lexer::{brace_regions, lex, LexSpec, StringDelim};
+use super::stats::Locs;
+
+/// Zig backend with inline `test` block awareness.
+#[derive(Debug, Default)]
+pub struct ZigBackend;
+
+const ZIG: LexSpec = LexSpec {
+    doc_line_comments: &["///", "//!"],
+    line_comments: &["//"],
+    block_comments: &[],
+    // `\\` multiline string lines are blanked before lexing; see
+    // `blank_multiline_strings`.
+    strings: &[
+        StringDelim {
+            open: "\"",
+            close: "\"",
+            escape: Some('\\'),
+            multiline: false,
+        },
+        StringDelim {
+            open: "'",
+            close: "'",
+            escape: Some('\\'),
+            multiline: false,
+        },
+    ],
+    raw_strings: false,
+    code_prefixes: &[],
+};
+
+impl LanguageBackend for ZigBackend {
+    fn supports_path(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("zig"))
+    }
+
+    fn supports_hint(&self, hint: &str) -> bool {
+        hint == "zig"
+    }
+
+    fn analyze_source(&self, path: &Path, source: &str) -> Result<FileAnalysis> {
+        self.analyze_source_in_context(path, source, generic_context_from_path(path))
+    }
+
+    fn analyze_source_in_context(
+        &self,
+        _path: &Path,
+        source: &str,
+        context: LogicContext,
+    ) -> Result<FileAnalysis> {
+        let lines = lex(&blank_multiline_strings(source), &ZIG);
+        let tests = brace_regions(lines.iter().map(|line| line.code.as_str()), is_test_decl);
+
+        let mut stats = Locs::new();
+        let mut line_classes = Vec::with_capacity(lines.len());
+        for (line, is_test) in lines.iter().zip(tests) {
+            let context = if is_test {
+                LogicContext::Tests
+            } else {
+                context
+            };
+            let class = line.class(context);
+            class.record(&mut stats);
+            line_classes.push(class);
+        }
+
+        Ok(FileAnalysis {
+            language: LanguageId::Zig,
+            stats,
+            line_classes,
+            parse_failure: None,
+        })
+    }
+}
+
+/// `source` with every `\\` multiline string line blanked to `""`, one line
+/// per source line.
+///
+/// A `\\` outside a `"`/`'` literal or comment starts a string that runs to
+/// the end of its line, and consecutive such lines form one literal. What
+/// follows it is string content, so `//` or `test {` there mean nothing.
+fn blank_multiline_strings(source: &str) -> String {
+    let mut blanked = String::with_capacity(source.len());
+    for line in source.lines() {
+        match multiline_string_start(line) {
+            Some(start) => {
+                blanked.push_str(&line[..start]);
+                blanked.push_str("\"\"");
+            }
+            None => blanked.push_str(line),
+        }
+        blanked.push('\n');
+    }
+    blanked
+}
+
+/// Byte offset of the `\\` starting a multiline string line, if any.
+fn multiline_string_start(line: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut escaped = false;
+    for (offset, ch) in line.char_indices() {
+        if let Some(open) = quote {
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == open {
+                quote = None;
+            }
+            continue;
+        }
+        match ch {
+            '"' | '\'' => quote = Some(ch),
+            '/' if line[offset..].starts_with("//") => return None,
+            '\\' if line[offset..].starts_with("\\\\") => return Some(offset),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// A `test` declaration: `test "name" {`, `test name {` or `test {`, but not a
+/// `.test` field or a `test(...)` call.
+fn is_test_decl(before: &str, ident: &str, after: &str) -> bool {
+    if ident != "test" || before.trim_end().ends_with('.') {
+        return false;
+    }
+    let after = after.trim_start();
+    after.starts_with('"')
+        || after.starts_with('{')
+        || after.starts_with(|c: char| c.is_alphabetic() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analyze(path: &str, source: &str) -> FileAnalysis {
+        ZigBackend.analyze_source(Path::new(path), source).unwrap()
+    }
+
+    #[test]
+    fn inline_test_blocks_are_tests() {
+        let analysis = analyze(
+            "src/math.zig",
+            r#"//! Math helpers.
+const std = @import("std");
+
+/// Adds two numbers.
+pub fn add(a: i32, b: i32) i32 {
+    return a + b; // simple
+}
+
+test "add" {
+    try std.testing.expectEqual(@as(i32, 3), add(1, 2));
+}
+
+test add {
+    if (add(0, 0) != 0) {
+        return error.Bad;
+    }
+}
+
+test {
+    std.testing.refAllDecls(@This());
+}
+"#,
+        );
+
+        assert_eq!(analysis.language, LanguageId::Zig);
+        assert_eq!(analysis.stats.docs, 2);
+        assert_eq!(analysis.stats.code, 4);
+        assert_eq!(analysis.stats.tests, 11);
+        assert_eq!(analysis.stats.blanks, 4);
+        assert_eq!(analysis.stats.comments, 0);
+    }
+
+    #[test]
+    fn strings_and_fields_do_not_start_tests() {
+        let analysis = analyze(
+            "src/config.zig",
+            r#"const help =
+    \\usage: tool // not a comment
+    \\test "x" {
+;
+const opts = .{ .test = true };
+const label = "test { // still a string";
+"#,
+        );
+
+        assert_eq!(analysis.stats.code, 6);
+        assert_eq!(analysis.stats.comments, 0);
+        assert_eq!(analysis.stats.tests, 0);
+    }
+
+    #[test]
+    fn multiline_strings_end_with_their_line_whatever_the_line_ending() {
+        for source in [
+            "const a = \\\\x // text\r\n    \\\\test \"y\" {\r\n;\r\n// note\r\n",
+            "const a = \\\\x // text\n    \\\\test \"y\" {\n;\n// note",
+        ] {
+            let analysis = analyze("src/help.zig", source);
+            assert_eq!(analysis.stats.code, 3, "{:?}", source);
+            assert_eq!(analysis.stats.comments, 1, "{:?}", source);
+            assert_eq!(analysis.stats.tests, 0, "{:?}", source);
+        }
+        assert_eq!(
+            multiline_string_start(r#"const s = "\\\\"; // \\\\ "#),
+            None
+        );
+    }
+
+    #[test]
+    fn test_directories_count_as_tests() {
+        let analysis = analyze("tests/parser.zig", "const x = 1;\n// note\n");
+
+        assert_eq!(analysis.stats.tests, 1);
+        assert_eq!(analysis.stats.comments, 1);
+    }
+}
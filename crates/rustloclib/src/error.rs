@@ -17,10 +17,43 @@ pub enum RustlocError {
     #[error("failed to parse cargo metadata: {0}")]
     CargoMetadata(String),
 
+    /// A Cargo.toml could not be parsed
+    #[error("invalid manifest '{path}': {message}")]
+    InvalidManifest { path: PathBuf, message: String },
+
+    /// A symbolic link leaves the walked root under `SymlinkPolicy::Error`
+    #[error("symbolic link '{link}' leads outside the root to '{target}'")]
+    SymlinkOutsideRoot { link: PathBuf, target: PathBuf },
+
     /// Invalid glob pattern
     #[error("invalid glob pattern '{pattern}': {message}")]
     InvalidGlob { pattern: String, message: String },
 
+    /// Invalid user-defined language configuration
+    #[error("invalid language config: {0}")]
+    InvalidLanguageConfig(String),
+
+    /// A path classification rule is malformed
+    #[error("invalid classify rule: {0}")]
+    InvalidClassifyRule(String),
+
+    /// A backend could not be registered
+    #[error("invalid backend: {0}")]
+    InvalidBackend(String),
+
+    /// An external plugin failed to analyze a file
+    #[error("plugin '{name}' failed: {message}")]
+    Plugin { name: String, message: String },
+
+    /// A parser rejected a source file under `FilterConfig::strict`
+    #[error("failed to parse '{path}' at {line}:{column}: {message}")]
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+
     /// Path does not exist
     #[error("path does not exist: {0}")]
     PathNotFound(PathBuf),
@@ -33,6 +66,13 @@ pub enum RustlocError {
     #[error("unsupported source file: {0}")]
     UnsupportedSourceFile(PathBuf),
 
+    /// Source file failed a content check (binary, minified or too large).
+    #[error("skipped {reason} file: {path}")]
+    SkippedFile {
+        path: PathBuf,
+        reason: crate::data::content::SkipReason,
+    },
+
     /// Not a Rust file.
     #[deprecated(note = "use UnsupportedSourceFile instead")]
     #[error("not a Rust file: {0}")]
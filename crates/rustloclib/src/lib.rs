@@ -5,9 +5,10 @@
 //! ## Overview
 //!
 //! Unlike generic LOC counters (tokei, cloc, scc), this library has semantic
-//! backends for languages where tests can live alongside production code. Rust is
-//! enabled by default; Python, TypeScript, and generic source backends can be
-//! selected through [`FilterConfig`]. It categorizes lines into one of 6 types:
+//! backends for languages where tests can live alongside production code. Rust
+//! is enabled by default; Python, TypeScript, JVM (Java/Kotlin), C/C++, Zig,
+//! Ruby, Markdown, configuration file, and generic source backends can be
+//! selected through [`FilterConfig`]. It categorizes lines into one of 9 types:
 //!
 //! - **code**: Production code logic lines
 //! - **tests**: Test code logic lines (same-file test constructs or test paths)
@@ -15,6 +16,9 @@
 //! - **docs**: Documentation comments (///, //!, /** */, /*! */)
 //! - **comments**: Regular comments (//, /* */)
 //! - **blanks**: Blank/whitespace-only lines
+//! - **preprocessor**: C/C++ preprocessor directives (`#include`, `#define`)
+//! - **config**: Configuration and data files (TOML, YAML, JSON, INI, SQL) and
+//!   configuration embedded in other files (Markdown front matter)
 //!
 //! The key insight: only actual code lines need context (code/tests/examples).
 //! A blank is a blank, a comment is a comment - where they appear doesn't matter.
@@ -39,20 +43,30 @@
 //!
 //! Find what files to analyze:
 //! - [`WorkspaceInfo`]: Discover Cargo workspace structure
-//! - [`FilterConfig`]: Include/exclude files with glob patterns
+//! - [`FilterConfig`]: Include/exclude files with glob patterns; discovery honours
+//!   `.gitignore`, `.ignore` and `.rustlocignore` unless `no_ignore` is set, and
+//!   `tracked_only` lists files from the git index instead; symbolic links are
+//!   followed without loops or double counting, and [`SymlinkPolicy`] decides
+//!   about links leaving the root
+//! - [`BuildModule`]: Maven/Gradle/Zig modules and Ruby gems used to group non-Cargo trees
+//! - [`ProjectModel`]: Cargo, Python, Node and Go packages for per-package grouping of any tree
 //!
 //! ### Stage 2: Data Collection ([`data`])
 //!
 //! Parse files and collect statistics:
 //! - [`gather_stats`]: Parse a single file into [`Locs`]
+//! - [`BackendRegistry`]: Named, prioritized [`LanguageBackend`]s; register your
+//!   own with [`BackendRegistry::register`] or load a [`LanguageConfig`] with
+//!   [`BackendRegistry::with_config`]
 //! - [`count_workspace`]: Count all files, returns [`CountResult`]
+//! - [`count_workspaces`]: Count every workspace beneath a directory in one report
 //! - [`diff_revspec`]: Compare commits via a git revspec string, returns [`DiffResult`]
 //!
 //! ### Stage 3: Query Processing ([`query`])
 //!
 //! Filter, aggregate, sort, and slice the collected data:
 //! - [`CountQuerySet`] / [`DiffQuerySet`]: Processed data ready for display
-//! - [`Aggregation`]: Total, ByCrate, ByModule, ByFile
+//! - [`Aggregation`]: Total, ByCrate, ByModule, ByTarget, ByFile
 //! - [`LineTypes`]: Which line types to include in output
 //! - [`Ordering`]: How to sort results
 //! - [`Predicate`] (built from [`Field`] + [`Op`]): Threshold filters,
@@ -149,18 +163,29 @@ pub mod error;
 
 // Re-export all public types at crate root for convenience
 pub use data::{
-    available_languages, count_directory, count_directory_with_options, count_file,
-    count_file_with_filter, count_workspace, default_languages, diff_revspec, diff_workdir,
-    gather_stats, gather_stats_for_path, sat_sub_u64, CountOptions, CountResult, CrateDiffStats,
-    CrateStats, DiffOptions, DiffResult, FileChangeType, FileDiffStats, FileStats, LanguageName,
-    LanguageSelection, Locs, LocsDiff, ModuleStats, VisitorContext, WorkdirDiffMode,
+    available_languages, count_dependencies, count_directory, count_directory_with_options,
+    count_file, count_file_with_filter, count_workspace, count_workspaces, default_languages,
+    diff_revspec, diff_workdir, gather_stats, gather_stats_for_path, sat_sub_u64, BackendRegistry,
+    ClassifyRule, ContentHints, CountOptions, CountResult, CrateDiffStats, CrateStats,
+    DetectedFile, Detection, DetectionReason, Diagnostic, DiagnosticKind, DiffOptions, DiffResult,
+    ExternalBackend, FileAnalysis, FileChangeType, FileDiffStats, FileStats, LanguageBackend,
+    LanguageConfig, LanguageDefinition, LanguageId, LanguageName, LanguageSelection, LineClass,
+    Locs, LocsDiff, LogicContext, ModuleStats, ParseFailure, PathClassifier, PluginDefinition,
+    PluginRequest, PluginResponse, SkipReason, SkippedFile, SourceEncoding, TargetDiffStats,
+    TargetStats, TranscodedFile, VisitorContext, WorkdirDiffMode, WorkspaceStats, BUILTIN_PRIORITY,
+    CONFIG_PRIORITY, PLUGIN_PROTOCOL_VERSION, WORKSPACE_ROOT_NAME,
 };
 pub use error::RustlocError;
 pub use query::{
     Aggregation, CountQuerySet, DiffQuerySet, Field, LineTypes, Op, OrderBy, OrderDirection,
     Ordering, Predicate, QueryItem,
 };
-pub use source::{CrateInfo, FilterConfig, WorkspaceInfo};
+pub use source::{
+    discover_dependencies, BuildModule, CrateInfo, Dependency, DependencyKind, DependencyQuery,
+    DependencySource, DiscoveredWorkspaces, Ecosystem, FilterConfig, LinkSkipReason, Package,
+    PackageProvider, ProjectModel, SkippedLink, SymlinkPolicy, TargetInfo, TargetKind,
+    WorkspaceDiscovery, WorkspaceInfo,
+};
 
 /// Result type for rustloclib operations
 pub type Result<T> = std::result::Result<T, RustlocError>;
@@ -3,13 +3,13 @@
 //! This module contains the configuration types that control what data the
 //! library computes and returns:
 //!
-//! - [`LineTypes`] — which of the 7 line types to include in results.
+//! - [`LineTypes`] — which of the 9 line types to include in results.
 //! - [`Aggregation`] — granularity of breakdown (`Total`, `ByCrate`,
-//!   `ByModule`, `ByFile`).
+//!   `ByModule`, `ByTarget`, `ByFile`).
 //! - [`Ordering`] (composed of [`OrderBy`] + [`OrderDirection`]) — how the
 //!   query set is sorted.
 //! - [`Predicate`] — a `<field> <op> <value>` threshold filter, built from
-//!   a [`Field`] (one of the 7 line types) and an [`Op`]
+//!   a [`Field`] (one of the 9 line types) and an [`Op`]
 //!   (`gt`/`gte`/`eq`/`ne`/`lt`/`lte`). Multiple predicates AND together
 //!   when applied via `CountQuerySet::filter` / `DiffQuerySet::filter`.
 //!
@@ -23,15 +23,21 @@ use std::str::FromStr;
 
 /// Filter for which line types to include in results.
 ///
-/// The 7 line types are:
+/// The 9 line types are:
 /// - `code`: Production code logic lines
 /// - `tests`: Test code logic lines
 /// - `examples`: Example code logic lines
 /// - `docs`: Documentation comments (anywhere)
 /// - `comments`: Regular comments (anywhere)
 /// - `blanks`: Blank lines (anywhere)
+/// - `preprocessor`: Preprocessor directives (anywhere)
+/// - `config`: Config and data files, and embedded configuration such as
+///   front matter (anywhere)
 /// - `total`: Total line count (precomputed sum of all types)
 ///
+/// `categories` switches the user-defined categories of classification rules
+/// on or off as a group.
+///
 /// The meaning depends on where a `LineTypes` is used:
 ///
 /// - As a **data filter** ([`Locs::filter`], [`CountOptions::line_types`]) a
@@ -61,13 +67,21 @@ pub struct LineTypes {
     pub comments: bool,
     /// Include blank lines
     pub blanks: bool,
+    /// Include preprocessor directive lines
+    pub preprocessor: bool,
+    /// Include configuration lines: whole config and data files (TOML, YAML,
+    /// JSON, INI, SQL) and embedded configuration (Markdown front matter)
+    pub config: bool,
+    /// Include the logic lines of user-defined categories
+    #[serde(default)]
+    pub categories: bool,
     /// Include total line count (precomputed)
     pub total: bool,
 }
 
 impl Default for LineTypes {
     fn default() -> Self {
-        // Default shows code, tests, docs, and total
+        // Default shows code, tests, docs, total and user-defined categories
         Self {
             code: true,
             tests: true,
@@ -75,6 +89,9 @@ impl Default for LineTypes {
             docs: true,
             comments: false,
             blanks: false,
+            preprocessor: false,
+            config: false,
+            categories: true,
             total: true,
         }
     }
@@ -91,6 +108,9 @@ impl LineTypes {
             docs: false,
             comments: false,
             blanks: false,
+            preprocessor: false,
+            config: false,
+            categories: false,
             total: true, // All is on by default
         }
     }
@@ -104,6 +124,9 @@ impl LineTypes {
             docs: true,
             comments: true,
             blanks: true,
+            preprocessor: true,
+            config: true,
+            categories: true,
             total: true,
         }
     }
@@ -117,6 +140,9 @@ impl LineTypes {
             docs: false,
             comments: false,
             blanks: false,
+            preprocessor: false,
+            config: false,
+            categories: false,
             total: false,
         }
     }
@@ -145,6 +171,9 @@ impl LineTypes {
             docs: false,
             comments: false,
             blanks: false,
+            preprocessor: false,
+            config: false,
+            categories: true,
             total: true,
         }
     }
@@ -185,6 +214,24 @@ impl LineTypes {
         self
     }
 
+    /// Builder: enable preprocessor directives
+    pub fn with_preprocessor(mut self) -> Self {
+        self.preprocessor = true;
+        self
+    }
+
+    /// Builder: enable embedded configuration
+    pub fn with_config(mut self) -> Self {
+        self.config = true;
+        self
+    }
+
+    /// Builder: enable user-defined categories
+    pub fn with_categories(mut self) -> Self {
+        self.categories = true;
+        self
+    }
+
     /// Builder: enable total
     pub fn with_total(mut self) -> Self {
         self.total = true;
@@ -206,10 +253,15 @@ pub enum Aggregation {
     /// Only return totals
     #[default]
     Total,
+    /// Include per-workspace breakdown (when several workspaces are counted)
+    ByWorkspace,
     /// Include per-crate breakdown
     ByCrate,
     /// Include per-module breakdown
     ByModule,
+    /// Include per-target breakdown (library, binaries, tests, examples,
+    /// benches and build script of each crate)
+    ByTarget,
     /// Include per-file breakdown
     ByFile,
 }
@@ -232,6 +284,10 @@ pub enum OrderBy {
     Comments,
     /// Order by blanks line count
     Blanks,
+    /// Order by preprocessor directive line count
+    Preprocessor,
+    /// Order by embedded configuration line count
+    Config,
     /// Order by total line count
     Total,
 }
@@ -248,6 +304,8 @@ impl FromStr for OrderBy {
             "docs" | "doc" => Ok(OrderBy::Docs),
             "comments" | "comment" => Ok(OrderBy::Comments),
             "blanks" | "blank" => Ok(OrderBy::Blanks),
+            "preprocessor" => Ok(OrderBy::Preprocessor),
+            "config" => Ok(OrderBy::Config),
             "total" => Ok(OrderBy::Total),
             _ => Err(format!("Unknown order field: {}", s)),
         }
@@ -256,7 +314,7 @@ impl FromStr for OrderBy {
 
 /// Numeric category that a filter `Predicate` operates on.
 ///
-/// The seven variants correspond one-to-one with the seven counted line
+/// The nine variants correspond one-to-one with the nine counted line
 /// types. `Total` reads `Locs::total` (the precomputed all-types sum) so
 /// filtering on `Total` matches the displayed `Total` column regardless
 /// of the active `LineTypes` selection.
@@ -268,6 +326,8 @@ pub enum Field {
     Docs,
     Comments,
     Blanks,
+    Preprocessor,
+    Config,
     Total,
 }
 
@@ -281,11 +341,13 @@ impl Field {
             Field::Docs => "docs",
             Field::Comments => "comments",
             Field::Blanks => "blanks",
+            Field::Preprocessor => "preprocessor",
+            Field::Config => "config",
             Field::Total => "total",
         }
     }
 
-    /// All seven variants in canonical order. Iteration order is the order
+    /// All nine variants in canonical order. Iteration order is the order
     /// the CLI generates flags, so it should be stable and predictable.
     pub fn all() -> &'static [Field] {
         &[
@@ -295,6 +357,8 @@ impl Field {
             Field::Docs,
             Field::Comments,
             Field::Blanks,
+            Field::Preprocessor,
+            Field::Config,
             Field::Total,
         ]
     }
@@ -557,9 +621,9 @@ mod tests {
 
     #[test]
     fn test_field_all_and_op_all_lengths() {
-        assert_eq!(Field::all().len(), 7);
+        assert_eq!(Field::all().len(), 9);
         assert_eq!(Op::all().len(), 6);
-        // 7 × 6 = 42 — the size of the synthetic CLI flag grid.
+        // 9 × 6 = 54 — the size of the synthetic CLI flag grid.
     }
 
     #[test]
@@ -9,7 +9,7 @@
 //! A QuerySet is where this library's pipeline ends: it sits between raw
 //! counting/diff results and whatever presentation the caller builds.
 //! It represents data that has been:
-//! - Aggregated to the requested level (crate, module, file)
+//! - Aggregated to the requested level (crate, module, target, file)
 //! - Sorted according to the ordering preference
 //!
 //! ## Line types are a *view descriptor*, not a data filter
@@ -59,10 +59,18 @@
 use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
-
-use crate::data::counter::{compute_module_name, CountResult};
+use std::path::{Path, PathBuf};
+
+use crate::data::content::{SkippedFile, SourceEncoding, TranscodedFile};
+use crate::data::counter::{
+    compute_module_name, deepest_python_packages, is_python_file, python_import_root,
+    python_module_name, CountResult,
+};
+use crate::data::detect::{DetectedFile, Detection};
+use crate::data::diagnostics::Diagnostic;
 use crate::data::diff::{DiffResult, LocsDiff};
 use crate::data::stats::Locs;
+use crate::source::filter::{FilterConfig, SkippedLink};
 
 use super::options::{Aggregation, Field, LineTypes, OrderBy, OrderDirection, Ordering, Predicate};
 
@@ -73,6 +81,13 @@ pub struct QueryItem<T> {
     pub label: String,
     /// Statistics for this item
     pub stats: T,
+    /// Backend a file row was analyzed by and why, when it was not chosen by
+    /// the file extension
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detection: Option<Detection>,
+    /// Encoding a file row was decoded from, when it was not plain UTF-8
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<SourceEncoding>,
 }
 
 /// Query set for count results — the canonical `count` response.
@@ -103,6 +118,26 @@ pub struct CountQuerySet {
     /// "X of Y" appropriately.
     #[serde(default)]
     pub top_applied: bool,
+    /// Symbolic links and duplicate paths discovery did not count. Not
+    /// affected by `top` or `filter`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_links: Vec<SkippedLink>,
+    /// Files that failed a content check and were not counted. Not affected
+    /// by `top` or `filter`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<SkippedFile>,
+    /// Per-file problems met while counting, skipped files included. Not
+    /// affected by `top` or `filter`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<Diagnostic>,
+    /// Files not detected by their extension, whatever the aggregation. Not
+    /// affected by `top` or `filter`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub detected: Vec<DetectedFile>,
+    /// Files decoded from another encoding, whatever the aggregation. Not
+    /// affected by `top` or `filter`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transcoded: Vec<TranscodedFile>,
 }
 
 /// Query set for diff results — the canonical `diff` response.
@@ -141,6 +176,21 @@ pub struct DiffQuerySet {
     /// True iff `top` was applied. See [`CountQuerySet::top_applied`].
     #[serde(default)]
     pub top_applied: bool,
+    /// Changed files that failed a content check. Not affected by `top` or
+    /// `filter`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<SkippedFile>,
+    /// Per-file problems met while diffing. Not affected by `top` or `filter`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<Diagnostic>,
+    /// Changed files not detected by their extension, whatever the
+    /// aggregation. Not affected by `top` or `filter`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub detected: Vec<DetectedFile>,
+    /// Changed files decoded from another encoding, whatever the aggregation.
+    /// Not affected by `top` or `filter`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transcoded: Vec<TranscodedFile>,
 }
 
 impl CountQuerySet {
@@ -164,8 +214,25 @@ impl CountQuerySet {
         line_types: LineTypes,
         ordering: Ordering,
     ) -> Self {
-        let items = build_count_items(result, &aggregation, &ordering);
-        let total = result.total;
+        let relative = |path: &Path| PathBuf::from(relative_path_label(path, &result.root));
+        let detected: Vec<DetectedFile> = result
+            .detected
+            .iter()
+            .map(|f| DetectedFile {
+                path: relative(&f.path),
+                detection: f.detection.clone(),
+            })
+            .collect();
+        let transcoded: Vec<TranscodedFile> = result
+            .transcoded
+            .iter()
+            .map(|f| TranscodedFile {
+                path: relative(&f.path),
+                encoding: f.encoding,
+            })
+            .collect();
+        let items = build_count_items(result, &aggregation, &ordering, &detected, &transcoded);
+        let total = result.total.clone();
         let total_items = items.len();
 
         CountQuerySet {
@@ -176,6 +243,11 @@ impl CountQuerySet {
             file_count: result.file_count,
             total_items,
             top_applied: false,
+            skipped_links: result.skipped_links.clone(),
+            skipped: result.skipped.clone(),
+            diagnostics: result.diagnostics.clone(),
+            detected,
+            transcoded,
         }
     }
 
@@ -237,6 +309,8 @@ fn locs_field_value(locs: &Locs, field: Field) -> i64 {
         Field::Docs => locs.docs,
         Field::Comments => locs.comments,
         Field::Blanks => locs.blanks,
+        Field::Preprocessor => locs.preprocessor,
+        Field::Config => locs.config,
         Field::Total => locs.total,
     };
     u64_to_i64_sat(v)
@@ -259,6 +333,8 @@ fn diff_field_value(diff: &LocsDiff, field: Field) -> i64 {
         Field::Docs => diff.net_docs(),
         Field::Comments => diff.net_comments(),
         Field::Blanks => diff.net_blanks(),
+        Field::Preprocessor => diff.net_preprocessor(),
+        Field::Config => diff.net_config(),
         Field::Total => diff.net_total(),
     }
 }
@@ -268,6 +344,19 @@ fn matches_diff(pred: &Predicate, diff: &LocsDiff) -> bool {
     pred.op.evaluate(lhs, u64_to_i64_sat(pred.value))
 }
 
+/// Per-file detail keyed by row label. Only file rows carry detail.
+fn file_details<'a, V>(
+    aggregation: &Aggregation,
+    files: impl Iterator<Item = (&'a Path, V)>,
+) -> HashMap<String, V> {
+    if *aggregation != Aggregation::ByFile {
+        return HashMap::new();
+    }
+    files
+        .map(|(path, detail)| (path.to_string_lossy().to_string(), detail))
+        .collect()
+}
+
 /// Compute a relative path label for a file.
 /// Returns the path relative to the workspace root, falling back to the full path if strip fails.
 fn relative_path_label(path: &std::path::Path, root: &std::path::Path) -> String {
@@ -297,7 +386,7 @@ impl DiffQuerySet {
         ordering: Ordering,
     ) -> Self {
         let items = build_diff_items(result, &aggregation, &ordering);
-        let total = result.total;
+        let total = result.total.clone();
         let total_items = items.len();
 
         DiffQuerySet {
@@ -312,6 +401,10 @@ impl DiffQuerySet {
             non_rust_removed: result.non_rust_removed,
             total_items,
             top_applied: false,
+            skipped: result.skipped.clone(),
+            diagnostics: result.diagnostics.clone(),
+            detected: result.detected.clone(),
+            transcoded: result.transcoded.clone(),
         }
     }
 
@@ -359,6 +452,8 @@ fn count_sort_key(locs: &Locs, order_by: &OrderBy) -> u64 {
         OrderBy::Docs => locs.docs,
         OrderBy::Comments => locs.comments,
         OrderBy::Blanks => locs.blanks,
+        OrderBy::Preprocessor => locs.preprocessor,
+        OrderBy::Config => locs.config,
         OrderBy::Total => locs.total,
     }
 }
@@ -371,13 +466,20 @@ fn build_count_items(
     result: &CountResult,
     aggregation: &Aggregation,
     ordering: &Ordering,
+    detected: &[DetectedFile],
+    transcoded: &[TranscodedFile],
 ) -> Vec<QueryItem<Locs>> {
     let mut items: Vec<(String, Locs)> = match aggregation {
         Aggregation::Total => return vec![],
+        Aggregation::ByWorkspace => result
+            .workspaces
+            .iter()
+            .map(|w| (w.name.clone(), w.stats.clone()))
+            .collect(),
         Aggregation::ByCrate => result
             .crates
             .iter()
-            .map(|c| (c.name.clone(), c.stats))
+            .map(|c| (c.name.clone(), c.stats.clone()))
             .collect(),
         Aggregation::ByModule => result
             .modules
@@ -388,15 +490,28 @@ fn build_count_items(
                 } else {
                     m.name.clone()
                 };
-                (label, m.stats)
+                (label, m.stats.clone())
             })
             .collect(),
+        Aggregation::ByTarget => result
+            .targets
+            .iter()
+            .map(|t| (t.name.clone(), t.stats.clone()))
+            .collect(),
         Aggregation::ByFile => result
             .files
             .iter()
-            .map(|f| (relative_path_label(&f.path, &result.root), f.stats))
+            .map(|f| (relative_path_label(&f.path, &result.root), f.stats.clone()))
             .collect(),
     };
+    let detections = file_details(
+        aggregation,
+        detected.iter().map(|f| (f.path.as_path(), &f.detection)),
+    );
+    let encodings = file_details(
+        aggregation,
+        transcoded.iter().map(|f| (f.path.as_path(), f.encoding)),
+    );
 
     // Sort based on ordering
     match ordering.by {
@@ -420,7 +535,12 @@ fn build_count_items(
     // Map to QueryItems
     items
         .into_iter()
-        .map(|(label, stats)| QueryItem { label, stats })
+        .map(|(label, stats)| QueryItem {
+            detection: detections.get(&label).map(|&d| d.clone()),
+            encoding: encodings.get(&label).copied(),
+            label,
+            stats,
+        })
         .collect()
 }
 
@@ -437,6 +557,8 @@ fn diff_sort_key(diff: &LocsDiff, order_by: &OrderBy) -> i64 {
         OrderBy::Docs => diff.net_docs(),
         OrderBy::Comments => diff.net_comments(),
         OrderBy::Blanks => diff.net_blanks(),
+        OrderBy::Preprocessor => diff.net_preprocessor(),
+        OrderBy::Config => diff.net_config(),
         OrderBy::Total => diff.net_total(),
     }
 }
@@ -451,14 +573,23 @@ fn build_diff_items(
     ordering: &Ordering,
 ) -> Vec<QueryItem<LocsDiff>> {
     let mut items: Vec<(String, LocsDiff)> = match aggregation {
-        Aggregation::Total => return vec![],
+        // A diff covers one repository and one workspace, so there is no
+        // per-workspace breakdown to show.
+        Aggregation::Total | Aggregation::ByWorkspace => return vec![],
         Aggregation::ByCrate => result
             .crates
             .iter()
-            .map(|c| (c.name.clone(), c.diff))
+            .map(|c| (c.name.clone(), c.diff.clone()))
             .collect(),
         Aggregation::ByModule => {
             let mut module_map: HashMap<String, LocsDiff> = HashMap::new();
+            // Python import names resolve from their distribution's source
+            // root, as when counting.
+            let python_packages = if result.files.iter().any(|f| is_python_file(&f.path)) {
+                deepest_python_packages(&result.root, &FilterConfig::new()).unwrap_or_default()
+            } else {
+                Vec::new()
+            };
             if result.crates.is_empty() {
                 for file in &result.files {
                     let abs_path = if file.path.is_absolute() {
@@ -466,14 +597,20 @@ fn build_diff_items(
                     } else {
                         result.root.join(&file.path)
                     };
-                    let module_name = compute_module_name(&abs_path, &result.root);
+                    let module_name = if is_python_file(&abs_path) {
+                        let import_root =
+                            python_import_root(&abs_path, &python_packages).unwrap_or(&result.root);
+                        python_module_name(&abs_path, import_root)
+                    } else {
+                        compute_module_name(&abs_path, &result.root)
+                    };
                     let full_name = if module_name.is_empty() {
                         "(root)".to_string()
                     } else {
                         module_name
                     };
                     let entry = module_map.entry(full_name).or_default();
-                    *entry += file.diff;
+                    *entry += &file.diff;
                 }
             } else {
                 for crate_diff in &result.crates {
@@ -489,25 +626,52 @@ fn build_diff_items(
                         } else {
                             result.root.join(&file.path)
                         };
-                        let local_module = compute_module_name(&abs_path, &effective_root);
-                        let full_name = if local_module.is_empty() {
-                            crate_diff.name.clone()
+                        let full_name = if is_python_file(&abs_path) {
+                            // Import names are already package-qualified.
+                            let import_root = python_import_root(&abs_path, &python_packages)
+                                .unwrap_or(&effective_root);
+                            match python_module_name(&abs_path, import_root) {
+                                name if name.is_empty() => crate_diff.name.clone(),
+                                name => name,
+                            }
                         } else {
-                            format!("{}::{}", crate_diff.name, local_module)
+                            match compute_module_name(&abs_path, &effective_root) {
+                                local if local.is_empty() => crate_diff.name.clone(),
+                                local => format!("{}::{}", crate_diff.name, local),
+                            }
                         };
                         let entry = module_map.entry(full_name).or_default();
-                        *entry += file.diff;
+                        *entry += &file.diff;
                     }
                 }
             }
             module_map.into_iter().collect()
         }
+        Aggregation::ByTarget => result
+            .targets
+            .iter()
+            .map(|t| (t.name.clone(), t.diff.clone()))
+            .collect(),
         Aggregation::ByFile => result
             .files
             .iter()
-            .map(|f| (f.path.to_string_lossy().to_string(), f.diff))
+            .map(|f| (f.path.to_string_lossy().to_string(), f.diff.clone()))
             .collect(),
     };
+    let detections = file_details(
+        aggregation,
+        result
+            .detected
+            .iter()
+            .map(|f| (f.path.as_path(), &f.detection)),
+    );
+    let encodings = file_details(
+        aggregation,
+        result
+            .transcoded
+            .iter()
+            .map(|f| (f.path.as_path(), f.encoding)),
+    );
 
     // Sort based on ordering
     match ordering.by {
@@ -531,13 +695,19 @@ fn build_diff_items(
     // Map to QueryItems
     items
         .into_iter()
-        .map(|(label, stats)| QueryItem { label, stats })
+        .map(|(label, stats)| QueryItem {
+            detection: detections.get(&label).map(|&d| d.clone()),
+            encoding: encodings.get(&label).copied(),
+            label,
+            stats,
+        })
         .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::data::detect::DetectionReason;
     use crate::data::stats::CrateStats;
     use crate::query::options::{Field, Op, Predicate};
     use std::path::PathBuf;
@@ -550,6 +720,9 @@ mod tests {
             docs: 0,
             comments: 0,
             blanks: 0,
+            preprocessor: 0,
+            config: 0,
+            categories: Default::default(),
             total: code + tests,
         }
     }
@@ -575,6 +748,13 @@ mod tests {
             ],
             files: vec![],
             modules: vec![],
+            targets: vec![],
+            workspaces: vec![],
+            skipped_links: vec![],
+            skipped: vec![],
+            diagnostics: vec![],
+            detected: vec![],
+            transcoded: vec![],
         }
     }
 
@@ -737,6 +917,13 @@ mod tests {
             ],
             files: vec![],
             modules: vec![],
+            targets: vec![],
+            workspaces: vec![],
+            skipped_links: vec![],
+            skipped: vec![],
+            diagnostics: vec![],
+            detected: vec![],
+            transcoded: vec![],
         }
     }
 
@@ -955,6 +1142,13 @@ mod tests {
                 ),
             ],
             modules: vec![],
+            targets: vec![],
+            workspaces: vec![],
+            skipped_links: vec![],
+            skipped: vec![],
+            diagnostics: vec![],
+            detected: vec![],
+            transcoded: vec![],
         };
 
         let qs = CountQuerySet::from_result(
@@ -987,6 +1181,9 @@ mod tests {
                 docs: 0,
                 comments: 0,
                 blanks: 0,
+                preprocessor: 0,
+                config: 0,
+                categories: Default::default(),
                 total: 200,
             },
             removed: Locs {
@@ -996,6 +1193,9 @@ mod tests {
                 docs: 0,
                 comments: 0,
                 blanks: 0,
+                preprocessor: 0,
+                config: 0,
+                categories: Default::default(),
                 total: 50,
             },
         };
@@ -1007,6 +1207,9 @@ mod tests {
                 docs: 0,
                 comments: 0,
                 blanks: 0,
+                preprocessor: 0,
+                config: 0,
+                categories: Default::default(),
                 total: 10,
             },
             removed: Locs {
@@ -1016,6 +1219,9 @@ mod tests {
                 docs: 0,
                 comments: 0,
                 blanks: 0,
+                preprocessor: 0,
+                config: 0,
+                categories: Default::default(),
                 total: 30,
             },
         };
@@ -1023,19 +1229,29 @@ mod tests {
         let big_file = FileDiffStats {
             path: PathBuf::from("big.rs"),
             change_type: FileChangeType::Modified,
-            diff: big,
+            diff: big.clone(),
+            detection: Some(Detection {
+                backend: "rust".to_string(),
+                reason: DetectionReason::Extension,
+            }),
+            encoding: Some(SourceEncoding::Utf16Le),
         };
         let small_file = FileDiffStats {
             path: PathBuf::from("small.rs"),
             change_type: FileChangeType::Modified,
-            diff: small,
+            diff: small.clone(),
+            detection: Some(Detection {
+                backend: "rust".to_string(),
+                reason: DetectionReason::Modeline,
+            }),
+            encoding: Some(SourceEncoding::Utf8),
         };
 
         DiffResult {
             root: PathBuf::from("/workspace"),
             from_commit: "HEAD~1".to_string(),
             to_commit: "HEAD".to_string(),
-            total: big + small,
+            total: big.clone() + small.clone(),
             crates: vec![CrateDiffStats {
                 name: "x".to_string(),
                 path: PathBuf::from("/workspace"),
@@ -1043,8 +1259,22 @@ mod tests {
                 files: vec![big_file.clone(), small_file.clone()],
             }],
             files: vec![big_file, small_file],
+            targets: vec![],
             non_rust_added: 0,
             non_rust_removed: 0,
+            skipped: vec![],
+            diagnostics: vec![],
+            detected: vec![DetectedFile {
+                path: PathBuf::from("small.rs"),
+                detection: Detection {
+                    backend: "rust".to_string(),
+                    reason: DetectionReason::Modeline,
+                },
+            }],
+            transcoded: vec![TranscodedFile {
+                path: PathBuf::from("big.rs"),
+                encoding: SourceEncoding::Utf16Le,
+            }],
         }
     }
 
@@ -1065,6 +1295,53 @@ mod tests {
         assert_eq!(qs.items[0].label, "big.rs");
     }
 
+    #[test]
+    fn test_file_rows_carry_transcoded_encodings_only() {
+        let result = sample_diff_result_two_files();
+        let encodings = |aggregation| {
+            DiffQuerySet::from_result(
+                &result,
+                aggregation,
+                LineTypes::everything(),
+                Ordering::by_label(),
+            )
+            .items
+            .into_iter()
+            .map(|item| item.encoding)
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            encodings(Aggregation::ByFile),
+            [Some(SourceEncoding::Utf16Le), None]
+        );
+        assert_eq!(encodings(Aggregation::ByCrate), [None]);
+
+        let qs = DiffQuerySet::from_result(
+            &result,
+            Aggregation::Total,
+            LineTypes::everything(),
+            Ordering::by_label(),
+        );
+        assert_eq!(qs.transcoded, result.transcoded);
+        assert_eq!(qs.detected, result.detected);
+    }
+
+    #[test]
+    fn test_file_rows_carry_detections_other_than_by_extension() {
+        let result = sample_diff_result_two_files();
+        let qs = DiffQuerySet::from_result(
+            &result,
+            Aggregation::ByFile,
+            LineTypes::everything(),
+            Ordering::by_label(),
+        );
+        assert_eq!(qs.items[0].detection, None);
+        let detection = qs.items[1].detection.as_ref().unwrap();
+        assert_eq!(detection.backend, "rust");
+        assert_eq!(detection.reason, DetectionReason::Modeline);
+    }
+
     #[test]
     fn test_diff_filter_negative_net_via_lt_zero() {
         // big.rs has net code = +150, small.rs has net code = -20.
@@ -0,0 +1,389 @@
+//! Third-party dependency discovery.
+//!
+//! Resolves a workspace's dependency graph with `cargo metadata --offline` and
+//! locates each dependency's sources: the local registry cache
+//! (`~/.cargo/registry/src`), git checkouts, a `vendor/` directory set up with
+//! `cargo vendor`, or a path dependency. Nothing is downloaded; a graph that
+//! needs sources the machine does not have is reported as an error.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use cargo_metadata::{DependencyKind as MetadataKind, MetadataCommand, Node, PackageId};
+use serde::{Deserialize, Serialize};
+
+use crate::error::RustlocError;
+use crate::Result;
+
+use super::workspace::{manifest_path, CrateInfo};
+
+/// Kind of a dependency edge, as declared in `Cargo.toml`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    /// `[dependencies]`
+    Normal,
+    /// `[dev-dependencies]`
+    Dev,
+    /// `[build-dependencies]`
+    Build,
+}
+
+impl DependencyKind {
+    /// All kinds, in declaration order.
+    pub const fn all() -> &'static [DependencyKind] {
+        &[Self::Normal, Self::Dev, Self::Build]
+    }
+
+    /// The kind's CLI name.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Dev => "dev",
+            Self::Build => "build",
+        }
+    }
+
+    fn from_metadata(kind: MetadataKind) -> Option<Self> {
+        match kind {
+            MetadataKind::Normal => Some(Self::Normal),
+            MetadataKind::Development => Some(Self::Dev),
+            MetadataKind::Build => Some(Self::Build),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DependencyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for DependencyKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|kind| kind.name() == value)
+            .ok_or_else(|| {
+                format!("unknown dependency kind '{value}'; expected normal, dev or build")
+            })
+    }
+}
+
+/// Where a dependency's sources were found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencySource {
+    /// The local registry cache (`~/.cargo/registry/src`)
+    Registry,
+    /// A git checkout (`~/.cargo/git/checkouts`)
+    Git,
+    /// A `vendor/` directory replacing the registry
+    Vendor,
+    /// A path dependency outside the workspace
+    Path,
+}
+
+/// Which dependencies to resolve.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyQuery {
+    /// Edge kinds to follow from the workspace members
+    pub kinds: Vec<DependencyKind>,
+    /// Follow dependencies of dependencies, not just direct ones
+    pub transitive: bool,
+    /// Only start from these workspace members (empty = all members)
+    pub members: Vec<String>,
+}
+
+impl Default for DependencyQuery {
+    fn default() -> Self {
+        Self {
+            kinds: vec![DependencyKind::Normal],
+            transitive: true,
+            members: Vec::new(),
+        }
+    }
+}
+
+impl DependencyQuery {
+    /// Create the default query: transitive normal dependencies of every member.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the edge kinds followed from the workspace members.
+    pub fn kinds(mut self, kinds: Vec<DependencyKind>) -> Self {
+        self.kinds = kinds;
+        self
+    }
+
+    /// Set whether dependencies of dependencies are included.
+    pub fn transitive(mut self, transitive: bool) -> Self {
+        self.transitive = transitive;
+        self
+    }
+
+    /// Only start from these workspace members.
+    pub fn members(mut self, members: Vec<String>) -> Self {
+        self.members = members;
+        self
+    }
+}
+
+/// A resolved third-party dependency.
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    /// Package name
+    pub name: String,
+    /// Resolved version
+    pub version: String,
+    /// Where the sources live
+    pub source: DependencySource,
+    /// Whether a workspace member depends on it directly
+    pub direct: bool,
+    /// The package's root and targets
+    pub crate_info: CrateInfo,
+}
+
+impl Dependency {
+    /// Row label, `name@version` as in a Cargo package ID spec.
+    pub fn label(&self) -> String {
+        format!("{}@{}", self.name, self.version)
+    }
+}
+
+/// Resolve the dependencies of the workspace at `path`, sorted by name and
+/// version.
+///
+/// Workspace members are never reported, even when one depends on another.
+/// Kinds only select the edges leaving the starting members: beyond them the
+/// walk follows normal and build edges, which is everything a dependency
+/// needs to build.
+pub fn discover_dependencies(
+    path: impl AsRef<Path>,
+    query: &DependencyQuery,
+) -> Result<Vec<Dependency>> {
+    let manifest_path = manifest_path(path.as_ref())?;
+    let metadata = MetadataCommand::new()
+        .manifest_path(&manifest_path)
+        .other_options(vec!["--offline".to_string()])
+        .exec()
+        .map_err(|e| RustlocError::CargoMetadata(e.to_string()))?;
+    let resolve = metadata.resolve.as_ref().ok_or_else(|| {
+        RustlocError::CargoMetadata("cargo metadata returned no dependency graph".to_string())
+    })?;
+
+    let nodes: HashMap<&PackageId, &Node> =
+        resolve.nodes.iter().map(|node| (&node.id, node)).collect();
+    let members: HashSet<&PackageId> = metadata.workspace_members.iter().collect();
+    let roots = metadata
+        .workspace_packages()
+        .into_iter()
+        .filter(|p| query.members.is_empty() || query.members.contains(&p.name))
+        .map(|p| &p.id);
+
+    // Package -> reached directly from a starting member.
+    let mut reached: HashMap<&PackageId, bool> = HashMap::new();
+    let mut queue: VecDeque<&PackageId> = VecDeque::new();
+    for root in roots {
+        for dep in edges(nodes.get(root).copied(), &query.kinds) {
+            if !members.contains(dep) {
+                reached.insert(dep, true);
+            }
+            if query.transitive {
+                queue.push_back(dep);
+            }
+        }
+    }
+    let mut expanded: HashSet<&PackageId> = HashSet::new();
+    while let Some(id) = queue.pop_front() {
+        if !expanded.insert(id) {
+            continue;
+        }
+        for dep in edges(
+            nodes.get(id).copied(),
+            &[DependencyKind::Normal, DependencyKind::Build],
+        ) {
+            if !members.contains(dep) {
+                reached.entry(dep).or_insert(false);
+            }
+            queue.push_back(dep);
+        }
+    }
+
+    let mut dependencies: Vec<Dependency> = metadata
+        .packages
+        .iter()
+        .filter_map(|package| {
+            let direct = *reached.get(&package.id)?;
+            let crate_info = CrateInfo::from_package(package);
+            Some(Dependency {
+                name: package.name.clone(),
+                version: package.version.to_string(),
+                source: source_of(
+                    package.source.as_ref().map(|s| s.repr.as_str()),
+                    &crate_info.root,
+                ),
+                direct,
+                crate_info,
+            })
+        })
+        .collect();
+
+    if let Some(missing) = dependencies.iter().find(|d| !d.crate_info.root.is_dir()) {
+        return Err(RustlocError::PathNotFound(missing.crate_info.root.clone()));
+    }
+    dependencies.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.version.cmp(&b.version)));
+    Ok(dependencies)
+}
+
+/// Packages `node` depends on through an edge of one of `kinds`.
+fn edges<'a>(
+    node: Option<&'a Node>,
+    kinds: &'a [DependencyKind],
+) -> impl Iterator<Item = &'a PackageId> + 'a {
+    node.into_iter()
+        .flat_map(|node| node.deps.iter())
+        .filter(move |dep| {
+            dep.dep_kinds.iter().any(|info| {
+                DependencyKind::from_metadata(info.kind).is_some_and(|kind| kinds.contains(&kind))
+            })
+        })
+        .map(|dep| &dep.pkg)
+}
+
+/// Classify where a package's sources were found. A vendored package keeps its
+/// registry or git source ID, so the `vendor` directory is checked first.
+fn source_of(source_id: Option<&str>, root: &Path) -> DependencySource {
+    let Some(source_id) = source_id else {
+        return DependencySource::Path;
+    };
+    let vendored = root
+        .parent()
+        .and_then(|parent| parent.file_name())
+        .is_some_and(|name| name == "vendor");
+    if vendored {
+        DependencySource::Vendor
+    } else if source_id.starts_with("git+") {
+        DependencySource::Git
+    } else {
+        DependencySource::Registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dependency_kinds_parse_by_name() {
+        assert_eq!("dev".parse::<DependencyKind>(), Ok(DependencyKind::Dev));
+        assert!("optional".parse::<DependencyKind>().is_err());
+    }
+
+    #[test]
+    fn sources_are_classified_from_the_source_id_and_location() {
+        let registry = "registry+https://github.com/rust-lang/crates.io-index";
+        assert_eq!(
+            source_of(
+                Some(registry),
+                Path::new("/home/u/.cargo/registry/src/index/serde-1.0.0")
+            ),
+            DependencySource::Registry
+        );
+        assert_eq!(
+            source_of(Some(registry), Path::new("/repo/vendor/serde")),
+            DependencySource::Vendor
+        );
+        assert_eq!(
+            source_of(
+                Some("git+https://example.com/dep#abc"),
+                Path::new("/home/u/.cargo/git/checkouts/dep/abc")
+            ),
+            DependencySource::Git
+        );
+        assert_eq!(
+            source_of(None, Path::new("/repo/../shared")),
+            DependencySource::Path
+        );
+    }
+
+    /// A workspace member, a path dependency with its own dependency, and a
+    /// dev-only dependency — all local, so the test needs no registry.
+    fn project(root: &Path) {
+        let manifest = |dir: &str, body: &str| {
+            std::fs::create_dir_all(root.join(dir).join("src")).unwrap();
+            std::fs::write(root.join(dir).join("Cargo.toml"), body).unwrap();
+            std::fs::write(root.join(dir).join("src/lib.rs"), "pub fn f() {}\n").unwrap();
+        };
+        manifest(
+            "app",
+            r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+direct = { path = "../direct" }
+
+[dev-dependencies]
+helper = { path = "../helper" }
+"#,
+        );
+        manifest(
+            "direct",
+            r#"[package]
+name = "direct"
+version = "1.2.0"
+edition = "2021"
+
+[dependencies]
+inner = { path = "../inner" }
+"#,
+        );
+        manifest(
+            "inner",
+            "[package]\nname = \"inner\"\nversion = \"0.3.0\"\nedition = \"2021\"\n",
+        );
+        manifest(
+            "helper",
+            "[package]\nname = \"helper\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        );
+    }
+
+    #[test]
+    fn discovers_direct_and_transitive_dependencies_by_kind() {
+        let temp = tempfile::tempdir().unwrap();
+        project(temp.path());
+        let app = temp.path().join("app");
+        let labels = |query: DependencyQuery| {
+            discover_dependencies(&app, &query)
+                .unwrap()
+                .iter()
+                .map(|d| (d.label(), d.direct, d.source))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            labels(DependencyQuery::new()),
+            [
+                ("direct@1.2.0".to_string(), true, DependencySource::Path),
+                ("inner@0.3.0".to_string(), false, DependencySource::Path),
+            ]
+        );
+        assert_eq!(
+            labels(DependencyQuery::new().transitive(false)),
+            [("direct@1.2.0".to_string(), true, DependencySource::Path)]
+        );
+        assert_eq!(
+            labels(DependencyQuery::new().kinds(vec![DependencyKind::Dev])),
+            [("helper@0.1.0".to_string(), true, DependencySource::Path)]
+        );
+    }
+}
@@ -1,17 +1,32 @@
 //! File filtering and discovery with glob pattern support.
 //!
 //! This module provides functionality to discover supported source files
-//! with support for include/exclude glob patterns.
+//! with support for include/exclude glob patterns. Discovery honours git
+//! ignore rules and [`IGNORE_FILE`] unless [`FilterConfig::no_ignore`] is set.
+//!
+//! Symbolic links are followed, but never into a loop, and a file reachable
+//! through several paths is counted once. Links leaving the root follow
+//! [`FilterConfig::symlinks`]; every link discovery declines is reported in
+//! [`DiscoveredFiles::skipped_links`]. Directories the walk cannot read are
+//! reported in [`DiscoveredFiles::diagnostics`], or fail discovery under
+//! [`FilterConfig::strict`].
 
+use std::fmt;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
 use glob::Pattern;
-use walkdir::WalkDir;
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
 
-use crate::data::{BackendRegistry, LanguageSelection};
+use crate::data::{BackendRegistry, Diagnostic, LanguageName, LanguageSelection};
 use crate::error::RustlocError;
 use crate::Result;
 
+/// rustloc-specific ignore file, read like `.gitignore` in every directory.
+pub const IGNORE_FILE: &str = ".rustlocignore";
+
 /// Configuration for file filtering.
 #[derive(Debug, Clone, Default)]
 pub struct FilterConfig {
@@ -21,6 +36,107 @@ pub struct FilterConfig {
     pub exclude: Vec<Pattern>,
     /// Language backend groups to analyze.
     pub languages: LanguageSelection,
+    /// Backends to analyze with, including user-defined languages.
+    pub registry: Arc<BackendRegistry>,
+    /// Walk every file, ignoring `.gitignore`, `.ignore` and [`IGNORE_FILE`].
+    pub no_ignore: bool,
+    /// Enumerate files from the git index instead of walking the filesystem.
+    pub tracked_only: bool,
+    /// What to do with symbolic links that lead outside the walked root.
+    pub symlinks: SymlinkPolicy,
+    /// Skip files larger than this many bytes instead of analyzing them.
+    pub max_file_size: Option<u64>,
+    /// Fail on the first file that cannot be read or parsed, instead of
+    /// recording a diagnostic and counting the rest.
+    pub strict: bool,
+}
+
+/// What discovery does with a symbolic link whose target lies outside the
+/// walked root.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkPolicy {
+    /// Follow the link and count what it leads to.
+    #[default]
+    Follow,
+    /// Leave the link out and report it.
+    Skip,
+    /// Fail discovery.
+    Error,
+}
+
+impl SymlinkPolicy {
+    /// All policies, in declaration order.
+    pub const fn all() -> &'static [SymlinkPolicy] {
+        &[Self::Follow, Self::Skip, Self::Error]
+    }
+
+    /// The policy's CLI name.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Follow => "follow",
+            Self::Skip => "skip",
+            Self::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for SymlinkPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for SymlinkPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|policy| policy.name() == value)
+            .ok_or_else(|| {
+                format!("unknown symlink policy '{value}'; expected follow, skip or error")
+            })
+    }
+}
+
+/// Why discovery left a path out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkSkipReason {
+    /// The link points at one of its own ancestors.
+    Loop,
+    /// The link leads outside the root and the policy is [`SymlinkPolicy::Skip`].
+    OutsideRoot,
+    /// The link's target does not exist.
+    Broken,
+    /// The file was already found through another path (same device and inode).
+    Duplicate,
+}
+
+/// A path discovery did not count, and why.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedLink {
+    /// The path as the walk reached it.
+    pub path: PathBuf,
+    /// Where it leads: the loop's ancestor, the resolved target, or the path
+    /// counted instead of a duplicate. `None` for broken links.
+    pub target: Option<PathBuf>,
+    /// Why it was skipped.
+    pub reason: LinkSkipReason,
+}
+
+/// Files found by discovery, with the links it declined to follow.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiscoveredFiles {
+    /// Supported files, sorted.
+    pub files: Vec<PathBuf>,
+    /// Links and duplicate paths that were not counted, sorted by path.
+    pub skipped_links: Vec<SkippedLink>,
+    /// Directories and files the walk could not read, with everything
+    /// beneath them.
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 impl FilterConfig {
@@ -71,18 +187,70 @@ impl FilterConfig {
         self
     }
 
+    /// Set the backend registry, e.g. one with user-defined languages.
+    pub fn registry(mut self, registry: impl Into<Arc<BackendRegistry>>) -> Self {
+        self.registry = registry.into();
+        self
+    }
+
+    /// Set whether discovery skips ignore files.
+    pub fn no_ignore(mut self, no_ignore: bool) -> Self {
+        self.no_ignore = no_ignore;
+        self
+    }
+
+    /// Set whether discovery only returns files tracked by git.
+    pub fn tracked_only(mut self, tracked_only: bool) -> Self {
+        self.tracked_only = tracked_only;
+        self
+    }
+
+    /// Set what discovery does with links leading outside the root.
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    /// Set the largest file, in bytes, that is analyzed.
+    pub fn max_file_size(mut self, bytes: Option<u64>) -> Self {
+        self.max_file_size = bytes;
+        self
+    }
+
+    /// Fail on unreadable and unparsable files instead of recording them.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
     /// Check if a path matches the filter criteria.
     ///
     /// A path matches if:
     /// 1. It is supported by a registered language backend
     /// 2. It matches at least one include pattern (or include is empty)
     /// 3. It doesn't match any exclude pattern
+    ///
+    /// Only the extension and file name route the path to a backend; see
+    /// [`matches_file`](Self::matches_file) for files on disk.
     pub fn matches(&self, path: &Path) -> bool {
-        // Must be supported by a language backend.
-        if !BackendRegistry::new().supports_path_with_languages(path, &self.languages) {
-            return false;
-        }
+        self.registry
+            .supports_path_with_languages(path, &self.languages)
+            && self.matches_patterns(path)
+    }
+
+    /// Like [`matches`](Self::matches), but a file no backend claims by path
+    /// is also read for a shebang or modeline, unless its extension is that of
+    /// an image, archive or other binary format.
+    pub fn matches_file(&self, path: &Path) -> bool {
+        self.matches_patterns(path)
+            && self
+                .registry
+                .supports_file_with_languages(path, &self.languages)
+    }
 
+    /// Whether `path` passes the include and exclude patterns, whatever its
+    /// language.
+    pub(crate) fn matches_patterns(&self, path: &Path) -> bool {
         let path_str = path.to_string_lossy();
 
         // Check excludes first
@@ -109,77 +277,415 @@ impl FilterConfig {
 }
 
 /// Check if a directory should be skipped during traversal.
-fn should_skip_dir(name: &str) -> bool {
+pub(crate) fn should_skip_dir(name: &str) -> bool {
     // Skip hidden directories and target/
     name.starts_with('.') || name == "target"
 }
 
+/// The hidden directory holding CI workflows, walked for config files only.
+const CI_DIR: &str = ".github";
+
+/// Whether a walk skips the directory `name`: [`should_skip_dir`], except for
+/// [`CI_DIR`] when `ci_dir` is set.
+fn skips_dir(name: &str, ci_dir: bool) -> bool {
+    should_skip_dir(name) && !(ci_dir && name == CI_DIR)
+}
+
 /// Discover supported source files in a directory.
 ///
-/// Walks the directory tree and returns all supported files that match the filter.
+/// Walks the directory tree and returns all supported files that match the
+/// filter. Hidden directories and `target/` are always skipped, except that
+/// `.github` is walked for its config files (CI workflows) when the filter
+/// selects [`LanguageName::Config`]. Unless the
+/// filter sets [`no_ignore`](FilterConfig::no_ignore), paths are also skipped
+/// when ignored by a `.gitignore` (nested, in a parent directory, the global
+/// excludes file or `.git/info/exclude`), an `.ignore`, or an [`IGNORE_FILE`],
+/// whether or not the tree is a git repository.
+///
+/// With [`tracked_only`](FilterConfig::tracked_only), files come from the git
+/// index of the repository containing `root` instead, and ignore files play no
+/// part; tracked files deleted from the working tree are skipped.
+///
+/// See [`discover_files_with_report`] for the links discovery skipped.
 pub fn discover_files(root: impl AsRef<Path>, filter: &FilterConfig) -> Result<Vec<PathBuf>> {
-    let root = root.as_ref();
+    discover_files_with_report(root, filter).map(|discovered| discovered.files)
+}
+
+/// Like [`discover_files`], also reporting the symbolic links and duplicate
+/// paths that were not counted.
+///
+/// # Errors
+///
+/// Besides unreadable roots, fails with [`RustlocError::SymlinkOutsideRoot`]
+/// when a link leaves the root under [`SymlinkPolicy::Error`].
+pub fn discover_files_with_report(
+    root: impl AsRef<Path>,
+    filter: &FilterConfig,
+) -> Result<DiscoveredFiles> {
+    discover_files_pruned(root.as_ref(), filter, &[])
+}
+
+/// Like [`discover_files_with_report`], but never descending into the
+/// directories in `prune`, such as the crates nested in a workspace root.
+pub(crate) fn discover_files_pruned(
+    root: &Path,
+    filter: &FilterConfig,
+    prune: &[&Path],
+) -> Result<DiscoveredFiles> {
+    let ci_dir = filter.languages.contains(LanguageName::Config);
+    let config = LanguageSelection::new(&[LanguageName::Config]);
+    let accept = |path: &Path| {
+        if in_ci_dir(root, path) {
+            filter.matches_patterns(path)
+                && filter.registry.supports_file_with_languages(path, &config)
+        } else {
+            filter.matches_file(path)
+        }
+    };
+    walk(root, filter, prune, ci_dir, &accept)
+}
+
+/// Whether `path` lies in [`CI_DIR`] beneath `root`.
+fn in_ci_dir(root: &Path, path: &Path) -> bool {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .any(|dir| dir.as_os_str() == CI_DIR)
+}
 
+/// Files under `root` whose name is one of `names`, such as `Cargo.toml`.
+///
+/// The walk is the one [`discover_files`] makes, so manifests in hidden
+/// directories, `target/` and ignored paths are left out the same way, but
+/// the filter's languages and include/exclude patterns play no part; `.github`
+/// is never walked, whatever the languages.
+pub(crate) fn discover_manifests(
+    root: &Path,
+    filter: &FilterConfig,
+    names: &[&str],
+) -> Result<Vec<PathBuf>> {
+    let is_manifest = |path: &Path| {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| names.contains(&name))
+    };
+    discover_manifests_matching(root, filter, &is_manifest)
+}
+
+/// Like [`discover_manifests`], keeping the files `accept` takes, for
+/// manifests named by pattern such as `*.gemspec`.
+pub(crate) fn discover_manifests_matching(
+    root: &Path,
+    filter: &FilterConfig,
+    accept: &dyn Fn(&Path) -> bool,
+) -> Result<Vec<PathBuf>> {
+    walk(root, filter, &[], false, accept).map(|discovered| discovered.files)
+}
+
+/// Walk `root` as the filter's walk settings say, keeping the files `accept`
+/// takes. [`CI_DIR`] is walked only when `ci_dir` is set.
+fn walk(
+    root: &Path,
+    filter: &FilterConfig,
+    prune: &[&Path],
+    ci_dir: bool,
+    accept: &dyn Fn(&Path) -> bool,
+) -> Result<DiscoveredFiles> {
     if !root.exists() {
         return Err(RustlocError::PathNotFound(root.to_path_buf()));
     }
 
-    let mut files = Vec::new();
-
     if root.is_file() {
-        if filter.matches(root) {
-            files.push(root.to_path_buf());
+        let mut discovered = DiscoveredFiles::default();
+        if accept(root) {
+            discovered.files.push(root.to_path_buf());
         }
-        return Ok(files);
+        return Ok(discovered);
     }
 
-    let walker = WalkDir::new(root).follow_links(true).into_iter();
+    if filter.tracked_only {
+        let mut files = tracked_files(root, ci_dir, accept)?;
+        files.retain(|path| !prune.iter().any(|dir| path.starts_with(dir)));
+        return Ok(DiscoveredFiles {
+            files,
+            ..DiscoveredFiles::default()
+        });
+    }
 
-    for entry in walker.filter_entry(|e| {
-        // Always include the root directory
-        if e.depth() == 0 {
-            return true;
-        }
-        // For non-root entries, skip hidden dirs and target/
-        if e.file_type().is_dir() {
-            let name = e.file_name().to_str().unwrap_or("");
-            return !should_skip_dir(name);
-        }
-        // Include files
-        true
-    }) {
+    let canonical_root = root
+        .canonicalize()
+        .map_err(|source| RustlocError::FileRead {
+            path: root.to_path_buf(),
+            source,
+        })?;
+    let skipped = Arc::new(Mutex::new(Vec::new()));
+    let escaped = Arc::new(Mutex::new(None));
+    let policy = filter.symlinks;
+    let prune: Vec<PathBuf> = prune.iter().map(|dir| dir.to_path_buf()).collect();
+
+    let respect_ignore = !filter.no_ignore;
+    let mut builder = WalkBuilder::new(root);
+    let (skipped_in_walk, escaped_in_walk) = (Arc::clone(&skipped), Arc::clone(&escaped));
+    builder
+        .follow_links(true)
+        .hidden(false)
+        .parents(respect_ignore)
+        .ignore(respect_ignore)
+        .git_ignore(respect_ignore)
+        .git_global(respect_ignore)
+        .git_exclude(respect_ignore)
+        .require_git(false)
+        .filter_entry(move |e| {
+            // Always include the root directory; skip hidden dirs, target/
+            // and pruned directories
+            if e.depth() > 0
+                && e.file_type().is_some_and(|t| t.is_dir())
+                && (skips_dir(e.file_name().to_str().unwrap_or(""), ci_dir)
+                    || prune.iter().any(|dir| dir == e.path()))
+            {
+                return false;
+            }
+            if e.depth() == 0 || !e.path_is_symlink() {
+                return true;
+            }
+            let link = |target, reason| SkippedLink {
+                path: e.path().to_path_buf(),
+                target,
+                reason,
+            };
+            let Ok(target) = e.path().canonicalize() else {
+                skipped_in_walk
+                    .lock()
+                    .unwrap()
+                    .push(link(None, LinkSkipReason::Broken));
+                return false;
+            };
+            if target.starts_with(&canonical_root) || policy == SymlinkPolicy::Follow {
+                return true;
+            }
+            if policy == SymlinkPolicy::Error {
+                escaped_in_walk
+                    .lock()
+                    .unwrap()
+                    .get_or_insert((e.path().to_path_buf(), target));
+            } else {
+                skipped_in_walk
+                    .lock()
+                    .unwrap()
+                    .push(link(Some(target), LinkSkipReason::OutsideRoot));
+            }
+            false
+        });
+    if respect_ignore {
+        builder.add_custom_ignore_filename(IGNORE_FILE);
+    }
+
+    let mut files = Vec::new();
+    let mut unreadable = Vec::new();
+    let mut diagnostics = Vec::new();
+    for entry in builder.build() {
         let entry = match entry {
             Ok(e) => e,
-            Err(_) => continue,
+            Err(err) => {
+                if let Some((child, ancestor)) = symlink_loop(&err) {
+                    unreadable.push(SkippedLink {
+                        path: child.to_path_buf(),
+                        target: Some(ancestor.to_path_buf()),
+                        reason: LinkSkipReason::Loop,
+                    });
+                } else if let Some(path) = error_path(&err).filter(|path| is_broken_link(path)) {
+                    unreadable.push(SkippedLink {
+                        path: path.to_path_buf(),
+                        target: None,
+                        reason: LinkSkipReason::Broken,
+                    });
+                } else {
+                    // An unreadable directory takes its whole subtree out of
+                    // the counts. Errors in ignore files drop nothing.
+                    let path = error_path(&err).unwrap_or(root).to_path_buf();
+                    if let Some(source) = err.into_io_error() {
+                        let error = RustlocError::FileRead {
+                            path: path.clone(),
+                            source,
+                        };
+                        if filter.strict {
+                            return Err(error);
+                        }
+                        diagnostics.push(Diagnostic::from_error(path, &error));
+                    }
+                }
+                continue;
+            }
         };
 
         let path = entry.path();
 
-        if path.is_file() && filter.matches(path) {
+        if path.is_file() && accept(path) {
             files.push(path.to_path_buf());
         }
     }
 
+    if let Some((link, target)) = escaped.lock().unwrap().take() {
+        return Err(RustlocError::SymlinkOutsideRoot { link, target });
+    }
+    let mut skipped_links = std::mem::take(&mut *skipped.lock().unwrap());
+    skipped_links.extend(unreadable);
+    Ok(DiscoveredFiles {
+        diagnostics,
+        ..deduplicate(files, skipped_links)
+    })
+}
+
+/// The link and ancestor of a symlink loop error, however it is wrapped.
+fn symlink_loop(err: &ignore::Error) -> Option<(&Path, &Path)> {
+    match err {
+        ignore::Error::Loop { ancestor, child } => Some((child, ancestor)),
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => symlink_loop(err),
+        _ => None,
+    }
+}
+
+/// The path an error is about, however it is wrapped.
+fn error_path(err: &ignore::Error) -> Option<&Path> {
+    match err {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            error_path(err)
+        }
+        _ => None,
+    }
+}
+
+fn is_broken_link(path: &Path) -> bool {
+    path.is_symlink() && !path.exists()
+}
+
+/// What makes two paths the same file: device and inode where the platform
+/// has them, the canonical path elsewhere.
+#[cfg(unix)]
+fn file_identity(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_identity(path: &Path) -> Option<PathBuf> {
+    path.canonicalize().ok()
+}
+
+/// Sort `files`, keeping one path per file. A path that is not reached
+/// through a link wins; the others are reported as duplicates.
+fn deduplicate(mut files: Vec<PathBuf>, mut skipped_links: Vec<SkippedLink>) -> DiscoveredFiles {
+    let through_link = |path: &Path| {
+        let lexical = crate::source::manifest::normalize(path).ok();
+        path.canonicalize().ok() != lexical
+    };
+    files.sort_by_cached_key(|path| (through_link(path), path.clone()));
+    files.dedup();
+
+    let mut seen = std::collections::HashMap::new();
+    let mut kept = Vec::with_capacity(files.len());
+    for path in files {
+        let Some(identity) = file_identity(&path) else {
+            kept.push(path);
+            continue;
+        };
+        match seen.get(&identity) {
+            Some(first) => skipped_links.push(SkippedLink {
+                path,
+                target: Some(PathBuf::clone(first)),
+                reason: LinkSkipReason::Duplicate,
+            }),
+            None => {
+                seen.insert(identity, path.clone());
+                kept.push(path);
+            }
+        }
+    }
+
     // Sort for deterministic output
-    files.sort();
+    kept.sort();
+    skipped_links.sort_by(|a, b| a.path.cmp(&b.path).then(a.reason.cmp(&b.reason)));
+    skipped_links.dedup_by(|a, b| a.path == b.path && a.reason == b.reason);
+    DiscoveredFiles {
+        files: kept,
+        skipped_links,
+        diagnostics: Vec::new(),
+    }
+}
+
+/// Files under `root` listed in the git index that `accept` takes, sorted.
+fn tracked_files(
+    root: &Path,
+    ci_dir: bool,
+    accept: &dyn Fn(&Path) -> bool,
+) -> Result<Vec<PathBuf>> {
+    let repo = gix::discover(root)
+        .map_err(|e| RustlocError::GitError(format!("Failed to discover git repository: {}", e)))?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| RustlocError::GitError("Repository has no work directory".to_string()))?;
+    let canonical = |path: &Path| {
+        path.canonicalize().map_err(|e| RustlocError::FileRead {
+            path: path.to_path_buf(),
+            source: e,
+        })
+    };
+    let workdir = canonical(workdir)?;
+    let canonical_root = canonical(root)?;
+    let index = repo
+        .index_or_empty()
+        .map_err(|e| RustlocError::GitError(format!("Failed to read index: {}", e)))?;
+
+    let mut files = Vec::new();
+    for entry in index.entries() {
+        let tracked = workdir.join(gix::path::from_bstr(entry.path(&index)));
+        let Ok(relative) = tracked.strip_prefix(&canonical_root) else {
+            continue;
+        };
+        let skipped = relative.parent().is_some_and(|dirs| {
+            dirs.components()
+                .any(|dir| skips_dir(&dir.as_os_str().to_string_lossy(), ci_dir))
+        });
+        let path = root.join(relative);
+        if !skipped && path.is_file() && accept(&path) {
+            files.push(path);
+        }
+    }
 
+    files.sort();
     Ok(files)
 }
 
 /// Discover supported source files in multiple directories.
 pub fn discover_files_in_dirs(dirs: &[&Path], filter: &FilterConfig) -> Result<Vec<PathBuf>> {
+    discover_files_in_dirs_with_report(dirs, filter).map(|discovered| discovered.files)
+}
+
+/// Like [`discover_files_in_dirs`], also reporting skipped links. A file
+/// reachable from several of the directories is counted once.
+pub fn discover_files_in_dirs_with_report(
+    dirs: &[&Path],
+    filter: &FilterConfig,
+) -> Result<DiscoveredFiles> {
     let mut all_files = Vec::new();
+    let mut skipped_links = Vec::new();
+    let mut diagnostics = Vec::new();
 
     for dir in dirs {
-        let files = discover_files(dir, filter)?;
-        all_files.extend(files);
+        let discovered = discover_files_with_report(dir, filter)?;
+        all_files.extend(discovered.files);
+        skipped_links.extend(discovered.skipped_links);
+        diagnostics.extend(discovered.diagnostics);
     }
 
-    // Remove duplicates and sort
-    all_files.sort();
-    all_files.dedup();
-
-    Ok(all_files)
+    Ok(DiscoveredFiles {
+        diagnostics,
+        ..deduplicate(all_files, skipped_links)
+    })
 }
 
 #[cfg(test)]
@@ -251,6 +757,22 @@ mod tests {
         assert!(filter.matches(Path::new("tests/app.test.ts")));
     }
 
+    #[test]
+    fn test_filter_sniffs_unclaimed_files_but_not_binaries() {
+        let temp = tempdir().unwrap();
+        let dir = temp.path();
+        fs::write(dir.join("deploy"), "#!/usr/bin/env python3\n").unwrap();
+        fs::write(dir.join("deploy.cgi"), "#!/usr/bin/env python3\n").unwrap();
+        fs::write(dir.join("deploy.zip"), "#!/usr/bin/env python3\n").unwrap();
+        let filter = FilterConfig::new().languages(crate::data::LanguageSelection::new(&[
+            crate::data::LanguageName::Python,
+        ]));
+
+        assert!(filter.matches_file(&dir.join("deploy")));
+        assert!(filter.matches_file(&dir.join("deploy.cgi")));
+        assert!(!filter.matches_file(&dir.join("deploy.zip")));
+    }
+
     #[test]
     fn test_filter_with_include_pattern() {
         let filter = FilterConfig::new().include("**/utils/*.rs").unwrap();
@@ -306,6 +828,79 @@ mod tests {
             .any(|p| p.to_string_lossy().contains(".hidden")));
     }
 
+    #[test]
+    fn test_discover_files_keeps_ci_workflows() {
+        let temp = tempdir().unwrap();
+        create_test_files(temp.path());
+        fs::create_dir_all(temp.path().join(".github/workflows")).unwrap();
+        fs::write(temp.path().join(".github/workflows/ci.yml"), "on: push\n").unwrap();
+        fs::write(temp.path().join(".hidden/settings.yml"), "a: 1\n").unwrap();
+
+        let filter = FilterConfig::new().languages(crate::data::LanguageSelection::new(&[
+            crate::data::LanguageName::Config,
+        ]));
+        let files = discover_files(temp.path(), &filter).unwrap();
+
+        assert!(files
+            .iter()
+            .any(|p| p.ends_with(".github/workflows/ci.yml")));
+        assert!(!files
+            .iter()
+            .any(|p| p.to_string_lossy().contains(".hidden")));
+    }
+
+    #[test]
+    fn test_discover_files_leaves_ci_dir_to_config() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join(".github/workflows")).unwrap();
+        fs::create_dir_all(root.join(".github/scripts")).unwrap();
+        fs::write(root.join(".github/workflows/ci.yml"), "on: push\n").unwrap();
+        fs::write(root.join(".github/scripts/release.py"), "print('hi')\n").unwrap();
+        fs::write(root.join("app.py"), "print('app')\n").unwrap();
+
+        let relative = |filter: &FilterConfig| -> Vec<PathBuf> {
+            discover_files(root, filter)
+                .unwrap()
+                .into_iter()
+                .map(|p| p.strip_prefix(root).unwrap().to_path_buf())
+                .collect()
+        };
+        let python = FilterConfig::new().languages(LanguageSelection::new(&[LanguageName::Python]));
+        assert_eq!(relative(&python), [PathBuf::from("app.py")]);
+
+        let python_and_config = FilterConfig::new().languages(LanguageSelection::new(&[
+            LanguageName::Python,
+            LanguageName::Config,
+        ]));
+        assert_eq!(
+            relative(&python_and_config),
+            [
+                PathBuf::from(".github/workflows/ci.yml"),
+                PathBuf::from("app.py"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_discover_manifests_skips_ci_dir() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join(".github/actions/x")).unwrap();
+        fs::write(
+            root.join(".github/actions/x/package.json"),
+            r#"{"name": "ci-action"}"#,
+        )
+        .unwrap();
+        fs::write(root.join("package.json"), r#"{"name": "app"}"#).unwrap();
+
+        let filter = FilterConfig::new().languages(LanguageSelection::all());
+        assert_eq!(
+            discover_manifests(root, &filter, &["package.json"]).unwrap(),
+            [root.join("package.json")]
+        );
+    }
+
     #[test]
     fn test_discover_files_with_filter() {
         let temp = tempdir().unwrap();
@@ -325,6 +920,99 @@ mod tests {
         assert!(!files.iter().any(|p| p.ends_with("examples/demo.rs")));
     }
 
+    #[test]
+    fn test_discover_files_honours_ignore_files() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        for dir in [
+            "src/generated",
+            "vendor/dep",
+            "node_modules/pkg",
+            "scratch",
+            ".git/info",
+        ] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "src/lib.rs",
+            "src/generated/out.rs",
+            "src/keep.rs",
+            "vendor/dep/lib.rs",
+            "node_modules/pkg/index.rs",
+            "scratch/notes.rs",
+            "local.rs",
+        ] {
+            fs::write(root.join(file), "fn f() {}\n").unwrap();
+        }
+        fs::write(root.join(".gitignore"), "node_modules/\n").unwrap();
+        fs::write(root.join("src/.gitignore"), "generated/\n*.rs\n!lib.rs\n").unwrap();
+        fs::write(root.join(".ignore"), "scratch/\n").unwrap();
+        fs::write(root.join(IGNORE_FILE), "vendor/\n").unwrap();
+        fs::write(root.join(".git/info/exclude"), "local.rs\n").unwrap();
+
+        let names = |filter: &FilterConfig| -> Vec<String> {
+            discover_files(root, filter)
+                .unwrap()
+                .iter()
+                .map(|path| {
+                    let relative = path.strip_prefix(root).unwrap();
+                    relative.to_string_lossy().replace('\\', "/")
+                })
+                .collect()
+        };
+
+        assert_eq!(names(&FilterConfig::new()), ["src/lib.rs"]);
+        assert_eq!(
+            names(&FilterConfig::new().no_ignore(true)),
+            [
+                "local.rs",
+                "node_modules/pkg/index.rs",
+                "scratch/notes.rs",
+                "src/generated/out.rs",
+                "src/keep.rs",
+                "src/lib.rs",
+                "vendor/dep/lib.rs",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_discover_files_tracked_only_reads_the_git_index() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "fn f() {}\n").unwrap();
+        fs::write(root.join("src/gone.rs"), "fn f() {}\n").unwrap();
+        fs::write(root.join("scratch.rs"), "fn f() {}\n").unwrap();
+        fs::write(root.join(".gitignore"), "src/lib.rs\n").unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(args)
+                .current_dir(root)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?}");
+        };
+        git(&["init", "-q"]);
+        git(&["add", "-f", "src/lib.rs", "src/gone.rs"]);
+        fs::remove_file(root.join("src/gone.rs")).unwrap();
+
+        let filter = FilterConfig::new().tracked_only(true);
+        let files = discover_files(root, &filter).unwrap();
+        // Tracked wins over .gitignore; untracked and deleted files are skipped.
+        assert_eq!(files, [root.join("src/lib.rs")]);
+
+        let files = discover_files(root.join("src"), &filter).unwrap();
+        assert_eq!(files, [root.join("src").join("lib.rs")]);
+
+        let not_a_repo = tempdir().unwrap();
+        assert!(matches!(
+            discover_files(not_a_repo.path(), &filter),
+            Err(RustlocError::GitError(_))
+        ));
+    }
+
     #[test]
     fn test_discover_single_file() {
         let temp = tempdir().unwrap();
@@ -357,4 +1045,100 @@ mod tests {
             panic!("Expected InvalidGlob error");
         }
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_discover_files_survives_loops_and_counts_each_file_once() {
+        use std::os::unix::fs::symlink;
+
+        let temp = tempdir().unwrap();
+        let root = temp.path().join("root");
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        fs::write(root.join("src/lib.rs"), "fn a() {}").unwrap();
+        symlink(root.join("src"), root.join("src/nested/again")).unwrap();
+        symlink(root.join("src/lib.rs"), root.join("alias.rs")).unwrap();
+        symlink(root.join("missing.rs"), root.join("broken.rs")).unwrap();
+
+        let discovered = discover_files_with_report(&root, &FilterConfig::new()).unwrap();
+
+        assert_eq!(discovered.files, [root.join("src/lib.rs")]);
+        let skipped: Vec<_> = discovered
+            .skipped_links
+            .iter()
+            .map(|link| (link.path.strip_prefix(&root).unwrap(), link.reason))
+            .collect();
+        assert_eq!(
+            skipped,
+            [
+                (Path::new("alias.rs"), LinkSkipReason::Duplicate),
+                (Path::new("broken.rs"), LinkSkipReason::Broken),
+                (Path::new("src/nested/again"), LinkSkipReason::Loop),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unreadable_directories_are_diagnostics_unless_strict() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("locked")).unwrap();
+        fs::write(root.join("locked/hidden.rs"), "fn h() {}").unwrap();
+        fs::write(root.join("main.rs"), "fn main() {}").unwrap();
+        let locked = root.join("locked");
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        let readable = fs::read_dir(&locked).is_ok();
+
+        let discovered = discover_files_with_report(root, &FilterConfig::new());
+        let strict = discover_files_with_report(root, &FilterConfig::new().strict(true));
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        // Permissions do not stop a superuser.
+        if readable {
+            return;
+        }
+
+        let discovered = discovered.unwrap();
+        assert_eq!(discovered.files, [root.join("main.rs")]);
+        assert_eq!(discovered.diagnostics.len(), 1);
+        assert_eq!(discovered.diagnostics[0].path, locked);
+        assert_eq!(
+            discovered.diagnostics[0].kind,
+            crate::data::DiagnosticKind::ReadError
+        );
+        assert!(matches!(strict, Err(RustlocError::FileRead { path, .. }) if path == locked));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_policy_decides_about_links_leaving_the_root() {
+        use std::os::unix::fs::symlink;
+
+        let temp = tempdir().unwrap();
+        let root = temp.path().join("root");
+        let outside = temp.path().join("outside");
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(root.join("main.rs"), "fn main() {}").unwrap();
+        fs::write(outside.join("vendored.rs"), "fn v() {}").unwrap();
+        symlink(&outside, root.join("vendor")).unwrap();
+
+        let discover =
+            |policy| discover_files_with_report(&root, &FilterConfig::new().symlinks(policy));
+
+        let followed = discover(SymlinkPolicy::Follow).unwrap();
+        assert_eq!(followed.files.len(), 2);
+        assert!(followed.skipped_links.is_empty());
+
+        let skipped = discover(SymlinkPolicy::Skip).unwrap();
+        assert_eq!(skipped.files, [root.join("main.rs")]);
+        assert_eq!(skipped.skipped_links.len(), 1);
+        assert_eq!(skipped.skipped_links[0].reason, LinkSkipReason::OutsideRoot);
+
+        assert!(matches!(
+            discover(SymlinkPolicy::Error),
+            Err(RustlocError::SymlinkOutsideRoot { .. })
+        ));
+    }
 }
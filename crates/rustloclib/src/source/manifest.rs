@@ -0,0 +1,572 @@
+//! Native `Cargo.toml` reader.
+//!
+//! Builds a [`WorkspaceInfo`] from the manifests alone, without running
+//! `cargo metadata`: no toolchain, no lockfile resolution, no registry access.
+//! It reproduces the parts of Cargo's model that counting needs — workspace
+//! membership (`members` globs, `exclude`, path dependencies inside the
+//! workspace, `default-members`) and target auto-discovery (`src/lib.rs`,
+//! `src/main.rs`, `src/bin/`, `tests/`, `examples/`, `benches/`, `build.rs`,
+//! the `auto*` switches and explicit target tables).
+//!
+//! It is deliberately lenient: a member whose manifest cannot be read is
+//! reported as a [`Diagnostic`] and skipped rather than failing the whole
+//! workspace, unless the caller is strict, and a member with no package name
+//! is skipped.
+
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+use toml::{Table, Value};
+
+use crate::data::Diagnostic;
+use crate::error::RustlocError;
+use crate::Result;
+
+use super::workspace::{CrateInfo, TargetInfo, TargetKind, WorkspaceInfo};
+
+/// Read the workspace the manifest at `manifest_path` belongs to.
+///
+/// A member manifest that cannot be read or parsed is recorded in
+/// `diagnostics` and left out, or fails the read when `strict`.
+pub(crate) fn read_workspace(
+    manifest_path: &Path,
+    strict: bool,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<WorkspaceInfo> {
+    let manifest_path = normalize(manifest_path)?;
+    let manifest = parse(&manifest_path)?;
+    let dir = parent(&manifest_path);
+
+    let (root, root_manifest) = if manifest.contains_key("workspace") {
+        (dir, manifest)
+    } else {
+        match find_root(&dir, &manifest)? {
+            Some(found) => found,
+            None => {
+                // A standalone package is a workspace of one.
+                let crates = package(&dir, &manifest).into_iter().collect::<Vec<_>>();
+                let default_members = crates.iter().map(|c| c.name.clone()).collect();
+                return Ok(WorkspaceInfo {
+                    root: dir,
+                    crates,
+                    default_members,
+                });
+            }
+        }
+    };
+
+    let workspace = root_manifest
+        .get("workspace")
+        .and_then(Value::as_table)
+        .cloned()
+        .unwrap_or_default();
+    let members = Members::new(&root, &workspace);
+
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    if root_manifest.contains_key("package") {
+        dirs.push(root.clone());
+    }
+    dirs.extend(members.expand(strings(&workspace, "members")));
+
+    // Path dependencies inside the workspace are members too.
+    let mut manifests: Vec<(PathBuf, Table)> = Vec::new();
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+    while let Some(dir) = dirs.pop() {
+        if !seen.insert(dir.clone()) {
+            continue;
+        }
+        let manifest = if dir == root {
+            root_manifest.clone()
+        } else {
+            let member_manifest = dir.join("Cargo.toml");
+            match parse(&member_manifest) {
+                Ok(manifest) => manifest,
+                Err(err) if strict => return Err(err),
+                Err(err) => {
+                    diagnostics.push(Diagnostic::from_error(&member_manifest, &err));
+                    continue;
+                }
+            }
+        };
+        for path in path_dependencies(&manifest) {
+            let dep = normalize(&dir.join(path))?;
+            if dep.starts_with(&root) && !members.excluded(&dep) && dep.join("Cargo.toml").is_file()
+            {
+                dirs.push(dep);
+            }
+        }
+        manifests.push((dir, manifest));
+    }
+
+    let mut crates: Vec<CrateInfo> = manifests
+        .iter()
+        .filter_map(|(dir, manifest)| package(dir, manifest))
+        .collect();
+    crates.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let default_dirs = if workspace.contains_key("default-members") {
+        members.expand(strings(&workspace, "default-members"))
+    } else if root_manifest.contains_key("package") {
+        vec![root.clone()]
+    } else {
+        crates.iter().map(|c| c.root.clone()).collect()
+    };
+    let default_members = crates
+        .iter()
+        .filter(|c| default_dirs.contains(&c.root))
+        .map(|c| c.name.clone())
+        .collect();
+
+    Ok(WorkspaceInfo {
+        root,
+        crates,
+        default_members,
+    })
+}
+
+/// Find the workspace root above a package, as Cargo does: the
+/// `package.workspace` key when set, otherwise the nearest ancestor manifest
+/// with a `[workspace]` table that claims the package as a member.
+fn find_root(dir: &Path, manifest: &Table) -> Result<Option<(PathBuf, Table)>> {
+    let explicit = manifest
+        .get("package")
+        .and_then(|p| p.get("workspace"))
+        .and_then(Value::as_str);
+    if let Some(explicit) = explicit {
+        let root = normalize(&dir.join(explicit))?;
+        let root_manifest = parse(&root.join("Cargo.toml"))?;
+        return Ok(Some((root, root_manifest)));
+    }
+
+    for ancestor in dir.ancestors().skip(1) {
+        let candidate = ancestor.join("Cargo.toml");
+        if !candidate.is_file() {
+            continue;
+        }
+        let Ok(root_manifest) = parse(&candidate) else {
+            continue;
+        };
+        let Some(workspace) = root_manifest.get("workspace").and_then(Value::as_table) else {
+            continue;
+        };
+        let members = Members::new(ancestor, workspace);
+        let claimed = members
+            .expand(strings(workspace, "members"))
+            .iter()
+            .any(|member| member == dir);
+        if claimed {
+            return Ok(Some((ancestor.to_path_buf(), root_manifest)));
+        }
+        // Cargo stops at the first workspace it finds; a package it does not
+        // claim is treated as standalone rather than as an error.
+        return Ok(None);
+    }
+    Ok(None)
+}
+
+/// `members` / `default-members` expansion relative to a workspace root.
+struct Members {
+    root: PathBuf,
+    exclude: Vec<PathBuf>,
+}
+
+impl Members {
+    fn new(root: &Path, workspace: &Table) -> Self {
+        let exclude = strings(workspace, "exclude")
+            .into_iter()
+            .filter_map(|path| normalize(&root.join(path)).ok())
+            .collect();
+        Self {
+            root: root.to_path_buf(),
+            exclude,
+        }
+    }
+
+    fn excluded(&self, dir: &Path) -> bool {
+        self.exclude
+            .iter()
+            .any(|excluded| dir.starts_with(excluded))
+    }
+
+    /// Package directories matched by `patterns`. Glob matches honour
+    /// `exclude`; a member named literally is kept, as in Cargo.
+    fn expand(&self, patterns: Vec<String>) -> Vec<PathBuf> {
+        let mut dirs = Vec::new();
+        for pattern in patterns {
+            let literal = !pattern.contains(['*', '?', '[']);
+            let full = self.root.join(&pattern);
+            let matches: Vec<PathBuf> = if literal {
+                vec![full]
+            } else {
+                glob::glob(&full.to_string_lossy())
+                    .map(|paths| paths.filter_map(|p| p.ok()).collect())
+                    .unwrap_or_default()
+            };
+            for dir in matches {
+                let Ok(dir) = normalize(&dir) else { continue };
+                if !dir.join("Cargo.toml").is_file() || (!literal && self.excluded(&dir)) {
+                    continue;
+                }
+                if !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
+            }
+        }
+        dirs
+    }
+}
+
+/// The crate a manifest declares, if it declares one.
+fn package(dir: &Path, manifest: &Table) -> Option<CrateInfo> {
+    let package = manifest.get("package")?.as_table()?;
+    let name = package.get("name")?.as_str()?.to_string();
+    let targets = targets(dir, &name, package, manifest);
+    Some(CrateInfo {
+        name,
+        root: dir.to_path_buf(),
+        targets,
+    })
+}
+
+/// A package's targets: explicit tables first, then Cargo's inferred ones,
+/// in the order lib, bins, examples, tests, benches, build script.
+fn targets(dir: &Path, name: &str, package: &Table, manifest: &Table) -> Vec<TargetInfo> {
+    let auto = |key: &str| package.get(key).and_then(Value::as_bool).unwrap_or(true);
+    let mut targets = Vec::new();
+
+    let lib = manifest.get("lib").and_then(Value::as_table);
+    let lib_path = match lib.and_then(|lib| lib.get("path")).and_then(Value::as_str) {
+        Some(path) => Some(dir.join(path)),
+        None => Some(dir.join("src/lib.rs")).filter(|path| path.is_file() && auto("autolib")),
+    };
+    if let Some(path) = lib_path {
+        let lib_name = lib
+            .and_then(|lib| lib.get("name"))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| name.replace('-', "_"));
+        targets.push(TargetInfo::new(lib_name, TargetKind::Lib, path));
+    }
+
+    for (kind, key, subdir, auto_key) in [
+        (TargetKind::Bin, "bin", "src/bin", "autobins"),
+        (TargetKind::Example, "example", "examples", "autoexamples"),
+        (TargetKind::Test, "test", "tests", "autotests"),
+        (TargetKind::Bench, "bench", "benches", "autobenches"),
+    ] {
+        let mut kind_targets: Vec<TargetInfo> = manifest
+            .get(key)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_table)
+            .filter_map(|table| {
+                let target_name = table.get("name")?.as_str()?;
+                let path = match table.get("path").and_then(Value::as_str) {
+                    Some(path) => dir.join(path),
+                    None => default_path(dir, kind, subdir, name, target_name)?,
+                };
+                Some(TargetInfo::new(target_name, kind, path))
+            })
+            .collect();
+        if auto(auto_key) {
+            let mut inferred = infer(dir, subdir, kind);
+            if kind == TargetKind::Bin && dir.join("src/main.rs").is_file() {
+                inferred.push(TargetInfo::new(name, kind, dir.join("src/main.rs")));
+            }
+            inferred.retain(|t| {
+                !kind_targets
+                    .iter()
+                    .any(|e| e.name == t.name || e.src_path == t.src_path)
+            });
+            inferred.sort_by(|a, b| a.name.cmp(&b.name));
+            kind_targets.extend(inferred);
+        }
+        targets.extend(kind_targets);
+    }
+
+    let build = match package.get("build") {
+        Some(Value::String(path)) => Some(dir.join(path)),
+        Some(Value::Boolean(false)) => None,
+        _ => Some(dir.join("build.rs")).filter(|path| path.is_file()),
+    };
+    if let Some(path) = build {
+        targets.push(TargetInfo::new(
+            "build-script-build",
+            TargetKind::CustomBuild,
+            path,
+        ));
+    }
+
+    targets
+}
+
+/// Where Cargo looks for an explicit target declared without a `path`.
+fn default_path(
+    dir: &Path,
+    kind: TargetKind,
+    subdir: &str,
+    package_name: &str,
+    target_name: &str,
+) -> Option<PathBuf> {
+    let mut candidates = Vec::new();
+    if kind == TargetKind::Bin && target_name == package_name {
+        candidates.push(dir.join("src/main.rs"));
+    }
+    candidates.push(dir.join(subdir).join(format!("{target_name}.rs")));
+    candidates.push(dir.join(subdir).join(target_name).join("main.rs"));
+    candidates.into_iter().find(|path| path.is_file())
+}
+
+/// Targets inferred from a conventional directory: `<subdir>/*.rs` and
+/// `<subdir>/*/main.rs`.
+fn infer(dir: &Path, subdir: &str, kind: TargetKind) -> Vec<TargetInfo> {
+    let Ok(entries) = std::fs::read_dir(dir.join(subdir)) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter_map(|path| {
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "rs") {
+                let name = path.file_stem()?.to_str()?.to_string();
+                Some(TargetInfo::new(name, kind, path))
+            } else if path.join("main.rs").is_file() {
+                let name = path.file_name()?.to_str()?.to_string();
+                Some(TargetInfo::new(name, kind, path.join("main.rs")))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// `path` values of every dependency table, including `[target.*]` ones.
+fn path_dependencies(manifest: &Table) -> Vec<String> {
+    let mut tables: Vec<&Table> = Vec::new();
+    let sections = ["dependencies", "dev-dependencies", "build-dependencies"];
+    for section in sections {
+        tables.extend(manifest.get(section).and_then(Value::as_table));
+    }
+    for platform in manifest
+        .get("target")
+        .and_then(Value::as_table)
+        .into_iter()
+        .flat_map(|targets| targets.values())
+    {
+        for section in sections {
+            tables.extend(platform.get(section).and_then(Value::as_table));
+        }
+    }
+    tables
+        .into_iter()
+        .flat_map(|table| table.values())
+        .filter_map(|dep| dep.get("path")?.as_str().map(str::to_string))
+        .collect()
+}
+
+fn strings(table: &Table, key: &str) -> Vec<String> {
+    table
+        .get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|value| value.as_str().map(str::to_string))
+        .collect()
+}
+
+fn parse(manifest_path: &Path) -> Result<Table> {
+    let text = std::fs::read_to_string(manifest_path).map_err(|source| RustlocError::FileRead {
+        path: manifest_path.to_path_buf(),
+        source,
+    })?;
+    text.parse::<Table>()
+        .map_err(|e| RustlocError::InvalidManifest {
+            path: manifest_path.to_path_buf(),
+            message: e.message().to_string(),
+        })
+}
+
+fn parent(path: &Path) -> PathBuf {
+    path.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+/// Absolute, with `.` and `..` folded lexically the way Cargo reports
+/// manifest paths (symlinks are left alone).
+pub(crate) fn normalize(path: &Path) -> Result<PathBuf> {
+    let absolute = std::path::absolute(path)?;
+    let mut normalized = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    Ok(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::workspace::WorkspaceDiscovery;
+
+    fn write(root: &Path, file: &str, content: &str) {
+        let path = root.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    fn package_manifest(name: &str) -> String {
+        format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n")
+    }
+
+    /// A virtual workspace exercising globs, `exclude`, an implicit path
+    /// dependency member, `default-members`, and inferred plus explicit targets.
+    fn workspace(root: &Path) {
+        write(
+            root,
+            "Cargo.toml",
+            r#"[workspace]
+members = ["crates/*"]
+exclude = ["crates/scratch"]
+default-members = ["crates/app"]
+"#,
+        );
+        write(
+            root,
+            "crates/app/Cargo.toml",
+            &format!(
+                "{}\n[dependencies]\nshared = {{ path = \"../../shared\" }}\n\n\
+                 [[bin]]\nname = \"tool\"\npath = \"cli/tool.rs\"\n",
+                package_manifest("my-app")
+            ),
+        );
+        for file in [
+            "crates/app/src/lib.rs",
+            "crates/app/src/main.rs",
+            "crates/app/src/bin/extra.rs",
+            "crates/app/src/bin/multi/main.rs",
+            "crates/app/cli/tool.rs",
+            "crates/app/tests/it.rs",
+            "crates/app/examples/demo.rs",
+            "crates/app/benches/speed.rs",
+            "crates/app/build.rs",
+            "shared/src/lib.rs",
+        ] {
+            write(root, file, "");
+        }
+        write(root, "shared/Cargo.toml", &package_manifest("shared"));
+        write(
+            root,
+            "crates/scratch/Cargo.toml",
+            &package_manifest("scratch"),
+        );
+        write(root, "crates/broken/Cargo.toml", "[package\n");
+        write(root, "crates/notes/README.md", "");
+    }
+
+    #[test]
+    fn reads_members_targets_and_default_members() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        workspace(root);
+
+        let info =
+            read_workspace(&root.join("crates/app/Cargo.toml"), false, &mut Vec::new()).unwrap();
+        assert_eq!(info.root, normalize(root).unwrap());
+        assert_eq!(info.crate_names(), ["my-app", "shared"]);
+        assert_eq!(info.default_members, ["my-app"]);
+
+        let app = info.get_crate("my-app").unwrap();
+        let targets: Vec<_> = app
+            .targets
+            .iter()
+            .map(|t| (t.kind, t.name.as_str()))
+            .collect();
+        assert_eq!(
+            targets,
+            [
+                (TargetKind::Lib, "my_app"),
+                (TargetKind::Bin, "tool"),
+                (TargetKind::Bin, "extra"),
+                (TargetKind::Bin, "multi"),
+                (TargetKind::Bin, "my-app"),
+                (TargetKind::Example, "demo"),
+                (TargetKind::Test, "it"),
+                (TargetKind::Bench, "speed"),
+                (TargetKind::CustomBuild, "build-script-build"),
+            ]
+        );
+    }
+
+    #[test]
+    fn auto_switches_and_build_false_drop_inferred_targets() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write(
+            root,
+            "Cargo.toml",
+            &format!(
+                "{}autotests = false\nbuild = false\n",
+                package_manifest("plain")
+            ),
+        );
+        for file in ["src/lib.rs", "tests/it.rs", "build.rs"] {
+            write(root, file, "");
+        }
+
+        let info = read_workspace(&root.join("Cargo.toml"), false, &mut Vec::new()).unwrap();
+        let kinds: Vec<_> = info.crates[0].targets.iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, [TargetKind::Lib]);
+        assert_eq!(info.default_members, ["plain"]);
+    }
+
+    #[test]
+    fn an_unreadable_root_manifest_is_an_error() {
+        let temp = tempfile::tempdir().unwrap();
+        write(temp.path(), "Cargo.toml", "[package\n");
+        let err =
+            read_workspace(&temp.path().join("Cargo.toml"), false, &mut Vec::new()).unwrap_err();
+        assert!(matches!(err, RustlocError::InvalidManifest { .. }), "{err}");
+    }
+
+    #[test]
+    fn an_unparsable_member_is_a_diagnostic_unless_strict() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        workspace(root);
+        let manifest = root.join("Cargo.toml");
+
+        let mut diagnostics = Vec::new();
+        let info = read_workspace(&manifest, false, &mut diagnostics).unwrap();
+        assert_eq!(info.crate_names(), ["my-app", "shared"]);
+        let [diagnostic] = diagnostics.as_slice() else {
+            panic!("expected one diagnostic: {diagnostics:?}");
+        };
+        assert!(diagnostic.path.ends_with("crates/broken/Cargo.toml"));
+
+        let err = read_workspace(&manifest, true, &mut Vec::new()).unwrap_err();
+        assert!(matches!(err, RustlocError::InvalidManifest { .. }), "{err}");
+    }
+
+    #[test]
+    fn agrees_with_cargo_metadata_on_this_repository() {
+        let repo = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+        let native = WorkspaceInfo::discover_with(&repo, WorkspaceDiscovery::Manifest).unwrap();
+        let metadata =
+            WorkspaceInfo::discover_with(&repo, WorkspaceDiscovery::CargoMetadata).unwrap();
+
+        assert_eq!(native.root, metadata.root);
+        assert_eq!(native.crate_names(), metadata.crate_names());
+        assert_eq!(native.default_members, metadata.default_members);
+        for (native, metadata) in native.crates.iter().zip(&metadata.crates) {
+            let mut expected = metadata.targets.clone();
+            let mut actual = native.targets.clone();
+            expected.sort_by(|a, b| a.src_path.cmp(&b.src_path));
+            actual.sort_by(|a, b| a.src_path.cmp(&b.src_path));
+            assert_eq!(actual, expected, "targets of {}", native.name);
+        }
+    }
+}
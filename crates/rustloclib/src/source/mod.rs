@@ -3,8 +3,11 @@
 //! This module handles the first stage of the pipeline - discovering what
 //! files to count. It provides:
 //!
-//! - **Workspace discovery**: Find crates in a Cargo workspace
+//! - **Workspace discovery**: Find crates in a Cargo workspace from its manifests
 //! - **File filtering**: Include/exclude files with glob patterns
+//! - **Dependency discovery**: Resolve third-party crates from the local Cargo caches
+//! - **Build modules**: Find Maven/Gradle/Zig modules and Ruby gems for per-module aggregation
+//! - **Project model**: Find Cargo, Python, Node and Go packages for per-package aggregation
 //!
 //! ## Example
 //!
@@ -19,8 +22,29 @@
 //!     .exclude("**/generated/**")?;
 //! ```
 
+pub mod dependencies;
 pub mod filter;
+pub(crate) mod manifest;
+pub mod modules;
+pub mod project;
 pub mod workspace;
 
-pub use filter::{discover_files, discover_files_in_dirs, FilterConfig};
-pub use workspace::{CrateInfo, WorkspaceInfo};
+pub use dependencies::{
+    discover_dependencies, Dependency, DependencyKind, DependencyQuery, DependencySource,
+};
+pub use filter::{
+    discover_files, discover_files_in_dirs, discover_files_in_dirs_with_report,
+    discover_files_with_report, DiscoveredFiles, FilterConfig, LinkSkipReason, SkippedLink,
+    SymlinkPolicy, IGNORE_FILE,
+};
+pub use modules::{
+    discover_build_modules, discover_jvm_modules, discover_ruby_modules, discover_zig_modules,
+    BuildModule,
+};
+pub use project::{
+    CargoProvider, Ecosystem, GoProvider, NodeProvider, Package, PackageProvider, ProjectModel,
+    PythonProvider,
+};
+pub use workspace::{
+    CrateInfo, DiscoveredWorkspaces, TargetInfo, TargetKind, WorkspaceDiscovery, WorkspaceInfo,
+};
@@ -0,0 +1,784 @@
+//! Build-tool module discovery for non-Cargo trees.
+//!
+//! Cargo workspaces describe their crates through `cargo metadata`; other
+//! ecosystems declare their modules in their own build files. This module reads
+//! those declarations so directory counts can be aggregated per build module
+//! instead of per directory.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::data::backend::LanguageName;
+use crate::error::RustlocError;
+use crate::Result;
+
+use super::filter::{discover_manifests, discover_manifests_matching, FilterConfig};
+use super::manifest::normalize;
+
+/// A module declared by a build tool (a Maven module, a Gradle project, a Zig
+/// package, a Ruby gem, ...).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildModule {
+    /// Module name as the build tool reports it (`core`, `app:api`)
+    pub name: String,
+    /// Root directory of the module
+    pub root: PathBuf,
+}
+
+impl BuildModule {
+    fn new(name: impl Into<String>, root: PathBuf) -> Self {
+        Self {
+            name: name.into(),
+            root,
+        }
+    }
+}
+
+/// Discover build modules under `root` for the filter's languages.
+///
+/// Build files are found with the filter's walk settings, so ignored paths and
+/// its symlink policy apply as they do to source discovery.
+///
+/// Modules are returned deepest-first, so the first module whose root contains
+/// a file is the most specific one.
+pub fn discover_build_modules(
+    root: impl AsRef<Path>,
+    filter: &FilterConfig,
+) -> Result<Vec<BuildModule>> {
+    let root = root.as_ref();
+    let languages = &filter.languages;
+    let mut modules = Vec::new();
+    if languages.contains(LanguageName::Jvm) {
+        modules.extend(discover_jvm_modules(root, filter)?);
+    }
+    if languages.contains(LanguageName::Zig) {
+        modules.extend(discover_zig_modules(root, filter)?);
+    }
+    if languages.contains(LanguageName::Ruby) {
+        modules.extend(discover_ruby_modules(root, filter)?);
+    }
+    modules.sort_by(|a, b| {
+        b.root
+            .components()
+            .count()
+            .cmp(&a.root.components().count())
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    modules.dedup_by(|a, b| a.root == b.root);
+    Ok(modules)
+}
+
+/// Discover Maven (`pom.xml` `<modules>`) and Gradle (`settings.gradle(.kts)`
+/// `include`) modules declared under `root`.
+///
+/// Build files are found with the filter's walk settings, so a `pom.xml` or
+/// settings script in an ignored directory declares nothing, as with the other
+/// ecosystems.
+pub fn discover_jvm_modules(
+    root: impl AsRef<Path>,
+    filter: &FilterConfig,
+) -> Result<Vec<BuildModule>> {
+    let root = root.as_ref();
+    let reached = discover_manifests(
+        root,
+        filter,
+        &["pom.xml", "settings.gradle.kts", "settings.gradle"],
+    )?
+    .iter()
+    .map(|path| normalize(path))
+    .collect::<Result<HashSet<_>>>()?;
+    let mut modules = Vec::new();
+    let mut visited = HashSet::new();
+    discover_maven_modules(root, "", &reached, &mut visited, &mut modules)?;
+    discover_gradle_modules(root, &reached, &mut modules)?;
+    Ok(modules)
+}
+
+/// The contents of the build file `path` if the walk `reached` it.
+fn read_reached(reached: &HashSet<PathBuf>, path: &Path) -> Result<Option<String>> {
+    if !reached.contains(&normalize(path)?) {
+        return Ok(None);
+    }
+    read_optional(path)
+}
+
+/// `visited` holds the canonical directories already read, so a `<module>`
+/// that leads back to a parent (`..`) is not followed again.
+fn discover_maven_modules(
+    dir: &Path,
+    prefix: &str,
+    reached: &HashSet<PathBuf>,
+    visited: &mut HashSet<PathBuf>,
+    modules: &mut Vec<BuildModule>,
+) -> Result<()> {
+    if !visited.insert(dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())) {
+        return Ok(());
+    }
+    let Some(pom) = read_reached(reached, &dir.join("pom.xml"))? else {
+        return Ok(());
+    };
+
+    for module in xml_elements(&pom, "module") {
+        let module_dir = dir.join(module);
+        if !module_dir.is_dir() {
+            continue;
+        }
+        let canonical = module_dir
+            .canonicalize()
+            .unwrap_or_else(|_| module_dir.clone());
+        if visited.contains(&canonical) {
+            continue;
+        }
+        let name = format!("{}{}", prefix, module.trim_end_matches('/'));
+        discover_maven_modules(
+            &module_dir,
+            &format!("{}/", name),
+            reached,
+            visited,
+            modules,
+        )?;
+        modules.push(BuildModule::new(name, module_dir));
+    }
+    Ok(())
+}
+
+fn discover_gradle_modules(
+    root: &Path,
+    reached: &HashSet<PathBuf>,
+    modules: &mut Vec<BuildModule>,
+) -> Result<()> {
+    let mut settings = None;
+    for name in ["settings.gradle.kts", "settings.gradle"] {
+        settings = read_reached(reached, &root.join(name))?;
+        if settings.is_some() {
+            break;
+        }
+    }
+    let Some(settings) = settings else {
+        return Ok(());
+    };
+
+    for (flat, project) in gradle_includes(&settings) {
+        let name = project.trim_start_matches(':');
+        if name.is_empty() {
+            continue;
+        }
+        // `includeFlat` projects are siblings of the root project.
+        let module_dir = if flat {
+            root.parent().unwrap_or(root).join(name)
+        } else {
+            root.join(name.replace(':', "/"))
+        };
+        if module_dir.is_dir() {
+            modules.push(BuildModule::new(name, module_dir));
+        }
+    }
+    Ok(())
+}
+
+/// `(flat, project)` for every project named by an `include` or
+/// `includeFlat` statement of a Gradle settings script. Statements may span
+/// lines: `include(` lists up to their `)`, and Groovy `include 'a',` lists
+/// while a line ends with a comma. Projects computed at runtime (`include(x)`,
+/// loops over directories) are not seen.
+fn gradle_includes(settings: &str) -> Vec<(bool, String)> {
+    const KEYWORD: &str = "include";
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let code = strip_gradle_comments(settings);
+    let mut projects = Vec::new();
+    let mut rest = code.as_str();
+    while let Some(start) = rest.find(KEYWORD) {
+        let qualified = rest[..start].ends_with(|c: char| is_ident(c) || c == '.');
+        let after = &rest[start + KEYWORD.len()..];
+        let (flat, args) = match after.strip_prefix("Flat") {
+            Some(args) => (true, args),
+            None => (false, after),
+        };
+        rest = args;
+        if qualified || args.starts_with(is_ident) {
+            continue;
+        }
+        let args = gradle_arguments(args);
+        projects.extend(
+            quoted_strings(args)
+                .into_iter()
+                .map(|project| (flat, project.to_string())),
+        );
+        rest = &rest[args.len()..];
+    }
+    projects
+}
+
+/// The argument text of a Gradle call starting at `after`.
+fn gradle_arguments(after: &str) -> &str {
+    let start = after.len() - after.trim_start_matches([' ', '\t']).len();
+    if after[start..].starts_with('(') {
+        let end = after[start..]
+            .find(')')
+            .map_or(after.len(), |end| start + end);
+        return &after[..end];
+    }
+    let mut end = 0;
+    for line in after.split_inclusive('\n') {
+        end += line.len();
+        if !line.trim_end().ends_with(',') {
+            break;
+        }
+    }
+    &after[..end]
+}
+
+/// A Groovy/Kotlin script with its `//` and `/* */` comments removed; line
+/// breaks are kept.
+fn strip_gradle_comments(script: &str) -> String {
+    let mut code = String::with_capacity(script.len());
+    let mut chars = script.chars().peekable();
+    let mut quote = None;
+    while let Some(ch) = chars.next() {
+        if let Some(open) = quote {
+            code.push(ch);
+            if ch == open {
+                quote = None;
+            }
+            continue;
+        }
+        match (ch, chars.peek()) {
+            ('"' | '\'', _) => {
+                quote = Some(ch);
+                code.push(ch);
+            }
+            ('/', Some('/')) => while chars.next_if(|&next| next != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    if next == '\n' {
+                        code.push('\n');
+                    }
+                    previous = next;
+                }
+            }
+            _ => code.push(ch),
+        }
+    }
+    code
+}
+
+/// Discover Zig packages and modules from the `build.zig` files under `root`.
+///
+/// Every nested package (a directory with its own `build.zig`) is a module,
+/// named after its `build.zig.zon` `.name` or its directory. Within a
+/// `build.zig`, `addModule`/`addExecutable`/`addLibrary`-style declarations
+/// whose `root_source_file` lives in a subdirectory make that subdirectory a
+/// module named after the declaration.
+///
+/// `build.zig` files are found with the filter's walk settings; those under
+/// the `zig-cache`/`zig-out` build directories are left out.
+pub fn discover_zig_modules(
+    root: impl AsRef<Path>,
+    filter: &FilterConfig,
+) -> Result<Vec<BuildModule>> {
+    let root = root.as_ref();
+    let mut modules = Vec::new();
+
+    let build_files = discover_manifests(root, filter, &["build.zig"])?
+        .into_iter()
+        .filter(|path| !inside_any(root, path, &["zig-cache", "zig-out"]));
+
+    for build_file in build_files {
+        let Some(dir) = build_file.parent() else {
+            continue;
+        };
+        if dir != root {
+            modules.push(BuildModule::new(zig_package_name(dir)?, dir.to_path_buf()));
+        }
+        let Some(build) = read_optional(&build_file)? else {
+            continue;
+        };
+        for (name, source_file) in zig_build_declarations(&build) {
+            let Some(module_dir) = dir.join(source_file).parent().map(Path::to_path_buf) else {
+                continue;
+            };
+            if module_dir != dir && module_dir.is_dir() {
+                modules.push(BuildModule::new(name, module_dir));
+            }
+        }
+    }
+    Ok(modules)
+}
+
+/// Whether `path` lies in a directory named one of `dirs` beneath `root`.
+fn inside_any(root: &Path, path: &Path, dirs: &[&str]) -> bool {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .parent()
+        .is_some_and(|parent| {
+            parent
+                .components()
+                .any(|dir| dirs.iter().any(|name| dir.as_os_str() == *name))
+        })
+}
+
+fn zig_package_name(dir: &Path) -> Result<String> {
+    let fallback = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let Some(zon) = read_optional(&dir.join("build.zig.zon"))? else {
+        return Ok(fallback);
+    };
+    // `.name = "pkg"` (Zig 0.13) or `.name = .pkg` (Zig 0.14+).
+    let name = zon.find(".name").and_then(|start| {
+        let value = zon[start + ".name".len()..]
+            .trim_start()
+            .strip_prefix('=')?
+            .trim_start();
+        let value = value
+            .strip_prefix('"')
+            .or_else(|| value.strip_prefix('.'))?;
+        let end = value.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))?;
+        Some(value[..end].to_string())
+    });
+    Ok(name.filter(|name| !name.is_empty()).unwrap_or(fallback))
+}
+
+/// `(name, root_source_file)` for each module or artifact declared in a
+/// `build.zig`.
+fn zig_build_declarations(build: &str) -> Vec<(String, String)> {
+    const CALLS: &[&str] = &[
+        "addModule(",
+        "addExecutable(",
+        "addLibrary(",
+        "addStaticLibrary(",
+        "addSharedLibrary(",
+    ];
+
+    let mut starts: Vec<(usize, &str)> = CALLS
+        .iter()
+        .flat_map(|call| build.match_indices(call))
+        .collect();
+    starts.sort();
+
+    let mut declarations = Vec::new();
+    for (position, &(start, call)) in starts.iter().enumerate() {
+        let end = starts
+            .get(position + 1)
+            .map_or(build.len(), |&(next, _)| next);
+        let args = &build[start + call.len()..end];
+        let name = if call == "addModule(" {
+            quoted_strings(args).first().copied()
+        } else {
+            field_string(args, ".name")
+        };
+        let source_file = field_string(args, ".root_source_file");
+        if let (Some(name), Some(source_file)) = (name, source_file) {
+            declarations.push((name.to_string(), source_file.to_string()));
+        }
+    }
+    declarations
+}
+
+/// Discover Ruby gems from the `*.gemspec` files under `root`.
+///
+/// Each gemspec makes its directory a module named after its `.name`, or the
+/// gemspec file name when the name is computed.
+///
+/// Gemspecs are found with the filter's walk settings; installed gems under
+/// `vendor` or `node_modules` are left out.
+pub fn discover_ruby_modules(
+    root: impl AsRef<Path>,
+    filter: &FilterConfig,
+) -> Result<Vec<BuildModule>> {
+    let root = root.as_ref();
+    let mut modules = Vec::new();
+
+    let is_gemspec = |path: &Path| path.extension().is_some_and(|ext| ext == "gemspec");
+    let gemspecs = discover_manifests_matching(root, filter, &is_gemspec)?
+        .into_iter()
+        .filter(|path| !inside_any(root, path, &["vendor", "node_modules"]));
+
+    for path in gemspecs {
+        let Some(dir) = path.parent() else {
+            continue;
+        };
+        let Some(gemspec) = read_optional(&path)? else {
+            continue;
+        };
+        let name = gemspec
+            .lines()
+            .find_map(|line| field_string(line.trim(), ".name"))
+            .map(str::to_string)
+            .or_else(|| {
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+            })
+            .unwrap_or_default();
+        modules.push(BuildModule::new(name, dir.to_path_buf()));
+    }
+    Ok(modules)
+}
+
+/// The first string literal after `field` on the same line of `args`.
+fn field_string<'a>(args: &'a str, field: &str) -> Option<&'a str> {
+    let rest = &args[args.find(field)? + field.len()..];
+    let line = rest.split('\n').next().unwrap_or_default();
+    quoted_strings(line).first().copied()
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    fs::read_to_string(path)
+        .map(Some)
+        .map_err(|source| RustlocError::FileRead {
+            path: path.to_path_buf(),
+            source,
+        })
+}
+
+/// Text content of every `<tag>...</tag>` element, outside XML comments.
+fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut elements = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find(&open) {
+        if let Some(comment) = rest[..start].find("<!--") {
+            match rest[comment..].find("-->") {
+                Some(end) => {
+                    rest = &rest[comment + end + 3..];
+                    continue;
+                }
+                None => break,
+            }
+        }
+        let body = &rest[start + open.len()..];
+        let Some(end) = body.find(&close) else {
+            break;
+        };
+        elements.push(body[..end].trim());
+        rest = &body[end + close.len()..];
+    }
+    elements
+}
+
+/// Single- or double-quoted strings on a Groovy/Kotlin DSL line.
+fn quoted_strings(line: &str) -> Vec<&str> {
+    let code = line.split("//").next().unwrap_or_default();
+    let mut strings = Vec::new();
+    let mut rest = code;
+    while let Some(start) = rest.find(['"', '\'']) {
+        let quote = rest[start..].chars().next().unwrap_or('"');
+        let body = &rest[start + 1..];
+        let Some(end) = body.find(quote) else {
+            break;
+        };
+        strings.push(&body[..end]);
+        rest = &body[end + 1..];
+    }
+    strings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::backend::LanguageSelection;
+    use tempfile::tempdir;
+
+    #[test]
+    fn discovers_nested_maven_modules() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("core")).unwrap();
+        fs::create_dir_all(root.join("services/api")).unwrap();
+        fs::write(
+            root.join("pom.xml"),
+            "<project><modules>\n  <module>core</module>\n  <!-- <module>old</module> -->\n  <module>services</module>\n</modules></project>\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("services/pom.xml"),
+            "<project><modules><module>api</module></modules></project>\n",
+        )
+        .unwrap();
+
+        let modules = discover_jvm_modules(root, &FilterConfig::new()).unwrap();
+        let names: Vec<_> = modules.iter().map(|m| m.name.as_str()).collect();
+
+        assert_eq!(names, vec!["core", "services/api", "services"]);
+        assert_eq!(modules[1].root, root.join("services/api"));
+    }
+
+    #[test]
+    fn discovers_gradle_projects_from_kotlin_and_groovy_settings() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("app/api")).unwrap();
+        fs::create_dir_all(root.join("lib")).unwrap();
+        fs::write(
+            root.join("settings.gradle.kts"),
+            "rootProject.name = \"demo\"\ninclude(\":app:api\", \"lib\")\n// include(\"gone\")\nincludeBuild(\"build-logic\")\n",
+        )
+        .unwrap();
+
+        let modules = discover_jvm_modules(root, &FilterConfig::new()).unwrap();
+
+        assert_eq!(
+            modules,
+            vec![
+                BuildModule::new("app:api", root.join("app/api")),
+                BuildModule::new("lib", root.join("lib")),
+            ]
+        );
+
+        fs::remove_file(root.join("settings.gradle.kts")).unwrap();
+        fs::write(root.join("settings.gradle"), "include 'lib'\n").unwrap();
+        assert_eq!(
+            discover_jvm_modules(root, &FilterConfig::new()).unwrap(),
+            vec![BuildModule::new("lib", root.join("lib"))]
+        );
+    }
+
+    #[test]
+    fn gradle_includes_may_span_lines_and_include_flat_siblings() {
+        let temp = tempdir().unwrap();
+        let root = temp.path().join("main");
+        for dir in ["main/app", "main/lib", "main/tools", "shared"] {
+            fs::create_dir_all(temp.path().join(dir)).unwrap();
+        }
+        fs::write(
+            root.join("settings.gradle.kts"),
+            "include(\n    \":app\", // the service\n    \":lib\",\n)\n/* include(\"gone\") */\nincludeFlat(\"shared\")\n",
+        )
+        .unwrap();
+        assert_eq!(
+            discover_jvm_modules(&root, &FilterConfig::new()).unwrap(),
+            vec![
+                BuildModule::new("app", root.join("app")),
+                BuildModule::new("lib", root.join("lib")),
+                BuildModule::new("shared", temp.path().join("shared")),
+            ]
+        );
+
+        fs::remove_file(root.join("settings.gradle.kts")).unwrap();
+        fs::write(
+            root.join("settings.gradle"),
+            "include 'app',\n        'tools'\nrootProject.name = 'main'\n",
+        )
+        .unwrap();
+        assert_eq!(
+            discover_jvm_modules(&root, &FilterConfig::new()).unwrap(),
+            vec![
+                BuildModule::new("app", root.join("app")),
+                BuildModule::new("tools", root.join("tools")),
+            ]
+        );
+    }
+
+    #[test]
+    fn jvm_build_files_follow_the_ignore_settings() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("legacy/core")).unwrap();
+        fs::write(root.join(".gitignore"), "legacy/\n").unwrap();
+        fs::write(
+            root.join("legacy/pom.xml"),
+            "<modules><module>core</module></modules>",
+        )
+        .unwrap();
+        fs::write(
+            root.join("pom.xml"),
+            "<modules><module>legacy</module></modules>",
+        )
+        .unwrap();
+
+        assert_eq!(
+            discover_jvm_modules(root, &FilterConfig::new()).unwrap(),
+            vec![BuildModule::new("legacy", root.join("legacy"))]
+        );
+        assert_eq!(
+            discover_jvm_modules(root, &FilterConfig::new().no_ignore(true)).unwrap(),
+            vec![
+                BuildModule::new("legacy/core", root.join("legacy/core")),
+                BuildModule::new("legacy", root.join("legacy")),
+            ]
+        );
+    }
+
+    #[test]
+    fn maven_module_cycles_are_followed_once() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("core")).unwrap();
+        fs::write(
+            root.join("pom.xml"),
+            "<modules><module>core</module></modules>",
+        )
+        .unwrap();
+        fs::write(
+            root.join("core/pom.xml"),
+            "<modules><module>..</module></modules>",
+        )
+        .unwrap();
+
+        assert_eq!(
+            discover_jvm_modules(root, &FilterConfig::new()).unwrap(),
+            vec![BuildModule::new("core", root.join("core"))]
+        );
+    }
+
+    #[test]
+    fn discovers_zig_packages_and_build_modules() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("src/net")).unwrap();
+        fs::create_dir_all(root.join("libs/codec/src")).unwrap();
+        fs::create_dir_all(root.join("zig-out/pkg")).unwrap();
+        fs::write(
+            root.join("build.zig"),
+            r#"pub fn build(b: *std.Build) void {
+    const net = b.addModule("net", .{ .root_source_file = b.path("src/net/root.zig") });
+    const exe = b.addExecutable(.{
+        .name = "server",
+        .root_source_file = b.path("src/main.zig"),
+    });
+}
+"#,
+        )
+        .unwrap();
+        fs::write(root.join("libs/codec/build.zig"), "").unwrap();
+        fs::write(
+            root.join("libs/codec/build.zig.zon"),
+            ".{\n    .name = .codec_lib,\n    .version = \"0.1.0\",\n}\n",
+        )
+        .unwrap();
+        fs::write(root.join("zig-out/pkg/build.zig"), "").unwrap();
+
+        let mut modules = discover_zig_modules(root, &FilterConfig::new()).unwrap();
+        modules.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!(
+            modules,
+            vec![
+                BuildModule::new("codec_lib", root.join("libs/codec")),
+                BuildModule::new("net", root.join("src/net")),
+                BuildModule::new("server", root.join("src")),
+            ]
+        );
+    }
+
+    #[test]
+    fn zig_build_files_follow_the_ignore_settings() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("third_party/vendored")).unwrap();
+        fs::write(root.join(".gitignore"), "third_party/\n").unwrap();
+        fs::write(root.join("build.zig"), "").unwrap();
+        fs::write(root.join("third_party/vendored/build.zig"), "").unwrap();
+
+        assert!(discover_zig_modules(root, &FilterConfig::new())
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            discover_zig_modules(root, &FilterConfig::new().no_ignore(true)).unwrap(),
+            vec![BuildModule::new(
+                "vendored",
+                root.join("third_party/vendored")
+            )]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn zig_discovery_survives_symlink_loops() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("libs/codec")).unwrap();
+        fs::write(root.join("libs/codec/build.zig"), "").unwrap();
+        std::os::unix::fs::symlink(root, root.join("libs/codec/back")).unwrap();
+
+        assert_eq!(
+            discover_zig_modules(root, &FilterConfig::new()).unwrap(),
+            vec![BuildModule::new("codec", root.join("libs/codec"))]
+        );
+    }
+
+    #[test]
+    fn discovers_ruby_gems_from_gemspecs() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("gems/billing")).unwrap();
+        fs::create_dir_all(root.join("vendor/bundle/rack")).unwrap();
+        fs::write(
+            root.join("shop.gemspec"),
+            "Gem::Specification.new do |spec|\n  spec.name    = \"shop\"\n  spec.version = \"1.0\"\nend\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("gems/billing/billing-core.gemspec"),
+            "Gem::Specification.new do |s|\n  s.name = NAME\nend\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("vendor/bundle/rack/rack.gemspec"),
+            "Gem::Specification.new { |s| s.name = 'rack' }\n",
+        )
+        .unwrap();
+
+        let mut modules = discover_ruby_modules(root, &FilterConfig::new()).unwrap();
+        modules.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!(
+            modules,
+            vec![
+                BuildModule::new("billing-core", root.join("gems/billing")),
+                BuildModule::new("shop", root.to_path_buf()),
+            ]
+        );
+    }
+
+    #[test]
+    fn gemspecs_follow_the_ignore_settings() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("tmp/checkout")).unwrap();
+        fs::write(root.join(".rustlocignore"), "tmp/\n").unwrap();
+        fs::write(
+            root.join("tmp/checkout/stale.gemspec"),
+            "Gem::Specification.new { |s| s.name = 'stale' }\n",
+        )
+        .unwrap();
+
+        assert!(discover_ruby_modules(root, &FilterConfig::new())
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            discover_ruby_modules(root, &FilterConfig::new().no_ignore(true)).unwrap(),
+            vec![BuildModule::new("stale", root.join("tmp/checkout"))]
+        );
+    }
+
+    #[test]
+    fn build_modules_follow_the_language_selection() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("core")).unwrap();
+        fs::write(
+            root.join("pom.xml"),
+            "<modules><module>core</module></modules>",
+        )
+        .unwrap();
+
+        assert!(discover_build_modules(root, &FilterConfig::new())
+            .unwrap()
+            .is_empty());
+        let jvm = FilterConfig::new().languages(LanguageSelection::new(&[LanguageName::Jvm]));
+        assert_eq!(discover_build_modules(root, &jvm).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,977 @@
+//! Language-agnostic project model.
+//!
+//! A Cargo workspace describes its crates, but most trees are not Cargo
+//! workspaces: Python projects declare packages in `pyproject.toml`, Node
+//! projects in `package.json`, Go in `go.mod`, and mixed repositories hold all
+//! of them side by side. This module finds those packages so per-package
+//! (`--by-crate`) aggregation works on any tree.
+//!
+//! Each ecosystem has a [`PackageProvider`]. Members of uv workspaces and
+//! `go.work` files have manifests of their own, which the providers find
+//! directly along with standalone packages. JavaScript workspaces also read
+//! their declarations (`workspaces`, `pnpm-workspace.yaml`, tsconfig
+//! `references`), since members there need not be named packages. Manifests
+//! are found by the same walk as source files, so ignore files apply to them.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+
+use super::filter::{discover_manifests, FilterConfig};
+use super::manifest::normalize;
+use super::workspace::{CrateInfo, WorkspaceDiscovery, WorkspaceInfo};
+
+/// The build ecosystem a package was declared in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ecosystem {
+    /// A Cargo crate (`Cargo.toml`)
+    Cargo,
+    /// A Python project (`pyproject.toml`)
+    Python,
+    /// A Node package (`package.json`)
+    Node,
+    /// A Go module (`go.mod`)
+    Go,
+}
+
+impl Ecosystem {
+    /// The ecosystem's name.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo",
+            Self::Python => "python",
+            Self::Node => "node",
+            Self::Go => "go",
+        }
+    }
+}
+
+impl fmt::Display for Ecosystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A package declared by a build tool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    /// Package name as its manifest declares it (`my-crate`, `@scope/ui`,
+    /// `example.com/svc`)
+    pub name: String,
+    /// Directory holding the manifest
+    pub root: PathBuf,
+    /// Directory imports resolve from: `src/` in a src-layout Python project,
+    /// the package root otherwise
+    pub source_root: PathBuf,
+    /// Where the package was declared
+    pub ecosystem: Ecosystem,
+}
+
+impl Package {
+    /// Create a package.
+    pub fn new(name: impl Into<String>, root: impl Into<PathBuf>, ecosystem: Ecosystem) -> Self {
+        let root = root.into();
+        Self {
+            name: name.into(),
+            source_root: root.clone(),
+            root,
+            ecosystem,
+        }
+    }
+
+    /// Set the directory imports resolve from.
+    pub fn with_source_root(mut self, source_root: impl Into<PathBuf>) -> Self {
+        self.source_root = source_root.into();
+        self
+    }
+}
+
+impl From<&CrateInfo> for Package {
+    fn from(crate_info: &CrateInfo) -> Self {
+        Self::new(
+            crate_info.name.clone(),
+            crate_info.root.clone(),
+            Ecosystem::Cargo,
+        )
+    }
+}
+
+/// Finds the packages one build ecosystem declares beneath a directory.
+///
+/// [`ProjectModel::discover`] runs the built-in providers
+/// ([`CargoProvider`], [`PythonProvider`], [`NodeProvider`], [`GoProvider`]);
+/// [`ProjectModel::discover_with`] runs any others alongside or instead.
+pub trait PackageProvider: Send + Sync {
+    /// File names of the manifests the provider reads, such as `go.mod`.
+    fn manifest_names(&self) -> &[&str];
+
+    /// The packages declared by `manifests`: the files beneath `root` with
+    /// one of the [`manifest_names`](Self::manifest_names).
+    fn packages(&self, root: &Path, manifests: &[PathBuf]) -> Result<Vec<Package>>;
+
+    /// Packages declared beneath `root`. Manifests are found with `filter`'s
+    /// walk settings (ignore files, tracked files only, symbolic links); its
+    /// languages and patterns do not apply.
+    fn discover(&self, root: &Path, filter: &FilterConfig) -> Result<Vec<Package>> {
+        let manifests = discover_manifests(root, filter, self.manifest_names())?;
+        self.packages(root, &manifests)
+    }
+}
+
+/// Every package found beneath a directory, across ecosystems.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectModel {
+    /// Directory the model was discovered from
+    pub root: PathBuf,
+    /// Packages, deepest root first
+    pub packages: Vec<Package>,
+}
+
+impl ProjectModel {
+    /// Discover the packages of every built-in ecosystem beneath `root`.
+    ///
+    /// Cargo crates come from [`WorkspaceInfo::discover_all`] with the given
+    /// discovery method. When two ecosystems declare a package in the same
+    /// directory (a Rust extension with a `pyproject.toml`), the earlier
+    /// ecosystem in [`Ecosystem`] order wins.
+    pub fn discover(
+        root: impl AsRef<Path>,
+        discovery: WorkspaceDiscovery,
+        filter: &FilterConfig,
+    ) -> Result<Self> {
+        Self::discover_with(root, &Self::providers(discovery), filter)
+    }
+
+    /// The built-in providers, in [`Ecosystem`] order.
+    pub fn providers(discovery: WorkspaceDiscovery) -> Vec<Box<dyn PackageProvider>> {
+        let mut providers: Vec<Box<dyn PackageProvider>> =
+            vec![Box::new(CargoProvider { discovery })];
+        providers.extend(Self::non_cargo_providers());
+        providers
+    }
+
+    /// The built-in providers other than Cargo's, for a tree whose Cargo
+    /// workspace is already known.
+    pub fn non_cargo_providers() -> Vec<Box<dyn PackageProvider>> {
+        vec![
+            Box::new(PythonProvider),
+            Box::new(NodeProvider),
+            Box::new(GoProvider),
+        ]
+    }
+
+    /// Discover the packages `providers` find beneath `root`, in one walk
+    /// for all of their manifests. When several declare a package in the
+    /// same directory, the earliest provider wins.
+    pub fn discover_with(
+        root: impl AsRef<Path>,
+        providers: &[Box<dyn PackageProvider>],
+        filter: &FilterConfig,
+    ) -> Result<Self> {
+        let root = normalize(root.as_ref())?;
+        let names: Vec<&str> = providers
+            .iter()
+            .flat_map(|provider| provider.manifest_names().iter().copied())
+            .collect();
+        let found = discover_manifests(&root, filter, &names)?;
+
+        let mut packages = Vec::new();
+        for provider in providers {
+            let names = provider.manifest_names();
+            let manifests: Vec<PathBuf> = found
+                .iter()
+                .filter(|manifest| {
+                    manifest
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| names.contains(&name))
+                })
+                .cloned()
+                .collect();
+            packages.extend(provider.packages(&root, &manifests)?);
+        }
+
+        // Stable, so packages sharing a root keep provider order.
+        packages.sort_by(|a, b| {
+            b.root
+                .components()
+                .count()
+                .cmp(&a.root.components().count())
+                .then_with(|| a.root.cmp(&b.root))
+        });
+        packages.dedup_by(|a, b| a.root == b.root);
+        Ok(Self { root, packages })
+    }
+
+    /// The most specific package containing `path`.
+    ///
+    /// The path can be absolute or relative to the model's root.
+    pub fn package_for_path(&self, path: &Path) -> Option<&Package> {
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.root.join(path)
+        };
+        self.packages
+            .iter()
+            .find(|package| path.starts_with(&package.root))
+    }
+}
+
+/// Cargo crates of every workspace beneath a directory.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CargoProvider {
+    /// How each workspace's crates are discovered
+    pub discovery: WorkspaceDiscovery,
+}
+
+impl PackageProvider for CargoProvider {
+    fn manifest_names(&self) -> &[&str] {
+        &["Cargo.toml"]
+    }
+
+    /// Manifests that cannot be read are left out.
+    fn packages(&self, root: &Path, manifests: &[PathBuf]) -> Result<Vec<Package>> {
+        Ok(
+            WorkspaceInfo::from_manifests(root, manifests.to_vec(), self.discovery, false)?
+                .workspaces
+                .iter()
+                .flat_map(|workspace| workspace.crates.iter().map(Package::from))
+                .collect(),
+        )
+    }
+}
+
+/// Python distributions: each `pyproject.toml` with a `[project]` name
+/// (setuptools, hatch, uv and other PEP 621 backends) or a `[tool.poetry]`
+/// name, and each legacy `setup.cfg` with a `[metadata]` name. A uv workspace
+/// root without its own `[project]` is not a package; its members are.
+///
+/// The source root follows the build backend's package location
+/// (setuptools `package-dir`, poetry `packages.from`, hatch wheel
+/// `packages`), falling back to `src/` when the project has one. Manifests
+/// inside virtualenvs and `__pycache__` are installed copies, not packages.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PythonProvider;
+
+impl PackageProvider for PythonProvider {
+    fn manifest_names(&self) -> &[&str] {
+        &["pyproject.toml", "setup.cfg"]
+    }
+
+    fn packages(&self, root: &Path, manifests: &[PathBuf]) -> Result<Vec<Package>> {
+        let found: Vec<PathBuf> = manifests
+            .iter()
+            .filter(|manifest| !in_python_environment(root, manifest))
+            .cloned()
+            .collect();
+        Ok(python_packages(&found))
+    }
+}
+
+fn python_packages(found: &[PathBuf]) -> Vec<Package> {
+    let mut packages: Vec<Package> = named(found, "pyproject.toml")
+        .filter_map(|manifest| {
+            let table: toml::Table = std::fs::read_to_string(manifest).ok()?.parse().ok()?;
+            let project_root = parent(manifest);
+            let name = table
+                .get("project")
+                .and_then(|project| project.get("name"))
+                .or_else(|| toml_path(&table, &["tool", "poetry", "name"]))?
+                .as_str()?
+                .to_string();
+            let source_root = python_source_root(&project_root, &table);
+            Some(Package::new(name, project_root, Ecosystem::Python).with_source_root(source_root))
+        })
+        .collect();
+
+    for manifest in named(found, "setup.cfg") {
+        let project_root = parent(manifest);
+        if packages.iter().any(|package| package.root == project_root) {
+            continue;
+        }
+        let Ok(text) = std::fs::read_to_string(manifest) else {
+            continue;
+        };
+        if let Some(name) = setup_cfg_name(&text) {
+            let source_root = python_source_root(&project_root, &toml::Table::new());
+            packages.push(
+                Package::new(name, project_root, Ecosystem::Python).with_source_root(source_root),
+            );
+        }
+    }
+    packages
+}
+
+/// Whether `manifest` lies in a virtualenv (a directory holding
+/// `pyvenv.cfg`) or a `__pycache__` beneath `root`.
+fn in_python_environment(root: &Path, manifest: &Path) -> bool {
+    manifest
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| *dir != root && dir.starts_with(root))
+        .any(|dir| dir.ends_with("__pycache__") || dir.join("pyvenv.cfg").is_file())
+}
+
+/// The directory a Python project's top-level packages live in.
+fn python_source_root(project_root: &Path, pyproject: &toml::Table) -> PathBuf {
+    let declared = toml_path(pyproject, &["tool", "setuptools", "package-dir", ""])
+        .and_then(|dir| dir.as_str())
+        .map(str::to_string)
+        .or_else(|| {
+            toml_path(pyproject, &["tool", "poetry", "packages"])?
+                .as_array()?
+                .iter()
+                .find_map(|package| Some(package.get("from")?.as_str()?.to_string()))
+        })
+        .or_else(|| {
+            let package = toml_path(
+                pyproject,
+                &["tool", "hatch", "build", "targets", "wheel", "packages"],
+            )?
+            .as_array()?
+            .first()?
+            .as_str()?;
+            let parent = Path::new(package).parent()?;
+            Some(parent.to_string_lossy().into_owned())
+        });
+
+    match declared {
+        Some(dir) => project_root.join(dir),
+        None if project_root.join("src").is_dir() => project_root.join("src"),
+        None => project_root.to_path_buf(),
+    }
+}
+
+/// The `name` key of a `setup.cfg`'s `[metadata]` section.
+fn setup_cfg_name(text: &str) -> Option<String> {
+    let mut in_metadata = false;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_metadata = line == "[metadata]";
+        } else if in_metadata {
+            if let Some((key, value)) = line.split_once('=') {
+                if key.trim() == "name" && !value.trim().is_empty() {
+                    return Some(value.trim().to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+fn toml_path<'a>(table: &'a toml::Table, keys: &[&str]) -> Option<&'a toml::Value> {
+    let (first, rest) = keys.split_first()?;
+    rest.iter()
+        .try_fold(table.get(*first)?, |value, key| value.get(key))
+}
+
+/// Node packages: the members declared by `package.json` `workspaces` (npm,
+/// yarn, Turborepo), `pnpm-workspace.yaml` and tsconfig project `references`,
+/// Nx `project.json` projects, and every other `package.json` with a `name`.
+///
+/// A declared member without a name of its own is named by its path relative
+/// to the declaring workspace. An unnamed monorepo root is not a package, and
+/// installed dependencies under `node_modules` are left out.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NodeProvider;
+
+impl PackageProvider for NodeProvider {
+    fn manifest_names(&self) -> &[&str] {
+        &[
+            "package.json",
+            "project.json",
+            "pnpm-workspace.yaml",
+            "tsconfig.json",
+        ]
+    }
+
+    fn packages(&self, root: &Path, manifests: &[PathBuf]) -> Result<Vec<Package>> {
+        let found: Vec<PathBuf> = manifests
+            .iter()
+            .filter(|manifest| !in_dir_named(root, manifest, "node_modules"))
+            .cloned()
+            .collect();
+        Ok(node_packages(&found))
+    }
+}
+
+fn node_packages(found: &[PathBuf]) -> Vec<Package> {
+    let mut packages: Vec<Package> = Vec::new();
+    let mut push = |name: String, dir: PathBuf| {
+        if !packages.iter().any(|package| package.root == dir) {
+            packages.push(Package::new(name, dir, Ecosystem::Node));
+        }
+    };
+
+    for manifest in named(found, "package.json") {
+        if let Some(name) = read_json(manifest).and_then(|json| json_name(&json)) {
+            push(name, parent(manifest));
+        }
+    }
+    for manifest in named(found, "project.json") {
+        let Some(json) = read_json(manifest) else {
+            continue;
+        };
+        let is_nx = ["targets", "sourceRoot", "projectType"]
+            .iter()
+            .any(|key| json.get(key).is_some());
+        if let Some(name) = json_name(&json).filter(|_| is_nx) {
+            push(name, parent(manifest));
+        }
+    }
+
+    for (workspace, members) in node_workspace_members(found) {
+        for member in members {
+            let name = read_json(&member.join("package.json"))
+                .and_then(|json| json_name(&json))
+                .unwrap_or_else(|| {
+                    let relative = member.strip_prefix(&workspace).unwrap_or(&member);
+                    relative.to_string_lossy().replace('\\', "/")
+                });
+            push(name, member);
+        }
+    }
+    packages
+}
+
+/// Each Node workspace root among the `found` manifests with the member
+/// directories it declares.
+fn node_workspace_members(found: &[PathBuf]) -> Vec<(PathBuf, Vec<PathBuf>)> {
+    let mut workspaces = Vec::new();
+
+    for manifest in named(found, "package.json") {
+        let Some(json) = read_json(manifest) else {
+            continue;
+        };
+        // `"workspaces": [...]` or yarn's `"workspaces": { "packages": [...] }`
+        let declared = json.get("workspaces").and_then(|workspaces| {
+            workspaces
+                .as_array()
+                .or_else(|| workspaces.get("packages")?.as_array())
+        });
+        if let Some(patterns) = declared {
+            let patterns = patterns.iter().filter_map(|p| p.as_str());
+            let dir = parent(manifest);
+            let members = expand_members(&dir, patterns, "package.json");
+            workspaces.push((dir, members));
+        }
+    }
+
+    for manifest in named(found, "pnpm-workspace.yaml") {
+        let Ok(text) = std::fs::read_to_string(manifest) else {
+            continue;
+        };
+        let dir = parent(manifest);
+        let members = expand_members(&dir, pnpm_packages(&text).into_iter(), "package.json");
+        workspaces.push((dir, members));
+    }
+
+    for manifest in named(found, "tsconfig.json") {
+        let Some(references) = std::fs::read_to_string(manifest)
+            .ok()
+            .and_then(|text| serde_json::from_str::<serde_json::Value>(&strip_jsonc(&text)).ok())
+            .and_then(|json| json.get("references")?.as_array().cloned())
+        else {
+            continue;
+        };
+        let dir = parent(manifest);
+        let members = references
+            .iter()
+            .filter_map(|reference| reference.get("path")?.as_str())
+            .filter_map(|path| {
+                let target = dir.join(path);
+                let member = if target.is_file() {
+                    parent(&target)
+                } else {
+                    target
+                };
+                let member = normalize(&member).ok()?;
+                (member.is_dir() && member != normalize(&dir).ok()?).then_some(member)
+            })
+            .collect();
+        workspaces.push((dir, members));
+    }
+    workspaces
+}
+
+/// Directories matched by workspace globs relative to `dir` that hold
+/// `marker`. Patterns starting with `!` exclude what they match.
+fn expand_members<'a>(
+    dir: &Path,
+    patterns: impl Iterator<Item = &'a str>,
+    marker: &str,
+) -> Vec<PathBuf> {
+    let (excluded, included): (Vec<&str>, Vec<&str>) =
+        patterns.partition(|pattern| pattern.starts_with('!'));
+    let glob_dirs = |pattern: &str| -> Vec<PathBuf> {
+        let pattern = pattern.trim_end_matches('/');
+        glob::glob(&dir.join(pattern).to_string_lossy())
+            .map(|paths| paths.filter_map(|p| p.ok()).collect())
+            .unwrap_or_default()
+    };
+    let excluded: Vec<PathBuf> = excluded
+        .iter()
+        .flat_map(|pattern| glob_dirs(&pattern[1..]))
+        .collect();
+
+    let mut members = Vec::new();
+    for member in included.iter().flat_map(|pattern| glob_dirs(pattern)) {
+        if member.join(marker).is_file()
+            && !excluded.contains(&member)
+            && !member.components().any(|c| c.as_os_str() == "node_modules")
+            && !members.contains(&member)
+        {
+            members.push(member);
+        }
+    }
+    members
+}
+
+/// The `packages:` list of a `pnpm-workspace.yaml`, in block style
+/// (`- 'apps/*'` items) or flow style (`['apps/*', 'libs/*']`, which may span
+/// lines).
+fn pnpm_packages(text: &str) -> Vec<&str> {
+    let mut in_packages = false;
+    let mut in_flow = false;
+    let mut packages = Vec::new();
+    for line in text.lines() {
+        let content = line.split(" #").next().unwrap_or_default().trim_end();
+        if content.trim().is_empty() || content.trim_start().starts_with('#') {
+            continue;
+        }
+        if in_flow {
+            in_flow = !push_flow_items(content, &mut packages);
+        } else if !line.starts_with([' ', '\t', '-']) {
+            let value = content
+                .trim()
+                .strip_prefix("packages:")
+                .map(str::trim_start);
+            in_packages = value == Some("");
+            if let Some(items) = value.and_then(|value| value.strip_prefix('[')) {
+                in_flow = !push_flow_items(items, &mut packages);
+            }
+        } else if in_packages {
+            if let Some(item) = content.trim().strip_prefix('-') {
+                packages.push(unquote(item));
+            }
+        }
+    }
+    packages
+}
+
+/// Push the comma-separated items of one line of a YAML flow sequence,
+/// returning whether its closing `]` was reached.
+fn push_flow_items<'a>(content: &'a str, packages: &mut Vec<&'a str>) -> bool {
+    let (items, closed) = match content.split_once(']') {
+        Some((items, _)) => (items, true),
+        None => (content, false),
+    };
+    packages.extend(
+        items
+            .split(',')
+            .map(unquote)
+            .filter(|item| !item.is_empty()),
+    );
+    closed
+}
+
+fn unquote(item: &str) -> &str {
+    item.trim().trim_matches(['"', '\''])
+}
+
+/// JSON with comments (`tsconfig.json`): drop comments and trailing commas.
+fn strip_jsonc(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            if c == '\\' {
+                out.extend(chars.next());
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => while chars.next_if(|&next| next != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            (']' | '}', _) => {
+                let kept = out.trim_end().len();
+                if out[..kept].ends_with(',') {
+                    out.truncate(kept - 1);
+                }
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn read_json(path: &Path) -> Option<serde_json::Value> {
+    serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+}
+
+fn json_name(json: &serde_json::Value) -> Option<String> {
+    Some(json.get("name")?.as_str()?.to_string())
+}
+
+/// Go modules: each `go.mod`, named by its `module` directive. Copies under
+/// `vendor/` belong to the module vendoring them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GoProvider;
+
+impl PackageProvider for GoProvider {
+    fn manifest_names(&self) -> &[&str] {
+        &["go.mod"]
+    }
+
+    fn packages(&self, root: &Path, manifests: &[PathBuf]) -> Result<Vec<Package>> {
+        Ok(manifests
+            .iter()
+            .filter(|manifest| !in_dir_named(root, manifest, "vendor"))
+            .filter_map(|manifest| {
+                let text = std::fs::read_to_string(manifest).ok()?;
+                let name = text.lines().find_map(|line| {
+                    let path = line.trim().strip_prefix("module")?;
+                    path.starts_with(char::is_whitespace)
+                        .then(|| path.trim().trim_matches('"').to_string())
+                })?;
+                Some(Package::new(name, parent(manifest), Ecosystem::Go))
+            })
+            .collect())
+    }
+}
+
+/// The `found` manifests called `file_name`.
+fn named<'a>(found: &'a [PathBuf], file_name: &'a str) -> impl Iterator<Item = &'a PathBuf> {
+    found
+        .iter()
+        .filter(move |manifest| manifest.file_name().is_some_and(|name| name == file_name))
+}
+
+/// Whether `manifest` lies in a directory called `dir_name` beneath `root`.
+fn in_dir_named(root: &Path, manifest: &Path, dir_name: &str) -> bool {
+    manifest
+        .parent()
+        .and_then(|dir| dir.strip_prefix(root).ok())
+        .is_some_and(|dir| dir.components().any(|c| c.as_os_str() == dir_name))
+}
+
+fn parent(path: &Path) -> PathBuf {
+    path.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, file: &str, content: &str) {
+        let path = root.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    /// A mixed repository: a uv workspace, a pnpm workspace, a Go module and
+    /// a Cargo crate, plus directories no provider may look into.
+    fn mixed(root: &Path) {
+        write(
+            root,
+            "pyproject.toml",
+            "[tool.uv.workspace]\nmembers = [\"py/*\"]\n",
+        );
+        write(root, "py/api/pyproject.toml", "[project]\nname = \"api\"\n");
+        write(
+            root,
+            "py/legacy/pyproject.toml",
+            "[tool.poetry]\nname = \"legacy\"\n",
+        );
+        write(root, "package.json", "{\"private\": true}\n");
+        write(root, "pnpm-workspace.yaml", "packages:\n  - \"web/*\"\n");
+        write(root, "web/ui/package.json", "{\"name\": \"@acme/ui\"}\n");
+        write(
+            root,
+            "web/ui/node_modules/dep/package.json",
+            "{\"name\": \"dep\"}\n",
+        );
+        write(root, "svc/go.mod", "module example.com/svc\n\ngo 1.22\n");
+        write(
+            root,
+            "native/Cargo.toml",
+            "[package]\nname = \"native\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        );
+        write(root, "native/src/lib.rs", "");
+        write(
+            root,
+            "native/pyproject.toml",
+            "[project]\nname = \"native-py\"\n",
+        );
+        write(root, ".venv/pyvenv.cfg", "");
+        write(
+            root,
+            ".venv/lib/pkg/pyproject.toml",
+            "[project]\nname = \"x\"\n",
+        );
+        write(root, "env/pyvenv.cfg", "");
+        write(
+            root,
+            "env/lib/pkg/pyproject.toml",
+            "[project]\nname = \"y\"\n",
+        );
+    }
+
+    #[test]
+    fn discovers_packages_across_ecosystems() {
+        let temp = tempfile::tempdir().unwrap();
+        mixed(temp.path());
+
+        let model = ProjectModel::discover(
+            temp.path(),
+            WorkspaceDiscovery::Manifest,
+            &FilterConfig::new(),
+        )
+        .unwrap();
+        let mut packages: Vec<_> = model
+            .packages
+            .iter()
+            .map(|p| (p.name.as_str(), p.ecosystem))
+            .collect();
+        packages.sort();
+        assert_eq!(
+            packages,
+            [
+                ("@acme/ui", Ecosystem::Node),
+                ("api", Ecosystem::Python),
+                ("example.com/svc", Ecosystem::Go),
+                ("legacy", Ecosystem::Python),
+                ("native", Ecosystem::Cargo),
+            ]
+        );
+    }
+
+    /// Deno packages, declared by `deno.json`, for the provider test.
+    struct DenoProvider;
+
+    impl PackageProvider for DenoProvider {
+        fn manifest_names(&self) -> &[&str] {
+            &["deno.json"]
+        }
+
+        fn packages(&self, _root: &Path, manifests: &[PathBuf]) -> Result<Vec<Package>> {
+            Ok(manifests
+                .iter()
+                .filter_map(|manifest| {
+                    let name = json_name(&read_json(manifest)?)?;
+                    Some(Package::new(name, parent(manifest), Ecosystem::Node))
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn providers_plug_in_and_manifests_honour_ignore_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write(root, "edge/deno.json", "{\"name\": \"@acme/edge\"}\n");
+        write(root, "svc/go.mod", "module example.com/svc\n");
+        write(root, "scratch/go.mod", "module example.com/scratch\n");
+        write(
+            root,
+            "vendor/example.com/dep/go.mod",
+            "module example.com/dep\n",
+        );
+        write(root, ".rustlocignore", "scratch/\n");
+
+        let mut providers = ProjectModel::providers(WorkspaceDiscovery::Manifest);
+        providers.push(Box::new(DenoProvider));
+        let names = |filter: &FilterConfig| -> Vec<String> {
+            let model = ProjectModel::discover_with(root, &providers, filter).unwrap();
+            let mut names: Vec<_> = model.packages.into_iter().map(|p| p.name).collect();
+            names.sort();
+            names
+        };
+        assert_eq!(
+            names(&FilterConfig::new()),
+            ["@acme/edge", "example.com/svc"]
+        );
+        assert_eq!(
+            names(&FilterConfig::new().no_ignore(true)),
+            ["@acme/edge", "example.com/scratch", "example.com/svc"]
+        );
+    }
+
+    #[test]
+    fn python_source_roots_follow_the_build_backend() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write(root, "flat/pyproject.toml", "[project]\nname = \"flat\"\n");
+        write(
+            root,
+            "srclayout/pyproject.toml",
+            "[project]\nname = \"srclayout\"\n",
+        );
+        write(root, "srclayout/src/pkg/__init__.py", "");
+        write(
+            root,
+            "setuptools/pyproject.toml",
+            "[project]\nname = \"st\"\n[tool.setuptools.package-dir]\n\"\" = \"lib\"\n",
+        );
+        write(
+            root,
+            "hatch/pyproject.toml",
+            "[project]\nname = \"hatched\"\n[tool.hatch.build.targets.wheel]\npackages = [\"code/hatched\"]\n",
+        );
+        write(root, "legacy/setup.cfg", "[metadata]\nname = legacy-dist\n");
+
+        let mut packages = PythonProvider.discover(root, &FilterConfig::new()).unwrap();
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        let roots: Vec<_> = packages
+            .iter()
+            .map(|p| {
+                let source = p.source_root.strip_prefix(root).unwrap();
+                (p.name.as_str(), source.to_string_lossy().replace('\\', "/"))
+            })
+            .collect();
+        assert_eq!(
+            roots,
+            [
+                ("flat", "flat".to_string()),
+                ("hatched", "hatch/code".to_string()),
+                ("legacy-dist", "legacy".to_string()),
+                ("srclayout", "srclayout/src".to_string()),
+                ("st", "setuptools/lib".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn node_workspaces_declare_their_members() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        // npm/Turborepo: globs with a negation; members may be unnamed
+        write(
+            root,
+            "npm/package.json",
+            "{\"private\": true, \"workspaces\": [\"packages/*\", \"!packages/skip\"]}",
+        );
+        write(
+            root,
+            "npm/packages/a/package.json",
+            "{\"name\": \"@npm/a\"}",
+        );
+        write(root, "npm/packages/b/package.json", "{\"private\": true}");
+        write(root, "npm/packages/skip/package.json", "{}");
+        // yarn's object form
+        write(
+            root,
+            "yarn/package.json",
+            "{\"workspaces\": {\"packages\": [\"libs/*\"]}}",
+        );
+        write(root, "yarn/libs/c/package.json", "{}");
+        // pnpm
+        write(
+            root,
+            "pnpm/pnpm-workspace.yaml",
+            "# monorepo\npackages:\n  - 'apps/*' # apps\n  - \"!apps/old\"\ncatalog:\n  - x\n",
+        );
+        write(root, "pnpm/apps/web/package.json", "{\"name\": \"web\"}");
+        write(root, "pnpm/apps/old/package.json", "{}");
+        // tsconfig project references (JSON with comments) and Nx projects
+        write(
+            root,
+            "ts/tsconfig.json",
+            "{\n  // solution\n  \"references\": [\n    { \"path\": \"./core\" }, /* b */\n    { \"path\": \"tools/tsconfig.build.json\" },\n  ],\n}",
+        );
+        write(root, "ts/core/tsconfig.json", "{}");
+        write(root, "ts/tools/tsconfig.build.json", "{}");
+        write(
+            root,
+            "nx/libs/feature/project.json",
+            "{\"name\": \"feature\", \"projectType\": \"library\"}",
+        );
+        write(root, "data/project.json", "{\"name\": \"not-a-project\"}");
+
+        let mut names: Vec<_> = NodeProvider
+            .discover(root, &FilterConfig::new())
+            .unwrap()
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "@npm/a",
+                "core",
+                "feature",
+                "libs/c",
+                "packages/b",
+                "tools",
+                "web"
+            ]
+        );
+    }
+
+    #[test]
+    fn pnpm_package_lists_may_be_flow_style() {
+        assert_eq!(
+            pnpm_packages("packages: ['apps/*', \"libs/*\"]\ncatalog:\n  - x\n"),
+            ["apps/*", "libs/*"]
+        );
+        assert_eq!(
+            pnpm_packages("packages: [\n  'apps/*', # apps\n  '!apps/old',\n]\n"),
+            ["apps/*", "!apps/old"]
+        );
+        assert_eq!(pnpm_packages("packages: []\n"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn package_for_path_picks_the_deepest_package() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write(root, "package.json", "{\"name\": \"outer\"}\n");
+        write(root, "inner/package.json", "{\"name\": \"inner\"}\n");
+
+        let model =
+            ProjectModel::discover(root, WorkspaceDiscovery::Manifest, &FilterConfig::new())
+                .unwrap();
+        let name = |path: &str| {
+            model
+                .package_for_path(Path::new(path))
+                .map(|p| p.name.as_str())
+        };
+        assert_eq!(name("inner/src/a.ts"), Some("inner"));
+        assert_eq!(name("src/b.ts"), Some("outer"));
+
+        let workspace = WorkspaceInfo {
+            root: model.root.clone(),
+            crates: Vec::new(),
+            default_members: Vec::new(),
+        }
+        .with_packages(model.packages);
+        let crate_name = workspace
+            .crate_for_path(Path::new("inner/index.ts"))
+            .map(|c| c.name.as_str());
+        assert_eq!(crate_name, Some("inner"));
+    }
+}
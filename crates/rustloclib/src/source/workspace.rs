@@ -1,15 +1,197 @@
 //! Cargo workspace discovery and crate enumeration.
 //!
 //! This module provides functionality to discover crates within a Cargo workspace
-//! and enumerate their source files.
+//! and enumerate their source files. Manifests are read directly by default
+//! (see [`WorkspaceDiscovery`]); `cargo metadata` remains available when
+//! Cargo's own answer is wanted.
 
+use std::fmt;
 use std::path::{Path, PathBuf};
 
-use cargo_metadata::{MetadataCommand, Package};
+use cargo_metadata::{workspace_default_members_is_missing, MetadataCommand, Package};
+use serde::{Deserialize, Serialize};
 
+use crate::data::Diagnostic;
 use crate::error::RustlocError;
 use crate::Result;
 
+use super::filter::{discover_manifests, FilterConfig};
+use super::project;
+
+/// Kind of a Cargo build target, as `cargo metadata` reports it.
+///
+/// Variants are ordered by precedence: when a file could belong to several
+/// targets, the earliest kind wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TargetKind {
+    /// The library target (`lib`, `rlib`, `proc-macro`, ...)
+    Lib,
+    /// A binary target
+    Bin,
+    /// An integration test target
+    Test,
+    /// An example target
+    Example,
+    /// A benchmark target
+    Bench,
+    /// The build script
+    CustomBuild,
+}
+
+impl TargetKind {
+    /// The kind as Cargo spells it.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Lib => "lib",
+            Self::Bin => "bin",
+            Self::Test => "test",
+            Self::Example => "example",
+            Self::Bench => "bench",
+            Self::CustomBuild => "custom-build",
+        }
+    }
+
+    /// Map a `cargo metadata` kind string; library crate types all map to
+    /// [`TargetKind::Lib`].
+    fn from_metadata(kind: &str) -> Option<Self> {
+        match kind {
+            "bin" => Some(Self::Bin),
+            "test" => Some(Self::Test),
+            "example" => Some(Self::Example),
+            "bench" => Some(Self::Bench),
+            "custom-build" => Some(Self::CustomBuild),
+            "lib" | "rlib" | "dylib" | "cdylib" | "staticlib" | "proc-macro" => Some(Self::Lib),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TargetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A build target of a crate: its library, a binary, a test, an example, a
+/// bench or the build script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetInfo {
+    /// Target name (`cli_integration` for `tests/cli_integration.rs`)
+    pub name: String,
+    /// Target kind
+    pub kind: TargetKind,
+    /// Root source file of the target
+    pub src_path: PathBuf,
+}
+
+impl TargetInfo {
+    /// Create a target.
+    pub fn new(name: impl Into<String>, kind: TargetKind, src_path: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            src_path: src_path.into(),
+        }
+    }
+
+    /// Row label for per-target output: `rustloc (bin rustloc)`, or
+    /// `rustloc (lib)` for the library and build script.
+    pub fn label(&self, crate_name: &str) -> String {
+        match self.kind {
+            TargetKind::Lib | TargetKind::CustomBuild => format!("{} ({})", crate_name, self.kind),
+            kind => format!("{} ({} {})", crate_name, kind, self.name),
+        }
+    }
+
+    /// Directory holding the target's root file.
+    fn dir(&self) -> &Path {
+        self.src_path.parent().unwrap_or(Path::new(""))
+    }
+
+    /// Directory the target's own submodules live in: the root file's
+    /// directory for `lib.rs`, `main.rs` and `mod.rs`, otherwise a sibling
+    /// directory named after the file (`src/bin/tool/` for `src/bin/tool.rs`).
+    fn module_dir(&self) -> PathBuf {
+        match self.src_path.file_stem().and_then(|stem| stem.to_str()) {
+            Some("lib" | "main" | "mod") | None => self.dir().to_path_buf(),
+            Some(stem) => self.dir().join(stem),
+        }
+    }
+
+    /// Whether the target's submodules live beside its root file in the
+    /// crate root (`[lib] path = "lib.rs"`), where the directory cannot be
+    /// walked as a whole.
+    fn modules_in(&self, crate_root: &Path) -> bool {
+        self.module_dir() == crate_root
+    }
+
+    /// Files and directories of the submodules the target's root file
+    /// declares: `name.rs` and `name/` for `mod name;`, or the file a
+    /// `#[path]` attribute names.
+    fn declared_modules(&self) -> Vec<PathBuf> {
+        let Ok(source) = std::fs::read_to_string(&self.src_path) else {
+            return Vec::new();
+        };
+        let dir = self.dir();
+        let mut modules = Vec::new();
+        let mut path_attr = None;
+        for line in source.lines() {
+            let mut line = line.trim();
+            while let Some(attr) = line.strip_prefix("#[") {
+                let Some(end) = attr.find(']') else {
+                    break;
+                };
+                if let Some(path) = path_attribute(&attr[..end]) {
+                    path_attr = Some(path);
+                }
+                line = attr[end + 1..].trim_start();
+            }
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            match (mod_declaration(line), path_attr.take()) {
+                (Some(_), Some(path)) => modules.push(dir.join(path)),
+                (Some(name), None) => {
+                    modules.push(dir.join(format!("{name}.rs")));
+                    modules.push(dir.join(name));
+                }
+                (None, _) => {}
+            }
+        }
+        modules
+    }
+}
+
+/// The module name of a `mod name;` declaration, with any visibility.
+fn mod_declaration(line: &str) -> Option<&str> {
+    let mut rest = line;
+    if let Some(after) = rest.strip_prefix("pub") {
+        rest = if after.starts_with('(') {
+            after[after.find(')')? + 1..].trim_start()
+        } else if after.starts_with(char::is_whitespace) {
+            after.trim_start()
+        } else {
+            return None;
+        };
+    }
+    let name = rest.strip_prefix("mod")?;
+    if !name.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let name = name.trim().strip_suffix(';')?.trim_end();
+    let name = name.strip_prefix("r#").unwrap_or(name);
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+    valid.then_some(name)
+}
+
+/// The file a `path = "..."` attribute names.
+fn path_attribute(attr: &str) -> Option<&str> {
+    let value = attr.strip_prefix("path")?.trim_start().strip_prefix('=')?;
+    let value = value.trim().strip_prefix('"')?;
+    Some(&value[..value.find('"')?])
+}
+
 /// Information about a crate in a workspace.
 #[derive(Debug, Clone)]
 pub struct CrateInfo {
@@ -17,84 +199,146 @@ pub struct CrateInfo {
     pub name: String,
     /// Root directory of the crate (where Cargo.toml is)
     pub root: PathBuf,
-    /// Source directories to scan (typically just "src")
-    pub src_dirs: Vec<PathBuf>,
-    /// Test directory if it exists
-    pub tests_dir: Option<PathBuf>,
-    /// Examples directory if it exists
-    pub examples_dir: Option<PathBuf>,
-    /// Benches directory if it exists
-    pub benches_dir: Option<PathBuf>,
-    /// Build script if it exists
-    pub build_script: Option<PathBuf>,
+    /// Build targets, in the order `cargo metadata` lists them
+    pub targets: Vec<TargetInfo>,
 }
 
 impl CrateInfo {
-    /// Create CrateInfo from a cargo_metadata Package
-    fn from_package(package: &Package) -> Self {
+    /// Create CrateInfo from a cargo_metadata Package.
+    ///
+    /// Targets come straight from the package metadata, so custom `path`s,
+    /// extra `[[test]]`/`[[example]]`/`[[bench]]` entries and `autotests =
+    /// false` style switches are all honoured.
+    pub(crate) fn from_package(package: &Package) -> Self {
         let root = package
             .manifest_path
             .parent()
             .map(|p| p.to_path_buf().into_std_path_buf())
             .unwrap_or_default();
 
-        let src_dir = root.join("src");
-        let tests_dir = root.join("tests");
-        let examples_dir = root.join("examples");
-        let benches_dir = root.join("benches");
-        let build_script = root.join("build.rs");
+        let targets = package
+            .targets
+            .iter()
+            .filter_map(|target| {
+                let kind = target
+                    .kind
+                    .iter()
+                    .filter_map(|kind| TargetKind::from_metadata(kind))
+                    .min()?;
+                Some(TargetInfo::new(
+                    target.name.clone(),
+                    kind,
+                    target.src_path.clone().into_std_path_buf(),
+                ))
+            })
+            .collect();
 
         Self {
             name: package.name.clone(),
-            root: root.clone(),
-            src_dirs: if src_dir.exists() {
-                vec![src_dir]
-            } else {
-                vec![]
-            },
-            tests_dir: if tests_dir.exists() {
-                Some(tests_dir)
-            } else {
-                None
-            },
-            examples_dir: if examples_dir.exists() {
-                Some(examples_dir)
-            } else {
-                None
-            },
-            benches_dir: if benches_dir.exists() {
-                Some(benches_dir)
-            } else {
-                None
-            },
-            build_script: if build_script.exists() {
-                Some(build_script)
-            } else {
-                None
-            },
+            root,
+            targets,
         }
     }
 
-    /// Get all directories (and files like build.rs) that should be scanned for this crate
-    pub fn all_dirs(&self) -> Vec<&Path> {
-        let mut dirs: Vec<&Path> = self.src_dirs.iter().map(|p| p.as_path()).collect();
-
-        if let Some(ref tests) = self.tests_dir {
-            dirs.push(tests.as_path());
-        }
-        if let Some(ref examples) = self.examples_dir {
-            dirs.push(examples.as_path());
-        }
-        if let Some(ref benches) = self.benches_dir {
-            dirs.push(benches.as_path());
-        }
-        if let Some(ref build_script) = self.build_script {
-            dirs.push(build_script.as_path());
+    /// Get all directories (and files like build.rs) that should be scanned for this crate.
+    ///
+    /// A target whose root file sits in a subdirectory contributes that whole
+    /// directory (`src/`, `tests/`), which picks up shared helpers such as
+    /// `tests/common/mod.rs`. A target whose root file sits directly in the
+    /// crate root contributes only that file and its submodules (its module
+    /// directory, or the modules a root-level `lib.rs` declares), so the rest
+    /// of the crate root is never walked.
+    pub fn all_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        for target in &self.targets {
+            if target.dir() != self.root {
+                dirs.push(target.dir().to_path_buf());
+                continue;
+            }
+            dirs.push(target.src_path.clone());
+            if target.modules_in(&self.root) {
+                dirs.extend(target.declared_modules());
+            } else {
+                dirs.push(target.module_dir());
+            }
         }
+        dirs.retain(|dir| dir.exists());
+        dirs.sort();
+        dirs.dedup();
+        // Nested directories are already covered by their parents.
+        let covered = dirs.clone();
+        dirs.retain(|dir| {
+            !covered
+                .iter()
+                .any(|other| other != dir && other.is_dir() && dir.starts_with(other))
+        });
+        dirs
+    }
 
+    /// Source roots used for module names: the directories of the library
+    /// and binary targets, library first.
+    pub fn src_dirs(&self) -> Vec<&Path> {
+        let mut targets: Vec<&TargetInfo> = self
+            .targets
+            .iter()
+            .filter(|t| matches!(t.kind, TargetKind::Lib | TargetKind::Bin))
+            .collect();
+        targets.sort_by_key(|t| t.kind);
+        let mut dirs: Vec<&Path> = Vec::new();
+        for target in targets {
+            if !dirs.contains(&target.dir()) {
+                dirs.push(target.dir());
+            }
+        }
         dirs
     }
 
+    /// The target a file belongs to.
+    ///
+    /// A target's root file belongs to it; otherwise the file belongs to the
+    /// target whose module directory contains it most specifically, and
+    /// failing that to the target whose root file shares its nearest
+    /// directory (so `tests/common/mod.rs` goes with the integration tests).
+    /// Ties go to the kind with the highest precedence, then to the first
+    /// target listed. Files in the crate root only match a target exactly,
+    /// or as a submodule a root-level target's root file declares.
+    ///
+    /// The path can be absolute or relative to the workspace root.
+    pub fn target_for_path(&self, path: &Path, workspace_root: &Path) -> Option<&TargetInfo> {
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            workspace_root.join(path)
+        };
+        if let Some(target) = self.targets.iter().find(|t| t.src_path == path) {
+            return Some(target);
+        }
+        let deepest = |dir_of: fn(&TargetInfo) -> PathBuf| {
+            self.targets
+                .iter()
+                .map(|target| (dir_of(target), target))
+                .filter(|(dir, _)| *dir != self.root && path.starts_with(dir))
+                .min_by(|(a, ta), (b, tb)| {
+                    b.components()
+                        .count()
+                        .cmp(&a.components().count())
+                        .then_with(|| ta.kind.cmp(&tb.kind))
+                })
+                .map(|(_, target)| target)
+        };
+        deepest(TargetInfo::module_dir)
+            .or_else(|| deepest(|target| target.dir().to_path_buf()))
+            .or_else(|| {
+                self.targets.iter().find(|target| {
+                    target.modules_in(&self.root)
+                        && target
+                            .declared_modules()
+                            .iter()
+                            .any(|module| path.starts_with(module))
+                })
+            })
+    }
+
     /// Check if a file path belongs to this crate.
     ///
     /// This is used to map arbitrary file paths (e.g., from git diffs) to their
@@ -117,6 +361,18 @@ impl CrateInfo {
     }
 }
 
+/// How a workspace's crates and targets are discovered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WorkspaceDiscovery {
+    /// Read the `Cargo.toml` files directly. Needs no Cargo toolchain, never
+    /// resolves the lockfile, and tolerates members with broken manifests.
+    #[default]
+    Manifest,
+    /// Ask `cargo metadata`, which is authoritative but needs a working
+    /// toolchain and may resolve dependencies.
+    CargoMetadata,
+}
+
 /// Workspace information containing all discovered crates.
 #[derive(Debug, Clone)]
 pub struct WorkspaceInfo {
@@ -124,41 +380,147 @@ pub struct WorkspaceInfo {
     pub root: PathBuf,
     /// All crates in the workspace
     pub crates: Vec<CrateInfo>,
+    /// Crates Cargo selects at the workspace root without `-p`
+    /// (`default-members`, or the root package, or every member)
+    pub default_members: Vec<String>,
+}
+
+/// Workspaces found beneath a directory by [`WorkspaceInfo::discover_all`].
+#[derive(Debug, Clone, Default)]
+pub struct DiscoveredWorkspaces {
+    /// Workspaces, sorted by root
+    pub workspaces: Vec<WorkspaceInfo>,
+    /// Manifests that could not be read or parsed, and were left out
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 impl WorkspaceInfo {
-    /// Discover workspace information from a path.
+    /// Discover workspace information from a path, reading the manifests
+    /// directly.
     ///
     /// The path can be:
     /// - A directory containing Cargo.toml
     /// - A path to a Cargo.toml file
+    ///
+    /// A member's manifest finds its workspace root the way Cargo does, so
+    /// the whole workspace is returned either way. Members whose manifest
+    /// cannot be read or parsed are left out.
     pub fn discover(path: impl AsRef<Path>) -> Result<Self> {
-        let path = path.as_ref();
+        Self::discover_with(path, WorkspaceDiscovery::Manifest)
+    }
+
+    /// Discover workspace information with an explicit discovery method.
+    pub fn discover_with(path: impl AsRef<Path>, discovery: WorkspaceDiscovery) -> Result<Self> {
+        Self::discover_reporting(path, discovery, false, &mut Vec::new())
+    }
 
-        if !path.exists() {
-            return Err(RustlocError::PathNotFound(path.to_path_buf()));
+    /// Discover workspace information, recording the members whose manifest
+    /// cannot be read or parsed in `diagnostics`, or failing on them when
+    /// `strict`. `cargo metadata` fails on them itself.
+    pub(crate) fn discover_reporting(
+        path: impl AsRef<Path>,
+        discovery: WorkspaceDiscovery,
+        strict: bool,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Result<Self> {
+        let manifest_path = manifest_path(path.as_ref())?;
+        match discovery {
+            WorkspaceDiscovery::Manifest => {
+                super::manifest::read_workspace(&manifest_path, strict, diagnostics)
+            }
+            WorkspaceDiscovery::CargoMetadata => Self::from_cargo_metadata(&manifest_path),
         }
+    }
 
-        // Find the manifest path
-        let manifest_path = if path.is_file() && path.file_name() == Some("Cargo.toml".as_ref()) {
-            path.to_path_buf()
-        } else if path.is_dir() {
-            let cargo_toml = path.join("Cargo.toml");
-            if cargo_toml.exists() {
-                cargo_toml
-            } else {
-                return Err(RustlocError::CargoTomlNotFound(path.to_path_buf()));
+    /// Discover every workspace at or below `dir`, sorted by root.
+    ///
+    /// Each `Cargo.toml` found by the same walk as
+    /// [`discover_files`](super::filter::discover_files) resolves to the
+    /// workspace it belongs to, so members report their workspace once and
+    /// standalone crates become workspaces of one. Hidden directories,
+    /// `target/` and paths ignored by `.gitignore`, `.ignore` or
+    /// `.rustlocignore` are skipped unless the filter sets
+    /// [`no_ignore`](FilterConfig::no_ignore), and
+    /// [`tracked_only`](FilterConfig::tracked_only) keeps to the git index.
+    /// Packages unpacked by `cargo vendor` and workspaces with no crates are
+    /// skipped.
+    ///
+    /// A manifest that cannot be read or parsed is left out with a
+    /// [`Diagnostic`], or fails discovery when the filter is
+    /// [`strict`](FilterConfig::strict).
+    pub fn discover_all(
+        dir: impl AsRef<Path>,
+        discovery: WorkspaceDiscovery,
+        filter: &FilterConfig,
+    ) -> Result<DiscoveredWorkspaces> {
+        let dir = dir.as_ref();
+        if !dir.is_dir() {
+            return Err(RustlocError::PathNotFound(dir.to_path_buf()));
+        }
+        let dir = super::manifest::normalize(dir)?;
+        let manifests = discover_manifests(&dir, filter, &["Cargo.toml"])?;
+        Self::from_manifests(&dir, manifests, discovery, filter.strict)
+    }
+
+    /// The workspaces of the `Cargo.toml` files found beneath `dir`, as
+    /// [`discover_all`](Self::discover_all) resolves them.
+    pub(crate) fn from_manifests(
+        dir: &Path,
+        mut manifests: Vec<PathBuf>,
+        discovery: WorkspaceDiscovery,
+        strict: bool,
+    ) -> Result<DiscoveredWorkspaces> {
+        manifests.retain(|manifest| !manifest.with_file_name(".cargo-checksum.json").exists());
+        // Shallow manifests first, so a workspace root is read before its
+        // members and the members are then recognised as already covered.
+        manifests.sort_by_key(|manifest| (manifest.components().count(), manifest.clone()));
+
+        let mut discovered = DiscoveredWorkspaces::default();
+        for manifest in manifests {
+            let package_dir = manifest.parent().unwrap_or(dir);
+            let covered = discovered.workspaces.iter().any(|ws| {
+                ws.root == package_dir || ws.crates.iter().any(|c| c.root == package_dir)
+            });
+            // A member reported while reading its workspace is not read again.
+            let reported = discovered.diagnostics.iter().any(|d| d.path == manifest);
+            if covered || reported {
+                continue;
             }
-        } else {
-            return Err(RustlocError::CargoTomlNotFound(path.to_path_buf()));
-        };
+            let workspace = match Self::discover_reporting(
+                &manifest,
+                discovery,
+                strict,
+                &mut discovered.diagnostics,
+            ) {
+                Ok(workspace) => workspace,
+                Err(err) if strict => return Err(err),
+                Err(err) => {
+                    discovered
+                        .diagnostics
+                        .push(Diagnostic::from_error(&manifest, &err));
+                    continue;
+                }
+            };
+            if !workspace.crates.is_empty()
+                && !discovered
+                    .workspaces
+                    .iter()
+                    .any(|ws| ws.root == workspace.root)
+            {
+                discovered.workspaces.push(workspace);
+            }
+        }
+        discovered.workspaces.sort_by(|a, b| a.root.cmp(&b.root));
+        Ok(discovered)
+    }
 
+    fn from_cargo_metadata(manifest_path: &Path) -> Result<Self> {
         let metadata = MetadataCommand::new()
-            .manifest_path(&manifest_path)
+            .manifest_path(manifest_path)
             .exec()
             .map_err(|e| RustlocError::CargoMetadata(e.to_string()))?;
 
-        let root = metadata.workspace_root.into_std_path_buf();
+        let root = metadata.workspace_root.clone().into_std_path_buf();
 
         // Get workspace members
         let workspace_members: std::collections::HashSet<_> =
@@ -171,7 +533,24 @@ impl WorkspaceInfo {
             .map(CrateInfo::from_package)
             .collect();
 
-        Ok(Self { root, crates })
+        // Cargo before 1.71 does not report default members.
+        let default_members =
+            if workspace_default_members_is_missing(&metadata.workspace_default_members) {
+                crates.iter().map(|c| c.name.clone()).collect()
+            } else {
+                metadata
+                    .workspace_packages()
+                    .iter()
+                    .filter(|p| metadata.workspace_default_members.contains(&p.id))
+                    .map(|p| p.name.clone())
+                    .collect()
+            };
+
+        Ok(Self {
+            root,
+            crates,
+            default_members,
+        })
     }
 
     /// Filter crates by name.
@@ -188,6 +567,12 @@ impl WorkspaceInfo {
 
         Self {
             root: self.root.clone(),
+            default_members: self
+                .default_members
+                .iter()
+                .filter(|name| names.contains(&name.as_str()))
+                .cloned()
+                .collect(),
             crates,
         }
     }
@@ -222,6 +607,45 @@ impl WorkspaceInfo {
             .iter()
             .find(|c| c.contains_path(path, &self.root))
     }
+
+    /// Add the packages other build tools declare, such as the Python and
+    /// Node packages of a mixed repository, as crates without targets.
+    ///
+    /// A package in a directory that already holds a crate is left out. The
+    /// crates are then ordered deepest root first, so
+    /// [`crate_for_path`](Self::crate_for_path) finds the most specific one.
+    pub fn with_packages(mut self, packages: impl IntoIterator<Item = project::Package>) -> Self {
+        for package in packages {
+            if self.crates.iter().any(|c| c.root == package.root) {
+                continue;
+            }
+            self.default_members.push(package.name.clone());
+            self.crates.push(CrateInfo {
+                name: package.name,
+                root: package.root,
+                targets: Vec::new(),
+            });
+        }
+        self.crates
+            .sort_by_key(|c| std::cmp::Reverse(c.root.components().count()));
+        self
+    }
+}
+
+/// Resolve a directory containing Cargo.toml, or a Cargo.toml itself, to the
+/// manifest path.
+pub(crate) fn manifest_path(path: &Path) -> Result<PathBuf> {
+    if !path.exists() {
+        return Err(RustlocError::PathNotFound(path.to_path_buf()));
+    }
+
+    if path.is_file() && path.file_name() == Some("Cargo.toml".as_ref()) {
+        Ok(path.to_path_buf())
+    } else if path.is_dir() && path.join("Cargo.toml").exists() {
+        Ok(path.join("Cargo.toml"))
+    } else {
+        Err(RustlocError::CargoTomlNotFound(path.to_path_buf()))
+    }
 }
 
 /// Check if a path is within a Cargo workspace.
@@ -238,24 +662,191 @@ pub fn is_cargo_project(path: impl AsRef<Path>) -> bool {
 mod tests {
     use super::*;
 
+    fn targets(root: &Path) -> Vec<TargetInfo> {
+        vec![
+            TargetInfo::new("demo", TargetKind::Lib, root.join("src/lib.rs")),
+            TargetInfo::new("demo", TargetKind::Bin, root.join("src/main.rs")),
+            TargetInfo::new("tool", TargetKind::Bin, root.join("src/bin/tool.rs")),
+            TargetInfo::new("it", TargetKind::Test, root.join("tests/it.rs")),
+            TargetInfo::new("other", TargetKind::Test, root.join("tests/other.rs")),
+            TargetInfo::new(
+                "build-script-build",
+                TargetKind::CustomBuild,
+                root.join("build.rs"),
+            ),
+        ]
+    }
+
     #[test]
     fn test_crate_info_all_dirs() {
-        // Test with a mock CrateInfo
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        for file in [
+            "src/lib.rs",
+            "src/main.rs",
+            "src/bin/tool.rs",
+            "tests/it.rs",
+            "tests/common/mod.rs",
+            "build.rs",
+            "scratch/notes.rs",
+        ] {
+            std::fs::create_dir_all(root.join(file).parent().unwrap()).unwrap();
+            std::fs::write(root.join(file), "").unwrap();
+        }
         let info = CrateInfo {
-            name: "test-crate".to_string(),
-            root: PathBuf::from("/project"),
-            src_dirs: vec![PathBuf::from("/project/src")],
-            tests_dir: Some(PathBuf::from("/project/tests")),
-            examples_dir: Some(PathBuf::from("/project/examples")),
-            benches_dir: None,
-            build_script: None,
+            name: "demo".to_string(),
+            root: root.to_path_buf(),
+            targets: targets(root),
         };
 
-        let dirs = info.all_dirs();
-        assert_eq!(dirs.len(), 3);
-        assert!(dirs.contains(&Path::new("/project/src")));
-        assert!(dirs.contains(&Path::new("/project/tests")));
-        assert!(dirs.contains(&Path::new("/project/examples")));
+        // `src/bin` is covered by `src`; the crate root itself is never walked.
+        assert_eq!(
+            info.all_dirs(),
+            [root.join("build.rs"), root.join("src"), root.join("tests")]
+        );
+        assert_eq!(info.src_dirs(), [root.join("src"), root.join("src/bin")]);
+    }
+
+    #[test]
+    fn test_crate_info_resolves_modules_of_root_level_targets() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::write(
+            root.join("lib.rs"),
+            "//! Crate docs\nmod util;\npub(crate) mod nested;\n#[cfg(test)] mod tests;\n\
+             #[path = \"other/place.rs\"]\nmod moved;\nmod inline {}\n",
+        )
+        .unwrap();
+        for file in [
+            "util.rs",
+            "util/deep.rs",
+            "nested/mod.rs",
+            "nested/inner.rs",
+            "tests.rs",
+            "other/place.rs",
+            "other/unused.rs",
+            "scratch.rs",
+        ] {
+            std::fs::create_dir_all(root.join(file).parent().unwrap()).unwrap();
+            std::fs::write(root.join(file), "").unwrap();
+        }
+        let info = CrateInfo {
+            name: "flat".to_string(),
+            root: root.to_path_buf(),
+            targets: vec![TargetInfo::new(
+                "flat",
+                TargetKind::Lib,
+                root.join("lib.rs"),
+            )],
+        };
+
+        assert_eq!(
+            info.all_dirs(),
+            [
+                root.join("lib.rs"),
+                root.join("nested"),
+                root.join("other/place.rs"),
+                root.join("tests.rs"),
+                root.join("util"),
+                root.join("util.rs"),
+            ]
+        );
+        for path in [
+            "util.rs",
+            "util/deep.rs",
+            "nested/inner.rs",
+            "other/place.rs",
+        ] {
+            assert_eq!(
+                info.target_for_path(Path::new(path), root).map(|t| t.kind),
+                Some(TargetKind::Lib),
+                "{}",
+                path
+            );
+        }
+        for path in ["scratch.rs", "other/unused.rs"] {
+            assert!(info.target_for_path(Path::new(path), root).is_none());
+        }
+    }
+
+    #[test]
+    fn test_crate_info_target_for_path() {
+        let root = Path::new("/project");
+        let info = CrateInfo {
+            name: "demo".to_string(),
+            root: root.to_path_buf(),
+            targets: targets(root),
+        };
+        let target = |path: &str| {
+            info.target_for_path(Path::new(path), root)
+                .map(|t| (t.kind, t.name.as_str()))
+        };
+
+        assert_eq!(target("src/util/mod.rs"), Some((TargetKind::Lib, "demo")));
+        assert_eq!(
+            target("/project/src/main.rs"),
+            Some((TargetKind::Bin, "demo"))
+        );
+        assert_eq!(
+            target("src/bin/tool/args.rs"),
+            Some((TargetKind::Bin, "tool"))
+        );
+        assert_eq!(
+            target("tests/other/helper.rs"),
+            Some((TargetKind::Test, "other"))
+        );
+        assert_eq!(
+            target("tests/common/mod.rs"),
+            Some((TargetKind::Test, "it"))
+        );
+        assert_eq!(
+            target("build.rs"),
+            Some((TargetKind::CustomBuild, "build-script-build"))
+        );
+        assert_eq!(target("notes.rs"), None);
+
+        assert_eq!(info.targets[2].label("demo"), "demo (bin tool)");
+        assert_eq!(info.targets[0].label("demo"), "demo (lib)");
+    }
+
+    #[test]
+    fn test_discover_reads_targets_from_cargo_metadata() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            r#"[package]
+name = "custom"
+version = "0.1.0"
+edition = "2021"
+autotests = false
+
+[lib]
+path = "core/entry.rs"
+
+[[test]]
+name = "checks"
+path = "checks/run.rs"
+"#,
+        )
+        .unwrap();
+        for file in ["core/entry.rs", "checks/run.rs", "tests/skipped.rs"] {
+            std::fs::create_dir_all(root.join(file).parent().unwrap()).unwrap();
+            std::fs::write(root.join(file), "").unwrap();
+        }
+
+        let workspace =
+            WorkspaceInfo::discover_with(root, WorkspaceDiscovery::CargoMetadata).unwrap();
+        let info = workspace.get_crate("custom").unwrap();
+        let kinds: Vec<_> = info.targets.iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, [TargetKind::Lib, TargetKind::Test]);
+
+        let dirs: Vec<_> = info
+            .all_dirs()
+            .into_iter()
+            .map(|dir| dir.file_name().unwrap().to_owned())
+            .collect();
+        assert_eq!(dirs, ["checks", "core"]);
     }
 
     #[test]
@@ -266,31 +857,20 @@ mod tests {
                 CrateInfo {
                     name: "crate-a".to_string(),
                     root: PathBuf::from("/workspace/crate-a"),
-                    src_dirs: vec![],
-                    tests_dir: None,
-                    examples_dir: None,
-                    benches_dir: None,
-                    build_script: None,
+                    targets: vec![],
                 },
                 CrateInfo {
                     name: "crate-b".to_string(),
                     root: PathBuf::from("/workspace/crate-b"),
-                    src_dirs: vec![],
-                    tests_dir: None,
-                    examples_dir: None,
-                    benches_dir: None,
-                    build_script: None,
+                    targets: vec![],
                 },
                 CrateInfo {
                     name: "crate-c".to_string(),
                     root: PathBuf::from("/workspace/crate-c"),
-                    src_dirs: vec![],
-                    tests_dir: None,
-                    examples_dir: None,
-                    benches_dir: None,
-                    build_script: None,
+                    targets: vec![],
                 },
             ],
+            default_members: vec![],
         };
 
         let filtered = workspace.filter_by_names(&["crate-a", "crate-c"]);
@@ -308,22 +888,15 @@ mod tests {
                 CrateInfo {
                     name: "alpha".to_string(),
                     root: PathBuf::from("/workspace/alpha"),
-                    src_dirs: vec![],
-                    tests_dir: None,
-                    examples_dir: None,
-                    benches_dir: None,
-                    build_script: None,
+                    targets: vec![],
                 },
                 CrateInfo {
                     name: "beta".to_string(),
                     root: PathBuf::from("/workspace/beta"),
-                    src_dirs: vec![],
-                    tests_dir: None,
-                    examples_dir: None,
-                    benches_dir: None,
-                    build_script: None,
+                    targets: vec![],
                 },
             ],
+            default_members: vec![],
         };
 
         let names = workspace.crate_names();
@@ -352,11 +925,11 @@ mod tests {
         let crate_info = CrateInfo {
             name: "my-crate".to_string(),
             root: PathBuf::from("/workspace/my-crate"),
-            src_dirs: vec![PathBuf::from("/workspace/my-crate/src")],
-            tests_dir: Some(PathBuf::from("/workspace/my-crate/tests")),
-            examples_dir: None,
-            benches_dir: None,
-            build_script: None,
+            targets: vec![TargetInfo::new(
+                "lib",
+                TargetKind::Lib,
+                "/workspace/my-crate/src/lib.rs",
+            )],
         };
 
         let workspace_root = PathBuf::from("/workspace");
@@ -387,22 +960,23 @@ mod tests {
                 CrateInfo {
                     name: "crate-a".to_string(),
                     root: PathBuf::from("/workspace/crate-a"),
-                    src_dirs: vec![PathBuf::from("/workspace/crate-a/src")],
-                    tests_dir: None,
-                    examples_dir: None,
-                    benches_dir: None,
-                    build_script: None,
+                    targets: vec![TargetInfo::new(
+                        "lib",
+                        TargetKind::Lib,
+                        "/workspace/crate-a/src/lib.rs",
+                    )],
                 },
                 CrateInfo {
                     name: "crate-b".to_string(),
                     root: PathBuf::from("/workspace/crate-b"),
-                    src_dirs: vec![PathBuf::from("/workspace/crate-b/src")],
-                    tests_dir: None,
-                    examples_dir: None,
-                    benches_dir: None,
-                    build_script: None,
+                    targets: vec![TargetInfo::new(
+                        "lib",
+                        TargetKind::Lib,
+                        "/workspace/crate-b/src/lib.rs",
+                    )],
                 },
             ],
+            default_members: vec![],
         };
 
         // Find crate by absolute path
@@ -423,4 +997,54 @@ mod tests {
         assert!(crate_a_rel.is_some());
         assert_eq!(crate_a_rel.unwrap().name, "crate-a");
     }
+
+    #[test]
+    fn test_discover_all_honours_ignore_files_and_reports_broken_manifests() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let package = |dir: &str, body: &str| {
+            std::fs::create_dir_all(root.join(dir).join("src")).unwrap();
+            std::fs::write(root.join(dir).join("Cargo.toml"), body).unwrap();
+            std::fs::write(root.join(dir).join("src/lib.rs"), "").unwrap();
+        };
+        let manifest = |name: &str| {
+            format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n")
+        };
+        package("app", &manifest("app"));
+        package("scratch/old", &manifest("old"));
+        package("broken", "[package\n");
+        std::fs::write(root.join(".rustlocignore"), "scratch/\n").unwrap();
+
+        let roots = |discovered: &DiscoveredWorkspaces| -> Vec<PathBuf> {
+            discovered
+                .workspaces
+                .iter()
+                .map(|ws| ws.root.strip_prefix(root).unwrap().to_path_buf())
+                .collect()
+        };
+        let filter = FilterConfig::new();
+        let discovered =
+            WorkspaceInfo::discover_all(root, WorkspaceDiscovery::Manifest, &filter).unwrap();
+        assert_eq!(roots(&discovered), [PathBuf::from("app")]);
+        assert_eq!(discovered.diagnostics.len(), 1);
+        assert!(discovered.diagnostics[0]
+            .path
+            .ends_with("broken/Cargo.toml"));
+
+        let discovered = WorkspaceInfo::discover_all(
+            root,
+            WorkspaceDiscovery::Manifest,
+            &filter.clone().no_ignore(true),
+        )
+        .unwrap();
+        assert_eq!(
+            roots(&discovered),
+            [PathBuf::from("app"), PathBuf::from("scratch/old")]
+        );
+
+        let err =
+            WorkspaceInfo::discover_all(root, WorkspaceDiscovery::Manifest, &filter.strict(true))
+                .unwrap_err();
+        assert!(matches!(err, RustlocError::InvalidManifest { .. }), "{err}");
+    }
 }
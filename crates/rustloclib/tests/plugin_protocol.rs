@@ -0,0 +1,99 @@
+//! Runs the reference plugin in `examples/line_plugin.rs` as a real
+//! subprocess through the external-process backend.
+
+use std::fs;
+use std::path::PathBuf;
+
+use rustloclib::{count_directory, BackendRegistry, DiagnosticKind, FilterConfig, LanguageConfig};
+use tempfile::TempDir;
+
+/// `cargo test` builds examples next to the test binaries' `deps` directory.
+fn line_plugin() -> PathBuf {
+    let exe = std::env::current_exe().expect("test binary path");
+    let path = exe
+        .parent()
+        .and_then(|deps| deps.parent())
+        .expect("test binary lives in target/<profile>/deps")
+        .join("examples")
+        .join(format!("line_plugin{}", std::env::consts::EXE_SUFFIX));
+    assert!(
+        path.exists(),
+        "{} is missing; run the tests through `cargo test`, which builds examples",
+        path.display()
+    );
+    path
+}
+
+fn registry_for(command: &str) -> BackendRegistry {
+    let config = LanguageConfig::from_toml(&format!(
+        "[[plugin]]\nname = \"dsl\"\nextensions = [\"dsl\"]\ncommand = {:?}\n",
+        command
+    ))
+    .unwrap();
+    BackendRegistry::with_config(config).unwrap()
+}
+
+#[test]
+fn counts_files_through_the_reference_plugin() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("main.dsl"),
+        ";; entry point\n\
+         ; setup\n\
+         run()\n\
+         \n\
+         test adds\n\
+         \x20 check(1 + 1 == 2)\n\
+         end\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("ignored.txt"), "not counted\n").unwrap();
+
+    let registry = registry_for(line_plugin().to_str().unwrap());
+    assert_eq!(registry.plugins(), ["dsl"]);
+    // Plugins run external programs, so "all" leaves them out.
+    assert!(!registry.all_languages().contains_name("dsl"));
+    let filter = FilterConfig::new()
+        .languages(registry.all_languages().with_name("dsl"))
+        .registry(registry);
+    let result = count_directory(dir.path(), &filter).unwrap();
+
+    assert_eq!(result.total.docs, 1);
+    assert_eq!(result.total.comments, 1);
+    assert_eq!(result.total.code, 1);
+    assert_eq!(result.total.blanks, 1);
+    assert_eq!(result.total.tests, 3);
+    assert_eq!(result.file_count, 1);
+}
+
+#[test]
+fn a_plugin_that_does_not_speak_the_protocol_is_reported_or_fails_when_strict() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("main.dsl"), "run()\n").unwrap();
+
+    // This test binary prints its test list, which is not a response.
+    let exe = std::env::current_exe().unwrap();
+    let config = LanguageConfig::from_toml(&format!(
+        "[[plugin]]\nname = \"dsl\"\nextensions = [\"dsl\"]\ncommand = {:?}\nargs = [\"--list\"]\n",
+        exe.to_str().unwrap()
+    ))
+    .unwrap();
+    let registry = BackendRegistry::with_config(config).unwrap();
+    let filter = FilterConfig::new()
+        .languages(registry.all_languages().with_name("dsl"))
+        .registry(registry);
+
+    let result = count_directory(dir.path(), &filter).unwrap();
+    assert_eq!(result.file_count, 0);
+    assert_eq!(result.diagnostics[0].kind, DiagnosticKind::AnalysisError);
+    assert!(
+        result.diagnostics[0]
+            .message
+            .contains("plugin 'dsl' failed"),
+        "{:?}",
+        result.diagnostics
+    );
+
+    let error = count_directory(dir.path(), &filter.strict(true)).unwrap_err();
+    assert!(error.to_string().contains("plugin 'dsl' failed"), "{error}");
+}